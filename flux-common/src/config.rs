@@ -32,6 +32,9 @@ pub struct CrateConfig {
     pub dump_constraint: bool,
     pub dump_checker_trace: bool,
     pub check_asserts: AssertBehavior,
+    pub check_overflow: bool,
+    pub check_macros: bool,
+    pub real_floats: bool,
 }
 
 #[derive(Deserialize)]
@@ -42,6 +45,15 @@ pub struct Config {
     pub check_asserts: AssertBehavior,
     pub dump_mir: bool,
     pub pointer_width: u64,
+    /// Whether to check that arithmetic operations do not overflow. This is the only setting that
+    /// enables the compiler's overflow checks, i.e., checked operations in MIR. A crate can also
+    /// opt in with `#![flux::cfg(check_overflow = "true")]`, in which case plain operations are
+    /// checked instead.
+    pub check_overflow: bool,
+    /// Whether to check functions generated by macros, e.g., by `macro_rules!` or `#[derive]`
+    pub check_macros: bool,
+    /// Whether to index floating-point values by reals approximating them
+    pub real_floats: bool,
 }
 
 pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
@@ -54,6 +66,9 @@ pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
             .set_default("check_asserts", "assume")?
             .set_default("check_asserts", "assume")?
             .set_default("pointer_width", 64)?
+            .set_default("check_overflow", false)?
+            .set_default("check_macros", true)?
+            .set_default("real_floats", false)?
             .add_source(Environment::with_prefix("LR").ignore_empty(true))
             .build()?
             .try_deserialize()
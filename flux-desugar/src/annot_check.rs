@@ -16,9 +16,14 @@ use rustc_span::{Span, Symbol};
 pub fn check_struct_def(
     tcx: TyCtxt,
     sess: &FluxSession,
+    def_id: DefId,
     struct_def: &StructDef<Res>,
 ) -> Result<(), ErrorGuaranteed> {
-    let def_id = struct_def.def_id.to_def_id();
+    // The fields of an opaque struct are not annotated and the fields of an extern one may not even
+    // be supported.
+    if struct_def.opaque {
+        return Ok(());
+    }
     let rust_adt_def = lowering::lower_adt_def(tcx, sess, tcx.adt_def(def_id))?;
     let rust_variant_def = &rust_adt_def.variants[0];
     iter::zip(&struct_def.fields, rust_variant_def.fields()).try_for_each_exhaust(
@@ -34,9 +39,9 @@ pub fn check_struct_def(
 pub fn check_enum_def(
     tcx: TyCtxt,
     sess: &FluxSession,
+    def_id: DefId,
     enum_def: &EnumDef<Res>,
 ) -> Result<(), ErrorGuaranteed> {
-    let def_id = enum_def.def_id.to_def_id();
     let rust_adt_def = lowering::lower_adt_def(tcx, sess, tcx.adt_def(def_id))?;
 
     iter::zip(&enum_def.variants, &rust_adt_def.variants).try_for_each_exhaust(
@@ -62,11 +67,21 @@ pub fn check_fn_sig(
     ck.zip_ty_locs(&fn_sig.ensures, &locs)
 }
 
+pub fn check_static_ty(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    def_id: DefId,
+    ty: &Ty<Res>,
+) -> Result<(), ErrorGuaranteed> {
+    let rust_ty = lowering::lower_type_of(tcx, sess, def_id)?;
+    ZipChecker::new(tcx, sess, def_id).zip_ty(ty, &rust_ty)
+}
+
 struct ZipChecker<'genv, 'tcx> {
     tcx: TyCtxt<'tcx>,
     sess: &'genv FluxSession,
     /// DefId of the definition being checked, this could either be a field on a struct,
-    /// a variant on a enum, or a function.
+    /// a variant on a enum, a function, or a static.
     def_id: DefId,
 }
 
@@ -182,18 +197,27 @@ impl<'genv, 'tcx> ZipChecker<'genv, 'tcx> {
             | (TyKind::Indexed { path, .. }, _)
             | (TyKind::Exists { path, .. }, _) => self.zip_path(path, rust_ty),
             (TyKind::Constr(_, ty), _) => self.zip_ty(ty, rust_ty),
+            (TyKind::ImplTrait(path), _) => self.zip_path(path, rust_ty),
             (TyKind::Ref(rk, ref_ty), rustc_ty::TyKind::Ref(rust_ty, mutability)) => {
                 self.zip_ty(ref_ty, rust_ty)?;
                 self.zip_mutability(ty.span, *rk, *mutability)
             }
             (TyKind::Array(ty, _), rustc_ty::TyKind::Array(rust_ty, _))
-            | (TyKind::Slice(ty), rustc_ty::TyKind::Slice(rust_ty)) => self.zip_ty(ty, rust_ty),
+            | (TyKind::Slice(ty, _), rustc_ty::TyKind::Slice(rust_ty)) => self.zip_ty(ty, rust_ty),
             (TyKind::Tuple(tys), rustc_ty::TyKind::Tuple(rust_tys))
                 if tys.len() == rust_tys.len() =>
             {
                 iter::zip(tys, rust_tys)
                     .try_for_each_exhaust(|(ty, rust_ty)| self.zip_ty(ty, rust_ty))
             }
+            (TyKind::FnPtr(inputs, output), rustc_ty::TyKind::FnPtr(rust_fn_sig))
+                if inputs.len() == rust_fn_sig.as_ref().skip_binder().inputs().len() =>
+            {
+                let rust_fn_sig = rust_fn_sig.as_ref().skip_binder();
+                iter::zip(inputs, rust_fn_sig.inputs())
+                    .try_for_each_exhaust(|(ty, rust_ty)| self.zip_ty(ty, rust_ty))?;
+                self.zip_ty(output, &rust_fn_sig.output())
+            }
             _ => {
                 Err(self.sess.emit_err(errors::InvalidRefinement::new(
                     self.tcx,
@@ -209,7 +233,7 @@ impl<'genv, 'tcx> ZipChecker<'genv, 'tcx> {
         match (&path.ident, rust_ty.kind()) {
             (Res::Adt(def_id1), rustc_ty::TyKind::Adt(def_id2, substs)) if def_id1 == def_id2 => {
                 let generics = self.tcx.generics_of(def_id1);
-                let max_args = generics.own_counts().types;
+                let max_args = generics.own_counts().types + generics.own_counts().consts;
                 let default_args = generics.own_defaults().types;
                 let min_args = max_args - default_args;
 
@@ -240,6 +264,17 @@ impl<'genv, 'tcx> ZipChecker<'genv, 'tcx> {
             {
                 Ok(())
             }
+            (Res::Opaque(def_id1), rustc_ty::TyKind::Opaque(def_id2, _)) if def_id1 == def_id2 => {
+                Ok(())
+            }
+            (Res::Projection(param_ty, def_id1), rustc_ty::TyKind::Projection(def_id2, substs))
+                if def_id1 == def_id2
+                    && let [rustc_ty::GenericArg::Ty(self_ty)] = &substs[..]
+                    && let rustc_ty::TyKind::Param(self_param_ty) = self_ty.kind()
+                    && param_ty == self_param_ty =>
+            {
+                Ok(())
+            }
             (Res::Bool, rustc_ty::TyKind::Bool)
             | (Res::Str, rustc_ty::TyKind::Str)
             | (Res::Char, rustc_ty::TyKind::Char) => Ok(()),
@@ -277,15 +312,35 @@ impl<'genv, 'tcx> ZipChecker<'genv, 'tcx> {
         args: &[Ty<Res>],
         rust_args: &[rustc_ty::GenericArg],
     ) -> Result<(), ErrorGuaranteed> {
-        let rust_args = rust_args.iter().filter_map(|rust_arg| {
-            match rust_arg {
-                rustc_ty::GenericArg::Ty(ty) => Some(ty),
-                rustc_ty::GenericArg::Lifetime(_) => None,
-            }
-        });
+        let rust_args = rust_args
+            .iter()
+            .filter(|rust_arg| !matches!(rust_arg, rustc_ty::GenericArg::Lifetime(_)));
         iter::zip(args, rust_args)
             .into_iter()
-            .try_for_each_exhaust(|(arg, rust_arg)| self.zip_ty(arg, rust_arg))
+            .try_for_each_exhaust(|(arg, rust_arg)| {
+                match rust_arg {
+                    rustc_ty::GenericArg::Ty(rust_ty) => self.zip_ty(arg, rust_ty),
+                    rustc_ty::GenericArg::Const(c) => self.zip_const(arg, c),
+                    rustc_ty::GenericArg::Lifetime(_) => unreachable!(),
+                }
+            })
+    }
+
+    fn zip_const(&self, arg: &Ty<Res>, c: &rustc_ty::Const) -> Result<(), ErrorGuaranteed> {
+        match (&arg.kind, c) {
+            (
+                TyKind::Path(Path { ident: Res::ConstParam(param1), args, .. }),
+                rustc_ty::Const::Param(param2),
+            ) if args.is_empty() && param1 == param2 => Ok(()),
+            _ => {
+                Err(self.sess.emit_err(errors::PathMismatch::new(
+                    self.tcx,
+                    c,
+                    arg.span,
+                    self.def_id,
+                )))
+            }
+        }
     }
 }
 
@@ -350,7 +405,12 @@ mod errors {
     }
 
     impl PathMismatch {
-        pub fn new(tcx: TyCtxt, rust_ty: &rustc_ty::Ty, flux_ty_span: Span, def_id: DefId) -> Self {
+        pub fn new(
+            tcx: TyCtxt,
+            rust_ty: &impl std::fmt::Debug,
+            flux_ty_span: Span,
+            def_id: DefId,
+        ) -> Self {
             let def = InvalidRefinementForDef::new(tcx, def_id);
             let flux_type = tcx
                 .sess
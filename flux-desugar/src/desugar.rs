@@ -8,8 +8,10 @@ use flux_syntax::surface::{self, Res, TyCtxt};
 use itertools::Itertools;
 use rustc_data_structures::fx::{FxIndexMap, IndexEntry};
 use rustc_errors::ErrorGuaranteed;
+use rustc_hash::FxHashMap;
 use rustc_hir::def_id::DefId;
-use rustc_span::{sym, symbol::kw, Symbol};
+use rustc_middle::ty::GenericParamDefKind;
+use rustc_span::{sym, symbol::kw, Span, Symbol};
 
 pub fn desugar_qualifier(
     tcx: TyCtxt,
@@ -17,21 +19,54 @@ pub fn desugar_qualifier(
     map: &fhir::Map,
     qualifier: surface::Qualifier,
 ) -> Result<fhir::Qualifier, ErrorGuaranteed> {
-    let binders = Binders::from_params(sess, &qualifier.args)?;
+    let binders = Binders::from_params(sess, map, &qualifier.args)?;
     let name = qualifier.name.name.to_ident_string();
     let expr = ExprCtxt::new(tcx, sess, map, &binders).desugar_expr(qualifier.expr);
 
     Ok(fhir::Qualifier { name, args: binders.into_params(), expr: expr? })
 }
 
+pub fn desugar_axiom(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    map: &fhir::Map,
+    axiom: surface::Axiom,
+) -> Result<fhir::Axiom, ErrorGuaranteed> {
+    let binders = Binders::from_params(sess, map, &axiom.args)?;
+    let expr = ExprCtxt::new(tcx, sess, map, &binders).desugar_expr(axiom.expr)?;
+
+    Ok(fhir::Axiom { args: binders.into_params(), expr })
+}
+
 pub fn resolve_uif_def(
     sess: &FluxSession,
+    map: &fhir::Map,
     uif_def: surface::UifDef,
 ) -> Result<fhir::UifDef, ErrorGuaranteed> {
-    let sort = resolve_func_sort(sess, &uif_def.inputs, &uif_def.output)?;
+    let sort = resolve_func_sort(sess, map, &uif_def.inputs, &uif_def.output)?;
     Ok(fhir::UifDef { name: uif_def.name.name, sort })
 }
 
+pub fn desugar_defn(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    map: &fhir::Map,
+    defn: surface::Defn,
+) -> Result<fhir::Defn, ErrorGuaranteed> {
+    let binders = Binders::from_params(sess, map, &defn.args)?;
+    let expr = ExprCtxt::new(tcx, sess, map, &binders).desugar_expr(defn.body)?;
+    let output = resolve_sort(sess, map, &defn.output)?;
+    let args = binders.into_params();
+
+    let inputs_and_output = args
+        .iter()
+        .map(|param| param.sort.clone())
+        .chain([output])
+        .collect_vec();
+    let sort = fhir::FuncSort { inputs_and_output: List::from_vec(inputs_and_output) };
+    Ok(fhir::Defn { name: defn.name.name, args, sort, expr })
+}
+
 pub fn desugar_adt_def(
     tcx: TyCtxt,
     sess: &FluxSession,
@@ -41,7 +76,8 @@ pub fn desugar_adt_def(
     invariants: Vec<surface::Expr>,
     opaque: bool,
 ) -> Result<fhir::AdtDef, ErrorGuaranteed> {
-    let binders = Binders::from_params(sess, refined_by)?;
+    let mut binders = Binders::from_params(sess, map, refined_by)?;
+    binders.gather_const_params(tcx, def_id);
 
     let invariants = invariants
         .into_iter()
@@ -56,10 +92,11 @@ pub fn desugar_struct_def(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    def_id: DefId,
     adt_def: surface::StructDef<Res>,
 ) -> Result<fhir::StructDef, ErrorGuaranteed> {
-    let def_id = adt_def.def_id.to_def_id();
-    let binders = Binders::from_params(sess, adt_def.refined_by.iter().flatten())?;
+    let mut binders = Binders::from_params(sess, map, adt_def.refined_by.iter().flatten())?;
+    binders.gather_const_params(tcx, def_id);
 
     let mut cx = DesugarCtxt::new(tcx, sess, map, binders);
 
@@ -80,13 +117,13 @@ pub fn desugar_enum_def(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    def_id: DefId,
     enum_def: surface::EnumDef<Res>,
 ) -> Result<fhir::EnumDef, ErrorGuaranteed> {
-    let def_id = enum_def.def_id.to_def_id();
     let variants = enum_def
         .variants
         .into_iter()
-        .map(|variant| desugar_variant(tcx, sess, map, variant))
+        .map(|variant| desugar_variant(tcx, sess, map, def_id, variant))
         .try_collect_exhaust()?;
 
     Ok(fhir::EnumDef { def_id, variants })
@@ -96,9 +133,11 @@ fn desugar_variant(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    def_id: DefId,
     variant: surface::VariantDef<Res>,
 ) -> Result<fhir::VariantDef, ErrorGuaranteed> {
     let mut binders = Binders::new();
+    binders.gather_const_params(tcx, def_id);
     binders.gather_variant_params(tcx, sess, map, &variant)?;
     let mut cx = DesugarCtxt::new(tcx, sess, map, binders);
 
@@ -117,9 +156,11 @@ pub fn desugar_fn_sig(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    def_id: DefId,
     fn_sig: surface::FnSig<Res>,
 ) -> Result<fhir::FnSig, ErrorGuaranteed> {
     let mut binders = Binders::new();
+    binders.gather_const_params(tcx, def_id);
     binders.gather_fn_sig_params(tcx, sess, map, &fn_sig)?;
     let mut cx = DesugarCtxt::new(tcx, sess, map, binders);
 
@@ -159,6 +200,16 @@ pub fn desugar_fn_sig(
     })
 }
 
+pub fn desugar_static_ty(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    map: &fhir::Map,
+    ty: surface::Ty<Res>,
+) -> Result<fhir::Ty, ErrorGuaranteed> {
+    let mut cx = DesugarCtxt::new(tcx, sess, map, Binders::new());
+    cx.desugar_ty(None, ty)
+}
+
 pub struct DesugarCtxt<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     sess: &'a FluxSession,
@@ -172,6 +223,9 @@ pub struct DesugarCtxt<'a, 'tcx> {
 struct Binders {
     name_gen: IndexGen<fhir::Name>,
     map: FxIndexMap<surface::Ident, Binder>,
+    /// The const generic parameters in scope. They are shadowed by refinement parameters with
+    /// the same name.
+    const_params: FxHashMap<Symbol, fhir::ParamConst>,
 }
 
 /// The different kind of binders that can appear in the surface syntax
@@ -197,11 +251,15 @@ enum Binder {
     Unrefined,
 }
 
+#[derive(Clone)]
 struct ExprCtxt<'a, 'tcx> {
     tcx: TyCtxt<'tcx>,
     sess: &'a FluxSession,
     map: &'a fhir::Map,
     binders: &'a Binders,
+    /// Variables bound by quantifiers enclosing the expression being desugared. They shadow
+    /// the binders in `binders`.
+    quantified: Vec<(surface::Ident, Binder)>,
 }
 
 enum BtyOrTy {
@@ -211,7 +269,12 @@ enum BtyOrTy {
 
 enum FuncRes<'a> {
     Param(fhir::Name, &'a fhir::Sort),
-    Uif(&'a fhir::UifDef),
+    /// A global function, either uninterpreted or defined.
+    Uif(&'a fhir::FuncSort),
+    /// A function on sets or maps interpreted by the solver.
+    Theory(fhir::TheoryFunc),
+    /// A constructor or a constructor test of a datatype sort.
+    Data(fhir::DataFunc),
 }
 
 impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
@@ -254,9 +317,6 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
         ty: surface::Ty<Res>,
     ) -> Result<fhir::Ty, ErrorGuaranteed> {
         let ty = match ty.kind {
-            surface::TyKind::Path(surface::Path { ident: Res::Float(float_ty), .. }) => {
-                fhir::Ty::Float(float_ty)
-            }
             surface::TyKind::Path(surface::Path { ident: Res::Param(param_ty), .. }) => {
                 fhir::Ty::Param(param_ty)
             }
@@ -273,7 +333,7 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
                 }
             }
             surface::TyKind::Indexed { path, indices } => {
-                match self.desugar_path(path)? {
+                match self.desugar_refined_path(path)? {
                     BtyOrTy::Bty(bty) => fhir::Ty::Indexed(bty, self.desugar_indices(indices)?),
                     BtyOrTy::Ty(_) => {
                         return Err(self.sess.emit_err(errors::ParamCountMismatch::new(
@@ -286,7 +346,7 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
             }
             surface::TyKind::Exists { bind: ident, path, pred } => {
                 let res = path.ident;
-                match self.desugar_path(path)? {
+                match self.desugar_refined_path(path)? {
                     BtyOrTy::Bty(bty) => {
                         if let Some(bind) = bind {
                             let binder = self.binders[bind].clone();
@@ -298,7 +358,8 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
                             let idxs = self.desugar_bind(bind)?;
                             fhir::Ty::Constr(pred, Box::new(fhir::Ty::Indexed(bty, idxs)))
                         } else {
-                            let binder = Binder::from_res(&self.binders.name_gen, self.map, res);
+                            let binder =
+                                Binder::from_refined_res(&self.binders.name_gen, self.map, res);
                             let (pred, binder) =
                                 self.binders.with_binder(ident, binder, |binders| {
                                     ExprCtxt::new(self.tcx, self.sess, self.map, binders)
@@ -322,11 +383,14 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
                 let ty = self.desugar_ty(None, *ty)?;
                 fhir::Ty::Constr(pred, Box::new(ty))
             }
-            surface::TyKind::Array(ty, _) => {
+            surface::TyKind::Array(ty, len) => {
                 let ty = self.desugar_ty(None, *ty)?;
-                fhir::Ty::Array(Box::new(ty), fhir::ArrayLen)
+                fhir::Ty::Array(Box::new(ty), self.desugar_array_len(len)?)
+            }
+            surface::TyKind::Slice(ty, len) => {
+                let ty = self.desugar_ty(None, *ty)?;
+                fhir::Ty::Slice(Box::new(ty), self.desugar_array_len(len)?)
             }
-            surface::TyKind::Slice(ty) => fhir::Ty::Slice(Box::new(self.desugar_ty(None, *ty)?)),
             surface::TyKind::Tuple(tys) => {
                 let tys = tys
                     .into_iter()
@@ -334,6 +398,23 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
                     .try_collect_exhaust()?;
                 fhir::Ty::Tuple(tys)
             }
+            surface::TyKind::FnPtr(inputs, output) => {
+                let inputs = inputs
+                    .into_iter()
+                    .map(|ty| self.desugar_ty(None, ty))
+                    .try_collect_exhaust()?;
+                let output = self.desugar_ty(None, *output)?;
+                fhir::Ty::FnPtr(inputs, Box::new(output))
+            }
+            surface::TyKind::ImplTrait(path) => {
+                let span = path.span;
+                match self.desugar_path(path)? {
+                    BtyOrTy::Ty(ty) => ty,
+                    BtyOrTy::Bty(_) => {
+                        return Err(self.sess.emit_err(errors::InvalidImplTrait { span }));
+                    }
+                }
+            }
         };
         Ok(ty)
     }
@@ -352,6 +433,21 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
         Ok(fhir::Indices { indices: exprs, span: indices.span })
     }
 
+    fn desugar_array_len(
+        &mut self,
+        len: surface::ArrayLen,
+    ) -> Result<fhir::ArrayLen, ErrorGuaranteed> {
+        match len {
+            surface::ArrayLen::Infer => Ok(fhir::ArrayLen::Infer),
+            surface::ArrayLen::Arg(arg) => {
+                // The length is a single index so binders used for it are never aggregate.
+                let mut args = self.desugar_refine_arg(arg)?;
+                debug_assert_eq!(args.len(), 1);
+                Ok(fhir::ArrayLen::Arg(args.remove(0)))
+            }
+        }
+    }
+
     fn desugar_bind(&self, bind: surface::Ident) -> Result<fhir::Indices, ErrorGuaranteed> {
         Ok(fhir::Indices { indices: self.bind_into_args(bind)?, span: bind.span })
     }
@@ -419,14 +515,35 @@ impl<'a, 'tcx> DesugarCtxt<'a, 'tcx> {
                     .try_collect_exhaust()?;
                 BtyOrTy::Bty(fhir::BaseTy::Adt(def_id, substs))
             }
+            Res::Float(float_ty) if self.map.real_floats() => {
+                BtyOrTy::Bty(fhir::BaseTy::Float(float_ty))
+            }
             Res::Float(float_ty) => BtyOrTy::Ty(fhir::Ty::Float(float_ty)),
             Res::Param(param_ty) => BtyOrTy::Ty(fhir::Ty::Param(param_ty)),
-            Res::Str => BtyOrTy::Ty(fhir::Ty::Str),
-            Res::Char => BtyOrTy::Ty(fhir::Ty::Char),
+            Res::ConstParam(param) => BtyOrTy::Ty(fhir::Ty::Const(param)),
+            Res::Opaque(def_id) => BtyOrTy::Ty(fhir::Ty::Opaque(def_id)),
+            Res::Projection(param_ty, def_id) => {
+                BtyOrTy::Ty(fhir::Ty::Projection(param_ty, def_id))
+            }
+            Res::Str => BtyOrTy::Bty(fhir::BaseTy::Str),
+            Res::Char => BtyOrTy::Bty(fhir::BaseTy::Char),
         };
         Ok(bty)
     }
 
+    /// Like [`Self::desugar_path`] but used when the path is explicitly refined, e.g., `T[@n]` or
+    /// `T{v: v > 0}`. A refined type parameter is taken to be indexed by an integer.
+    fn desugar_refined_path(
+        &mut self,
+        path: surface::Path<Res>,
+    ) -> Result<BtyOrTy, ErrorGuaranteed> {
+        if let Res::Param(param_ty) = path.ident {
+            Ok(BtyOrTy::Bty(fhir::BaseTy::Param(param_ty)))
+        } else {
+            self.desugar_path(path)
+        }
+    }
+
     fn desugar_variant_ret(
         &mut self,
         ret: surface::VariantRet<Res>,
@@ -452,7 +569,7 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
         map: &'a fhir::Map,
         binders: &'a Binders,
     ) -> Self {
-        Self { tcx, sess, map, binders }
+        Self { tcx, sess, map, binders, quantified: vec![] }
     }
 
     fn desugar_expr(&self, expr: surface::Expr) -> Result<fhir::Expr, ErrorGuaranteed> {
@@ -471,6 +588,12 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
                     FuncRes::Uif(_) => {
                         fhir::ExprKind::App(fhir::Func::Uif(func.name, func.span), args)
                     }
+                    FuncRes::Theory(theory_func) => {
+                        fhir::ExprKind::App(fhir::Func::Theory(theory_func), args)
+                    }
+                    FuncRes::Data(data_func) => {
+                        fhir::ExprKind::App(fhir::Func::Data(data_func), args)
+                    }
                     FuncRes::Param(name, _) => {
                         let func =
                             fhir::Func::Var(fhir::Ident { name, source_info: to_src_info(func) });
@@ -484,10 +607,37 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
                 let e2 = self.desugar_expr(e2);
                 fhir::ExprKind::IfThenElse(Box::new([p?, e1?, e2?]))
             }
+            surface::ExprKind::Quant(quant, var, box [lo, hi, body]) => {
+                let lo = self.desugar_expr(lo);
+                let hi = self.desugar_expr(hi);
+                let name = self.binders.fresh();
+                let mut cx = self.clone();
+                cx.quantified
+                    .push((var, Binder::Single(name, fhir::Sort::Int)));
+                let body = cx.desugar_expr(body);
+                let var = fhir::Ident { name, source_info: to_src_info(var) };
+                fhir::ExprKind::Quant(desugar_quantifier(quant), var, Box::new([lo?, hi?, body?]))
+            }
         };
         Ok(fhir::Expr { kind, span: expr.span })
     }
 
+    fn get_binder(&self, ident: surface::Ident) -> Option<&Binder> {
+        self.quantified
+            .iter()
+            .rev()
+            .find_map(|(var, binder)| (*var == ident).then_some(binder))
+            .or_else(|| self.binders.get(ident))
+    }
+
+    fn def_ident(&self, ident: surface::Ident) -> Option<surface::Ident> {
+        self.quantified
+            .iter()
+            .rev()
+            .find_map(|(var, _)| (*var == ident).then_some(*var))
+            .or_else(|| self.binders.def_ident(ident))
+    }
+
     fn desugar_exprs(&self, exprs: Vec<surface::Expr>) -> Result<Vec<fhir::Expr>, ErrorGuaranteed> {
         exprs
             .into_iter()
@@ -496,7 +646,7 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
     }
 
     fn resolve_func(&self, func: surface::Ident) -> Result<FuncRes, ErrorGuaranteed> {
-        match (self.binders.get(func), self.map.uif(func.name)) {
+        match (self.get_binder(func), self.map.uif(func.name)) {
             (Some(Binder::Single(name, sort)), _) => Ok(FuncRes::Param(*name, sort)),
             (Some(Binder::Aggregate(_, fields)), _) => {
                 Err(self
@@ -504,13 +654,34 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
                     .emit_err(errors::InvalidAggregateUse::new(func, fields.keys())))
             }
             (Some(Binder::Unrefined), _) => {
-                let def_ident = self.binders.def_ident(func).unwrap();
+                let def_ident = self.def_ident(func).unwrap();
                 Err(self
                     .sess
                     .emit_err(errors::InvalidUnrefinedParam::new(def_ident, func)))
             }
-            (None, Some(uif)) => Ok(FuncRes::Uif(uif)),
-            (None, None) => Err(self.sess.emit_err(errors::UnresolvedVar::new(func))),
+            (None, Some(uif)) => Ok(FuncRes::Uif(&uif.sort)),
+            (None, None) => {
+                if let Some(defn) = self.map.defn(func.name) {
+                    Ok(FuncRes::Uif(&defn.sort))
+                } else if let Some(theory_func) = resolve_theory_func(func.name) {
+                    Ok(FuncRes::Theory(theory_func))
+                } else if let Some(data_func) = self.resolve_data_func(func.name) {
+                    Ok(FuncRes::Data(data_func))
+                } else {
+                    Err(self.sess.emit_err(errors::UnresolvedVar::new(func)))
+                }
+            }
+        }
+    }
+
+    /// Resolves the constructor `Ctor` or the constructor test `is_Ctor` of a datatype sort
+    fn resolve_data_func(&self, name: Symbol) -> Option<fhir::DataFunc> {
+        if let Some((def_id, variant, _)) = self.map.data_ctor_by_name(name) {
+            Some(fhir::DataFunc::Ctor(def_id, variant))
+        } else {
+            let name = Symbol::intern(name.as_str().strip_prefix("is_")?);
+            let (def_id, variant, _) = self.map.data_ctor_by_name(name)?;
+            Some(fhir::DataFunc::Test(def_id, variant))
         }
     }
 
@@ -523,6 +694,16 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
                 }
             }
             surface::LitKind::Bool => Ok(fhir::Lit::Bool(lit.symbol == kw::True)),
+            surface::LitKind::Float => {
+                match lit.symbol.as_str().replace('_', "").parse::<f64>() {
+                    Ok(r) if r.is_finite() => Ok(fhir::Lit::Real(r)),
+                    _ => {
+                        Err(self
+                            .sess
+                            .emit_err(errors::UnexpectedLiteral { span: lit.span }))
+                    }
+                }
+            }
             _ => {
                 Err(self
                     .sess
@@ -532,7 +713,7 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
     }
 
     fn desugar_var(&self, ident: surface::Ident) -> Result<fhir::Expr, ErrorGuaranteed> {
-        let kind = match (self.binders.get(ident), self.map.const_by_name(ident.name)) {
+        let kind = match (self.get_binder(ident), self.map.const_by_name(ident.name)) {
             (Some(Binder::Single(name, _)), _) => {
                 fhir::ExprKind::Var(*name, ident.name, ident.span)
             }
@@ -547,13 +728,25 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
                 }
             }
             (Some(Binder::Unrefined), _) => {
-                let def_ident = self.binders.def_ident(ident).unwrap();
+                let def_ident = self.def_ident(ident).unwrap();
                 return Err(self
                     .sess
                     .emit_err(errors::InvalidUnrefinedParam::new(def_ident, ident)));
             }
+            (None, _) if let Some(param) = self.binders.const_params.get(&ident.name) => {
+                fhir::ExprKind::ConstParam(*param, ident.span)
+            }
             (None, Some(const_info)) => fhir::ExprKind::Const(const_info.def_id, ident.span),
-            (None, None) => return Err(self.sess.emit_err(errors::UnresolvedVar::new(ident))),
+            (None, None) => {
+                // A constructor without fields, e.g., `Red`
+                match self.map.data_ctor_by_name(ident.name) {
+                    Some((def_id, variant, ctor)) if ctor.fields.is_empty() => {
+                        let func = fhir::DataFunc::Ctor(def_id, variant);
+                        fhir::ExprKind::App(fhir::Func::Data(func), vec![])
+                    }
+                    _ => return Err(self.sess.emit_err(errors::UnresolvedVar::new(ident))),
+                }
+            }
         };
         Ok(fhir::Expr { kind, span: ident.span })
     }
@@ -563,31 +756,59 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
         expr: surface::Expr,
         fld: surface::Ident,
     ) -> Result<fhir::Expr, ErrorGuaranteed> {
-        // This error never occurs because the parser forces `expr` to be an ident, but we report an error just in case.
-        let surface::ExprKind::Var(ident) = expr.kind else {
-            return Err(self
-                .sess
-                .emit_err(errors::InvalidDotVar { span: expr.span }))
+        Ok(self.desugar_proj(expr, fld)?.0)
+    }
+
+    /// Desugars the projection `expr.fld` returning the sort of the projected field. Projections
+    /// can be chained when the field has a datatype sort, e.g., `seg.start.x`.
+    fn desugar_proj(
+        &self,
+        expr: surface::Expr,
+        fld: surface::Ident,
+    ) -> Result<(fhir::Expr, fhir::Sort), ErrorGuaranteed> {
+        let ident = match expr.kind {
+            surface::ExprKind::Var(ident) => ident,
+            surface::ExprKind::Dot(e, inner) => {
+                let span = expr.span.to(fld.span);
+                return match self.desugar_proj(*e, inner)? {
+                    (e, fhir::Sort::Data(def_id)) => self.desugar_data_field(e, def_id, fld, span),
+                    _ => Err(self.sess.emit_err(errors::InvalidDotVar { span })),
+                };
+            }
+            // This error never occurs because the parser forces `expr` to be an ident or a
+            // projection, but we report an error just in case.
+            _ => {
+                return Err(self
+                    .sess
+                    .emit_err(errors::InvalidDotVar { span: expr.span }))
+            }
         };
 
-        match self.binders.get(ident) {
+        match self.get_binder(ident) {
+            Some(Binder::Single(name, fhir::Sort::Data(def_id))) => {
+                let var = fhir::Expr {
+                    kind: fhir::ExprKind::Var(*name, ident.name, ident.span),
+                    span: ident.span,
+                };
+                self.desugar_data_field(var, *def_id, fld, ident.span.to(fld.span))
+            }
             Some(Binder::Single(_, sort)) => {
-                let def_ident = self.binders.def_ident(ident).unwrap();
+                let def_ident = self.def_ident(ident).unwrap();
                 Err(self
                     .sess
                     .emit_err(errors::InvalidPrimitiveDotAccess::new(def_ident, sort, ident, fld)))
             }
             Some(Binder::Aggregate(def_id, fields)) => {
-                let (name, _) = fields.get(&fld.name).ok_or_else(|| {
+                let (name, sort) = fields.get(&fld.name).ok_or_else(|| {
                     self.sess
                         .emit_err(errors::FieldNotFound::new(self.tcx, self.map, *def_id, fld))
                 })?;
                 let span = ident.span.to(fld.span);
                 let kind = fhir::ExprKind::Var(*name, ident.name, span);
-                Ok(fhir::Expr { kind, span })
+                Ok((fhir::Expr { kind, span }, sort.clone()))
             }
             Some(Binder::Unrefined) => {
-                let def_ident = self.binders.def_ident(ident).unwrap();
+                let def_ident = self.def_ident(ident).unwrap();
                 Err(self
                     .sess
                     .emit_err(errors::InvalidUnrefinedParam::new(def_ident, ident)))
@@ -596,8 +817,29 @@ impl<'a, 'tcx> ExprCtxt<'a, 'tcx> {
         }
     }
 
+    fn desugar_data_field(
+        &self,
+        expr: fhir::Expr,
+        def_id: DefId,
+        fld: surface::Ident,
+        span: Span,
+    ) -> Result<(fhir::Expr, fhir::Sort), ErrorGuaranteed> {
+        let func = self
+            .map
+            .data_decl(def_id)
+            .unwrap()
+            .field(fld.name)
+            .ok_or_else(|| {
+                self.sess
+                    .emit_err(errors::FieldNotFound::new(self.tcx, self.map, def_id, fld))
+            })?;
+        let sort = self.map.data_func_sort(func).output().clone();
+        let kind = fhir::ExprKind::App(fhir::Func::Data(func), vec![expr]);
+        Ok((fhir::Expr { kind, span }, sort))
+    }
+
     fn desugar_loc(&self, loc: surface::Ident) -> Result<fhir::Ident, ErrorGuaranteed> {
-        match self.binders.get(loc) {
+        match self.get_binder(loc) {
             Some(&Binder::Single(name, _)) => {
                 Ok(fhir::Ident { name, source_info: to_src_info(loc) })
             }
@@ -619,37 +861,82 @@ fn desugar_ref_kind(rk: surface::RefKind) -> fhir::RefKind {
     }
 }
 
-fn resolve_sort(sess: &FluxSession, sort: &surface::Sort) -> Result<fhir::Sort, ErrorGuaranteed> {
+fn resolve_sort(
+    sess: &FluxSession,
+    map: &fhir::Map,
+    sort: &surface::Sort,
+) -> Result<fhir::Sort, ErrorGuaranteed> {
     match sort {
-        surface::Sort::Base(sort) => resolve_base_sort(sess, *sort),
+        surface::Sort::Base(sort) => resolve_base_sort(sess, map, *sort),
+        surface::Sort::BitVec(size) => Ok(fhir::Sort::BitVec(*size)),
+        surface::Sort::Set(sort) => Ok(fhir::Sort::Set(Box::new(resolve_sort(sess, map, sort)?))),
+        surface::Sort::Map(k, v) => {
+            let k = resolve_sort(sess, map, k)?;
+            let v = resolve_sort(sess, map, v)?;
+            Ok(fhir::Sort::Map(Box::new(k), Box::new(v)))
+        }
         surface::Sort::Func { inputs, output } => {
-            Ok(resolve_func_sort(sess, inputs, output)?.into())
+            Ok(resolve_func_sort(sess, map, inputs, output)?.into())
         }
         surface::Sort::Infer => todo!(),
     }
 }
 
+/// The functions on sets and maps, and the conversions between sorts available in refinements.
+/// User defined functions with the same name take precedence.
+fn resolve_theory_func(name: Symbol) -> Option<fhir::TheoryFunc> {
+    let func = match name.as_str() {
+        "empty" => fhir::TheoryFunc::SetEmpty,
+        "singleton" => fhir::TheoryFunc::SetSingleton,
+        "union" => fhir::TheoryFunc::SetUnion,
+        "intersection" => fhir::TheoryFunc::SetIntersection,
+        "difference" => fhir::TheoryFunc::SetDifference,
+        "member" => fhir::TheoryFunc::SetMember,
+        "subset" => fhir::TheoryFunc::SetSubset,
+        "map_default" => fhir::TheoryFunc::MapDefault,
+        "select" => fhir::TheoryFunc::MapSelect,
+        "store" => fhir::TheoryFunc::MapStore,
+        "to_real" => fhir::TheoryFunc::IntToReal,
+        "to_int" => fhir::TheoryFunc::RealToInt,
+        "int_to_bv32" => fhir::TheoryFunc::IntToBv32,
+        "int_to_bv64" => fhir::TheoryFunc::IntToBv64,
+        "bv32_to_int" => fhir::TheoryFunc::Bv32ToInt,
+        "bv64_to_int" => fhir::TheoryFunc::Bv64ToInt,
+        "bvashr" => fhir::TheoryFunc::BvAShr,
+        _ => return None,
+    };
+    Some(func)
+}
+
 fn resolve_func_sort(
     sess: &FluxSession,
+    map: &fhir::Map,
     inputs: &[surface::Ident],
     output: &surface::Ident,
 ) -> Result<fhir::FuncSort, ErrorGuaranteed> {
     let mut inputs_and_output: Vec<fhir::Sort> = inputs
         .iter()
-        .map(|sort| resolve_base_sort(sess, *sort))
+        .map(|sort| resolve_base_sort(sess, map, *sort))
         .try_collect_exhaust()?;
-    inputs_and_output.push(resolve_base_sort(sess, *output)?);
+    inputs_and_output.push(resolve_base_sort(sess, map, *output)?);
     Ok(fhir::FuncSort { inputs_and_output: List::from_vec(inputs_and_output) })
 }
 
 fn resolve_base_sort(
     sess: &FluxSession,
+    map: &fhir::Map,
     sort: surface::Ident,
 ) -> Result<fhir::Sort, ErrorGuaranteed> {
     if sort.name == SORTS.int {
         Ok(fhir::Sort::Int)
     } else if sort.name == sym::bool {
         Ok(fhir::Sort::Bool)
+    } else if sort.name == SORTS.real {
+        Ok(fhir::Sort::Real)
+    } else if let Some(data_decl) = map.data_decl_by_name(sort.name) {
+        Ok(fhir::Sort::Data(data_decl.def_id))
+    } else if map.is_opaque_sort(sort.name) {
+        Ok(fhir::Sort::User(sort.name))
     } else {
         Err(sess.emit_err(errors::UnresolvedSort::new(sort)))
     }
@@ -657,11 +944,31 @@ fn resolve_base_sort(
 
 impl Binders {
     fn new() -> Binders {
-        Binders { name_gen: IndexGen::new(), map: FxIndexMap::default() }
+        Binders {
+            name_gen: IndexGen::new(),
+            map: FxIndexMap::default(),
+            const_params: FxHashMap::default(),
+        }
+    }
+
+    /// Brings into scope the const generic parameters of the item `def_id`, including those
+    /// of its parents, e.g., the `impl` block of a method.
+    fn gather_const_params(&mut self, tcx: TyCtxt, def_id: DefId) {
+        let mut generics = Some(tcx.generics_of(def_id));
+        while let Some(g) = generics {
+            for param in &g.params {
+                if let GenericParamDefKind::Const { .. } = param.kind {
+                    let param_const = fhir::ParamConst { index: param.index, name: param.name };
+                    self.const_params.insert(param.name, param_const);
+                }
+            }
+            generics = g.parent.map(|parent| tcx.generics_of(parent));
+        }
     }
 
     fn from_params<'a>(
         sess: &FluxSession,
+        map: &fhir::Map,
         params: impl IntoIterator<Item = &'a surface::RefineParam>,
     ) -> Result<Self, ErrorGuaranteed> {
         let mut binders = Self::new();
@@ -669,7 +976,7 @@ impl Binders {
             binders.insert_binder(
                 sess,
                 param.name,
-                Binder::Single(binders.fresh(), resolve_sort(sess, &param.sort)?),
+                Binder::Single(binders.fresh(), resolve_sort(sess, map, &param.sort)?),
             )?;
         }
         Ok(binders)
@@ -787,7 +1094,7 @@ impl Binders {
             self.insert_binder(
                 sess,
                 param.name,
-                Binder::Single(self.fresh(), resolve_sort(sess, &param.sort)?),
+                Binder::Single(self.fresh(), resolve_sort(sess, map, &param.sort)?),
             )?;
         }
         for arg in &fn_sig.args {
@@ -833,7 +1140,7 @@ impl Binders {
     ) -> Result<(), ErrorGuaranteed> {
         match &ty.kind {
             surface::TyKind::Indexed { path, indices } => {
-                let binder = Binder::from_res(&self.name_gen, map, path.ident);
+                let binder = Binder::from_refined_res(&self.name_gen, map, path.ident);
                 if bind.is_some() {
                     // This code is currently not reachable because the parser won't allow it as it conflicts with alias
                     // applications. If we ever allow this we should think about the meaning of the syntax `x: T[@n]` and
@@ -878,10 +1185,17 @@ impl Binders {
                 }
                 self.path_gather_params(tcx, sess, map, path, allow_binder)
             }
-            surface::TyKind::Array(ty, _) | surface::TyKind::Slice(ty) => {
+            surface::TyKind::Array(ty, len) | surface::TyKind::Slice(ty, len) => {
                 if let Some(bind) = bind {
                     self.insert_binder(sess, bind, Binder::Unrefined)?;
                 }
+                if let surface::ArrayLen::Arg(surface::RefineArg::Bind(ident, span)) = len {
+                    if !allow_binder {
+                        return Err(sess.emit_err(errors::IllegalBinder::new(*span)));
+                    }
+                    let binder = Binder::Single(self.name_gen.fresh(), fhir::Sort::Int);
+                    self.insert_binder(sess, *ident, binder)?;
+                }
                 self.ty_gather_params(tcx, sess, map, None, ty, false)
             }
             surface::TyKind::Ref(_, ty) | surface::TyKind::Constr(_, ty) => {
@@ -899,16 +1213,32 @@ impl Binders {
                 }
                 Ok(())
             }
+            surface::TyKind::FnPtr(inputs, output) => {
+                if let Some(bind) = bind {
+                    self.insert_binder(sess, bind, Binder::Unrefined)?;
+                }
+                // Function pointers don't have refinement parameters, so binders are not allowed.
+                for ty in inputs.iter().chain([&**output]) {
+                    self.ty_gather_params(tcx, sess, map, None, ty, false)?;
+                }
+                Ok(())
+            }
             surface::TyKind::Exists { path, .. } => {
                 if let Some(bind) = bind {
                     self.insert_binder(
                         sess,
                         bind,
-                        Binder::from_res(&self.name_gen, map, path.ident),
+                        Binder::from_refined_res(&self.name_gen, map, path.ident),
                     )?;
                 }
                 self.path_gather_params(tcx, sess, map, path, false)
             }
+            surface::TyKind::ImplTrait(_) => {
+                if let Some(bind) = bind {
+                    self.insert_binder(sess, bind, Binder::Unrefined)?;
+                }
+                Ok(())
+            }
         }
     }
 
@@ -985,6 +1315,18 @@ fn desugar_bin_op(op: surface::BinOp) -> fhir::BinOp {
         surface::BinOp::Sub => fhir::BinOp::Sub,
         surface::BinOp::Mod => fhir::BinOp::Mod,
         surface::BinOp::Mul => fhir::BinOp::Mul,
+        surface::BinOp::BitAnd => fhir::BinOp::BitAnd,
+        surface::BinOp::BitOr => fhir::BinOp::BitOr,
+        surface::BinOp::BitXor => fhir::BinOp::BitXor,
+        surface::BinOp::Shl => fhir::BinOp::Shl,
+        surface::BinOp::Shr => fhir::BinOp::Shr,
+    }
+}
+
+fn desugar_quantifier(quant: surface::Quantifier) -> fhir::Quantifier {
+    match quant {
+        surface::Quantifier::Forall => fhir::Quantifier::Forall,
+        surface::Quantifier::Exists => fhir::Quantifier::Exists,
     }
 }
 
@@ -992,7 +1334,15 @@ impl Binder {
     fn from_res(name_gen: &IndexGen<fhir::Name>, map: &fhir::Map, res: surface::Res) -> Binder {
         match res {
             Res::Bool => Binder::Single(name_gen.fresh(), fhir::Sort::Bool),
-            Res::Int(_) | Res::Uint(_) => Binder::Single(name_gen.fresh(), fhir::Sort::Int),
+            Res::Int(_) | Res::Uint(_) | Res::Str | Res::Char => {
+                Binder::Single(name_gen.fresh(), fhir::Sort::Int)
+            }
+            Res::Float(_) if map.real_floats() => {
+                Binder::Single(name_gen.fresh(), fhir::Sort::Real)
+            }
+            Res::Adt(def_id) if map.data_decl(def_id).is_some() => {
+                Binder::Single(name_gen.fresh(), fhir::Sort::Data(def_id))
+            }
             Res::Adt(def_id) => {
                 let fields: FxIndexMap<_, _> = map
                     .refined_by(def_id)
@@ -1005,7 +1355,24 @@ impl Binder {
                     .collect();
                 Binder::Aggregate(def_id, fields)
             }
-            Res::Float(_) | Res::Param(_) | Res::Str | Res::Char => Binder::Unrefined,
+            Res::Float(_)
+            | Res::Param(_)
+            | Res::ConstParam(_)
+            | Res::Opaque(_)
+            | Res::Projection(..) => Binder::Unrefined,
+        }
+    }
+
+    /// The binder for a path that is explicitly refined. See [`DesugarCtxt::desugar_refined_path`].
+    fn from_refined_res(
+        name_gen: &IndexGen<fhir::Name>,
+        map: &fhir::Map,
+        res: surface::Res,
+    ) -> Binder {
+        if let Res::Param(_) = res {
+            Binder::Single(name_gen.fresh(), fhir::Sort::Int)
+        } else {
+            Binder::from_res(name_gen, map, res)
         }
     }
 
@@ -1032,10 +1399,11 @@ fn to_src_info(ident: surface::Ident) -> fhir::SourceInfo {
 
 struct Sorts {
     int: Symbol,
+    real: Symbol,
 }
 
 static SORTS: std::sync::LazyLock<Sorts> =
-    std::sync::LazyLock::new(|| Sorts { int: Symbol::intern("int") });
+    std::sync::LazyLock::new(|| Sorts { int: Symbol::intern("int"), real: Symbol::intern("real") });
 
 mod errors {
     use flux_macros::{Diagnostic, Subdiagnostic};
@@ -1244,4 +1612,12 @@ mod errors {
             Self { span }
         }
     }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::invalid_impl_trait, code = "FLUX")]
+    pub struct InvalidImplTrait {
+        #[primary_span]
+        #[label]
+        pub span: Span,
+    }
 }
@@ -1,30 +1,36 @@
 #![feature(rustc_private)]
 #![feature(min_specialization)]
-#![feature(box_patterns, once_cell)]
+#![feature(box_patterns, once_cell, if_let_guard, let_chains)]
 
 extern crate rustc_data_structures;
 extern crate rustc_errors;
 extern crate rustc_hash;
 extern crate rustc_hir;
+extern crate rustc_hir_analysis;
 extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_span;
 
 mod annot_check;
 mod desugar;
+mod reflect;
 mod table_resolver;
 
-pub use desugar::{desugar_adt_def, desugar_qualifier, resolve_uif_def};
+pub use desugar::{
+    desugar_adt_def, desugar_axiom, desugar_defn, desugar_qualifier, resolve_uif_def,
+};
 use flux_errors::FluxSession;
 use flux_middle::fhir;
 use flux_syntax::surface::{self, TyCtxt};
+pub use reflect::{reflect_adts, reflect_fns, ReflectedAdt, ReflectedFn};
 use rustc_errors::ErrorGuaranteed;
-use rustc_hir::def_id::LocalDefId;
+use rustc_hir::def_id::{DefId, LocalDefId};
 
 pub fn desugar_struct_def(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    adt_def_id: DefId,
     struct_def: surface::StructDef,
 ) -> Result<fhir::StructDef, ErrorGuaranteed> {
     // Resolve
@@ -32,16 +38,17 @@ pub fn desugar_struct_def(
     let struct_def = resolver.resolve_struct_def(struct_def)?;
 
     // Check
-    annot_check::check_struct_def(tcx, sess, &struct_def)?;
+    annot_check::check_struct_def(tcx, sess, adt_def_id, &struct_def)?;
 
     // Desugar
-    desugar::desugar_struct_def(tcx, sess, map, struct_def)
+    desugar::desugar_struct_def(tcx, sess, map, adt_def_id, struct_def)
 }
 
 pub fn desugar_enum_def(
     tcx: TyCtxt,
     sess: &FluxSession,
     map: &fhir::Map,
+    adt_def_id: DefId,
     enum_def: surface::EnumDef,
 ) -> Result<fhir::EnumDef, ErrorGuaranteed> {
     // Resolve
@@ -49,10 +56,28 @@ pub fn desugar_enum_def(
     let enum_def = resolver.resolve_enum_def(enum_def)?;
 
     // Check
-    annot_check::check_enum_def(tcx, sess, &enum_def)?;
+    annot_check::check_enum_def(tcx, sess, adt_def_id, &enum_def)?;
 
     // Desugar
-    desugar::desugar_enum_def(tcx, sess, map, enum_def)
+    desugar::desugar_enum_def(tcx, sess, map, adt_def_id, enum_def)
+}
+
+pub fn desugar_static_ty(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    map: &fhir::Map,
+    def_id: LocalDefId,
+    ty: surface::Ty,
+) -> Result<fhir::Ty, ErrorGuaranteed> {
+    // Resolve
+    let resolver = table_resolver::Resolver::new(tcx, sess, def_id)?;
+    let ty = resolver.resolve_static_ty(ty)?;
+
+    // Check
+    annot_check::check_static_ty(tcx, sess, def_id.to_def_id(), &ty)?;
+
+    // Desugar
+    desugar::desugar_static_ty(tcx, sess, map, ty)
 }
 
 pub fn desugar_fn_sig(
@@ -70,5 +95,5 @@ pub fn desugar_fn_sig(
     annot_check::check_fn_sig(tcx, sess, def_id.to_def_id(), &fn_sig)?;
 
     // Desugar
-    desugar::desugar_fn_sig(tcx, sess, map, fn_sig)
+    desugar::desugar_fn_sig(tcx, sess, map, def_id.to_def_id(), fn_sig)
 }
@@ -0,0 +1,582 @@
+//! Reflection of pure rust functions into the refinement logic.
+//!
+//! A function annotated with `#[flux::reflect]` is translated into a [`fhir::Defn`] by
+//! symbolically executing its mir. Every path from the entry block to a `return` must be loop
+//! free, so the result is a tree of if-then-else expressions over the arguments. Only functions
+//! taking and returning integers and booleans are supported, and their body can only use
+//! arithmetic, comparisons, branches and calls to other reflected functions. Overflow checks and
+//! other assertions are ignored, i.e., the definition describes the value returned by the
+//! function when it doesn't panic.
+//!
+//! Besides the definition, we generate the signature `fn(T[@a0], ..) -> R[f(a0, ..)]` which is
+//! used for functions without an explicit `#[flux::sig]`.
+//!
+//! A struct or enum annotated with `#[flux::reflect]` is translated into a datatype sort
+//! ([`fhir::DataDecl`]) with one constructor per variant. The type is refined by a single index of
+//! that sort and every variant gets the signature `fn(T0[@a0], ..) -> S[Ctor(a0, ..)]`, i.e., a
+//! value of the type is indexed by the value of the sort it denotes.
+use flux_common::{index::IndexGen, iter::IterExt};
+use flux_errors::FluxSession;
+use flux_middle::{
+    fhir,
+    rustc::{
+        mir::{
+            BasicBlock, BinOp, Body, Constant, Operand, Place, PlaceElem, Rvalue, StatementKind,
+            TerminatorKind, UnOp, RETURN_PLACE, START_BLOCK,
+        },
+        ty::{Ty, TyKind},
+    },
+};
+use itertools::Itertools;
+use rustc_data_structures::fx::{FxIndexMap, FxIndexSet};
+use rustc_errors::ErrorGuaranteed;
+use rustc_hash::FxHashMap;
+use rustc_hir::def_id::LocalDefId;
+use rustc_middle::{
+    mir::Local,
+    ty::{self, TyCtxt},
+};
+use rustc_span::{symbol::kw, Span, Symbol};
+
+pub struct ReflectedFn {
+    pub def_id: LocalDefId,
+    pub defn: fhir::Defn,
+    pub fn_sig: fhir::FnSig,
+}
+
+/// Reflects the functions in `bodies`. The definitions are returned in an order where every
+/// function comes after the reflected functions it calls, which is the order in which they need
+/// to be registered.
+pub fn reflect_fns<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    sess: &FluxSession,
+    bodies: &FxIndexMap<LocalDefId, Body<'tcx>>,
+) -> Result<Vec<ReflectedFn>, ErrorGuaranteed> {
+    let mut reflected = FxIndexMap::default();
+    for (def_id, body) in bodies {
+        let (reflected_fn, callees) =
+            ReflectCtxt::new(tcx, sess, bodies, *def_id, body).reflect()?;
+        reflected.insert(*def_id, (reflected_fn, callees));
+    }
+
+    let mut order = FxIndexSet::default();
+    let mut stack = vec![];
+    for def_id in bodies.keys() {
+        sort_callees_first(tcx, sess, &reflected, *def_id, &mut stack, &mut order)?;
+    }
+    Ok(order
+        .into_iter()
+        .map(|def_id| reflected.remove(&def_id).unwrap().0)
+        .collect())
+}
+
+fn sort_callees_first(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    reflected: &FxIndexMap<LocalDefId, (ReflectedFn, Vec<LocalDefId>)>,
+    def_id: LocalDefId,
+    stack: &mut Vec<LocalDefId>,
+    order: &mut FxIndexSet<LocalDefId>,
+) -> Result<(), ErrorGuaranteed> {
+    if order.contains(&def_id) {
+        return Ok(());
+    }
+    if stack.contains(&def_id) {
+        return Err(sess.emit_err(errors::RecursiveReflect::new(tcx, def_id)));
+    }
+    stack.push(def_id);
+    for callee in &reflected[&def_id].1 {
+        sort_callees_first(tcx, sess, reflected, *callee, stack, order)?;
+    }
+    stack.pop();
+    order.insert(def_id);
+    Ok(())
+}
+
+pub struct ReflectedAdt {
+    pub data_decl: fhir::DataDecl,
+    pub adt_def: fhir::AdtDef,
+    pub variants: fhir::EnumDef,
+}
+
+/// Reflects the structs and enums in `def_ids`. The fields of a reflected type can only be
+/// integers, booleans, or other reflected types.
+pub fn reflect_adts(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    def_ids: &[LocalDefId],
+) -> Result<Vec<ReflectedAdt>, ErrorGuaranteed> {
+    def_ids
+        .iter()
+        .map(|def_id| reflect_adt(tcx, sess, def_ids, *def_id))
+        .try_collect_exhaust()
+}
+
+fn reflect_adt(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    reflected: &[LocalDefId],
+    def_id: LocalDefId,
+) -> Result<ReflectedAdt, ErrorGuaranteed> {
+    let span = tcx.def_span(def_id);
+    if tcx.generics_of(def_id).count() > 0 {
+        return Err(sess.emit_err(errors::GenericAdt { span }));
+    }
+    let adt_def = tcx.adt_def(def_id);
+    let def_id = def_id.to_def_id();
+    let name_gen = IndexGen::new();
+
+    let mut ctors = vec![];
+    let mut variants = vec![];
+    for (variant_idx, variant_def) in adt_def.variants().iter_enumerated() {
+        let mut fields = vec![];
+        let mut params = vec![];
+        let mut field_tys = vec![];
+        for field in &variant_def.fields {
+            let span = tcx.def_span(field.did);
+            let (sort, bty) = match tcx.type_of(field.did).kind() {
+                ty::Int(int_ty) => (fhir::Sort::Int, fhir::BaseTy::Int(*int_ty)),
+                ty::Uint(uint_ty) => (fhir::Sort::Int, fhir::BaseTy::Uint(*uint_ty)),
+                ty::Bool => (fhir::Sort::Bool, fhir::BaseTy::Bool),
+                ty::Adt(adt_def, _)
+                    if adt_def
+                        .did()
+                        .as_local()
+                        .map_or(false, |did| reflected.contains(&did)) =>
+                {
+                    (fhir::Sort::Data(adt_def.did()), fhir::BaseTy::Adt(adt_def.did(), vec![]))
+                }
+                _ => return Err(sess.emit_err(errors::UnsupportedField { span })),
+            };
+            let name = name_gen.fresh();
+            fields.push((field.name, sort.clone()));
+            params.push(fhir::RefineParam {
+                name: fhir::Ident { name, source_info: (span, field.name) },
+                sort,
+            });
+            field_tys.push(fhir::Ty::Indexed(
+                bty,
+                fhir::Indices {
+                    indices: vec![fhir::RefineArg::Expr {
+                        expr: mk_var(name, field.name, span),
+                        is_binder: true,
+                    }],
+                    span,
+                },
+            ));
+        }
+        let args = params
+            .iter()
+            .map(|param| {
+                mk_var(param.name.name, param.name.source_info.1, param.name.source_info.0)
+            })
+            .collect();
+        let ctor = fhir::DataFunc::Ctor(def_id, variant_idx);
+        let ret = fhir::VariantRet {
+            bty: fhir::BaseTy::Adt(def_id, vec![]),
+            indices: fhir::Indices {
+                indices: vec![fhir::RefineArg::Expr {
+                    expr: fhir::Expr {
+                        kind: fhir::ExprKind::App(fhir::Func::Data(ctor), args),
+                        span,
+                    },
+                    is_binder: false,
+                }],
+                span,
+            },
+        };
+        ctors.push(fhir::DataCtor { name: variant_def.name, fields });
+        variants.push(fhir::VariantDef { params, fields: field_tys, ret });
+    }
+
+    let data_decl = fhir::DataDecl { def_id, name: tcx.item_name(def_id), ctors };
+    let refined_by = fhir::RefinedBy {
+        params: vec![fhir::RefineParam {
+            name: fhir::Ident { name: name_gen.fresh(), source_info: (span, kw::SelfLower) },
+            sort: fhir::Sort::Data(def_id),
+        }],
+        span,
+    };
+    let adt_def = fhir::AdtDef::new(def_id, refined_by, vec![], false);
+    Ok(ReflectedAdt { data_decl, adt_def, variants: fhir::EnumDef { def_id, variants } })
+}
+
+struct ReflectCtxt<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    sess: &'a FluxSession,
+    bodies: &'a FxIndexMap<LocalDefId, Body<'tcx>>,
+    def_id: LocalDefId,
+    body: &'a Body<'tcx>,
+    name_gen: IndexGen<fhir::Name>,
+    /// The reflected functions called in the body
+    callees: Vec<LocalDefId>,
+}
+
+type Env = FxHashMap<Local, fhir::Expr>;
+
+impl<'a, 'tcx> ReflectCtxt<'a, 'tcx> {
+    fn new(
+        tcx: TyCtxt<'tcx>,
+        sess: &'a FluxSession,
+        bodies: &'a FxIndexMap<LocalDefId, Body<'tcx>>,
+        def_id: LocalDefId,
+        body: &'a Body<'tcx>,
+    ) -> Self {
+        Self { tcx, sess, bodies, def_id, body, name_gen: IndexGen::new(), callees: vec![] }
+    }
+
+    fn reflect(mut self) -> Result<(ReflectedFn, Vec<LocalDefId>), ErrorGuaranteed> {
+        let span = self.tcx.def_span(self.def_id);
+        let name = self.tcx.item_name(self.def_id.to_def_id());
+
+        let mut env = Env::default();
+        let mut params = vec![];
+        let mut args = vec![];
+        let arg_names = self.tcx.fn_arg_names(self.def_id.to_def_id());
+        for (local, arg_name) in self.body.args_iter().zip(arg_names) {
+            let (sort, bty) = self.reflected_ty(&self.body.local_decls[local].ty)?;
+            let fresh = self.name_gen.fresh();
+            let var = || mk_var(fresh, arg_name.name, arg_name.span);
+            env.insert(local, var());
+            args.push(fhir::Ty::Indexed(
+                bty,
+                fhir::Indices {
+                    indices: vec![fhir::RefineArg::Expr { expr: var(), is_binder: true }],
+                    span: arg_name.span,
+                },
+            ));
+            params.push((fresh, arg_name.name, arg_name.span, sort));
+        }
+        let (output, ret_bty) = self.reflected_ty(&self.body.local_decls[RETURN_PLACE].ty)?;
+
+        let expr = self.reflect_block(START_BLOCK, env, &mut vec![])?;
+
+        let mk_params = || {
+            params
+                .iter()
+                .map(|(name, sym, span, sort)| {
+                    fhir::RefineParam {
+                        name: fhir::Ident { name: *name, source_info: (*span, *sym) },
+                        sort: sort.clone(),
+                    }
+                })
+                .collect_vec()
+        };
+        let ret_args = params
+            .iter()
+            .map(|(name, sym, span, _)| mk_var(*name, *sym, *span))
+            .collect();
+        let ret = fhir::Ty::Indexed(
+            ret_bty,
+            fhir::Indices {
+                indices: vec![fhir::RefineArg::Expr {
+                    expr: fhir::Expr {
+                        kind: fhir::ExprKind::App(fhir::Func::Uif(name, span), ret_args),
+                        span,
+                    },
+                    is_binder: false,
+                }],
+                span,
+            },
+        );
+        let fn_sig =
+            fhir::FnSig { params: mk_params(), requires: vec![], args, ret, ensures: vec![] };
+
+        let sort =
+            fhir::FuncSort::new(params.iter().map(|param| param.3.clone()).collect(), output);
+        let defn = fhir::Defn { name, args: mk_params(), sort, expr };
+
+        Ok((ReflectedFn { def_id: self.def_id, defn, fn_sig }, self.callees))
+    }
+
+    fn reflected_ty(&self, ty: &Ty) -> Result<(fhir::Sort, fhir::BaseTy), ErrorGuaranteed> {
+        match ty.kind() {
+            TyKind::Int(int_ty) => Ok((fhir::Sort::Int, fhir::BaseTy::Int(*int_ty))),
+            TyKind::Uint(uint_ty) => Ok((fhir::Sort::Int, fhir::BaseTy::Uint(*uint_ty))),
+            TyKind::Bool => Ok((fhir::Sort::Bool, fhir::BaseTy::Bool)),
+            _ => {
+                Err(self.sess.emit_err(errors::UnsupportedSignature {
+                    span: self.tcx.def_span(self.def_id),
+                }))
+            }
+        }
+    }
+
+    /// Returns the value returned by the function when execution reaches `bb` with the values of
+    /// locals in `env`. The `path` contains the blocks visited to reach `bb` which we use to
+    /// reject loops.
+    fn reflect_block(
+        &mut self,
+        bb: BasicBlock,
+        mut env: Env,
+        path: &mut Vec<BasicBlock>,
+    ) -> Result<fhir::Expr, ErrorGuaranteed> {
+        let data = &self.body.basic_blocks[bb];
+        let terminator = data.terminator.as_ref().unwrap();
+        let span = terminator.source_info.span;
+        if path.contains(&bb) {
+            return Err(self.sess.emit_err(errors::UnsupportedLoop { span }));
+        }
+
+        for stmt in &data.statements {
+            let span = stmt.source_info.span;
+            match &stmt.kind {
+                // Unit values are produced by blocks used as statements and never reach the result
+                StatementKind::Assign(_, Rvalue::Use(Operand::Constant(Constant::Unit))) => {}
+                StatementKind::Assign(place, rvalue) => {
+                    if !place.projection.is_empty() {
+                        return Err(self.emit_unsupported(span));
+                    }
+                    let expr = self.reflect_rvalue(&env, rvalue, span)?;
+                    env.insert(place.local, expr);
+                }
+                StatementKind::FakeRead(_)
+                | StatementKind::AscribeUserType(..)
+                | StatementKind::Nop => {}
+                StatementKind::SetDiscriminant(..) => return Err(self.emit_unsupported(span)),
+            }
+        }
+
+        path.push(bb);
+        let expr = match &terminator.kind {
+            TerminatorKind::Return => {
+                env.remove(&RETURN_PLACE)
+                    .ok_or_else(|| self.emit_unsupported(span))?
+            }
+            TerminatorKind::Goto { target }
+            | TerminatorKind::Drop { target, .. }
+            | TerminatorKind::Assert { target, .. }
+            | TerminatorKind::FalseEdge { real_target: target, .. }
+            | TerminatorKind::FalseUnwind { real_target: target, .. } => {
+                self.reflect_block(*target, env, path)?
+            }
+            TerminatorKind::SwitchInt { discr, targets } => {
+                let discr_ty = self.operand_ty(discr);
+                let discr = self.reflect_operand(&env, discr, span)?;
+                let mut expr = self.reflect_block(targets.otherwise(), env.clone(), path)?;
+                for (bits, target) in targets.iter().collect_vec().into_iter().rev() {
+                    let then = self.reflect_block(target, env.clone(), path)?;
+                    expr = match discr_ty.map(Ty::kind) {
+                        Some(TyKind::Bool) if bits == 0 => mk_ite(discr.clone(), expr, then, span),
+                        kind => {
+                            let lit = mk_lit(fhir::Lit::Int(bits_to_int(kind, bits)), span);
+                            let cond = mk_binary_op(fhir::BinOp::Eq, discr.clone(), lit, span);
+                            mk_ite(cond, then, expr, span)
+                        }
+                    };
+                }
+                expr
+            }
+            TerminatorKind::Call { func, args, destination, target: Some(target), .. } => {
+                let Some(callee) = func.as_local().filter(|callee| self.bodies.contains_key(callee))
+                    else { return Err(self.emit_unsupported(span)) };
+                if !destination.projection.is_empty() {
+                    return Err(self.emit_unsupported(span));
+                }
+                let args = args
+                    .iter()
+                    .map(|arg| self.reflect_operand(&env, arg, span))
+                    .try_collect()?;
+                let name = self.tcx.item_name(callee.to_def_id());
+                let kind = fhir::ExprKind::App(fhir::Func::Uif(name, span), args);
+                env.insert(destination.local, fhir::Expr { kind, span });
+                self.callees.push(callee);
+                self.reflect_block(*target, env, path)?
+            }
+            _ => return Err(self.emit_unsupported(span)),
+        };
+        path.pop();
+        Ok(expr)
+    }
+
+    fn reflect_rvalue(
+        &self,
+        env: &Env,
+        rvalue: &Rvalue,
+        span: Span,
+    ) -> Result<fhir::Expr, ErrorGuaranteed> {
+        match rvalue {
+            Rvalue::Use(op) => self.reflect_operand(env, op, span),
+            // The overflow flag of a checked operation is only used in assertions, which we
+            // ignore, so we store the result directly in the local holding the tuple.
+            Rvalue::BinaryOp(bin_op, op1, op2) | Rvalue::CheckedBinaryOp(bin_op, op1, op2) => {
+                let is_bool = self.is_bool(op1);
+                let bin_op = match bin_op {
+                    BinOp::Gt => fhir::BinOp::Gt,
+                    BinOp::Ge => fhir::BinOp::Ge,
+                    BinOp::Lt => fhir::BinOp::Lt,
+                    BinOp::Le => fhir::BinOp::Le,
+                    BinOp::Eq if is_bool => fhir::BinOp::Iff,
+                    BinOp::Eq => fhir::BinOp::Eq,
+                    BinOp::Ne => fhir::BinOp::Ne,
+                    BinOp::Add => fhir::BinOp::Add,
+                    BinOp::Sub => fhir::BinOp::Sub,
+                    BinOp::Mul => fhir::BinOp::Mul,
+                    BinOp::Div => fhir::BinOp::Div,
+                    BinOp::Rem => fhir::BinOp::Mod,
+                    BinOp::BitAnd if is_bool => fhir::BinOp::And,
+                    BinOp::BitOr if is_bool => fhir::BinOp::Or,
+                    BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr => {
+                        return Err(self.emit_unsupported(span));
+                    }
+                };
+                let e1 = self.reflect_operand(env, op1, span)?;
+                let e2 = self.reflect_operand(env, op2, span)?;
+                Ok(mk_binary_op(bin_op, e1, e2, span))
+            }
+            Rvalue::UnaryOp(UnOp::Not, op) if self.is_bool(op) => {
+                let e = self.reflect_operand(env, op, span)?;
+                Ok(mk_ite(
+                    e,
+                    mk_lit(fhir::Lit::Bool(false), span),
+                    mk_lit(fhir::Lit::Bool(true), span),
+                    span,
+                ))
+            }
+            Rvalue::UnaryOp(UnOp::Neg, op) => {
+                let e = self.reflect_operand(env, op, span)?;
+                Ok(mk_binary_op(fhir::BinOp::Sub, mk_lit(fhir::Lit::Int(0), span), e, span))
+            }
+            _ => Err(self.emit_unsupported(span)),
+        }
+    }
+
+    fn reflect_operand(
+        &self,
+        env: &Env,
+        op: &Operand,
+        span: Span,
+    ) -> Result<fhir::Expr, ErrorGuaranteed> {
+        match op {
+            Operand::Copy(place) | Operand::Move(place) => self.reflect_place(env, place, span),
+            Operand::Constant(Constant::Int(n, _)) => Ok(mk_lit(fhir::Lit::Int(*n), span)),
+            Operand::Constant(Constant::Uint(n, _)) => {
+                let n = i128::try_from(*n).map_err(|_| self.emit_unsupported(span))?;
+                Ok(mk_lit(fhir::Lit::Int(n), span))
+            }
+            Operand::Constant(Constant::Bool(b)) => Ok(mk_lit(fhir::Lit::Bool(*b), span)),
+            Operand::Constant(_) => Err(self.emit_unsupported(span)),
+        }
+    }
+
+    fn reflect_place(
+        &self,
+        env: &Env,
+        place: &Place,
+        span: Span,
+    ) -> Result<fhir::Expr, ErrorGuaranteed> {
+        // The only tuples we know about are the results of checked operations, whose first field
+        // is the result stored in `env`.
+        let supported = match &place.projection[..] {
+            [] => true,
+            [PlaceElem::Field(field)] => field.as_u32() == 0,
+            _ => false,
+        };
+        match env.get(&place.local) {
+            Some(expr) if supported => Ok(expr.clone()),
+            _ => Err(self.emit_unsupported(span)),
+        }
+    }
+
+    fn operand_ty(&self, op: &Operand) -> Option<&'a Ty> {
+        match op {
+            Operand::Copy(place) | Operand::Move(place) if place.projection.is_empty() => {
+                Some(&self.body.local_decls[place.local].ty)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_bool(&self, op: &Operand) -> bool {
+        matches!(op, Operand::Constant(Constant::Bool(_)))
+            || matches!(self.operand_ty(op).map(Ty::kind), Some(TyKind::Bool))
+    }
+
+    fn emit_unsupported(&self, span: Span) -> ErrorGuaranteed {
+        self.sess.emit_err(errors::UnsupportedOperation { span })
+    }
+}
+
+/// Interprets the bits of a `SwitchInt` target according to the type of the discriminant.
+fn bits_to_int(kind: Option<&TyKind>, bits: u128) -> i128 {
+    match kind {
+        Some(TyKind::Int(int_ty)) => {
+            let shift = 128 - int_ty.bit_width().unwrap_or(64);
+            ((bits << shift) as i128) >> shift
+        }
+        _ => bits as i128,
+    }
+}
+
+fn mk_var(name: fhir::Name, sym: Symbol, span: Span) -> fhir::Expr {
+    fhir::Expr { kind: fhir::ExprKind::Var(name, sym, span), span }
+}
+
+fn mk_lit(lit: fhir::Lit, span: Span) -> fhir::Expr {
+    fhir::Expr { kind: fhir::ExprKind::Literal(lit), span }
+}
+
+fn mk_binary_op(op: fhir::BinOp, e1: fhir::Expr, e2: fhir::Expr, span: Span) -> fhir::Expr {
+    fhir::Expr { kind: fhir::ExprKind::BinaryOp(op, Box::new([e1, e2])), span }
+}
+
+fn mk_ite(p: fhir::Expr, e1: fhir::Expr, e2: fhir::Expr, span: Span) -> fhir::Expr {
+    fhir::Expr { kind: fhir::ExprKind::IfThenElse(Box::new([p, e1, e2])), span }
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_hir::def_id::LocalDefId;
+    use rustc_middle::ty::TyCtxt;
+    use rustc_span::{Span, Symbol};
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_unsupported_signature, code = "FLUX")]
+    pub struct UnsupportedSignature {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_unsupported_operation, code = "FLUX")]
+    #[note]
+    pub struct UnsupportedOperation {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_unsupported_loop, code = "FLUX")]
+    pub struct UnsupportedLoop {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_generic_adt, code = "FLUX")]
+    pub struct GenericAdt {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_unsupported_field, code = "FLUX")]
+    pub struct UnsupportedField {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(desugar::reflect_recursive, code = "FLUX")]
+    pub struct RecursiveReflect {
+        #[primary_span]
+        span: Span,
+        name: Symbol,
+    }
+
+    impl RecursiveReflect {
+        pub fn new(tcx: TyCtxt, def_id: LocalDefId) -> Self {
+            Self { span: tcx.def_span(def_id), name: tcx.item_name(def_id.to_def_id()) }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use flux_common::iter::IterExt;
 use flux_errors::FluxSession;
 use flux_syntax::surface::{self, Ident, Path, Res, Ty};
@@ -5,17 +7,29 @@ use hir::{def_id::DefId, ItemKind};
 use rustc_errors::ErrorGuaranteed;
 use rustc_hash::FxHashMap;
 use rustc_hir::{self as hir, def_id::LocalDefId};
-use rustc_middle::ty::{ParamTy, TyCtxt, TyKind};
-use rustc_span::{Span, Symbol};
+use rustc_middle::ty::{DefIdTree, ParamConst, ParamTy, TyCtxt, TyKind};
+use rustc_span::{
+    symbol::{kw, sym},
+    Span, Symbol,
+};
 
 pub struct Resolver<'genv, 'tcx> {
     sess: &'genv FluxSession,
     table: NameResTable<'genv, 'tcx>,
+    /// Index of the next `impl Trait` to resolve, see [`NameResTable::impl_traits`]
+    next_impl_trait: Cell<usize>,
 }
 
 struct NameResTable<'sess, 'tcx> {
     res: FxHashMap<Symbol, Res>,
     generics: FxHashMap<DefId, ParamTy>,
+    /// The name of the trait bounding each synthetic type parameter, i.e., each `impl Trait` in
+    /// argument position.
+    synthetic: FxHashMap<DefId, Symbol>,
+    /// `impl Trait` types don't have a name, so we collect them in the order they appear in the
+    /// signature together with the name of their trait.
+    impl_traits: Vec<(Symbol, Res)>,
+    owner: LocalDefId,
     sess: &'sess FluxSession,
     tcx: TyCtxt<'tcx>,
 }
@@ -26,15 +40,8 @@ impl<'sess, 'tcx> Resolver<'sess, 'tcx> {
         sess: &'sess FluxSession,
         def_id: LocalDefId,
     ) -> Result<Self, ErrorGuaranteed> {
-        let table = match tcx.def_kind(def_id) {
-            hir::def::DefKind::Struct | hir::def::DefKind::Enum | hir::def::DefKind::Fn => {
-                NameResTable::from_item(tcx, sess, def_id)?
-            }
-            hir::def::DefKind::AssocFn => NameResTable::from_impl_item(tcx, sess, def_id)?,
-            kind => panic!("unsupported kind {kind:?}"),
-        };
-
-        Ok(Self { sess, table })
+        let table = NameResTable::from_def_id(tcx, sess, def_id)?;
+        Ok(Self { sess, table, next_impl_trait: Cell::new(0) })
     }
 
     pub fn resolve_enum_def(
@@ -95,25 +102,31 @@ impl<'sess, 'tcx> Resolver<'sess, 'tcx> {
         })
     }
 
+    pub fn resolve_static_ty(&self, ty: Ty) -> Result<Ty<Res>, ErrorGuaranteed> {
+        self.resolve_ty(ty)
+    }
+
     #[allow(dead_code)]
     pub fn resolve_fn_sig(
         &self,
         fn_sig: surface::FnSig,
     ) -> Result<surface::FnSig<Res>, ErrorGuaranteed> {
+        // Arguments must be resolved before the return type so `impl Trait` types are resolved
+        // in the order they appear in the Rust signature.
         let args = fn_sig
             .args
             .into_iter()
             .map(|arg| self.resolve_arg(arg))
             .try_collect_exhaust();
 
+        let returns = fn_sig.returns.map(|ty| self.resolve_ty(ty)).transpose();
+
         let ensures = fn_sig
             .ensures
             .into_iter()
             .map(|(loc, ty)| Ok((loc, self.resolve_ty(ty)?)))
             .try_collect_exhaust();
 
-        let returns = fn_sig.returns.map(|ty| self.resolve_ty(ty)).transpose();
-
         Ok(surface::FnSig {
             params: fn_sig.params,
             requires: fn_sig.requires,
@@ -158,9 +171,9 @@ impl<'sess, 'tcx> Resolver<'sess, 'tcx> {
                 let ty = self.resolve_ty(*ty)?;
                 surface::TyKind::Array(Box::new(ty), len)
             }
-            surface::TyKind::Slice(ty) => {
+            surface::TyKind::Slice(ty, len) => {
                 let ty = self.resolve_ty(*ty)?;
-                surface::TyKind::Slice(Box::new(ty))
+                surface::TyKind::Slice(Box::new(ty), len)
             }
             surface::TyKind::Tuple(tys) => {
                 let tys = tys
@@ -169,10 +182,42 @@ impl<'sess, 'tcx> Resolver<'sess, 'tcx> {
                     .try_collect_exhaust()?;
                 surface::TyKind::Tuple(tys)
             }
+            surface::TyKind::FnPtr(inputs, output) => {
+                let inputs = inputs
+                    .into_iter()
+                    .map(|ty| self.resolve_ty(ty))
+                    .try_collect_exhaust()?;
+                let output = self.resolve_ty(*output)?;
+                surface::TyKind::FnPtr(inputs, Box::new(output))
+            }
+            surface::TyKind::ImplTrait(path) => {
+                surface::TyKind::ImplTrait(self.resolve_impl_trait(path)?)
+            }
         };
         Ok(surface::Ty { kind, span: ty.span })
     }
 
+    fn resolve_impl_trait(&self, path: Path) -> Result<Path<Res>, ErrorGuaranteed> {
+        if !path.args.is_empty() {
+            return Err(self.sess.emit_err(errors::UnsupportedSignature {
+                span: path.span,
+                note: "generic arguments in `impl Trait` are not supported".to_string(),
+            }));
+        }
+        let idx = self.next_impl_trait.get();
+        self.next_impl_trait.set(idx + 1);
+        match self.table.impl_traits.get(idx) {
+            Some((trait_name, res)) if *trait_name == path.ident.name => {
+                Ok(Path { ident: *res, args: vec![], span: path.span })
+            }
+            _ => {
+                Err(self
+                    .sess
+                    .emit_err(errors::UnresolvedImplTrait::new(path.ident)))
+            }
+        }
+    }
+
     fn resolve_path(&self, path: Path) -> Result<Path<Res>, ErrorGuaranteed> {
         let ident = self.resolve_ident(path.ident)?;
         let args = path
@@ -193,13 +238,32 @@ impl<'sess, 'tcx> Resolver<'sess, 'tcx> {
 }
 
 impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
+    fn from_def_id(
+        tcx: TyCtxt<'tcx>,
+        sess: &'sess FluxSession,
+        def_id: LocalDefId,
+    ) -> Result<Self, ErrorGuaranteed> {
+        match tcx.def_kind(def_id) {
+            hir::def::DefKind::Struct
+            | hir::def::DefKind::Enum
+            | hir::def::DefKind::Fn
+            | hir::def::DefKind::Static(_) => NameResTable::from_item(tcx, sess, def_id),
+            hir::def::DefKind::AssocFn if tcx.trait_of_item(def_id.to_def_id()).is_some() => {
+                NameResTable::from_trait_item(tcx, sess, def_id)
+            }
+            hir::def::DefKind::AssocFn => NameResTable::from_impl_item(tcx, sess, def_id),
+            hir::def::DefKind::Closure => NameResTable::from_closure(tcx, sess, def_id),
+            kind => panic!("unsupported kind {kind:?}"),
+        }
+    }
+
     fn from_item(
         tcx: TyCtxt<'tcx>,
         sess: &'sess FluxSession,
         def_id: LocalDefId,
     ) -> Result<Self, ErrorGuaranteed> {
         let item = tcx.hir().expect_item(def_id);
-        let mut table = Self::new(tcx, sess);
+        let mut table = Self::new(tcx, sess, def_id);
         match &item.kind {
             ItemKind::Struct(data, generics) => {
                 table.insert_generics(tcx, generics);
@@ -227,6 +291,9 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
                 table.insert_generics(tcx, generics);
                 table.collect_from_fn_sig(fn_sig)?;
             }
+            ItemKind::Static(ty, ..) => {
+                table.collect_from_ty(ty)?;
+            }
             _ => {}
         }
         Ok(table)
@@ -239,7 +306,7 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
     ) -> Result<Self, ErrorGuaranteed> {
         let impl_item = tcx.hir().expect_impl_item(def_id);
 
-        let mut table = Self::new(tcx, sess);
+        let mut table = Self::new(tcx, sess, def_id);
 
         // Insert generics from parent impl
         if let Some(parent_impl_did) = tcx.impl_of_method(def_id.to_def_id()) {
@@ -261,8 +328,80 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
         Ok(table)
     }
 
-    fn new(tcx: TyCtxt<'tcx>, sess: &'sess FluxSession) -> NameResTable<'sess, 'tcx> {
-        NameResTable { sess, res: FxHashMap::default(), generics: FxHashMap::default(), tcx }
+    fn from_trait_item(
+        tcx: TyCtxt<'tcx>,
+        sess: &'sess FluxSession,
+        def_id: LocalDefId,
+    ) -> Result<Self, ErrorGuaranteed> {
+        let trait_item = tcx.hir().expect_trait_item(def_id);
+
+        let mut table = Self::new(tcx, sess, def_id);
+
+        // Insert generics from parent trait
+        if let Some(parent_trait_did) = tcx.trait_of_item(def_id.to_def_id()) {
+            let parent_trait_item = tcx.hir().expect_item(parent_trait_did.expect_local());
+            if let ItemKind::Trait(_, _, generics, ..) = &parent_trait_item.kind {
+                table.insert_generics(tcx, generics);
+            }
+        }
+
+        table.insert_generics(tcx, trait_item.generics);
+        match &trait_item.kind {
+            rustc_hir::TraitItemKind::Fn(fn_sig, _) => {
+                table.collect_from_fn_sig(fn_sig)?;
+            }
+            rustc_hir::TraitItemKind::Const(_, _) | rustc_hir::TraitItemKind::Type(_, _) => {}
+        }
+
+        Ok(table)
+    }
+
+    /// A closure sees the names in scope of the item defining it. The types of its arguments are
+    /// usually omitted, so we also insert the names of the types in its inferred signature.
+    fn from_closure(
+        tcx: TyCtxt<'tcx>,
+        sess: &'sess FluxSession,
+        def_id: LocalDefId,
+    ) -> Result<Self, ErrorGuaranteed> {
+        let root_id = tcx.typeck_root_def_id(def_id.to_def_id()).expect_local();
+        let mut table = Self::from_def_id(tcx, sess, root_id)?;
+
+        let hir_id = tcx.hir().local_def_id_to_hir_id(def_id);
+        if let hir::ExprKind::Closure(closure) = tcx.hir().expect_expr(hir_id).kind {
+            closure
+                .fn_decl
+                .inputs
+                .iter()
+                .try_for_each_exhaust(|ty| table.collect_from_ty(ty))?;
+            if let hir::FnRetTy::Return(ty) = closure.fn_decl.output {
+                table.collect_from_ty(ty)?;
+            }
+        }
+
+        let TyKind::Closure(_, substs) = tcx.type_of(def_id).kind() else {
+            unreachable!("closure without a closure type")
+        };
+        let fn_sig = tcx.signature_unclosure(substs.as_closure().sig(), hir::Unsafety::Normal);
+        for ty in fn_sig.skip_binder().inputs_and_output {
+            table.collect_from_rustc_ty(ty);
+        }
+        Ok(table)
+    }
+
+    fn new(
+        tcx: TyCtxt<'tcx>,
+        sess: &'sess FluxSession,
+        owner: LocalDefId,
+    ) -> NameResTable<'sess, 'tcx> {
+        NameResTable {
+            sess,
+            res: FxHashMap::default(),
+            generics: FxHashMap::default(),
+            synthetic: FxHashMap::default(),
+            impl_traits: vec![],
+            owner,
+            tcx,
+        }
     }
 
     fn get(&self, sym: Symbol) -> Option<&Res> {
@@ -274,14 +413,33 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
     }
 
     fn insert_generics(&mut self, tcx: TyCtxt, generics: &hir::Generics) {
-        for (idx, param) in generics.params.iter().enumerate() {
-            if let hir::GenericParamKind::Type { .. } = param.kind {
-                let def_id = tcx.hir().local_def_id(param.hir_id).to_def_id();
-                assert!(!self.generics.contains_key(&def_id));
-
-                let name = param.name.ident().name;
-                let param_ty = ParamTy { index: idx as u32, name };
-                self.generics.insert(def_id, param_ty);
+        for param in generics.params {
+            let def_id = tcx.hir().local_def_id(param.hir_id).to_def_id();
+            // The index must take into account the generics of the parent, e.g., the
+            // generics of an impl or a trait when inserting the generics of a method.
+            let index = || tcx.generics_of(tcx.parent(def_id)).param_def_id_to_index[&def_id];
+            let name = param.name.ident().name;
+            match param.kind {
+                hir::GenericParamKind::Type { synthetic, .. } => {
+                    assert!(!self.generics.contains_key(&def_id));
+                    self.generics
+                        .insert(def_id, ParamTy { index: index(), name });
+                    if synthetic {
+                        let bounds = generics
+                            .bounds_for_param(def_id.expect_local())
+                            .flat_map(|pred| pred.bounds);
+                        if let Some(trait_name) = self.trait_name_of_bounds(bounds) {
+                            self.synthetic.insert(def_id, trait_name);
+                        }
+                    }
+                }
+                // A const argument is an anonymous constant in HIR, so we cannot collect its
+                // resolution from the types in the signature. We insert the parameter instead.
+                hir::GenericParamKind::Const { .. } => {
+                    self.res
+                        .insert(name, Res::ConstParam(ParamConst { index: index(), name }));
+                }
+                hir::GenericParamKind::Lifetime { .. } => {}
             }
         }
     }
@@ -343,6 +501,9 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
             hir::def::Res::SelfTyAlias { alias_to: def_id, forbid_generic: false, .. } => {
                 Ok(Res::Adt(def_id))
             }
+            hir::def::Res::SelfTyParam { .. } => {
+                Ok(Res::Param(ParamTy { index: 0, name: kw::SelfUpper }))
+            }
             hir::def::Res::PrimTy(hir::PrimTy::Str) => Ok(Res::Str),
             hir::def::Res::PrimTy(hir::PrimTy::Char) => Ok(Res::Char),
             hir::def::Res::Def(hir::def::DefKind::TyAlias, did) => {
@@ -371,6 +532,31 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
                 self.collect_from_ty(mut_ty.ty)
             }
             hir::TyKind::Tup(tys) => tys.iter().try_for_each(|ty| self.collect_from_ty(ty)),
+            hir::TyKind::Path(hir::QPath::Resolved(None, path))
+                if let hir::def::Res::Def(hir::def::DefKind::TyParam, did) = path.res
+                    && let Some(trait_name) = self.synthetic.get(&did) =>
+            {
+                let param_ty = self.get_param_ty(did).unwrap();
+                self.impl_traits.push((*trait_name, Res::Param(param_ty)));
+                Ok(())
+            }
+            hir::TyKind::Path(hir::QPath::TypeRelative(qself, segment)) => {
+                self.collect_from_assoc_ty(ty, qself, segment)
+            }
+            hir::TyKind::OpaqueDef(item_id, ..) => {
+                let hir::ItemKind::OpaqueTy(opaque_ty) = &self.tcx.hir().item(*item_id).kind else {
+                    unreachable!("opaque type without an opaque item")
+                };
+                let Some(trait_name) = self.trait_name_of_bounds(opaque_ty.bounds) else {
+                    return Err(self.sess.emit_err(errors::UnsupportedSignature {
+                        span: ty.span,
+                        note: "unsupported opaque type".to_string(),
+                    }));
+                };
+                self.impl_traits
+                    .push((trait_name, Res::Opaque(item_id.owner_id.to_def_id())));
+                Ok(())
+            }
             hir::TyKind::Path(qpath) => {
                 let path = if let hir::QPath::Resolved(None, path) = qpath {
                     path
@@ -398,9 +584,18 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
                     .flatten()
                     .try_for_each_exhaust(|arg| self.collect_from_generic_arg(arg))
             }
-            hir::TyKind::BareFn(_)
-            | hir::TyKind::Never
-            | hir::TyKind::OpaqueDef(..)
+            hir::TyKind::BareFn(bare_fn) => {
+                bare_fn
+                    .decl
+                    .inputs
+                    .iter()
+                    .try_for_each_exhaust(|ty| self.collect_from_ty(ty))?;
+                if let hir::FnRetTy::Return(ty) = bare_fn.decl.output {
+                    self.collect_from_ty(ty)?;
+                }
+                Ok(())
+            }
+            hir::TyKind::Never
             | hir::TyKind::TraitObject(..)
             | hir::TyKind::Typeof(_)
             | hir::TyKind::Infer
@@ -408,16 +603,90 @@ impl<'sess, 'tcx> NameResTable<'sess, 'tcx> {
         }
     }
 
+    /// Inserts the names of the types in an already lowered type. Names that are already in the
+    /// table are not overwritten.
+    fn collect_from_rustc_ty(&mut self, ty: rustc_middle::ty::Ty) {
+        match ty.kind() {
+            TyKind::Ref(_, ty, _) | TyKind::Slice(ty) | TyKind::Array(ty, _) => {
+                self.collect_from_rustc_ty(*ty);
+            }
+            TyKind::Tuple(tys) => tys.iter().for_each(|ty| self.collect_from_rustc_ty(ty)),
+            TyKind::Adt(adt_def, substs) => {
+                let did = adt_def.did();
+                self.res
+                    .entry(self.tcx.item_name(did))
+                    .or_insert(Res::Adt(did));
+                substs.types().for_each(|ty| self.collect_from_rustc_ty(ty));
+            }
+            TyKind::Str => {
+                self.res.entry(sym::str).or_insert(Res::Str);
+            }
+            _ => {
+                if let Some(res) = Self::res_from_ty(ty) {
+                    self.res
+                        .entry(Symbol::intern(&ty.to_string()))
+                        .or_insert(res);
+                }
+            }
+        }
+    }
+
+    /// An associated type of a type parameter, e.g., `T::Item`, is inserted with its full name. If
+    /// the projection can be normalized with the bounds in scope it resolves to the normalized type.
+    fn collect_from_assoc_ty(
+        &mut self,
+        ty: &hir::Ty,
+        qself: &hir::Ty,
+        segment: &hir::PathSegment,
+    ) -> Result<(), ErrorGuaranteed> {
+        let unsupported = || {
+            self.sess.emit_err(errors::UnsupportedSignature {
+                span: ty.span,
+                note: "unsupported associated type".to_string(),
+            })
+        };
+        let qself_segment = match qself.kind {
+            hir::TyKind::Path(hir::QPath::Resolved(
+                None,
+                hir::Path { segments: [segment], .. },
+            )) => segment,
+            _ => return Err(unsupported()),
+        };
+        let rustc_ty = rustc_hir_analysis::hir_ty_to_ty(self.tcx, ty);
+        let rustc_ty = self
+            .tcx
+            .try_normalize_erasing_regions(self.tcx.param_env(self.owner), rustc_ty)
+            .unwrap_or(rustc_ty);
+        let res = match rustc_ty.kind() {
+            // Only associated types of traits without generic parameters other than `Self`
+            TyKind::Projection(proj)
+                if let TyKind::Param(param_ty) = proj.self_ty().kind()
+                    && proj.substs.len() == 1 =>
+            {
+                Res::Projection(*param_ty, proj.item_def_id)
+            }
+            _ => Self::res_from_ty(rustc_ty).ok_or_else(unsupported)?,
+        };
+        let name = Symbol::intern(&format!("{}::{}", qself_segment.ident, segment.ident));
+        self.res.insert(name, res);
+        Ok(())
+    }
+
+    fn trait_name_of_bounds<'hir>(
+        &self,
+        bounds: impl IntoIterator<Item = &'hir hir::GenericBound<'hir>>,
+    ) -> Option<Symbol> {
+        bounds
+            .into_iter()
+            .find_map(|bound| bound.trait_ref()?.trait_def_id())
+            .map(|def_id| self.tcx.item_name(def_id))
+    }
+
     fn collect_from_generic_arg(&mut self, arg: &hir::GenericArg) -> Result<(), ErrorGuaranteed> {
         match arg {
             hir::GenericArg::Type(ty) => self.collect_from_ty(ty),
-            hir::GenericArg::Lifetime(_) => Ok(()),
-            hir::GenericArg::Const(_) => {
-                Err(self.sess.emit_err(errors::UnsupportedSignature {
-                    span: arg.span(),
-                    note: "const generics are not supported yet".to_string(),
-                }))
-            }
+            // Const parameters are already in the table, see `insert_generics`
+            hir::GenericArg::Lifetime(_) | hir::GenericArg::Const(_) => Ok(()),
 
             hir::GenericArg::Infer(_) => unreachable!(),
         }
@@ -452,4 +721,19 @@ mod errors {
             Self { span: ident.span, path: ident }
         }
     }
+
+    #[derive(Diagnostic)]
+    #[diag(resolver::unresolved_impl_trait, code = "FLUX")]
+    #[help]
+    pub struct UnresolvedImplTrait {
+        #[primary_span]
+        pub span: Span,
+        pub trait_name: Ident,
+    }
+
+    impl UnresolvedImplTrait {
+        pub fn new(ident: surface::Ident) -> Self {
+            Self { span: ident.span, trait_name: ident }
+        }
+    }
 }
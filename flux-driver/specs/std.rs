@@ -0,0 +1,328 @@
+// Refined signatures for commonly used items in the standard library. This file is compiled as a
+// standalone crate and the specs are made available to every crate checked with flux (see
+// `flux_driver::std_specs`).
+
+// Option
+
+#[flux::extern_spec]
+#[flux::refined_by(b: bool)]
+enum OptionSpec<T> {
+    #[flux::variant(Option<T>[false])]
+    None,
+    #[flux::variant({T} -> Option<T>[true])]
+    Some(T),
+    Extern(Option<T>),
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Option<T>[@b]) -> bool[b])]
+fn option_is_some<T>(opt: &Option<T>) -> bool {
+    Option::is_some(opt)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Option<T>[@b]) -> bool[b == false])]
+fn option_is_none<T>(opt: &Option<T>) -> bool {
+    Option::is_none(opt)
+}
+
+// Result
+
+#[flux::extern_spec]
+#[flux::refined_by(ok: bool)]
+enum ResultSpec<T, E> {
+    #[flux::variant({T} -> Result<T, E>[true])]
+    Ok(T),
+    #[flux::variant({E} -> Result<T, E>[false])]
+    Err(E),
+    Extern(Result<T, E>),
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Result<T, E>[@b]) -> bool[b])]
+fn result_is_ok<T, E>(res: &Result<T, E>) -> bool {
+    Result::is_ok(res)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Result<T, E>[@b]) -> bool[b == false])]
+fn result_is_err<T, E>(res: &Result<T, E>) -> bool {
+    Result::is_err(res)
+}
+
+// Vec
+
+#[flux::extern_spec]
+#[flux::refined_by(len: int)]
+struct VecSpec<T>(Vec<T>);
+
+#[flux::extern_spec]
+#[flux::sig(fn() -> Vec<T>[0])]
+fn vec_new<T>() -> Vec<T> {
+    Vec::new()
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(usize) -> Vec<T>[0])]
+fn vec_with_capacity<T>(capacity: usize) -> Vec<T> {
+    Vec::with_capacity(capacity)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(vec: &strg Vec<T>[@n], T) ensures vec: Vec<T>[n + 1])]
+fn vec_push<T>(vec: &mut Vec<T>, value: T) {
+    Vec::push(vec, value)
+}
+
+#[flux::extern_spec]
+#[flux::sig(
+    fn(vec: &strg Vec<T>[@n]) -> Option<T>[n > 0]
+    ensures vec: Vec<T>[if n > 0 { n - 1 } else { 0 }]
+)]
+fn vec_pop<T>(vec: &mut Vec<T>) -> Option<T> {
+    Vec::pop(vec)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(vec: &strg Vec<T>) ensures vec: Vec<T>[0])]
+fn vec_clear<T>(vec: &mut Vec<T>) {
+    Vec::clear(vec)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Vec<T>[@n]) -> usize[n])]
+fn vec_len<T>(vec: &Vec<T>) -> usize {
+    Vec::len(vec)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Vec<T>[@n]) -> bool[n == 0])]
+fn vec_is_empty<T>(vec: &Vec<T>) -> bool {
+    Vec::is_empty(vec)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&Vec<T>[@n], usize{v: v < n}) -> &T)]
+fn vec_index<T>(vec: &Vec<T>, index: usize) -> &T {
+    <Vec<T> as ::std::ops::Index<usize>>::index(vec, index)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&mut Vec<T>[@n], usize{v: v < n}) -> &mut T)]
+fn vec_index_mut<T>(vec: &mut Vec<T>, index: usize) -> &mut T {
+    <Vec<T> as ::std::ops::IndexMut<usize>>::index_mut(vec, index)
+}
+
+// Methods of slices called on a vector, e.g., `Vec::get`, go through `deref`
+#[flux::extern_spec]
+#[flux::sig(fn(&Vec<T>[@n]) -> &[T][n])]
+fn vec_deref<T>(vec: &Vec<T>) -> &[T] {
+    <Vec<T> as ::std::ops::Deref>::deref(vec)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&mut Vec<T>[@n]) -> &mut [T][n])]
+fn vec_deref_mut<T>(vec: &mut Vec<T>) -> &mut [T] {
+    <Vec<T> as ::std::ops::DerefMut>::deref_mut(vec)
+}
+
+// Slices
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n]) -> usize[n])]
+fn slice_len<T>(slice: &[T]) -> usize {
+    <[T]>::len(slice)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n]) -> bool[n == 0])]
+fn slice_is_empty<T>(slice: &[T]) -> bool {
+    <[T]>::is_empty(slice)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n], usize[@i]) -> Option<&T>[i < n])]
+fn slice_get<T>(slice: &[T], index: usize) -> Option<&T> {
+    <[T]>::get(slice, index)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&mut [T][@n], usize[@i]) -> Option<&mut T>[i < n])]
+fn slice_get_mut<T>(slice: &mut [T], index: usize) -> Option<&mut T> {
+    <[T]>::get_mut(slice, index)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n]) -> Option<&T>[n > 0])]
+fn slice_first<T>(slice: &[T]) -> Option<&T> {
+    <[T]>::first(slice)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n]) -> Option<&T>[n > 0])]
+fn slice_last<T>(slice: &[T]) -> Option<&T> {
+    <[T]>::last(slice)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n], mid: usize{mid <= n}) -> (&[T][mid], &[T][n - mid]))]
+fn slice_split_at<T>(slice: &[T], mid: usize) -> (&[T], &[T]) {
+    <[T]>::split_at(slice, mid)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&mut [T][@n], mid: usize{mid <= n}) -> (&mut [T][mid], &mut [T][n - mid]))]
+fn slice_split_at_mut<T>(slice: &mut [T], mid: usize) -> (&mut [T], &mut [T]) {
+    <[T]>::split_at_mut(slice, mid)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&mut [T][@n], usize{v: v < n}, usize{v: v < n}))]
+fn slice_swap<T>(slice: &mut [T], a: usize, b: usize) {
+    <[T]>::swap(slice, a, b)
+}
+
+// Strings and characters
+
+#[flux::extern_spec]
+#[flux::sig(fn(&str[@n]) -> usize[n])]
+fn str_len(s: &str) -> usize {
+    <str>::len(s)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&str[@n]) -> bool[n == 0])]
+fn str_is_empty(s: &str) -> bool {
+    <str>::is_empty(s)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&str[@n]) -> &[u8][n])]
+fn str_as_bytes(s: &str) -> &[u8] {
+    <str>::as_bytes(s)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&char[@c]) -> bool[48 <= c && c <= 57])]
+fn char_is_ascii_digit(c: &char) -> bool {
+    char::is_ascii_digit(c)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(&char[@c]) -> bool[c <= 127])]
+fn char_is_ascii(c: &char) -> bool {
+    char::is_ascii(c)
+}
+
+// A digit is one of `0-9`, `a-z` or `A-Z`, and only the first `radix` of them are valid
+#[flux::extern_spec]
+#[flux::sig(
+    fn(char[@c], radix: u32{radix <= 36}) -> Option<u32{v: v < radix}>[
+        (48 <= c && c <= 57 && c < 48 + radix)
+        || (97 <= c && c < 87 + radix)
+        || (65 <= c && c < 55 + radix)
+    ]
+)]
+fn char_to_digit(c: char, radix: u32) -> Option<u32> {
+    char::to_digit(c, radix)
+}
+
+// Checked arithmetic
+
+#[flux::extern_spec]
+#[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a + b]>)]
+fn usize_checked_add(a: usize, b: usize) -> Option<usize> {
+    usize::checked_add(a, b)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a - b]>[a >= b])]
+fn usize_checked_sub(a: usize, b: usize) -> Option<usize> {
+    usize::checked_sub(a, b)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(usize[@a], usize[@b]) -> Option<usize[a * b]>)]
+fn usize_checked_mul(a: usize, b: usize) -> Option<usize> {
+    usize::checked_mul(a, b)
+}
+
+// Ranges
+
+use ::std::ops::{Range, RangeInclusive};
+
+#[flux::extern_spec]
+#[flux::refined_by(lo: int, hi: int)]
+struct RangeSpec<Idx> {
+    #[flux::field(Idx[@lo])]
+    start: Idx,
+    #[flux::field(Idx[@hi])]
+    end: Idx,
+    _extern: Range<Idx>,
+}
+
+#[flux::extern_spec]
+#[flux::sig(
+    fn(it: &strg Range<A>[@lo, @hi]) -> Option<A{v: lo <= v && v < hi}>[lo < hi]
+    ensures it: Range<A>[if lo < hi { lo + 1 } else { lo }, hi]
+)]
+fn range_next<A>(it: &mut Range<A>) -> Option<A>
+where
+    Range<A>: Iterator<Item = A>,
+{
+    <Range<A> as Iterator>::next(it)
+}
+
+#[flux::extern_spec]
+#[flux::refined_by(lo: int, hi: int)]
+struct RangeInclusiveSpec<Idx>(RangeInclusive<Idx>);
+
+#[flux::extern_spec]
+#[flux::sig(fn(A[@lo], A[@hi]) -> RangeInclusive<A>[lo, hi])]
+fn range_inclusive_new<A>(start: A, end: A) -> RangeInclusive<A> {
+    RangeInclusive::new(start, end)
+}
+
+#[flux::extern_spec]
+#[flux::sig(
+    fn(it: &strg RangeInclusive<A>[@lo, @hi]) -> Option<A{v: lo <= v && v <= hi}>[lo <= hi]
+    ensures it: RangeInclusive<A>[if lo <= hi { lo + 1 } else { lo }, hi]
+)]
+fn range_inclusive_next<A>(it: &mut RangeInclusive<A>) -> Option<A>
+where
+    RangeInclusive<A>: Iterator<Item = A>,
+{
+    <RangeInclusive<A> as Iterator>::next(it)
+}
+
+// Slice iterators
+
+use ::std::slice::Iter;
+
+#[flux::extern_spec]
+#[flux::refined_by(idx: int, len: int)]
+struct IterSpec<'a, T>(Iter<'a, T>);
+
+#[flux::extern_spec]
+#[flux::sig(fn(&[T][@n]) -> Iter<T>[0, n])]
+fn slice_iter<T>(slice: &[T]) -> Iter<'_, T> {
+    <[T]>::iter(slice)
+}
+
+#[flux::extern_spec]
+#[flux::sig(
+    fn(it: &strg Iter<T>[@i, @n]) -> Option<&T>[i < n]
+    ensures it: Iter<T>[if i < n { i + 1 } else { i }, n]
+)]
+fn slice_iter_next<'a, T: 'a>(it: &mut Iter<'a, T>) -> Option<&'a T> {
+    <Iter<'a, T> as Iterator>::next(it)
+}
+
+// Traits. Impls without a refined signature, e.g., derived impls, inherit these signatures and are
+// checked against them.
+
+#[flux::extern_spec]
+#[flux::sig(fn(&T[@x]) -> T[x])]
+fn clone<T: Clone>(x: &T) -> T {
+    Clone::clone(x)
+}
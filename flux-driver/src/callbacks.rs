@@ -4,23 +4,34 @@ use flux_errors::FluxSession;
 use flux_middle::{
     fhir::{self, ConstInfo},
     global_env::GlobalEnv,
+    metadata::CrateMetadata,
     rustc,
 };
 use flux_syntax::surface;
 use flux_typeck::{self as typeck, wf::Wf};
+use itertools::Itertools;
+use rustc_data_structures::fx::FxIndexMap;
 use rustc_driver::{Callbacks, Compilation};
 use rustc_errors::ErrorGuaranteed;
-use rustc_hir::{def::DefKind, def_id::LocalDefId};
+use rustc_hash::FxHashMap;
+use rustc_hir::{
+    def::DefKind,
+    def_id::{DefId, LocalDefId},
+};
 use rustc_interface::{interface::Compiler, Queries};
 use rustc_middle::ty::{
     query::{query_values, Providers},
     TyCtxt, WithOptConstParam,
 };
+use rustc_session::{
+    config::{CrateType, OutputType},
+    Session,
+};
 use typeck::invariants;
 
 use crate::{
     collector::{IgnoreKey, Ignores, SpecCollector, Specs},
-    mir_storage,
+    mir_storage, std_specs,
 };
 
 pub(crate) struct FluxCallbacks {
@@ -60,7 +71,9 @@ impl Callbacks for FluxCallbacks {
             sess.finish_diagnostics();
         });
 
-        if self.full_compilation {
+        // Emitting only metadata doesn't create a binary, so we continue to write the `.rmeta`
+        // file needed to check dependent crates.
+        if self.full_compilation || only_emits_metadata(compiler.session()) {
             Compilation::Continue
         } else {
             Compilation::Stop
@@ -68,6 +81,13 @@ impl Callbacks for FluxCallbacks {
     }
 }
 
+fn only_emits_metadata(sess: &Session) -> bool {
+    sess.opts
+        .output_types
+        .keys()
+        .all(|output_type| *output_type == OutputType::Metadata)
+}
+
 fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
     let mut specs = SpecCollector::collect(tcx, sess)?;
 
@@ -76,15 +96,26 @@ fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
         return Ok(());
     }
 
-    let map = build_fhir_map(tcx, sess, &mut specs)?;
+    // The bundled specs come first so the ones exported by dependencies take precedence
+    let mut dependencies = std_specs::load(tcx, sess)
+        .into_iter()
+        .chain(CrateMetadata::read_dependencies(tcx, sess))
+        .collect_vec();
+
+    let map = build_fhir_map(tcx, sess, &mut specs, &mut dependencies)?;
     check_wf(sess, &map)?;
 
     let mut genv = GlobalEnv::new(tcx, sess, map);
+    for meta in dependencies {
+        genv.import_crate_metadata(meta);
+    }
     // Assert behavior from Crate config
     // TODO(atgeller) rest of settings from crate config
     if let Some(crate_config) = specs.crate_config {
         let assert_behavior = crate_config.check_asserts;
         genv.register_assert_behavior(assert_behavior);
+        genv.register_check_overflow(crate_config.check_overflow);
+        genv.register_check_macros(crate_config.check_macros);
     }
 
     let ck = CrateChecker::new(&mut genv, specs.ignores);
@@ -95,13 +126,43 @@ fn check_crate(tcx: TyCtxt, sess: &FluxSession) -> Result<(), ErrorGuaranteed> {
 
     let crate_items = tcx.hir_crate_items(());
     let items = crate_items.items().map(|item| item.owner_id.def_id);
+    let trait_items = crate_items
+        .trait_items()
+        .map(|trait_item| trait_item.owner_id.def_id);
     let impl_items = crate_items
         .impl_items()
         .map(|impl_item| impl_item.owner_id.def_id);
 
     items
+        .chain(trait_items)
         .chain(impl_items)
-        .try_for_each_exhaust(|def_id| ck.check_def(def_id))
+        .try_for_each_exhaust(|def_id| ck.check_def(def_id))?;
+
+    // Only export the refinements if they were successfully checked
+    write_crate_metadata(ck.genv);
+    Ok(())
+}
+
+/// Writes the refinements of the local crate so they can be used when checking dependent crates.
+/// Executables cannot be depended on, so we don't write anything for them.
+fn write_crate_metadata(genv: &GlobalEnv) {
+    let tcx = genv.tcx;
+    if tcx
+        .sess
+        .crate_types()
+        .iter()
+        .all(|crate_type| *crate_type == CrateType::Executable)
+    {
+        return;
+    }
+    let mut meta = genv.crate_metadata();
+    if std_specs::is_std_specs_crate(tcx) {
+        std_specs::retain_extern_items(&mut meta);
+    }
+    if let Err(err) = meta.write(tcx) {
+        genv.sess
+            .emit_warning(errors::CannotWriteMetadata { err: err.to_string() });
+    }
 }
 
 struct CrateChecker<'genv, 'tcx> {
@@ -136,8 +197,17 @@ impl<'genv, 'tcx> CrateChecker<'genv, 'tcx> {
         }
 
         match self.genv.tcx.def_kind(def_id.to_def_id()) {
-            DefKind::Fn | DefKind::AssocFn => self.check_fn(def_id),
+            DefKind::Fn => self.check_fn(def_id),
+            DefKind::AssocFn => {
+                [self.check_impl_fn_sig(def_id), self.check_fn(def_id)]
+                    .into_iter()
+                    .try_collect_exhaust()
+            }
             DefKind::Enum | DefKind::Struct => self.check_adt_invariants(def_id),
+            // Statics without an annotation are trivially well-typed.
+            DefKind::Static(_) if self.genv.map().static_ty(def_id.to_def_id()).is_some() => {
+                self.check_fn(def_id)
+            }
             _ => Ok(()),
         }
     }
@@ -147,15 +217,39 @@ impl<'genv, 'tcx> CrateChecker<'genv, 'tcx> {
             return Ok(());
         }
 
+        // Trait methods without a default body have nothing to check
+        if !self.genv.tcx.is_mir_available(def_id.to_def_id()) {
+            return Ok(());
+        }
+
         let mir = unsafe { mir_storage::retrieve_mir_body(self.genv.tcx, def_id).body };
 
-        // HACK(nilehmann) this will ignore any code generated by a macro. This is
-        // a temporary workaround to allow `#[derive(PartialEq, Eq)]` and should be
-        // removed.
-        if mir.span.ctxt() > rustc_span::SyntaxContext::root() {
+        if !self.genv.check_macros() && mir.span.from_expansion() {
+            self.genv
+                .sess
+                .emit_warning(errors::MacroNotChecked { span: mir.span.source_callsite() });
             return Ok(());
         }
 
+        let body = self.lower_mir_body(mir)?;
+        let closures = self.lower_closure_bodies(&body)?;
+
+        typeck::check(self.genv, def_id.to_def_id(), &body, &closures)
+    }
+
+    /// Methods in a trait impl without a refined signature inherit the signature of the trait
+    /// method, so we only need to compare signatures if the impl method has its own.
+    fn check_impl_fn_sig(&self, def_id: LocalDefId) -> Result<(), ErrorGuaranteed> {
+        if self.genv.map().fn_sig(def_id.to_def_id()).is_none() {
+            return Ok(());
+        }
+        typeck::check_impl_fn_sig(self.genv, def_id.to_def_id())
+    }
+
+    fn lower_mir_body(
+        &self,
+        mir: rustc_middle::mir::Body<'tcx>,
+    ) -> Result<rustc::mir::Body<'tcx>, ErrorGuaranteed> {
         if flux_common::config::CONFIG.dump_mir {
             let mut w = std::io::BufWriter::new(std::io::stdout());
             rustc_middle::mir::pretty::write_mir_fn(
@@ -167,10 +261,26 @@ impl<'genv, 'tcx> CrateChecker<'genv, 'tcx> {
             .unwrap();
         }
 
-        let body =
-            rustc::lowering::LoweringCtxt::lower_mir_body(self.genv.tcx, self.genv.sess, mir)?;
+        rustc::lowering::LoweringCtxt::lower_mir_body(self.genv.tcx, self.genv.sess, mir)
+    }
 
-        typeck::check(self.genv, def_id.to_def_id(), &body)
+    /// Lowers the bodies of all the closures defined inside `body`, including closures nested
+    /// inside other closures.
+    fn lower_closure_bodies(
+        &self,
+        body: &rustc::mir::Body<'tcx>,
+    ) -> Result<Vec<(DefId, rustc::mir::Body<'tcx>)>, ErrorGuaranteed> {
+        let mut closures = vec![];
+        let mut pending = body.closures();
+        while let Some(closure_id) = pending.pop() {
+            let mir = unsafe {
+                mir_storage::retrieve_mir_body(self.genv.tcx, closure_id.expect_local()).body
+            };
+            let closure_body = self.lower_mir_body(mir)?;
+            pending.extend(closure_body.closures());
+            closures.push((closure_id, closure_body));
+        }
+        Ok(closures)
     }
 
     fn check_adt_invariants(&self, def_id: LocalDefId) -> Result<(), ErrorGuaranteed> {
@@ -186,11 +296,61 @@ fn build_fhir_map(
     tcx: TyCtxt,
     sess: &FluxSession,
     specs: &mut Specs,
+    dependencies: &mut [CrateMetadata],
 ) -> Result<fhir::Map, ErrorGuaranteed> {
     let mut map = fhir::Map::default();
+    let real_floats = specs
+        .crate_config
+        .as_ref()
+        .map_or(flux_common::config::CONFIG.real_floats, |config| config.real_floats);
+    map.set_real_floats(real_floats);
 
     let mut err: Option<ErrorGuaranteed> = None;
 
+    // Register the refinements exported by dependencies
+    for meta in dependencies {
+        for adt in &meta.adts {
+            map.insert_adt(adt.adt_def.def_id(), adt.to_fhir());
+        }
+        for uif_def in std::mem::take(&mut meta.uifs) {
+            map.insert_uif(uif_def.name, uif_def);
+        }
+        for data_decl in std::mem::take(&mut meta.data_decls) {
+            map.insert_data_decl(data_decl);
+        }
+        for name in std::mem::take(&mut meta.opaque_sorts) {
+            map.insert_opaque_sort(name);
+        }
+    }
+
+    // Register opaque sorts
+    for decl in std::mem::take(&mut specs.opaque_sorts) {
+        map.insert_opaque_sort(decl.name.name);
+    }
+
+    // Register reflected structs and enums. Their sorts can be used by any of the annotations
+    // desugared below.
+    match desugar::reflect_adts(tcx, sess, &specs.reflected_adts) {
+        Ok(reflected) => {
+            for desugar::ReflectedAdt { data_decl, adt_def, variants } in reflected {
+                let def_id = data_decl.def_id.expect_local();
+                map.insert_data_decl(data_decl);
+                map.insert_adt(adt_def.def_id, adt_def);
+                // Variants of reflected structs have the same shape as the ones of an enum
+                map.insert_enum(def_id, variants);
+            }
+        }
+        Err(e) => err = Some(e),
+    }
+
+    // Register Extern Specs
+    for (def_id, extern_def_id) in &specs.externs {
+        map.insert_extern(*extern_def_id, *def_id);
+        if let Some(tys) = specs.extern_instances.remove(def_id) {
+            map.insert_extern_instance(*extern_def_id, tys);
+        }
+    }
+
     // Register Consts
     for (def_id, const_sig) in std::mem::take(&mut specs.consts) {
         let did = def_id.to_def_id();
@@ -203,29 +363,59 @@ fn build_fhir_map(
         .into_iter()
         .try_for_each_exhaust(|uif_def| {
             let name = uif_def.name;
-            let uif_def = desugar::resolve_uif_def(sess, uif_def)?;
+            let uif_def = desugar::resolve_uif_def(sess, &map, uif_def)?;
             map.insert_uif(name.name, uif_def);
             Ok(())
         })
         .err()
         .or(err);
 
+    // Register Defns. A definition can only use the ones registered before it.
+    err = std::mem::take(&mut specs.defns)
+        .into_iter()
+        .try_for_each_exhaust(|defn| {
+            let defn = desugar::desugar_defn(tcx, sess, &map, defn)?;
+            map.insert_defn(defn.name, defn);
+            Ok(())
+        })
+        .err()
+        .or(err);
+
+    // Register reflected functions as definitions. Their signatures are registered with the
+    // other signatures below.
+    let mut reflected_sigs = FxHashMap::default();
+    match reflect_fns(tcx, sess, specs) {
+        Ok(reflected) => {
+            for desugar::ReflectedFn { def_id, defn, fn_sig } in reflected {
+                map.insert_defn(defn.name, defn);
+                reflected_sigs.insert(def_id, fn_sig);
+            }
+        }
+        Err(e) => err = Some(e),
+    }
+
     // Register AdtDefs
     err = specs
         .structs
         .iter_mut()
         .try_for_each_exhaust(|(def_id, def)| {
+            // The refinements of an extern spec are attached to the extern struct
+            let adt_def_id = specs
+                .externs
+                .get(def_id)
+                .copied()
+                .unwrap_or_else(|| def_id.to_def_id());
             let refined_by = def.refined_by.as_ref().unwrap_or(surface::RefinedBy::DUMMY);
             let adt_def = desugar::desugar_adt_def(
                 tcx,
                 sess,
                 &map,
-                def_id.to_def_id(),
+                adt_def_id,
                 refined_by,
                 std::mem::take(&mut def.invariants),
                 def.opaque,
             )?;
-            map.insert_adt(*def_id, adt_def);
+            map.insert_adt(adt_def_id, adt_def);
             Ok(())
         })
         .err()
@@ -234,17 +424,22 @@ fn build_fhir_map(
         .enums
         .iter_mut()
         .try_for_each_exhaust(|(def_id, def)| {
+            let adt_def_id = specs
+                .externs
+                .get(def_id)
+                .copied()
+                .unwrap_or_else(|| def_id.to_def_id());
             let refined_by = def.refined_by.as_ref().unwrap_or(surface::RefinedBy::DUMMY);
             let adt_def = desugar::desugar_adt_def(
                 tcx,
                 sess,
                 &map,
-                def_id.to_def_id(),
+                adt_def_id,
                 refined_by,
                 std::mem::take(&mut def.invariants),
                 false,
             )?;
-            map.insert_adt(*def_id, adt_def);
+            map.insert_adt(adt_def_id, adt_def);
             Ok(())
         })
         .err()
@@ -267,11 +462,28 @@ fn build_fhir_map(
         .err()
         .or(err);
 
+    // Axioms
+    err = std::mem::take(&mut specs.axioms)
+        .into_iter()
+        .try_for_each_exhaust(|axiom| {
+            let axiom = desugar::desugar_axiom(tcx, sess, &map, axiom)?;
+            map.insert_axiom(axiom);
+            Ok(())
+        })
+        .err()
+        .or(err);
+
     // Variants
     err = std::mem::take(&mut specs.structs)
         .into_iter()
         .try_for_each_exhaust(|(def_id, struct_def)| {
-            map.insert_struct(def_id, desugar::desugar_struct_def(tcx, sess, &map, struct_def)?);
+            let adt_def_id = specs
+                .externs
+                .get(&def_id)
+                .copied()
+                .unwrap_or_else(|| def_id.to_def_id());
+            let struct_def = desugar::desugar_struct_def(tcx, sess, &map, adt_def_id, struct_def)?;
+            map.insert_struct(def_id, struct_def);
             Ok(())
         })
         .err()
@@ -280,7 +492,13 @@ fn build_fhir_map(
     err = std::mem::take(&mut specs.enums)
         .into_iter()
         .try_for_each_exhaust(|(def_id, enum_def)| {
-            map.insert_enum(def_id, desugar::desugar_enum_def(tcx, sess, &map, enum_def)?);
+            let adt_def_id = specs
+                .externs
+                .get(&def_id)
+                .copied()
+                .unwrap_or_else(|| def_id.to_def_id());
+            let enum_def = desugar::desugar_enum_def(tcx, sess, &map, adt_def_id, enum_def)?;
+            map.insert_enum(def_id, enum_def);
             Ok(())
         })
         .err()
@@ -298,12 +516,25 @@ fn build_fhir_map(
                 let fn_sig = surface::expand::expand_sig(&aliases, fn_sig)?;
                 let fn_sig = desugar::desugar_fn_sig(tcx, sess, &map, def_id, fn_sig)?;
                 map.insert_fn_sig(def_id, fn_sig);
+            } else if let Some(fn_sig) = reflected_sigs.remove(&def_id) {
+                map.insert_fn_sig(def_id, fn_sig);
             }
             Ok(())
         })
         .err()
         .or(err);
 
+    // Statics
+    err = std::mem::take(&mut specs.statics)
+        .into_iter()
+        .try_for_each_exhaust(|(def_id, ty)| {
+            let ty = desugar::desugar_static_ty(tcx, sess, &map, def_id, ty)?;
+            map.insert_static(def_id, ty);
+            Ok(())
+        })
+        .err()
+        .or(err);
+
     if let Some(err) = err {
         Err(err)
     } else {
@@ -311,11 +542,39 @@ fn build_fhir_map(
     }
 }
 
+/// Translates the functions annotated with `#[flux::reflect]` into refinement-level definitions.
+/// The mir of a reflected function is copied rather than taken out of storage because the function
+/// is still checked against its (generated) signature.
+fn reflect_fns(
+    tcx: TyCtxt,
+    sess: &FluxSession,
+    specs: &Specs,
+) -> Result<Vec<desugar::ReflectedFn>, ErrorGuaranteed> {
+    let bodies: FxIndexMap<_, _> = specs
+        .fns
+        .iter()
+        .filter(|(_, spec)| spec.reflect)
+        .map(|(def_id, _)| *def_id)
+        .sorted_by_key(|def_id| def_id.local_def_index)
+        .map(|def_id| {
+            let body_with_facts = unsafe { mir_storage::retrieve_mir_body(tcx, def_id) };
+            let mir = body_with_facts.body.clone();
+            unsafe { mir_storage::store_mir_body(tcx, def_id, body_with_facts) };
+            Ok((def_id, rustc::lowering::LoweringCtxt::lower_mir_body(tcx, sess, mir)?))
+        })
+        .try_collect_exhaust()?;
+    desugar::reflect_fns(tcx, sess, &bodies)
+}
+
 fn check_wf(sess: &FluxSession, map: &fhir::Map) -> Result<(), ErrorGuaranteed> {
     let wf = Wf::new(sess, map);
 
     let mut err: Option<ErrorGuaranteed> = None;
 
+    for defn in map.defns() {
+        err = wf.check_defn(defn).err().or(err);
+    }
+
     for adt_def in map.adts() {
         err = wf.check_adt_def(adt_def).err().or(err);
     }
@@ -324,6 +583,10 @@ fn check_wf(sess: &FluxSession, map: &fhir::Map) -> Result<(), ErrorGuaranteed>
         err = wf.check_qualifier(qualifier).err().or(err);
     }
 
+    for axiom in map.axioms() {
+        err = wf.check_axiom(axiom).err().or(err);
+    }
+
     for struct_def in map.structs() {
         let refined_by = map.refined_by(struct_def.def_id).unwrap();
         err = wf.check_struct_def(refined_by, struct_def).err().or(err);
@@ -337,6 +600,10 @@ fn check_wf(sess: &FluxSession, map: &fhir::Map) -> Result<(), ErrorGuaranteed>
         err = wf.check_fn_sig(fn_sig).err().or(err);
     }
 
+    for (_, ty) in map.statics() {
+        err = wf.check_static_ty(ty).err().or(err);
+    }
+
     if let Some(err) = err {
         Err(err)
     } else {
@@ -380,3 +647,22 @@ fn is_tool_registered(tcx: TyCtxt) -> bool {
     }
     false
 }
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Span;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck::macro_not_checked, code = "FLUX")]
+    #[note]
+    pub struct MacroNotChecked {
+        #[primary_span]
+        pub span: Span,
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(refineck::cannot_write_metadata, code = "FLUX")]
+    pub struct CannotWriteMetadata {
+        pub err: String,
+    }
+}
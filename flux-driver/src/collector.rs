@@ -5,9 +5,11 @@ use flux_common::{
     iter::IterExt,
 };
 use flux_errors::{FluxSession, ResultExt};
+use flux_middle::rustc;
 use flux_syntax::{
-    parse_expr, parse_fn_surface_sig, parse_qualifier, parse_refined_by, parse_ty,
-    parse_type_alias, parse_uif_def, parse_variant, surface, ParseResult,
+    parse_axiom, parse_defns, parse_expr, parse_fn_surface_sig, parse_opaque_sort_decl,
+    parse_qualifier, parse_refined_by, parse_ty, parse_type_alias, parse_uif_def, parse_variant,
+    surface, ParseResult,
 };
 use itertools::Itertools;
 use rustc_ast::{
@@ -15,9 +17,17 @@ use rustc_ast::{
 };
 use rustc_errors::{ErrorGuaranteed, IntoDiagnostic};
 use rustc_hash::{FxHashMap, FxHashSet};
-use rustc_hir::{def_id::LocalDefId, EnumDef, ImplItemKind, Item, ItemKind, VariantData};
-use rustc_middle::ty::{ScalarInt, TyCtxt};
-use rustc_span::{Span, Symbol};
+use rustc_hir::{
+    def::{DefKind, Res},
+    def_id::{DefId, LocalDefId},
+    EnumDef, ExprKind, ImplItemKind, Item, ItemKind, TraitItemKind, VariantData,
+};
+use rustc_middle::ty::{
+    self,
+    subst::{GenericArgKind, SubstsRef},
+    ScalarInt, TyCtxt, TypeVisitable,
+};
+use rustc_span::Span;
 
 pub(crate) struct SpecCollector<'tcx, 'a> {
     tcx: TyCtxt<'tcx>,
@@ -41,17 +51,32 @@ pub(crate) struct Specs {
     pub fns: FxHashMap<LocalDefId, FnSpec>,
     pub structs: FxHashMap<LocalDefId, surface::StructDef>,
     pub enums: FxHashMap<LocalDefId, surface::EnumDef>,
+    /// Structs and enums annotated with `#[flux::reflect]`, which don't have any other
+    /// annotation.
+    pub reflected_adts: Vec<LocalDefId>,
     pub qualifs: Vec<surface::Qualifier>,
     pub uifs: Vec<surface::UifDef>,
+    pub opaque_sorts: Vec<surface::OpaqueSortDecl>,
+    pub axioms: Vec<surface::Axiom>,
+    pub defns: Vec<surface::Defn>,
     pub aliases: surface::AliasMap,
     pub ignores: Ignores,
     pub consts: FxHashMap<LocalDefId, ConstSig>,
+    pub statics: FxHashMap<LocalDefId, surface::Ty>,
+    /// Maps the local item annotated with `#[flux::extern_spec]` to the item in another crate
+    /// it specifies.
+    pub externs: FxHashMap<LocalDefId, DefId>,
+    /// The generic arguments an extern spec for a method in a trait impl fixes to a concrete type
+    /// (see [`SpecCollector::extract_extern_fn`]).
+    pub extern_instances: FxHashMap<LocalDefId, Vec<Option<rustc::ty::Ty>>>,
     pub crate_config: Option<config::CrateConfig>,
 }
 
 pub(crate) struct FnSpec {
     pub fn_sig: Option<surface::FnSig>,
     pub assume: bool,
+    /// Whether the function is annotated with `#[flux::reflect]`
+    pub reflect: bool,
 }
 
 #[derive(Debug)]
@@ -83,10 +108,21 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
                 ItemKind::Mod(..) => collector.parse_mod_spec(def_id, attrs),
                 ItemKind::TyAlias(..) => collector.parse_tyalias_spec(def_id, attrs),
                 ItemKind::Const(_ty, _body_id) => collector.parse_const_spec(item, attrs),
+                ItemKind::Static(..) => collector.parse_static_spec(def_id, attrs),
                 _ => Ok(()),
             };
         }
 
+        for trait_item_id in crate_items.trait_items() {
+            let trait_item = tcx.hir().trait_item(trait_item_id);
+            let def_id = trait_item.owner_id.def_id;
+            if let TraitItemKind::Fn(..) = &trait_item.kind {
+                let hir_id = trait_item.hir_id();
+                let attrs = tcx.hir().attrs(hir_id);
+                let _ = collector.parse_fn_spec(def_id, attrs);
+            }
+        }
+
         for impl_item_id in crate_items.impl_items() {
             let impl_item = tcx.hir().impl_item(impl_item_id);
             let def_id = impl_item.owner_id.def_id;
@@ -97,6 +133,15 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             }
         }
 
+        // Closures are not items, so we look for their annotations among the owners of bodies.
+        for def_id in tcx.hir().body_owners() {
+            if tcx.is_closure(def_id.to_def_id()) {
+                let hir_id = tcx.hir().local_def_id_to_hir_id(def_id);
+                let attrs = tcx.hir().attrs(hir_id);
+                let _ = collector.parse_fn_spec(def_id, attrs);
+            }
+        }
+
         if let Some(e) = collector.error_guaranteed {
             Err(e)
         } else {
@@ -113,13 +158,191 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         self.report_dups(&attrs)?;
         // TODO(nilehmann) error if it has non-fun attrs
 
-        let assume = attrs.assume();
+        let mut assume = attrs.assume();
+        let reflect = attrs.reflect();
         let fn_sig = attrs.fn_sig();
 
-        self.specs.fns.insert(def_id, FnSpec { fn_sig, assume });
+        if attrs.extern_spec() {
+            let extern_def_id = self.extract_extern_fn(def_id)?;
+            self.specs.externs.insert(def_id, extern_def_id);
+            // The body of an extern spec is only used to name the extern function, so there is
+            // nothing to check.
+            assume = true;
+        }
+
+        self.specs
+            .fns
+            .insert(def_id, FnSpec { fn_sig, assume, reflect });
+        Ok(())
+    }
+
+    /// The body of a function annotated with `#[flux::extern_spec]` must be a single call to the
+    /// extern function, e.g.,
+    ///
+    /// ```ignore
+    /// #[flux::extern_spec]
+    /// #[flux::sig(fn(&Vec<T>[@n]) -> usize[n])]
+    /// fn len<T>(v: &Vec<T>) -> usize {
+    ///     Vec::len(v)
+    /// }
+    /// ```
+    fn extract_extern_fn(&mut self, def_id: LocalDefId) -> Result<DefId, ErrorGuaranteed> {
+        let span = self.tcx.def_span(def_id);
+        let body = self.tcx.hir().body(self.tcx.hir().body_owned_by(def_id));
+        let ExprKind::Block(block, _) = body.value.kind else {
+            return Err(self.emit_err(errors::MalformedExternSpec { span }));
+        };
+        let typeck_results = self.tcx.typeck(def_id);
+        let (res, hir_id) = match block.expr {
+            Some(expr) if block.stmts.is_empty() => {
+                match &expr.kind {
+                    ExprKind::Call(callee, _) if let ExprKind::Path(qpath) = &callee.kind => {
+                        (typeck_results.qpath_res(qpath, callee.hir_id), callee.hir_id)
+                    }
+                    ExprKind::MethodCall(..) => {
+                        let res = typeck_results
+                            .type_dependent_def(expr.hir_id)
+                            .map_or(Res::Err, |(kind, def_id)| Res::Def(kind, def_id));
+                        (res, expr.hir_id)
+                    }
+                    _ => (Res::Err, expr.hir_id),
+                }
+            }
+            _ => (Res::Err, body.value.hir_id),
+        };
+        let extern_def_id = match res {
+            Res::Def(DefKind::Fn | DefKind::AssocFn, extern_def_id) => extern_def_id,
+            _ => return Err(self.emit_err(errors::MalformedExternSpec { span })),
+        };
+        // Calls to a trait method are resolved to the method in the impl, e.g., the spec for
+        // `<Vec<T> as Index<usize>>::index(v, i)` is attached to `Vec`'s implementation of `index`.
+        // The impl may be more generic than the spec, e.g., `Vec` implements `Index<I>` for every
+        // `I: SliceIndex<[T]>`, so we record the generic arguments of the impl fixed by the spec
+        // and only use the spec for calls instantiating them in the same way.
+        // If the impl cannot be selected because its bounds are only satisfied through a where
+        // clause of the spec, e.g., `where Range<A>: Iterator`, we look for an impl of the trait
+        // for the self type instead.
+        let extern_def_id = if let Some(trait_def_id) = self.tcx.trait_of_item(extern_def_id) {
+            let substs = typeck_results.node_substs(hir_id);
+            let param_env = self.tcx.param_env(def_id);
+            match self
+                .tcx
+                .resolve_instance(param_env.and((extern_def_id, substs)))
+            {
+                Ok(Some(instance)) if instance.def_id() != extern_def_id => {
+                    self.record_extern_instance(def_id, instance.substs)?;
+                    instance.def_id()
+                }
+                // A spec for a method called on a type parameter is a spec for the trait method,
+                // which is inherited by the impls without a refined signature.
+                _ if let ty::Param(_) = substs.type_at(0).kind() => extern_def_id,
+                _ => {
+                    self.tcx
+                        .find_map_relevant_impl(trait_def_id, substs.type_at(0), |impl_def_id| {
+                            self.tcx
+                                .impl_item_implementor_ids(impl_def_id)
+                                .get(&extern_def_id)
+                                .copied()
+                        })
+                        .unwrap_or(extern_def_id)
+                }
+            }
+        } else {
+            extern_def_id
+        };
+        if extern_def_id.is_local() {
+            return Err(self.emit_err(errors::MalformedExternSpec { span }));
+        }
+        Ok(extern_def_id)
+    }
+
+    fn record_extern_instance(
+        &mut self,
+        def_id: LocalDefId,
+        substs: SubstsRef<'tcx>,
+    ) -> Result<(), ErrorGuaranteed> {
+        let tys = self
+            .tcx
+            .erase_regions(substs)
+            .iter()
+            .map(|arg| {
+                match arg.unpack() {
+                    GenericArgKind::Type(ty) if !ty.needs_subst() => {
+                        rustc::lowering::lower_ty(self.tcx, ty).map(Some)
+                    }
+                    _ => Ok(None),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                self.emit_err(errors::MalformedExternSpec { span: self.tcx.def_span(def_id) })
+            })?;
+        if tys.iter().any(Option::is_some) {
+            self.specs.extern_instances.insert(def_id, tys);
+        }
         Ok(())
     }
 
+    /// The last field of a struct annotated with `#[flux::extern_spec]` must have the extern type.
+    /// If it is the only field, e.g., `struct VecSpec<T>(Vec<T>);`, the extern struct is opaque.
+    /// Otherwise, the fields before it must mirror the fields of the extern struct, e.g.,
+    ///
+    /// ```ignore
+    /// #[flux::extern_spec]
+    /// #[flux::refined_by(lo: int, hi: int)]
+    /// struct RangeSpec<Idx> {
+    ///     #[flux::field(Idx[@lo])]
+    ///     start: Idx,
+    ///     #[flux::field(Idx[@hi])]
+    ///     end: Idx,
+    ///     _extern: Range<Idx>,
+    /// }
+    /// ```
+    fn extract_extern_adt(&mut self, def_id: LocalDefId) -> Result<DefId, ErrorGuaranteed> {
+        let span = self.tcx.def_span(def_id);
+        let fields = &self.tcx.adt_def(def_id).non_enum_variant().fields;
+        if let Some(field) = fields.last() &&
+            let rustc_middle::ty::Adt(adt_def, _) = self.tcx.type_of(field.did).kind() &&
+            adt_def.is_struct() &&
+            !adt_def.did().is_local() &&
+            (fields.len() == 1 || adt_def.non_enum_variant().fields.len() + 1 == fields.len())
+        {
+            Ok(adt_def.did())
+        } else {
+            Err(self.emit_err(errors::MalformedExternSpec { span }))
+        }
+    }
+
+    /// An enum annotated with `#[flux::extern_spec]` must list the variants of the extern enum
+    /// followed by an extra variant with a single field of the extern type, e.g.,
+    ///
+    /// ```ignore
+    /// #[flux::extern_spec]
+    /// #[flux::refined_by(b: bool)]
+    /// enum OptionSpec<T> {
+    ///     #[flux::variant(Option<T>[false])]
+    ///     None,
+    ///     #[flux::variant({T} -> Option<T>[true])]
+    ///     Some(T),
+    ///     Extern(Option<T>),
+    /// }
+    /// ```
+    fn extract_extern_enum(&mut self, def_id: LocalDefId) -> Result<DefId, ErrorGuaranteed> {
+        let span = self.tcx.def_span(def_id);
+        let variants = self.tcx.adt_def(def_id).variants();
+        if let Some(variant) = variants.iter().last() &&
+            let [field] = &variant.fields[..] &&
+            let rustc_middle::ty::Adt(adt_def, _) = self.tcx.type_of(field.did).kind() &&
+            adt_def.is_enum() &&
+            !adt_def.did().is_local() &&
+            adt_def.variants().len() + 1 == variants.len()
+        {
+            Ok(adt_def.did())
+        } else {
+            Err(self.emit_err(errors::MalformedExternSpec { span }))
+        }
+    }
+
     fn parse_const_spec(
         &mut self,
         item: &Item,
@@ -147,6 +370,37 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             Err(self.emit_err(errors::InvalidConstant { span }))
         }
     }
+
+    /// The annotation on a static is a type rather than a function signature, so we parse the
+    /// `#[flux::sig]` attribute here instead of going through [`Self::parse_flux_attr`].
+    fn parse_static_spec(
+        &mut self,
+        def_id: LocalDefId,
+        attrs: &[Attribute],
+    ) -> Result<(), ErrorGuaranteed> {
+        for attr in attrs {
+            let AttrKind::Normal(attr_item, ..) = &attr.kind else { continue };
+            let attr_item = &attr_item.item;
+            let segments = &attr_item.path.segments[..];
+            if !matches!(segments, [first, ..] if first.ident.as_str() == "flux") {
+                continue;
+            }
+            match (segments, &attr_item.args) {
+                ([_, segment], MacArgs::Delimited(span, _, tokens))
+                    if segment.ident.as_str() == "sig" =>
+                {
+                    let ty = self.parse(tokens.clone(), span.entire(), parse_ty)?;
+                    if self.specs.statics.insert(def_id, ty).is_some() {
+                        let span = attr_item.span();
+                        return Err(self.emit_err(errors::DuplicatedAttr { span, name: "sig" }));
+                    }
+                }
+                _ => return Err(self.emit_err(errors::InvalidAttr { span: attr_item.span() })),
+            }
+        }
+        Ok(())
+    }
+
     fn parse_tyalias_spec(
         &mut self,
         _def_id: LocalDefId,
@@ -180,8 +434,18 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         let mut attrs = self.parse_flux_attrs(attrs)?;
         self.report_dups(&attrs)?;
         let refined_by = attrs.refined_by();
-        let variants = def
-            .variants
+        let reflect = attrs.reflect();
+        let extern_spec = attrs.extern_spec();
+
+        let mut variants = def.variants;
+        if extern_spec {
+            let extern_def_id = self.extract_extern_enum(def_id)?;
+            self.specs.externs.insert(def_id, extern_def_id);
+            // The last variant only names the extern enum and it is not part of the spec.
+            variants = &variants[..variants.len() - 1];
+        }
+
+        let variants = variants
             .iter()
             .map(|variant| self.parse_variant_spec(self.tcx.hir().attrs(variant.id)))
             .try_collect_exhaust()?;
@@ -193,6 +457,17 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
 
         let invariants = attrs.invariants();
 
+        if reflect {
+            if refined_by.is_some() || extern_spec || !variants.is_empty() || !invariants.is_empty()
+            {
+                return Err(
+                    self.emit_err(errors::InvalidReflect { span: self.tcx.def_span(def_id) })
+                );
+            }
+            self.specs.reflected_adts.push(def_id);
+            return Ok(());
+        }
+
         self.specs
             .enums
             .insert(def_id, surface::EnumDef { def_id, refined_by, variants, invariants });
@@ -210,18 +485,44 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         // TODO(nilehmann) error on field attrs if opaque
         // TODO(nilehmann) error if it has non-struct attrs
 
-        let opaque = attrs.opaque();
+        let mut opaque = attrs.opaque();
+        let reflect = attrs.reflect();
+        let extern_spec = attrs.extern_spec();
+
+        let mut fields = data.fields();
+        if extern_spec {
+            let extern_def_id = self.extract_extern_adt(def_id)?;
+            self.specs.externs.insert(def_id, extern_def_id);
+            // The last field only names the extern struct and it is not part of the spec. If
+            // there are no other fields, we cannot see the fields of the extern struct.
+            fields = &fields[..fields.len() - 1];
+            opaque |= fields.is_empty();
+        }
 
         let refined_by = attrs.refined_by();
 
-        let fields = data
-            .fields()
+        let fields: Vec<_> = fields
             .iter()
             .map(|field| self.parse_field_spec(self.tcx.hir().attrs(field.hir_id)))
             .try_collect_exhaust()?;
 
         let invariants = attrs.invariants();
 
+        if reflect {
+            if refined_by.is_some()
+                || opaque
+                || extern_spec
+                || fields.iter().any(Option::is_some)
+                || !invariants.is_empty()
+            {
+                return Err(
+                    self.emit_err(errors::InvalidReflect { span: self.tcx.def_span(def_id) })
+                );
+            }
+            self.specs.reflected_adts.push(def_id);
+            return Ok(());
+        }
+
         self.specs
             .structs
             .insert(def_id, surface::StructDef { def_id, refined_by, fields, opaque, invariants });
@@ -244,6 +545,15 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
         let mut uif_defs = attrs.uif_defs();
         self.specs.uifs.append(&mut uif_defs);
 
+        let mut defns = attrs.defns();
+        self.specs.defns.append(&mut defns);
+
+        let mut opaque_sorts = attrs.opaque_sorts();
+        self.specs.opaque_sorts.append(&mut opaque_sorts);
+
+        let mut axioms = attrs.axioms();
+        self.specs.axioms.append(&mut axioms);
+
         let crate_config = attrs.crate_config();
         self.specs.crate_config = crate_config;
         Ok(())
@@ -312,6 +622,18 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
                 let uif_def = self.parse(tokens.clone(), span.entire(), parse_uif_def)?;
                 FluxAttrKind::UifDef(uif_def)
             }
+            ("opaque_sort", MacArgs::Delimited(span, _, tokens)) => {
+                let decl = self.parse(tokens.clone(), span.entire(), parse_opaque_sort_decl)?;
+                FluxAttrKind::OpaqueSort(decl)
+            }
+            ("axiom", MacArgs::Delimited(span, _, tokens)) => {
+                let axiom = self.parse(tokens.clone(), span.entire(), parse_axiom)?;
+                FluxAttrKind::Axiom(axiom)
+            }
+            ("defs", MacArgs::Delimited(span, _, tokens)) => {
+                let defns = self.parse(tokens.clone(), span.entire(), parse_defns)?;
+                FluxAttrKind::Defns(defns)
+            }
             ("cfg", MacArgs::Delimited(_, _, _)) => {
                 let crate_cfg = FluxAttrCFG::parse_cfg(attr_item)
                     .emit(self.sess)?
@@ -338,6 +660,8 @@ impl<'tcx, 'a> SpecCollector<'tcx, 'a> {
             ("ignore", MacArgs::Empty) => FluxAttrKind::Ignore,
             ("opaque", MacArgs::Empty) => FluxAttrKind::Opaque,
             ("assume", MacArgs::Empty) => FluxAttrKind::Assume,
+            ("reflect", MacArgs::Empty) => FluxAttrKind::Reflect,
+            ("extern_spec", MacArgs::Empty) => FluxAttrKind::ExternSpec,
             _ => return Err(self.emit_err(errors::InvalidAttr { span: attr_item.span() })),
         };
         Ok(FluxAttr { kind, span: attr_item.span() })
@@ -390,11 +714,18 @@ impl Specs {
             fns: FxHashMap::default(),
             structs: FxHashMap::default(),
             enums: FxHashMap::default(),
+            reflected_adts: Vec::default(),
             qualifs: Vec::default(),
             uifs: Vec::default(),
+            opaque_sorts: Vec::default(),
+            axioms: Vec::default(),
+            defns: Vec::default(),
             aliases: FxHashMap::default(),
             ignores: FxHashSet::default(),
             consts: FxHashMap::default(),
+            statics: FxHashMap::default(),
+            externs: FxHashMap::default(),
+            extern_instances: FxHashMap::default(),
             crate_config: None,
         }
     }
@@ -414,11 +745,15 @@ struct FluxAttr {
 #[derive(Debug)]
 enum FluxAttrKind {
     Assume,
+    Reflect,
     Opaque,
     FnSig(surface::FnSig),
     RefinedBy(surface::RefinedBy),
     Qualifier(surface::Qualifier),
     UifDef(surface::UifDef),
+    OpaqueSort(surface::OpaqueSortDecl),
+    Axiom(surface::Axiom),
+    Defns(Vec<surface::Defn>),
     TypeAlias(surface::Alias),
     Field(surface::Ty),
     Variant(surface::VariantDef),
@@ -426,6 +761,7 @@ enum FluxAttrKind {
     CrateConfig(config::CrateConfig),
     Invariant(surface::Expr),
     Ignore,
+    ExternSpec,
 }
 
 macro_rules! attr_name {
@@ -481,6 +817,10 @@ impl FluxAttrs {
         read_flag!(self, Assume)
     }
 
+    fn reflect(&mut self) -> bool {
+        read_flag!(self, Reflect)
+    }
+
     fn ignore(&mut self) -> bool {
         read_flag!(self, Ignore)
     }
@@ -489,6 +829,10 @@ impl FluxAttrs {
         read_flag!(self, Opaque)
     }
 
+    fn extern_spec(&mut self) -> bool {
+        read_flag!(self, ExternSpec)
+    }
+
     fn fn_sig(&mut self) -> Option<surface::FnSig> {
         read_attr!(self, FnSig)
     }
@@ -505,6 +849,18 @@ impl FluxAttrs {
         read_attrs!(self, UifDef)
     }
 
+    fn opaque_sorts(&mut self) -> Vec<surface::OpaqueSortDecl> {
+        read_attrs!(self, OpaqueSort)
+    }
+
+    fn axioms(&mut self) -> Vec<surface::Axiom> {
+        read_attrs!(self, Axiom)
+    }
+
+    fn defns(&mut self) -> Vec<surface::Defn> {
+        read_attrs!(self, Defns).into_iter().flatten().collect()
+    }
+
     fn alias(&mut self) -> Option<surface::Alias> {
         read_attr!(self, TypeAlias)
     }
@@ -534,6 +890,7 @@ impl FluxAttrKind {
     fn name(&self) -> &'static str {
         match self {
             FluxAttrKind::Assume => attr_name!(Assume),
+            FluxAttrKind::Reflect => attr_name!(Reflect),
             FluxAttrKind::Opaque => attr_name!(Opaque),
             FluxAttrKind::FnSig(_) => attr_name!(FnSig),
             FluxAttrKind::ConstSig(_) => attr_name!(ConstSig),
@@ -545,7 +902,11 @@ impl FluxAttrKind {
             FluxAttrKind::CrateConfig(_) => attr_name!(CrateConfig),
             FluxAttrKind::Ignore => attr_name!(Ignore),
             FluxAttrKind::UifDef(_) => attr_name!(UifDef),
+            FluxAttrKind::OpaqueSort(_) => attr_name!(OpaqueSort),
+            FluxAttrKind::Axiom(_) => attr_name!(Axiom),
+            FluxAttrKind::Defns(_) => attr_name!(Defns),
             FluxAttrKind::Invariant(_) => attr_name!(Invariant),
+            FluxAttrKind::ExternSpec => attr_name!(ExternSpec),
         }
     }
 }
@@ -622,7 +983,7 @@ impl FluxAttrCFG {
                     // TODO: support types of values other than strings
                     let value = item
                         .value_str()
-                        .map(Symbol::to_ident_string)
+                        .map(|value| value.as_str().to_string())
                         .ok_or_else(|| {
                             errors::CFGError { span, message: "unsupported value".to_string() }
                         })?;
@@ -650,6 +1011,11 @@ impl FluxAttrCFG {
             try_read_setting!(self, "dump_checker_trace", bool, config::CONFIG.dump_checker_trace)?;
         let check_asserts =
             try_read_setting!(self, "check_asserts", AssertBehavior, config::CONFIG.check_asserts)?;
+        let check_overflow =
+            try_read_setting!(self, "check_overflow", bool, config::CONFIG.check_overflow)?;
+        let check_macros =
+            try_read_setting!(self, "check_macros", bool, config::CONFIG.check_macros)?;
+        let real_floats = try_read_setting!(self, "real_floats", bool, config::CONFIG.real_floats)?;
 
         if let Some((name, setting)) = self.map.iter().next() {
             return Err(errors::CFGError {
@@ -658,7 +1024,15 @@ impl FluxAttrCFG {
             });
         }
 
-        Ok(CrateConfig { log_dir, dump_constraint, dump_checker_trace, check_asserts })
+        Ok(CrateConfig {
+            log_dir,
+            dump_constraint,
+            dump_checker_trace,
+            check_asserts,
+            check_overflow,
+            check_macros,
+            real_floats,
+        })
     }
 }
 
@@ -681,6 +1055,13 @@ mod errors {
         pub span: Span,
     }
 
+    #[derive(Diagnostic)]
+    #[diag(parse::invalid_reflect, code = "FLUX")]
+    pub struct InvalidReflect {
+        #[primary_span]
+        pub span: Span,
+    }
+
     #[derive(Diagnostic)]
     #[diag(parse::invalid_constant, code = "FLUX")]
     pub struct InvalidConstant {
@@ -688,6 +1069,14 @@ mod errors {
         pub span: Span,
     }
 
+    #[derive(Diagnostic)]
+    #[diag(parse::malformed_extern_spec, code = "FLUX")]
+    #[note]
+    pub struct MalformedExternSpec {
+        #[primary_span]
+        pub span: Span,
+    }
+
     #[derive(Diagnostic)]
     #[diag(parse::cfg_error, code = "FLUX")]
     pub struct CFGError {
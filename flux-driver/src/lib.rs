@@ -1,8 +1,9 @@
-#![feature(rustc_private, box_patterns, once_cell)]
+#![feature(rustc_private, box_patterns, once_cell, let_chains, if_let_guard)]
 
 extern crate rustc_ast;
 extern crate rustc_ast_pretty;
 extern crate rustc_borrowck;
+extern crate rustc_data_structures;
 extern crate rustc_driver;
 extern crate rustc_errors;
 extern crate rustc_hash;
@@ -11,12 +12,15 @@ extern crate rustc_interface;
 extern crate rustc_middle;
 extern crate rustc_session;
 extern crate rustc_span;
+extern crate rustc_target;
 
 mod callbacks;
 mod collector;
 mod mir_storage;
+mod std_specs;
 
 use callbacks::FluxCallbacks;
+use flux_common::config::CONFIG;
 use rustc_driver::{catch_with_exit_code, RunCompiler};
 
 /// Get the path to the sysroot of the current rustup toolchain. Return `None` if the rustup
@@ -34,6 +38,14 @@ pub fn run_compiler(mut args: Vec<String>, in_cargo: bool) -> i32 {
     args.push(sysroot().expect("Flux Rust requires rustup to be built."));
     // Add release mode to the arguments.
     args.push("-O".into());
+    // Release mode disables overflow checks. When checking for overflow is enabled globally we
+    // turn them back on to get the checked arithmetic operations in MIR. Only the global setting
+    // can do this, because the arguments are fixed before the crate is parsed. Crates enabling it
+    // with `#![flux::cfg(check_overflow = "true")]` are checked on the plain operations instead,
+    // which is equally sound but reports overflow on the operation rather than on an assert.
+    if CONFIG.check_overflow {
+        args.push("-Coverflow-checks=on".into());
+    }
     // HACK(nilehmann) When running flux we want to stop compilation after analysis
     // to avoid creating a binary. However, stopping compilation messes up with cargo so we
     // pass full_compilation=true if we detect we are being called from cargo
@@ -0,0 +1,132 @@
+//! Refined signatures for the standard library bundled with flux.
+//!
+//! The specs are written as regular extern specs in `specs/std.rs`. Instead of adding them to the
+//! crate being checked, we compile them as a standalone crate with a separate invocation of flux,
+//! which exports them in a sidecar file (see [`flux_middle::metadata`]). We then load the sidecar
+//! as if the specs were exported by a dependency, so extern specs in the crate or its dependencies
+//! take precedence over the bundled ones. The compiled specs are cached next to the output of the
+//! crate being checked.
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use flux_errors::FluxSession;
+use flux_middle::metadata::CrateMetadata;
+use rustc_ast::Attribute;
+use rustc_hash::FxHasher;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::{sym, Symbol};
+use rustc_target::spec::TargetTriple;
+
+const SPECS: &str = include_str!("../specs/std.rs");
+
+const CRATE_NAME: &str = "flux_std_specs";
+
+/// Loads the bundled specs for a crate using the flux tool, compiling them if they are not cached.
+/// Crates without `std` are left alone because the specs mention items that are only available in
+/// `std`.
+pub(crate) fn load(tcx: TyCtxt, sess: &FluxSession) -> Option<CrateMetadata> {
+    if is_std_specs_crate(tcx) || is_no_std(tcx.hir().krate_attrs()) {
+        return None;
+    }
+    let dir = cache_dir(tcx);
+    if !sidecar_path(&dir).exists() {
+        if let Err(err) = build(tcx, &dir) {
+            sess.emit_warning(errors::CannotBuildStdSpecs { err });
+            return None;
+        }
+    }
+    CrateMetadata::read_file(tcx, sess, Symbol::intern(CRATE_NAME), &sidecar_path(&dir), None)
+}
+
+/// Whether the crate being checked is the one with the bundled specs.
+pub(crate) fn is_std_specs_crate(tcx: TyCtxt) -> bool {
+    tcx.crate_name(LOCAL_CRATE).as_str() == CRATE_NAME
+}
+
+/// The crate with the bundled specs is never loaded by the crates using them, so we can only
+/// export the refinements for items defined in other crates.
+pub(crate) fn retain_extern_items(meta: &mut CrateMetadata) {
+    meta.fn_sigs.retain(|(def_id, _)| !def_id.is_local());
+    meta.adts.retain(|adt| !adt.adt_def.def_id().is_local());
+    meta.data_decls
+        .retain(|data_decl| !data_decl.def_id.is_local());
+}
+
+/// The directory where the specs are compiled. Its name includes a hash of everything the compiled
+/// specs depend on, so we never use specs compiled by a different version of flux or for a
+/// different standard library.
+fn cache_dir(tcx: TyCtxt) -> PathBuf {
+    let mut hasher = FxHasher::default();
+    SPECS.hash(&mut hasher);
+    tcx.sess.sysroot.hash(&mut hasher);
+    tcx.sess.opts.target_triple.hash(&mut hasher);
+    if let Ok(modified) = std::env::current_exe().and_then(|exe| exe.metadata()?.modified()) {
+        modified.hash(&mut hasher);
+    }
+    tcx.output_filenames(())
+        .out_directory
+        .join(format!("{CRATE_NAME}-{:016x}", hasher.finish()))
+}
+
+fn sidecar_path(dir: &Path) -> PathBuf {
+    CrateMetadata::sidecar_path(&dir.join(format!("lib{CRATE_NAME}.rmeta")))
+}
+
+/// Compiles the specs into `dir`. We compile them in a fresh directory and move it into place once
+/// we are done, so concurrent invocations of flux never see a partially written directory.
+fn build(tcx: TyCtxt, dir: &Path) -> Result<(), String> {
+    let tmp_dir = dir.with_extension(format!("tmp{}", std::process::id()));
+    let result = compile(tcx, &tmp_dir).and_then(|()| {
+        match std::fs::rename(&tmp_dir, dir) {
+            Ok(()) => Ok(()),
+            // Another invocation compiled the specs in the meantime
+            Err(_) if sidecar_path(dir).exists() => Ok(()),
+            Err(err) => Err(err.to_string()),
+        }
+    });
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+    result
+}
+
+fn compile(tcx: TyCtxt, out_dir: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(out_dir).map_err(|err| err.to_string())?;
+    let src = out_dir.join(format!("{CRATE_NAME}.rs"));
+    std::fs::write(&src, format!("#![feature(register_tool)]\n#![register_tool(flux)]\n{SPECS}"))
+        .map_err(|err| err.to_string())?;
+
+    let mut cmd = Command::new(std::env::current_exe().map_err(|err| err.to_string())?);
+    cmd.arg(&src)
+        .args(["--crate-name", CRATE_NAME, "--crate-type", "rlib", "--emit=metadata"])
+        .args(["--edition=2021", "-A", "warnings"])
+        .arg("--out-dir")
+        .arg(out_dir);
+    if let TargetTriple::TargetTriple(triple) = &tcx.sess.opts.target_triple {
+        cmd.args(["--target", triple]);
+    }
+    let output = cmd.output().map_err(|err| err.to_string())?;
+    if output.status.success() && sidecar_path(out_dir).exists() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
+
+fn is_no_std(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| attr.has_name(sym::no_std) || attr.has_name(sym::no_core))
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck::cannot_build_std_specs, code = "FLUX")]
+    pub struct CannotBuildStdSpecs {
+        pub err: String,
+    }
+}
@@ -53,3 +53,26 @@ desugar_def_span_note =
 desugar_illegal_binder =
     illegal binder
     .label = binder not allowed in this position
+
+desugar_invalid_impl_trait =
+    invalid `impl Trait` type
+    .label = does not refer to an opaque type
+
+desugar_reflect_unsupported_signature =
+    reflected functions can only take and return integers and booleans
+
+desugar_reflect_unsupported_operation =
+    unsupported operation in reflected function
+    .note = reflected functions can only use arithmetic, comparisons, branches, and calls to other reflected functions
+
+desugar_reflect_unsupported_loop =
+    loops are not supported in reflected functions
+
+desugar_reflect_recursive =
+    reflected function `{$name}` cannot be recursive
+
+desugar_reflect_generic_adt =
+    reflected types cannot have generic parameters
+
+desugar_reflect_unsupported_field =
+    fields of reflected types can only be integers, booleans, or other reflected types
@@ -10,8 +10,15 @@ parse_cfg_error =
 parse_syntax_err =
     syntax error: {$msg}
 
+parse_invalid_reflect =
+    a type annotated with `#[flux::reflect]` cannot have other flux annotations
+
 parse_invalid_constant =
     invalid constant
 
+parse_malformed_extern_spec =
+    malformed extern spec
+    .note = an extern spec must be a function whose body calls an extern function, a struct with a single field of an extern type, or an enum whose last variant has a single field of an extern type
+
 parse_invalid_alias_application =
     invalid alias application
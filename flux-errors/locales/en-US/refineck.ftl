@@ -37,3 +37,22 @@ refineck_opaque_struct_error =
 refineck_unsupported_call =
     unsupported type in function call
     .function_definition = function defined here
+
+refineck_unsupported_fn_ptr =
+    cannot use a function with strong references as a function pointer
+
+refineck_unsupported =
+    unsupported {$descr}
+
+refineck_macro_not_checked =
+    function generated by a macro is not checked
+    .note = checking macro-expanded code is disabled with `check_macros = "false"`
+
+refineck_cannot_write_metadata =
+    cannot write flux metadata: {$err}
+
+refineck_cannot_build_std_specs =
+    cannot build the specs for the standard library bundled with flux: {$err}
+
+refineck_cannot_read_metadata =
+    cannot read flux metadata of crate `{$krate}`: {$err}
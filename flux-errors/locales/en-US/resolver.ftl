@@ -5,3 +5,7 @@ resolver_unsupported_signature =
 resolver_unresolved_path =
     cannot resolve `{$path}`
     .help = flux can only resolve a path if it is present in the definition being refined
+
+resolver_unresolved_impl_trait =
+    cannot resolve `impl {$trait_name}`
+    .help = `impl Trait` types are matched in the order they appear in the Rust signature
@@ -22,6 +22,23 @@ wf_missing_ensures =
 wf_expected_fun =
     expected function, found `{$found}`
 
+wf_expected_bitvec =
+    mismatched sorts
+    .label = expected bit vector, found `{$found}`
+    .help = integers can be converted with `int_to_bv32` or `int_to_bv64`
+
+wf_expected_set =
+    mismatched sorts
+    .label = expected set, found `{$found}`
+
+wf_expected_map =
+    mismatched sorts
+    .label = expected map, found `{$found}`
+
+wf_cannot_infer_sort =
+    cannot infer sort
+    .label = the sort of this expression cannot be determined from its arguments
+
 wf_invalid_param_in_func_pos =
     illegal use of refinement parameter
     .label = {$is_pred ->
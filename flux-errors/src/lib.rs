@@ -59,6 +59,13 @@ impl FluxSession {
         err.into_diagnostic(&self.parse_sess.span_diagnostic).emit()
     }
 
+    #[track_caller]
+    pub fn emit_warning<'a>(&'a self, warning: impl IntoDiagnostic<'a, ()>) {
+        warning
+            .into_diagnostic(&self.parse_sess.span_diagnostic)
+            .emit();
+    }
+
     pub fn abort_if_errors(&self) {
         self.parse_sess.span_diagnostic.abort_if_errors();
     }
@@ -6,6 +6,7 @@ use std::{
 use flux_common::format::PadAdapter;
 use itertools::Itertools;
 use rustc_index::newtype_index;
+use rustc_macros::{Decodable, Encodable};
 
 pub enum Constraint<Tag> {
     Pred(Pred, Option<Tag>),
@@ -18,9 +19,18 @@ pub enum Constraint<Tag> {
 pub enum Sort {
     Int,
     Bool,
+    Real,
     Unit,
     Pair(Box<Sort>, Box<Sort>),
+    /// A bit vector with the given width
+    BitVec(u32),
     Func(FuncSort),
+    /// A finite set of elements of the given sort
+    Set(Box<Sort>),
+    /// A map from keys of the first sort to values of the second one
+    Map(Box<Sort>, Box<Sort>),
+    /// A user defined datatype declared with a [`DataDecl`]
+    Data(String),
 }
 
 #[derive(Clone)]
@@ -43,12 +53,18 @@ pub enum Expr {
     Pair(Box<[Expr; 2]>),
     Proj(Box<Expr>, Proj),
     IfThenElse(Box<[Expr; 3]>),
+    /// A variable bound by an enclosing quantifier. Quantified variables are numbered by the
+    /// nesting level of the quantifier binding them.
+    BoundVar(BoundVar),
+    /// A quantified expression binding a single variable of the given sort.
+    Quant(Quantifier, BoundVar, Sort, Box<Expr>),
     Unit,
 }
 
 pub enum Func {
     Var(Name),
     Uif(String),
+    Theory(TheoryFunc),
 }
 
 #[derive(Clone, Copy)]
@@ -73,13 +89,26 @@ impl UifDef {
         UifDef { name, sort }
     }
 }
+
+/// A datatype without type parameters. Each constructor comes with a selector for each of its
+/// fields and the solver defines a test `is$ctor` for every constructor.
+pub struct DataDecl {
+    pub name: String,
+    pub ctors: Vec<DataCtor>,
+}
+
+pub struct DataCtor {
+    pub name: String,
+    pub fields: Vec<(String, Sort)>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Const {
     pub name: Name,
     pub val: i128,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum BinOp {
     Iff,
     Imp,
@@ -96,21 +125,69 @@ pub enum BinOp {
     Mul,
     Div,
     Mod,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+    /// Bitwise and of bit vectors
+    BitAnd,
+    /// Bitwise or of bit vectors
+    BitOr,
+    /// Bitwise xor of bit vectors
+    BitXor,
+    /// Left shift of bit vectors
+    Shl,
+    /// Logical right shift of bit vectors
+    Shr,
+}
+
+/// Functions interpreted by the theories of sets and maps, and conversions between integers and
+/// reals or bit vectors. The sorts of set and map functions are polymorphic in the sort of the elements, keys and
+/// values.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
+pub enum TheoryFunc {
+    /// The empty set. The solver expects a dummy integer argument.
+    SetEmpty,
+    SetSingleton,
+    SetUnion,
+    SetIntersection,
+    SetDifference,
+    SetMember,
+    SetSubset,
+    /// A map mapping every key to the same value
+    MapDefault,
+    MapSelect,
+    MapStore,
+    IntToReal,
+    /// The largest integer smaller than or equal to a real
+    RealToInt,
+    /// Conversions between integers and bit vectors. Fixpoint only supports widths of 32 and 64
+    /// bits.
+    IntToBv32,
+    IntToBv64,
+    Bv32ToInt,
+    Bv64ToInt,
+    /// Arithmetic right shift on bit vectors, i.e., shifting in copies of the sign bit
+    BvAShr,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
+pub enum Quantifier {
+    Forall,
+    Exists,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum UnOp {
     Not,
     Neg,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum Constant {
     Int(Sign, u128),
     Bool(bool),
+    /// The bits of a finite `f64`. We store the bits so constants can be compared and hashed.
+    Real(u64),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum Sign {
     Positive,
     Negative,
@@ -122,6 +199,12 @@ newtype_index! {
     }
 }
 
+newtype_index! {
+    pub struct BoundVar {
+        DEBUG_FORMAT = "b{}",
+    }
+}
+
 newtype_index! {
     pub struct Name {
         DEBUG_FORMAT = "a{}",
@@ -217,9 +300,14 @@ impl fmt::Display for Sort {
         match self {
             Sort::Int => write!(f, "int"),
             Sort::Bool => write!(f, "bool"),
+            Sort::Real => write!(f, "real"),
             Sort::Unit => write!(f, "Unit"),
             Sort::Pair(s1, s2) => write!(f, "(Pair {s1} {s2})"),
+            Sort::BitVec(size) => write!(f, "(BitVec Size{size})"),
             Sort::Func(sort) => write!(f, "{sort}"),
+            Sort::Set(sort) => write!(f, "(Set_Set {sort})"),
+            Sort::Map(k, v) => write!(f, "(Map_t {k} {v})"),
+            Sort::Data(name) => write!(f, "{name}"),
         }
     }
 }
@@ -283,6 +371,11 @@ impl fmt::Display for Expr {
         match self {
             Expr::Var(x) => write!(f, "{x:?}"),
             Expr::Constant(c) => write!(f, "{c}"),
+            Expr::BinaryOp(op, box [e1, e2]) if op.is_bitvec_op() => {
+                // Bit vector operators are theory functions in fixpoint and are thus printed
+                // in application form.
+                write!(f, "({op} {} {})", FmtParens(e1), FmtParens(e2))
+            }
             Expr::BinaryOp(op, box [e1, e2]) => {
                 write!(f, "{} {op} {}", FmtParens(e1), FmtParens(e2))?;
                 Ok(())
@@ -298,12 +391,17 @@ impl fmt::Display for Expr {
             Expr::Proj(e, Proj::Fst) => write!(f, "(fst {e})"),
             Expr::Proj(e, Proj::Snd) => write!(f, "(snd {e})"),
             Expr::Unit => write!(f, "Unit"),
+            Expr::App(func, args) if args.is_empty() => write!(f, "{func}"),
             Expr::App(func, args) => {
                 write!(f, "({func} {})", args.iter().map(FmtParens).format(" "),)
             }
             Expr::IfThenElse(box [p, e1, e2]) => {
                 write!(f, "if {p} then {e1} else {e2}")
             }
+            Expr::BoundVar(bvar) => write!(f, "{bvar:?}"),
+            Expr::Quant(quant, bvar, sort, body) => {
+                write!(f, "({quant} [{bvar:?} : {sort}] . {body})")
+            }
         }
     }
 }
@@ -313,6 +411,7 @@ impl fmt::Display for Func {
         match self {
             Func::Var(name) => write!(f, "{name:?}"),
             Func::Uif(uif) => write!(f, "{uif}"),
+            Func::Theory(func) => write!(f, "{func}"),
         }
     }
 }
@@ -466,16 +565,72 @@ impl fmt::Display for BinOp {
             BinOp::Mul => write!(f, "*"),
             BinOp::Div => write!(f, "/"),
             BinOp::Mod => write!(f, "mod"),
+            BinOp::BitAnd => write!(f, "bvand"),
+            BinOp::BitOr => write!(f, "bvor"),
+            BinOp::BitXor => write!(f, "bvxor"),
+            BinOp::Shl => write!(f, "bvshl"),
+            BinOp::Shr => write!(f, "bvlshr"),
         }
     }
 }
 
+impl BinOp {
+    pub fn is_bitvec_op(&self) -> bool {
+        matches!(self, BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::Shl | BinOp::Shr)
+    }
+}
+
 impl fmt::Debug for BinOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
     }
 }
 
+impl fmt::Display for TheoryFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TheoryFunc::SetEmpty => write!(f, "Set_empty"),
+            TheoryFunc::SetSingleton => write!(f, "Set_sng"),
+            TheoryFunc::SetUnion => write!(f, "Set_cup"),
+            TheoryFunc::SetIntersection => write!(f, "Set_cap"),
+            TheoryFunc::SetDifference => write!(f, "Set_dif"),
+            TheoryFunc::SetMember => write!(f, "Set_mem"),
+            TheoryFunc::SetSubset => write!(f, "Set_sub"),
+            TheoryFunc::MapDefault => write!(f, "Map_default"),
+            TheoryFunc::MapSelect => write!(f, "Map_select"),
+            TheoryFunc::MapStore => write!(f, "Map_store"),
+            TheoryFunc::IntToReal => write!(f, "to_real"),
+            TheoryFunc::RealToInt => write!(f, "to_int"),
+            TheoryFunc::IntToBv32 => write!(f, "int_to_bv32"),
+            TheoryFunc::IntToBv64 => write!(f, "int_to_bv64"),
+            TheoryFunc::Bv32ToInt => write!(f, "bv32_to_int"),
+            TheoryFunc::Bv64ToInt => write!(f, "bv64_to_int"),
+            TheoryFunc::BvAShr => write!(f, "bvashr"),
+        }
+    }
+}
+
+impl fmt::Debug for TheoryFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl fmt::Display for Quantifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantifier::Forall => write!(f, "forall"),
+            Quantifier::Exists => write!(f, "exists"),
+        }
+    }
+}
+
+impl fmt::Debug for Quantifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
 impl fmt::Display for UnOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -491,6 +646,16 @@ impl fmt::Display for Constant {
             Constant::Int(Sign::Positive, n) => write!(f, "{n}"),
             Constant::Int(Sign::Negative, n) => write!(f, "-{n}"),
             Constant::Bool(b) => write!(f, "{b}"),
+            Constant::Real(bits) => {
+                // Floats are printed without exponent, but we need a decimal point to
+                // distinguish reals from integers.
+                let val = f64::from_bits(*bits);
+                if val.fract() == 0.0 {
+                    write!(f, "{val:.1}")
+                } else {
+                    write!(f, "{val}")
+                }
+            }
         }
     }
 }
@@ -498,6 +663,11 @@ impl fmt::Display for Constant {
 impl Constant {
     pub const ZERO: Constant = Constant::Int(Sign::Positive, 0);
     pub const ONE: Constant = Constant::Int(Sign::Positive, 1);
+
+    /// Returns the real constant for `val` or `None` if it is infinite or NaN.
+    pub fn real(val: f64) -> Option<Constant> {
+        val.is_finite().then(|| Constant::Real(val.to_bits()))
+    }
 }
 
 impl From<u128> for Constant {
@@ -1,6 +1,7 @@
 #![feature(rustc_private, min_specialization, once_cell, box_patterns)]
 
 extern crate rustc_index;
+extern crate rustc_macros;
 extern crate rustc_serialize;
 
 mod constraint;
@@ -13,8 +14,8 @@ use std::{
 };
 
 pub use constraint::{
-    BinOp, Const, Constant, Constraint, Expr, Func, FuncSort, KVid, Name, Pred, Proj, Qualifier,
-    Sign, Sort, UifDef, UnOp,
+    BinOp, BoundVar, Const, Constant, Constraint, DataCtor, DataDecl, Expr, Func, FuncSort, KVid,
+    Name, Pred, Proj, Qualifier, Quantifier, Sign, Sort, TheoryFunc, UifDef, UnOp,
 };
 use flux_common::format::PadAdapter;
 use itertools::Itertools;
@@ -28,6 +29,10 @@ pub struct Task<Tag> {
     pub constraint: Constraint<Tag>,
     pub qualifiers: Vec<Qualifier>,
     pub uifs: Vec<UifDef>,
+    /// Closed formulas assumed by every constraint. Fixpoint doesn't have a way to declare global
+    /// facts, so they are added as hypotheses to the top of the constraint.
+    pub axioms: Vec<Expr>,
+    pub data_decls: Vec<DataDecl>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -66,8 +71,10 @@ impl<Tag: fmt::Display + FromStr> Task<Tag> {
         constraint: Constraint<Tag>,
         qualifiers: Vec<Qualifier>,
         uifs: Vec<UifDef>,
+        axioms: Vec<Expr>,
+        data_decls: Vec<DataDecl>,
     ) -> Self {
-        Task { constants, kvars, constraint, qualifiers, uifs }
+        Task { constants, kvars, constraint, qualifiers, uifs, axioms, data_decls }
     }
 
     pub fn check(&self) -> io::Result<FixpointResult<Tag>> {
@@ -109,6 +116,10 @@ impl<Tag: fmt::Display> fmt::Display for Task<Tag> {
         writeln!(f, "(data Pair 2 = [| Pair {{ fst: @(0), snd: @(1) }} ])")?;
         writeln!(f, "(data Unit 0 = [| Unit {{ }}])")?;
 
+        for data_decl in &self.data_decls {
+            writeln!(f, "{data_decl}")?;
+        }
+
         for (name, sort) in &self.constants {
             write!(f, "(constant {name:?} {sort:?})")?;
         }
@@ -123,11 +134,27 @@ impl<Tag: fmt::Display> fmt::Display for Task<Tag> {
 
         writeln!(f)?;
         write!(f, "(constraint")?;
-        write!(PadAdapter::wrap_fmt(f, 2), "\n{}", self.constraint)?;
+        write!(PadAdapter::wrap_fmt(f, 2), "\n{}", Assuming(&self.axioms, &self.constraint))?;
         writeln!(f, "\n)")
     }
 }
 
+/// A constraint guarded by a list of hypotheses
+struct Assuming<'a, Tag>(&'a [Expr], &'a Constraint<Tag>);
+
+impl<Tag: fmt::Display> fmt::Display for Assuming<'_, Tag> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            [] => write!(f, "{}", self.1),
+            [axiom, axioms @ ..] => {
+                write!(f, "(forall ((_ Unit) ({axiom}))")?;
+                write!(PadAdapter::wrap_fmt(f, 2), "\n{}", Assuming(axioms, self.1))?;
+                write!(f, "\n)")
+            }
+        }
+    }
+}
+
 impl fmt::Display for KVar {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -147,6 +174,35 @@ impl fmt::Display for UifDef {
     }
 }
 
+impl fmt::Display for DataDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "(data {} 0 = [{}])",
+            self.name,
+            self.ctors
+                .iter()
+                .format_with("", |ctor, f| f(&format_args!("| {ctor} ")))
+        )
+    }
+}
+
+impl fmt::Display for DataCtor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.fields.is_empty() {
+            return write!(f, "{} {{ }}", self.name);
+        }
+        write!(
+            f,
+            "{} {{ {} }}",
+            self.name,
+            self.fields
+                .iter()
+                .format_with(", ", |(name, sort), f| f(&format_args!("{name}: {sort}")))
+        )
+    }
+}
+
 impl<Tag: fmt::Display> fmt::Debug for Task<Tag> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Display::fmt(self, f)
@@ -23,18 +23,20 @@ use std::{
 };
 
 use flux_common::format::PadAdapter;
-pub use flux_fixpoint::BinOp;
+pub use flux_fixpoint::{BinOp, Quantifier, TheoryFunc};
 use itertools::Itertools;
+use rustc_data_structures::fx::FxIndexMap;
 use rustc_hash::{FxHashMap, FxHashSet};
 use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_index::newtype_index;
-pub use rustc_middle::ty::{FloatTy, IntTy, ParamTy, UintTy};
+use rustc_macros::{Decodable, Encodable};
+pub use rustc_middle::ty::{FloatTy, IntTy, ParamConst, ParamTy, UintTy};
 use rustc_span::{Span, Symbol, DUMMY_SP};
 pub use rustc_target::abi::VariantIdx;
 
 use crate::{
     intern::{impl_internable, List},
-    pretty,
+    pretty, rustc,
 };
 
 #[derive(Debug, Clone)]
@@ -51,19 +53,36 @@ pub struct Qualifier {
     pub expr: Expr,
 }
 
+/// A fact assumed by the solver when checking every function. The arguments are universally
+/// quantified over the body.
+#[derive(Debug)]
+pub struct Axiom {
+    pub args: Vec<RefineParam>,
+    pub expr: Expr,
+}
+
 /// A map between rust definitions and flux annotations in their desugared `fhir` form.
 ///
 /// note: `Map` is a very generic name, so we typically use the type qualified as `fhir::Map`.
 #[derive(Default, Debug)]
 pub struct Map {
     uifs: FxHashMap<Symbol, UifDef>,
+    defns: FxIndexMap<Symbol, Defn>,
     consts: FxHashMap<Symbol, ConstInfo>,
     qualifiers: Vec<Qualifier>,
-    adts: FxHashMap<LocalDefId, AdtDef>,
+    axioms: Vec<Axiom>,
+    opaque_sorts: FxHashSet<Symbol>,
+    adts: FxHashMap<DefId, AdtDef>,
+    data_decls: FxIndexMap<DefId, DataDecl>,
     structs: FxHashMap<LocalDefId, StructDef>,
     enums: FxHashMap<LocalDefId, EnumDef>,
     fns: FxHashMap<LocalDefId, FnSig>,
+    statics: FxHashMap<LocalDefId, Ty>,
     assumes: FxHashSet<LocalDefId>,
+    externs: FxHashMap<DefId, LocalDefId>,
+    extern_instances: FxHashMap<DefId, Vec<Option<rustc::ty::Ty>>>,
+    /// Whether floats are refined by reals, see [`BaseTy::Float`]
+    real_floats: bool,
 }
 
 #[derive(Debug)]
@@ -131,20 +150,35 @@ pub enum Ty {
     /// for specifying constraints on indexed values e.g. `{i32[@a] | 0 <= a}`
     Constr(Expr, Box<Ty>),
     Float(FloatTy),
-    Str,
-    Char,
     Ptr(Ident),
     Ref(RefKind, Box<Ty>),
     Param(ParamTy),
     Tuple(Vec<Ty>),
     Array(Box<Ty>, ArrayLen),
-    Slice(Box<Ty>),
+    Slice(Box<Ty>, ArrayLen),
+    /// A function pointer with its refined inputs and output. Function pointers don't have
+    /// refinement parameters of their own.
+    FnPtr(Vec<Ty>, Box<Ty>),
     Never,
+    /// A const generic parameter passed as a generic argument, e.g., the `N` in `Buf<N>`. This
+    /// is only valid as an argument of a [`BaseTy::Adt`].
+    Const(ParamConst),
+    /// The opaque type of an `impl Trait` in return position
+    Opaque(DefId),
+    /// An associated type of a type parameter, e.g., `T::Item`, identified by the [`DefId`] of
+    /// the associated item
+    Projection(ParamTy, DefId),
+}
+
+/// The length of an array or a slice, which is used to index the type.
+pub enum ArrayLen {
+    /// The length is not refined, e.g., `[i32; _]` or `[i32]`.
+    Infer,
+    /// e.g., `[i32; 4]`, `[i32; @n]` or `[i32][n]`.
+    Arg(RefineArg),
 }
 
-pub struct ArrayLen;
-
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Encodable, Decodable)]
 pub enum RefKind {
     Shr,
     Mut,
@@ -185,7 +219,17 @@ pub enum BaseTy {
     Int(IntTy),
     Uint(UintTy),
     Bool,
+    /// A floating-point number indexed by a real approximating its value. Only produced when the
+    /// `real_floats` setting is enabled, otherwise floats are unrefined, see [`Ty::Float`].
+    Float(FloatTy),
+    /// A string slice indexed by its length in bytes
+    Str,
+    /// A character indexed by its code point
+    Char,
     Adt(DefId, Vec<Ty>),
+    /// A type parameter indexed by an integer, e.g., `T[@n]`. The index is only meaningful when
+    /// the parameter is instantiated with a type indexed by a single integer, e.g., `usize`.
+    Param(ParamTy),
 }
 
 #[derive(Debug)]
@@ -194,40 +238,76 @@ pub struct RefineParam {
     pub sort: Sort,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum Sort {
     Int,
     Bool,
+    Real,
     Loc,
+    /// A bit vector with the given width
+    BitVec(u32),
     Tuple(List<Sort>),
     Func(FuncSort),
+    /// A finite set of elements of the given sort, e.g., `Set<int>`
+    Set(Box<Sort>),
+    /// A map from keys of the first sort to values of the second one, e.g., `Map<int, int>`
+    Map(Box<Sort>, Box<Sort>),
+    /// The sort of a struct or enum annotated with `#[flux::reflect]`, see [`DataDecl`].
+    Data(DefId),
+    /// An uninterpreted sort declared with `#[flux::opaque_sort]`. Values of these sorts can only
+    /// be compared for equality or passed to uninterpreted functions.
+    User(Symbol),
     Infer,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub struct FuncSort {
     pub inputs_and_output: List<Sort>,
 }
 
+#[derive(Clone)]
 pub struct Expr {
     pub kind: ExprKind,
     pub span: Span,
 }
 
+#[derive(Clone)]
 pub enum ExprKind {
     Const(DefId, Span),
+    /// A const generic parameter of the item being refined, e.g., `N` in `[i32; N]`
+    ConstParam(ParamConst, Span),
     Var(Name, Symbol, Span),
     Literal(Lit),
     BinaryOp(BinOp, Box<[Expr; 2]>),
     App(Func, Vec<Expr>),
     IfThenElse(Box<[Expr; 3]>),
+    /// A bounded quantifier over the integers, e.g., `forall i in lo..hi { p }`. The bounds are
+    /// stored before the body, i.e., `[lo, hi, p]`.
+    Quant(Quantifier, Ident, Box<[Expr; 3]>),
 }
 
+#[derive(Clone)]
 pub enum Func {
     /// A function comming from a refinement parameter.
     Var(Ident),
-    /// An _global_ uninterpreted function.
+    /// A _global_ function, either uninterpreted or defined with `#[flux::defs]`.
     Uif(Symbol, Span),
+    /// A function on sets or maps interpreted by the solver, e.g., `union` or `select`.
+    Theory(TheoryFunc),
+    /// A constructor, field selector or constructor test of a datatype sort.
+    Data(DataFunc),
+}
+
+/// The functions generated for a [`DataDecl`]. Fields are identified by their position in the
+/// constructor.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
+pub enum DataFunc {
+    /// e.g., `Point(x, y)`
+    Ctor(DefId, VariantIdx),
+    /// e.g., `p.x`
+    Field(DefId, VariantIdx, u32),
+    /// e.g., `is_Circle(s)`
+    Test(DefId, VariantIdx),
 }
 
 /// representation of uninterpreted functions
@@ -240,6 +320,7 @@ pub struct UFun {
 pub enum Lit {
     Int(i128),
     Bool(bool),
+    Real(f64),
 }
 
 pub type SourceInfo = (Span, Symbol);
@@ -294,12 +375,58 @@ pub struct RefinedBy {
     pub span: Span,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Encodable, Decodable)]
 pub struct UifDef {
     pub name: Symbol,
     pub sort: FuncSort,
 }
 
+/// A struct or enum annotated with `#[flux::reflect]`. The type can be used as a sort whose values
+/// are built with one constructor per variant, and values of the type are indexed by the value of
+/// the sort they denote, e.g., `Point { x: 1, y: 2 }` has type `Point[Point(1, 2)]`.
+#[derive(Debug, Clone, Encodable, Decodable)]
+pub struct DataDecl {
+    pub def_id: DefId,
+    pub name: Symbol,
+    pub ctors: Vec<DataCtor>,
+}
+
+#[derive(Debug, Clone, Encodable, Decodable)]
+pub struct DataCtor {
+    pub name: Symbol,
+    pub fields: Vec<(Symbol, Sort)>,
+}
+
+/// A refinement function with an interpreted body. The body can only mention functions defined
+/// before it, so definitions cannot be recursive.
+#[derive(Debug)]
+pub struct Defn {
+    pub name: Symbol,
+    pub args: Vec<RefineParam>,
+    pub sort: FuncSort,
+    pub expr: Expr,
+}
+
+impl DataDecl {
+    /// Finds the first constructor with a field of the given name
+    pub fn field(&self, name: Symbol) -> Option<DataFunc> {
+        self.ctors.iter().enumerate().find_map(|(variant, ctor)| {
+            let field = ctor.fields.iter().position(|(fld, _)| *fld == name)?;
+            Some(DataFunc::Field(self.def_id, VariantIdx::from_usize(variant), field as u32))
+        })
+    }
+}
+
+impl DataFunc {
+    pub fn def_id(&self) -> DefId {
+        match self {
+            DataFunc::Ctor(def_id, _) | DataFunc::Field(def_id, ..) | DataFunc::Test(def_id, _) => {
+                *def_id
+            }
+        }
+    }
+}
+
 impl AdtDef {
     pub fn new(def_id: DefId, refined_by: RefinedBy, invariants: Vec<Expr>, opaque: bool) -> Self {
         let sorts = refined_by.iter().map(|param| param.sort.clone()).collect();
@@ -371,6 +498,16 @@ impl Map {
         self.qualifiers.iter()
     }
 
+    // Axioms
+
+    pub fn insert_axiom(&mut self, axiom: Axiom) {
+        self.axioms.push(axiom);
+    }
+
+    pub fn axioms(&self) -> impl Iterator<Item = &Axiom> {
+        self.axioms.iter()
+    }
+
     // FnSigs
 
     pub fn insert_fn_sig(&mut self, def_id: LocalDefId, fn_sig: FnSig) {
@@ -381,6 +518,11 @@ impl Map {
         self.assumes.insert(def_id);
     }
 
+    pub fn fn_sig(&self, def_id: DefId) -> Option<&FnSig> {
+        self.fns
+            .get(&self.extern_spec(def_id).or(def_id.as_local())?)
+    }
+
     pub fn fn_sigs(&self) -> impl Iterator<Item = (DefId, &FnSig)> {
         self.fns
             .iter()
@@ -395,6 +537,63 @@ impl Map {
         }
     }
 
+    // Extern specs
+
+    pub fn insert_extern(&mut self, extern_def_id: DefId, def_id: LocalDefId) {
+        self.externs.insert(extern_def_id, def_id);
+    }
+
+    /// Returns the local item annotated with `#[flux::extern_spec]` specifying `extern_def_id`
+    pub fn extern_spec(&self, extern_def_id: DefId) -> Option<LocalDefId> {
+        self.externs.get(&extern_def_id).copied()
+    }
+
+    pub fn externs(&self) -> impl Iterator<Item = (DefId, LocalDefId)> + '_ {
+        self.externs
+            .iter()
+            .map(|(extern_def_id, def_id)| (*extern_def_id, *def_id))
+    }
+
+    /// Restricts the extern spec for `extern_def_id` to calls where the generic arguments are
+    /// instantiated with the given types. `None` stands for any generic argument.
+    pub fn insert_extern_instance(
+        &mut self,
+        extern_def_id: DefId,
+        tys: Vec<Option<rustc::ty::Ty>>,
+    ) {
+        self.extern_instances.insert(extern_def_id, tys);
+    }
+
+    pub fn extern_instance(&self, extern_def_id: DefId) -> Option<&[Option<rustc::ty::Ty>]> {
+        self.extern_instances.get(&extern_def_id).map(Vec::as_slice)
+    }
+
+    // Settings
+
+    pub fn set_real_floats(&mut self, real_floats: bool) {
+        self.real_floats = real_floats;
+    }
+
+    pub fn real_floats(&self) -> bool {
+        self.real_floats
+    }
+
+    // Statics
+
+    pub fn insert_static(&mut self, def_id: LocalDefId, ty: Ty) {
+        self.statics.insert(def_id, ty);
+    }
+
+    pub fn static_ty(&self, def_id: DefId) -> Option<&Ty> {
+        self.statics.get(&def_id.as_local()?)
+    }
+
+    pub fn statics(&self) -> impl Iterator<Item = (DefId, &Ty)> {
+        self.statics
+            .iter()
+            .map(|(def_id, ty)| (def_id.to_def_id(), ty))
+    }
+
     // Structs
 
     pub fn insert_struct(&mut self, def_id: LocalDefId, struct_def: StructDef) {
@@ -443,29 +642,106 @@ impl Map {
         self.uifs.get(sym.borrow())
     }
 
+    // Opaque Sorts
+
+    pub fn insert_opaque_sort(&mut self, name: Symbol) {
+        self.opaque_sorts.insert(name);
+    }
+
+    pub fn opaque_sorts(&self) -> impl Iterator<Item = Symbol> + '_ {
+        self.opaque_sorts.iter().copied()
+    }
+
+    pub fn is_opaque_sort(&self, name: Symbol) -> bool {
+        self.opaque_sorts.contains(&name)
+    }
+
+    // Defns
+
+    pub fn insert_defn(&mut self, symb: Symbol, defn: Defn) {
+        self.defns.insert(symb, defn);
+    }
+
+    /// The interpreted functions in the order they were defined.
+    pub fn defns(&self) -> impl Iterator<Item = &Defn> {
+        self.defns.values()
+    }
+
+    pub fn defn(&self, sym: impl Borrow<Symbol>) -> Option<&Defn> {
+        self.defns.get(sym.borrow())
+    }
+
     // ADT
 
-    pub fn insert_adt(&mut self, def_id: LocalDefId, sort_info: AdtDef) {
+    pub fn insert_adt(&mut self, def_id: DefId, sort_info: AdtDef) {
         self.adts.insert(def_id, sort_info);
     }
 
     pub fn sorts_of(&self, def_id: DefId) -> Option<&[Sort]> {
-        let info = self.adts.get(&def_id.as_local()?)?;
+        let info = self.adts.get(&def_id)?;
         Some(&info.sorts)
     }
 
     pub fn refined_by(&self, def_id: DefId) -> Option<&RefinedBy> {
-        let adt_def = self.adts.get(&def_id.as_local()?)?;
+        let adt_def = self.adts.get(&def_id)?;
         Some(&adt_def.refined_by)
     }
 
-    pub fn adt(&self, def_id: LocalDefId) -> &AdtDef {
+    pub fn adt(&self, def_id: DefId) -> &AdtDef {
         &self.adts[&def_id]
     }
 
     pub fn adts(&self) -> impl Iterator<Item = &AdtDef> {
         self.adts.values()
     }
+
+    // Datatypes
+
+    pub fn insert_data_decl(&mut self, data_decl: DataDecl) {
+        self.data_decls.insert(data_decl.def_id, data_decl);
+    }
+
+    pub fn data_decl(&self, def_id: DefId) -> Option<&DataDecl> {
+        self.data_decls.get(&def_id)
+    }
+
+    pub fn data_decls(&self) -> impl Iterator<Item = &DataDecl> {
+        self.data_decls.values()
+    }
+
+    pub fn data_func_sort(&self, func: DataFunc) -> FuncSort {
+        let data_decl = &self.data_decls[&func.def_id()];
+        let sort = Sort::Data(data_decl.def_id);
+        match func {
+            DataFunc::Ctor(_, variant) => {
+                let fields = &data_decl.ctors[variant.as_usize()].fields;
+                FuncSort::new(fields.iter().map(|(_, sort)| sort.clone()).collect(), sort)
+            }
+            DataFunc::Field(_, variant, field) => {
+                let (_, fld_sort) = &data_decl.ctors[variant.as_usize()].fields[field as usize];
+                FuncSort::new(vec![sort], fld_sort.clone())
+            }
+            DataFunc::Test(..) => FuncSort::new(vec![sort], Sort::Bool),
+        }
+    }
+
+    pub fn data_decl_by_name(&self, name: Symbol) -> Option<&DataDecl> {
+        self.data_decls
+            .values()
+            .find(|data_decl| data_decl.name == name)
+    }
+
+    /// Finds the constructor with the given name in any of the datatypes
+    pub fn data_ctor_by_name(&self, name: Symbol) -> Option<(DefId, VariantIdx, &DataCtor)> {
+        self.data_decls.values().find_map(|data_decl| {
+            let (variant, ctor) = data_decl
+                .ctors
+                .iter()
+                .enumerate()
+                .find(|(_, ctor)| ctor.name == name)?;
+            Some((data_decl.def_id, VariantIdx::from_usize(variant), ctor))
+        })
+    }
 }
 
 impl_internable!([Sort]);
@@ -543,20 +819,31 @@ impl fmt::Debug for Ty {
             Ty::Ref(RefKind::Mut, ty) => write!(f, "&mut {ty:?}"),
             Ty::Ref(RefKind::Shr, ty) => write!(f, "&{ty:?}"),
             Ty::Param(param) => write!(f, "{param}"),
+            Ty::Const(param) => write!(f, "{param}"),
+            Ty::Opaque(def_id) => write!(f, "impl {def_id:?}"),
+            Ty::Projection(param, def_id) => {
+                let item_name = rustc_middle::ty::tls::with(|tcx| tcx.item_name(*def_id));
+                write!(f, "{param}::{item_name}")
+            }
             Ty::Tuple(tys) => write!(f, "({:?})", tys.iter().format(", ")),
             Ty::Never => write!(f, "!"),
             Ty::Constr(pred, ty) => write!(f, "{{{ty:?} : {pred:?}}}"),
             Ty::Array(ty, len) => write!(f, "[{ty:?}; {len:?}]"),
-            Ty::Slice(ty) => write!(f, "[{ty:?}]"),
-            Ty::Str => write!(f, "str"),
-            Ty::Char => write!(f, "char"),
+            Ty::Slice(ty, ArrayLen::Infer) => write!(f, "[{ty:?}]"),
+            Ty::Slice(ty, len) => write!(f, "[{ty:?}][{len:?}]"),
+            Ty::FnPtr(inputs, output) => {
+                write!(f, "fn({:?}) -> {output:?}", inputs.iter().format(", "))
+            }
         }
     }
 }
 
 impl fmt::Debug for ArrayLen {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "_")
+        match self {
+            ArrayLen::Infer => write!(f, "_"),
+            ArrayLen::Arg(arg) => write!(f, "{arg:?}"),
+        }
     }
 }
 
@@ -566,7 +853,11 @@ impl fmt::Debug for BaseTy {
             BaseTy::Int(int_ty) => write!(f, "{}", int_ty.name_str())?,
             BaseTy::Uint(uint_ty) => write!(f, "{}", uint_ty.name_str())?,
             BaseTy::Bool => write!(f, "bool")?,
+            BaseTy::Float(float_ty) => write!(f, "{}", float_ty.name_str())?,
+            BaseTy::Str => write!(f, "str")?,
+            BaseTy::Char => write!(f, "char")?,
             BaseTy::Adt(did, _) => write!(f, "{}", pretty::def_id_to_string(*did))?,
+            BaseTy::Param(param) => write!(f, "{param}")?,
         }
         if let BaseTy::Adt(_, substs) = self && !substs.is_empty() {
             write!(f, "<{:?}>", substs.iter().format(", "))?;
@@ -619,10 +910,14 @@ impl fmt::Debug for Expr {
             ExprKind::BinaryOp(op, box [e1, e2]) => write!(f, "({e1:?} {op:?} {e2:?})"),
             ExprKind::Literal(lit) => write!(f, "{lit:?}"),
             ExprKind::Const(x, _) => write!(f, "{x:?}"),
+            ExprKind::ConstParam(param, _) => write!(f, "{param}"),
             ExprKind::App(uf, es) => write!(f, "{uf:?}({es:?})"),
             ExprKind::IfThenElse(box [p, e1, e2]) => {
                 write!(f, "(if {p:?} {{ {e1:?} }} else {{ {e2:?} }})")
             }
+            ExprKind::Quant(quant, x, box [lo, hi, body]) => {
+                write!(f, "({quant:?} {x:?} in {lo:?}..{hi:?} {{ {body:?} }})")
+            }
         }
     }
 }
@@ -632,6 +927,24 @@ impl fmt::Debug for Func {
         match self {
             Self::Var(func) => write!(f, "{func:?}"),
             Self::Uif(sym, _) => write!(f, "{sym}"),
+            Self::Theory(func) => write!(f, "{func}"),
+            Self::Data(func) => write!(f, "{func:?}"),
+        }
+    }
+}
+
+impl fmt::Debug for DataFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFunc::Ctor(def_id, variant) => {
+                write!(f, "{}::{variant:?}", pretty::def_id_to_string(*def_id))
+            }
+            DataFunc::Field(def_id, variant, field) => {
+                write!(f, "{}::{variant:?}.{field}", pretty::def_id_to_string(*def_id))
+            }
+            DataFunc::Test(def_id, variant) => {
+                write!(f, "is_{}::{variant:?}", pretty::def_id_to_string(*def_id))
+            }
         }
     }
 }
@@ -647,6 +960,7 @@ impl fmt::Debug for Lit {
         match self {
             Lit::Int(i) => write!(f, "{i}"),
             Lit::Bool(b) => write!(f, "{b}"),
+            Lit::Real(r) => write!(f, "{r:?}"),
         }
     }
 }
@@ -656,9 +970,15 @@ impl fmt::Display for Sort {
         match self {
             Sort::Bool => write!(f, "bool"),
             Sort::Int => write!(f, "int"),
+            Sort::Real => write!(f, "real"),
             Sort::Loc => write!(f, "loc"),
+            Sort::BitVec(size) => write!(f, "bitvec<{size}>"),
             Sort::Func(sort) => write!(f, "{sort}"),
             Sort::Tuple(sorts) => write!(f, "({})", sorts.iter().join(", ")),
+            Sort::Set(sort) => write!(f, "Set<{sort}>"),
+            Sort::Map(k, v) => write!(f, "Map<{k}, {v}>"),
+            Sort::Data(def_id) => write!(f, "{}", pretty::def_id_to_string(*def_id)),
+            Sort::User(name) => write!(f, "{name}"),
             Sort::Infer => write!(f, "_"),
         }
     }
@@ -1,20 +1,21 @@
-use std::{cell::RefCell, collections::hash_map};
+use std::{cell::RefCell, iter};
 
 use flux_common::config::{AssertBehavior, CONFIG};
 use flux_errors::FluxSession;
 use itertools::Itertools;
 use rustc_errors::FatalError;
-use rustc_hash::FxHashMap;
-use rustc_hir::{def_id::DefId, LangItem};
-use rustc_middle::ty::TyCtxt;
+use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hir::{def::DefKind, def_id::DefId, LangItem};
 pub use rustc_middle::ty::Variance;
+use rustc_middle::ty::{subst::InternalSubsts, TyCtxt};
 pub use rustc_span::symbol::Ident;
 
 pub use crate::rustc::lowering::UnsupportedFnSig;
 use crate::{
     fhir::{self, VariantIdx},
     intern::List,
-    rty::{self, Binders},
+    metadata::{AdtMetadata, CrateMetadata},
+    rty::{self, fold::TypeFoldable, Binders},
     rustc,
 };
 
@@ -25,41 +26,76 @@ pub struct GlobalEnv<'genv, 'tcx> {
     pub tcx: TyCtxt<'tcx>,
     pub sess: &'genv FluxSession,
     pub qualifiers: Vec<rty::Qualifier>,
+    /// Closed formulas assumed when checking every function
+    pub axioms: Vec<rty::Expr>,
+    defns: rty::Defns,
     fn_sigs: RefCell<FxHashMap<DefId, rty::PolySig>>,
+    /// Functions of other crates with a refined signature, given by an extern spec or exported by
+    /// a dependency.
+    extern_fn_sigs: FxHashSet<DefId>,
+    static_tys: FxHashMap<DefId, rty::Ty>,
     map: fhir::Map,
     adt_defs: RefCell<FxHashMap<DefId, rty::AdtDef>>,
     adt_variants: RefCell<FxHashMap<DefId, Option<Vec<rty::PolyVariant>>>>,
     check_asserts: AssertBehavior,
+    check_overflow: bool,
+    check_macros: bool,
+    real_floats: bool,
 }
 
 impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
     pub fn new(tcx: TyCtxt<'tcx>, sess: &'genv FluxSession, map: fhir::Map) -> Self {
         let check_asserts = CONFIG.check_asserts;
+        let check_overflow = CONFIG.check_overflow;
+        let check_macros = CONFIG.check_macros;
+        // Whether floats are refined determines the sorts of the specs, so the setting must be
+        // fixed before they are converted.
+        let real_floats = map.real_floats();
+
+        // Definitions are converted in order so each one is inlined in the ones that follow
+        let mut defns = rty::Defns::default();
+        for defn in map.defns() {
+            let body = rty::conv::conv_defn(&defns, defn);
+            defns.insert(defn.name, body);
+        }
 
         let mut adt_defs = FxHashMap::default();
         for adt_def in map.adts() {
-            let adt_def = rty::conv::conv_adt_def(tcx, adt_def);
+            let adt_def = rty::conv::conv_adt_def(tcx, &defns, adt_def);
             adt_defs.insert(adt_def.def_id(), adt_def);
         }
 
         let mut qualifiers = vec![];
         for qualifier in map.qualifiers() {
-            qualifiers.push(rty::conv::ConvCtxt::conv_qualifier(qualifier));
+            qualifiers.push(rty::conv::ConvCtxt::conv_qualifier(&defns, qualifier));
         }
 
+        let axioms = map
+            .axioms()
+            .map(|axiom| rty::conv::conv_axiom(&defns, axiom))
+            .collect();
+
         let mut genv = GlobalEnv {
             fn_sigs: RefCell::new(FxHashMap::default()),
+            extern_fn_sigs: FxHashSet::default(),
+            static_tys: FxHashMap::default(),
             adt_defs: RefCell::new(adt_defs),
             adt_variants: RefCell::new(FxHashMap::default()),
             qualifiers,
+            axioms,
+            defns,
             tcx,
             sess,
             check_asserts,
+            check_overflow,
+            check_macros,
+            real_floats,
             map,
         };
         genv.register_struct_def_variants();
         genv.register_enum_def_variants();
         genv.register_fn_sigs();
+        genv.register_static_tys();
 
         genv
     }
@@ -89,26 +125,252 @@ impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
             let fn_sig = rty::conv::ConvCtxt::conv_fn_sig(self, fn_sig);
             self.fn_sigs.get_mut().insert(def_id, fn_sig);
         }
+        // Extern functions take the signature of their extern spec
+        for (extern_def_id, def_id) in self.map.externs() {
+            if let Some(fn_sig) = self.fn_sigs.get_mut().get(&def_id.to_def_id()) {
+                let fn_sig = fn_sig.clone();
+                self.fn_sigs.get_mut().insert(extern_def_id, fn_sig);
+                self.extern_fn_sigs.insert(extern_def_id);
+            }
+        }
+    }
+
+    fn register_static_tys(&mut self) {
+        for (def_id, ty) in self.map.statics() {
+            let ty = rty::conv::ConvCtxt::conv_static_ty(self, ty);
+            self.static_tys.insert(def_id, ty);
+        }
+    }
+
+    /// Registers the refinements exported by a dependency. Extern specs in the local crate take
+    /// precedence over the ones exported by the dependency.
+    pub fn import_crate_metadata(&mut self, meta: CrateMetadata) {
+        for (def_id, fn_sig) in meta.fn_sigs {
+            if self.map.extern_spec(def_id).is_none() {
+                self.fn_sigs.get_mut().insert(def_id, fn_sig);
+                self.extern_fn_sigs.insert(def_id);
+            }
+        }
+        for (def_id, tys) in meta.extern_instances {
+            if self.map.extern_spec(def_id).is_none() {
+                self.map.insert_extern_instance(def_id, tys);
+            }
+        }
+        for adt in meta.adts {
+            let def_id = adt.adt_def.def_id();
+            if self.map.extern_spec(def_id).is_none() {
+                self.adt_defs.get_mut().insert(def_id, adt.adt_def);
+                self.adt_variants.get_mut().insert(def_id, adt.variants);
+            }
+        }
+        self.qualifiers.extend(meta.qualifiers);
+        self.axioms.extend(meta.axioms);
+    }
+
+    /// Collects the refinements of the local crate that should be available to dependent crates.
+    pub fn crate_metadata(&self) -> CrateMetadata {
+        let fn_sigs = self
+            .map
+            .fn_sigs()
+            .map(|(def_id, _)| def_id)
+            .chain(self.map.externs().map(|(extern_def_id, _)| extern_def_id))
+            .filter_map(|def_id| Some((def_id, self.fn_sigs.borrow().get(&def_id)?.clone())))
+            .collect();
+
+        let extern_instances = self
+            .map
+            .externs()
+            .filter_map(|(extern_def_id, _)| {
+                Some((extern_def_id, self.map.extern_instance(extern_def_id)?.to_vec()))
+            })
+            .collect();
+
+        let adts = self
+            .map
+            .adts()
+            .filter(|adt_def| {
+                adt_def.def_id.is_local() || self.map.extern_spec(adt_def.def_id).is_some()
+            })
+            .map(|adt_def| {
+                let def_id = adt_def.def_id;
+                let refined_by = adt_def
+                    .refined_by
+                    .iter()
+                    .map(|param| (param.name.source_info.1, param.sort.clone()))
+                    .collect();
+                let variants = self
+                    .tcx
+                    .adt_def(def_id)
+                    .variants()
+                    .indices()
+                    .map(|variant_idx| self.variant(def_id, variant_idx))
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok();
+                AdtMetadata { refined_by, adt_def: self.adt_def(def_id), variants }
+            })
+            .collect();
+
+        let qualifiers = self
+            .map
+            .qualifiers()
+            .map(|qualifier| rty::conv::ConvCtxt::conv_qualifier(&self.defns, qualifier))
+            .collect();
+
+        let uifs = self
+            .map
+            .uifs()
+            .map(|uif| fhir::UifDef { name: uif.name, sort: uif.sort.clone() })
+            .collect();
+
+        let data_decls = self
+            .map
+            .data_decls()
+            .filter(|data_decl| data_decl.def_id.is_local())
+            .cloned()
+            .collect();
+
+        let axioms = self
+            .map
+            .axioms()
+            .map(|axiom| rty::conv::conv_axiom(&self.defns, axiom))
+            .collect();
+
+        let opaque_sorts = self.map.opaque_sorts().collect();
+
+        CrateMetadata {
+            fn_sigs,
+            extern_instances,
+            adts,
+            qualifiers,
+            axioms,
+            uifs,
+            data_decls,
+            opaque_sorts,
+        }
     }
 
     pub fn map(&self) -> &fhir::Map {
         &self.map
     }
 
+    pub fn defns(&self) -> &rty::Defns {
+        &self.defns
+    }
+
     pub fn register_assert_behavior(&mut self, behavior: AssertBehavior) {
         self.check_asserts = behavior;
     }
 
+    pub fn register_check_overflow(&mut self, check_overflow: bool) {
+        self.check_overflow = check_overflow;
+    }
+
+    pub fn register_check_macros(&mut self, check_macros: bool) {
+        self.check_macros = check_macros;
+    }
+
     pub fn lookup_fn_sig(&self, def_id: DefId) -> Result<rty::PolySig, UnsupportedFnSig> {
-        match self.fn_sigs.borrow_mut().entry(def_id) {
-            hash_map::Entry::Occupied(entry) => Ok(entry.get().clone()),
-            hash_map::Entry::Vacant(entry) => {
-                Ok(entry.insert(self.default_fn_sig(def_id)?).clone())
+        if let Some(fn_sig) = self.fn_sigs.borrow().get(&def_id) {
+            return Ok(fn_sig.clone());
+        }
+        // Computing the default signature may require looking up other signatures, so we cannot
+        // hold the borrow while computing it.
+        let fn_sig = self.default_fn_sig(def_id)?;
+        self.fn_sigs.borrow_mut().insert(def_id, fn_sig.clone());
+        Ok(fn_sig)
+    }
+
+    /// Like [`GlobalEnv::lookup_fn_sig`], but if `def_id` has an extern spec restricted to some
+    /// instances of a generic impl (see [`fhir::Map::insert_extern_instance`]) and the call
+    /// instantiates the impl differently, we use the unrefined signature.
+    pub fn lookup_fn_sig_for_call(
+        &self,
+        def_id: DefId,
+        substs: &[rustc::ty::GenericArg],
+    ) -> Result<rty::PolySig, UnsupportedFnSig> {
+        if let Some(tys) = self.map.extern_instance(def_id) {
+            let applies = iter::zip(tys, substs).all(|(ty, arg)| {
+                match (ty, arg) {
+                    (None, _) => true,
+                    (Some(ty), rustc::ty::GenericArg::Ty(arg)) => ty == arg,
+                    (Some(_), _) => false,
+                }
+            });
+            if !applies {
+                return self.default_fn_sig(def_id);
             }
         }
+        self.lookup_fn_sig(def_id)
+    }
+
+    /// Returns the type of a `static` item, which is either its refined type if it has a
+    /// `#[flux::sig]` annotation or its rust type refined with `true` otherwise.
+    pub fn lookup_static_ty(&self, def_id: DefId) -> rty::Ty {
+        if let Some(ty) = self.static_tys.get(&def_id) {
+            return ty.clone();
+        }
+        self.default_type_of(def_id)
+    }
+
+    /// If `impl_f` is a method in a trait impl and the corresponding method in the trait has a
+    /// refined signature, returns the signature of the trait method instantiated with the generic
+    /// arguments of the impl.
+    pub fn trait_fn_sig_for_impl_fn(
+        &self,
+        impl_f: DefId,
+    ) -> Result<Option<rty::PolySig>, UnsupportedFnSig> {
+        let tcx = self.tcx;
+        let Some(impl_id) = tcx.impl_of_method(impl_f) else { return Ok(None) };
+        let Some(trait_ref) = tcx.impl_trait_ref(impl_id) else { return Ok(None) };
+        let Some(trait_f) = tcx.associated_item(impl_f).trait_item_def_id else { return Ok(None) };
+        // The method of a trait in another crate can also be refined, e.g., derived impls of `Clone`
+        // inherit the signature bundled for `Clone::clone`.
+        if self.map.fn_sig(trait_f).is_none() && !self.extern_fn_sigs.contains(&trait_f) {
+            return Ok(None);
+        }
+
+        let substs = InternalSubsts::identity_for_item(tcx, impl_f).rebase_onto(
+            tcx,
+            impl_id,
+            trait_ref.substs,
+        );
+        let substs = rustc::lowering::lower_substs(tcx, substs)
+            .map_err(|err| UnsupportedFnSig { span: tcx.def_span(impl_f), reason: err.reason })?
+            .iter()
+            .map(|arg| {
+                self.refine_generic_arg(arg, &mut |sorts| Binders::new(rty::Pred::tt(), sorts))
+            })
+            .collect_vec();
+
+        Ok(Some(self.lookup_fn_sig(trait_f)?.replace_generic_args(&substs)))
+    }
+
+    /// Returns the generic arguments mapping each generic parameter of `def_id` to itself.
+    pub fn identity_substs(&self, def_id: DefId) -> Result<Vec<rty::GenericArg>, UnsupportedFnSig> {
+        let substs = InternalSubsts::identity_for_item(self.tcx, def_id);
+        let substs = rustc::lowering::lower_substs(self.tcx, substs)
+            .map_err(|err| {
+                UnsupportedFnSig { span: self.tcx.def_span(def_id), reason: err.reason }
+            })?
+            .iter()
+            .map(|arg| {
+                self.refine_generic_arg(arg, &mut |sorts| Binders::new(rty::Pred::tt(), sorts))
+            })
+            .collect_vec();
+        Ok(substs)
     }
 
     fn default_fn_sig(&self, def_id: DefId) -> Result<rty::PolySig, UnsupportedFnSig> {
+        // Methods in a trait impl without an annotation inherit the signature of the trait.
+        if let Some(fn_sig) = self.trait_fn_sig_for_impl_fn(def_id)? {
+            return Ok(fn_sig);
+        }
+        // The initializer of a static is checked as a function without arguments returning the
+        // type of the static.
+        if let DefKind::Static(_) = self.tcx.def_kind(def_id) {
+            let fn_sig = rty::FnSig::new(vec![], vec![], self.lookup_static_ty(def_id), vec![]);
+            return Ok(Binders::new(fn_sig, vec![]));
+        }
         let fn_sig = rustc::lowering::lower_fn_sig_of(self.tcx, def_id)?.skip_binder();
         Ok(self.refine_fn_sig(&fn_sig, &mut |sorts| Binders::new(rty::Pred::tt(), sorts)))
     }
@@ -144,6 +406,18 @@ impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
         &self.check_asserts
     }
 
+    pub fn check_overflow(&self) -> bool {
+        self.check_overflow
+    }
+
+    pub fn check_macros(&self) -> bool {
+        self.check_macros
+    }
+
+    pub fn real_floats(&self) -> bool {
+        self.real_floats
+    }
+
     pub fn variant_sig(
         &self,
         def_id: DefId,
@@ -254,7 +528,6 @@ impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
             rustc::ty::TyKind::Ref(ty, rustc::ty::Mutability::Not) => {
                 return rty::Ty::mk_ref(rty::RefKind::Shr, self.refine_ty(ty, mk_pred));
             }
-            rustc::ty::TyKind::Float(float_ty) => return rty::Ty::float(*float_ty),
             rustc::ty::TyKind::Tuple(tys) => {
                 let tys = tys
                     .iter()
@@ -273,12 +546,53 @@ impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
             rustc::ty::TyKind::Bool => rty::BaseTy::Bool,
             rustc::ty::TyKind::Int(int_ty) => rty::BaseTy::Int(*int_ty),
             rustc::ty::TyKind::Uint(uint_ty) => rty::BaseTy::Uint(*uint_ty),
+            rustc::ty::TyKind::Float(float_ty) if self.real_floats => rty::BaseTy::Real(*float_ty),
+            rustc::ty::TyKind::Float(float_ty) => return rty::Ty::float(*float_ty),
             rustc::ty::TyKind::Str => rty::BaseTy::Str,
             rustc::ty::TyKind::Array(ty, len) => {
-                rty::BaseTy::Array(self.refine_ty(ty, mk_pred), len.clone())
+                let ty = self.refine_ty(ty, mk_pred);
+                return rty::Ty::array(ty, rty::Expr::from_const(len));
             }
             rustc::ty::TyKind::Slice(ty) => rty::BaseTy::Slice(self.refine_ty(ty, mk_pred)),
             rustc::ty::TyKind::Char => rty::BaseTy::Char,
+            rustc::ty::TyKind::Closure(def_id, upvar_tys) => {
+                let upvar_tys = upvar_tys
+                    .iter()
+                    .map(|ty| self.refine_ty(ty, mk_pred))
+                    .collect_vec();
+                rty::BaseTy::Closure(*def_id, List::from_vec(upvar_tys))
+            }
+            rustc::ty::TyKind::FnDef(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| self.refine_generic_arg(arg, mk_pred))
+                    .collect_vec();
+                rty::BaseTy::FnDef(*def_id, List::from_vec(substs))
+            }
+            rustc::ty::TyKind::FnPtr(fn_sig) => {
+                rty::BaseTy::FnPtr(self.refine_fn_sig(fn_sig.as_ref().skip_binder(), mk_pred))
+            }
+            rustc::ty::TyKind::Dynamic(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| self.refine_generic_arg(arg, mk_pred))
+                    .collect_vec();
+                rty::BaseTy::Dynamic(*def_id, List::from_vec(substs))
+            }
+            rustc::ty::TyKind::Opaque(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| self.refine_generic_arg(arg, mk_pred))
+                    .collect_vec();
+                rty::BaseTy::Opaque(*def_id, List::from_vec(substs))
+            }
+            rustc::ty::TyKind::Projection(item_def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| self.refine_generic_arg(arg, mk_pred))
+                    .collect_vec();
+                rty::BaseTy::Projection(*item_def_id, List::from_vec(substs))
+            }
         };
         let sorts = bty.sorts();
         if sorts.is_empty() {
@@ -297,6 +611,82 @@ impl<'genv, 'tcx> GlobalEnv<'genv, 'tcx> {
         match ty {
             rustc::ty::GenericArg::Ty(ty) => rty::GenericArg::Ty(self.refine_ty(ty, mk_pred)),
             rustc::ty::GenericArg::Lifetime(_) => rty::GenericArg::Lifetime,
+            rustc::ty::GenericArg::Const(c) => rty::GenericArg::Const(rty::Expr::from_const(c)),
+        }
+    }
+
+    /// Normalizes the projections in an instantiated signature using `rust_sig`, the signature of
+    /// the callee instantiated with the same generic arguments. See
+    /// [`GlobalEnv::normalize_projections`].
+    pub fn normalize_fn_sig_projections(
+        &self,
+        fn_sig: &rty::FnSig,
+        rust_sig: &rustc::ty::FnSig,
+    ) -> rty::FnSig {
+        let args = iter::zip(fn_sig.args(), rust_sig.inputs())
+            .map(|(ty, rust_ty)| self.normalize_projections(ty, rust_ty))
+            .collect_vec();
+        let ret = self.normalize_projections(fn_sig.ret(), &rust_sig.output());
+        rty::FnSig::new(fn_sig.requires().clone(), args, ret, fn_sig.ensures().clone())
+    }
+
+    /// Replaces the projections in `ty` with the corresponding (unrefined) parts of `rust_ty`,
+    /// which must be the normalized version of `ty`. Refinements outside the projections are
+    /// preserved, e.g., normalizing `Option<I::Item>[true]` against `Option<i32>` results in
+    /// `Option<i32{v: true}>[true]`.
+    pub fn normalize_projections(&self, ty: &rty::Ty, rust_ty: &rustc::ty::Ty) -> rty::Ty {
+        match (ty.kind(), rust_ty.kind()) {
+            (
+                rty::TyKind::Indexed(rty::BaseTy::Projection(..), _)
+                | rty::TyKind::Exists(rty::BaseTy::Projection(..), _),
+                _,
+            ) => self.refine_ty(rust_ty, &mut |sorts| Binders::new(rty::Pred::tt(), sorts)),
+            (rty::TyKind::Indexed(bty, idxs), _) => {
+                rty::Ty::indexed(self.normalize_bty_projections(bty, rust_ty), idxs.clone())
+            }
+            (rty::TyKind::Exists(bty, pred), _) => {
+                rty::Ty::exists(self.normalize_bty_projections(bty, rust_ty), pred.clone())
+            }
+            (rty::TyKind::Constr(pred, ty), _) => {
+                rty::Ty::constr(pred.clone(), self.normalize_projections(ty, rust_ty))
+            }
+            (rty::TyKind::Ref(rk, ty), rustc::ty::TyKind::Ref(rust_ty, _)) => {
+                rty::Ty::mk_ref(*rk, self.normalize_projections(ty, rust_ty))
+            }
+            (rty::TyKind::Tuple(tys), rustc::ty::TyKind::Tuple(rust_tys)) => {
+                let tys = iter::zip(tys, rust_tys)
+                    .map(|(ty, rust_ty)| self.normalize_projections(ty, rust_ty))
+                    .collect_vec();
+                rty::Ty::tuple(tys)
+            }
+            _ => ty.clone(),
+        }
+    }
+
+    fn normalize_bty_projections(&self, bty: &rty::BaseTy, rust_ty: &rustc::ty::Ty) -> rty::BaseTy {
+        match (bty, rust_ty.kind()) {
+            (rty::BaseTy::Adt(adt_def, substs), rustc::ty::TyKind::Adt(_, rust_substs)) => {
+                let substs = iter::zip(substs, rust_substs)
+                    .map(|(arg, rust_arg)| {
+                        match (arg, rust_arg) {
+                            (rty::GenericArg::Ty(ty), rustc::ty::GenericArg::Ty(rust_ty)) => {
+                                rty::GenericArg::Ty(self.normalize_projections(ty, rust_ty))
+                            }
+                            (rty::GenericArg::Ty(ty), _) => rty::GenericArg::Ty(ty.clone()),
+                            (rty::GenericArg::Lifetime, _) => rty::GenericArg::Lifetime,
+                            (rty::GenericArg::Const(e), _) => rty::GenericArg::Const(e.clone()),
+                        }
+                    })
+                    .collect_vec();
+                rty::BaseTy::adt(adt_def.clone(), substs)
+            }
+            (rty::BaseTy::Array(ty), rustc::ty::TyKind::Array(rust_ty, _)) => {
+                rty::BaseTy::Array(self.normalize_projections(ty, rust_ty))
+            }
+            (rty::BaseTy::Slice(ty), rustc::ty::TyKind::Slice(rust_ty)) => {
+                rty::BaseTy::Slice(self.normalize_projections(ty, rust_ty))
+            }
+            _ => bty.clone(),
         }
     }
 }
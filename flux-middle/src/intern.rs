@@ -248,6 +248,7 @@ use std::{
 
 use dashmap::{lock::RwLockWriteGuard, DashMap, SharedValue};
 use rustc_hash::FxHasher;
+use rustc_serialize::{Decodable, Decoder, Encodable, Encoder};
 
 type InternMap<T> = DashMap<Arc<T>, (), BuildHasherDefault<FxHasher>>;
 type Guard<T> =
@@ -421,6 +422,28 @@ impl<T: Internable + ?Sized> Hash for Interned<T> {
     }
 }
 
+/// Interned values are encoded by value and interned again when decoded.
+impl<E: Encoder, T: Internable + Encodable<E> + ?Sized> Encodable<E> for Interned<T> {
+    fn encode(&self, s: &mut E) {
+        (**self).encode(s);
+    }
+}
+
+impl<D: Decoder, T: Internable + Decodable<D>> Decodable<D> for Interned<T> {
+    fn decode(d: &mut D) -> Self {
+        Interned::new(T::decode(d))
+    }
+}
+
+impl<D: Decoder, T: Decodable<D>> Decodable<D> for Interned<[T]>
+where
+    [T]: Internable,
+{
+    fn decode(d: &mut D) -> Self {
+        List::from_vec(<Vec<T> as Decodable<D>>::decode(d))
+    }
+}
+
 impl<T: PartialOrd + Internable> PartialOrd for Interned<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         <T as PartialOrd>::partial_cmp(&self.arc, &other.arc)
@@ -8,14 +8,18 @@ extern crate rustc_errors;
 extern crate rustc_hash;
 extern crate rustc_hir;
 extern crate rustc_index;
+extern crate rustc_macros;
 extern crate rustc_middle;
 extern crate rustc_serialize;
+extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
+extern crate rustc_type_ir;
 
 pub mod fhir;
 pub mod global_env;
 pub mod intern;
+pub mod metadata;
 pub mod pretty;
 pub mod rty;
 pub mod rustc;
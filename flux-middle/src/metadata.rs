@@ -0,0 +1,404 @@
+//! Refinement information exported by a crate so it can be used when checking its dependents.
+//!
+//! Specs only live in the [`fhir::Map`] of the crate being checked, so we serialize the refined
+//! signatures and definitions of a crate into a sidecar file next to its `.rmeta` file. When
+//! checking a crate we load the sidecar files of all its dependencies (if any). [`DefId`]s are
+//! encoded as [`DefPathHash`]es, which are stable across crates. The sidecar also records the
+//! [`Svh`] of the crate, so we can detect when it is stale because the crate was recompiled
+//! without flux.
+use std::path::{Path, PathBuf};
+
+use flux_errors::FluxSession;
+use rustc_data_structures::svh::Svh;
+use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::{CrateNum, DefId, DefPathHash, StableCrateId, CRATE_DEF_ID, LOCAL_CRATE};
+use rustc_macros::{TyDecodable, TyEncodable};
+use rustc_middle::{
+    mir::interpret::AllocId,
+    ty::{
+        self,
+        codec::{TyDecoder, TyEncoder},
+        TyCtxt,
+    },
+};
+use rustc_serialize::{
+    opaque::{MemDecoder, MemEncoder},
+    Decodable, Decoder, Encodable, Encoder,
+};
+use rustc_session::output::filename_for_metadata;
+use rustc_span::{Symbol, DUMMY_SP};
+
+use crate::{fhir, rty, rustc};
+
+const METADATA_EXTENSION: &str = "fluxmeta";
+
+/// Magic header identifying flux metadata, followed by a version number that should be bumped
+/// whenever the encoding changes.
+const METADATA_HEADER: &[u8] = b"flux\0\0\0\x05";
+
+#[derive(TyEncodable, TyDecodable)]
+pub struct CrateMetadata {
+    pub fn_sigs: Vec<(DefId, rty::PolySig)>,
+    /// Extern specs restricted to some instances of a generic impl, see
+    /// [`fhir::Map::insert_extern_instance`].
+    pub extern_instances: Vec<(DefId, Vec<Option<rustc::ty::Ty>>)>,
+    pub adts: Vec<AdtMetadata>,
+    pub qualifiers: Vec<rty::Qualifier>,
+    pub axioms: Vec<rty::Expr>,
+    pub uifs: Vec<fhir::UifDef>,
+    pub data_decls: Vec<fhir::DataDecl>,
+    pub opaque_sorts: Vec<Symbol>,
+}
+
+#[derive(TyEncodable, TyDecodable)]
+pub struct AdtMetadata {
+    /// The names and sorts of the parameters in the `#[flux::refined_by]` annotation, needed to
+    /// desugar types mentioning the adt in dependent crates.
+    pub refined_by: Vec<(Symbol, fhir::Sort)>,
+    pub adt_def: rty::AdtDef,
+    /// The refined variants of the adt or `None` if it is opaque.
+    pub variants: Option<Vec<rty::PolyVariant>>,
+}
+
+impl CrateMetadata {
+    /// Writes the metadata of the local crate next to its `.rmeta` file.
+    pub fn write(&self, tcx: TyCtxt) -> std::io::Result<()> {
+        let crate_name = tcx.crate_name(LOCAL_CRATE);
+        let path = filename_for_metadata(tcx.sess, crate_name.as_str(), tcx.output_filenames(()));
+
+        let mut encoder = EncodeContext::new(tcx);
+        encoder.emit_raw_bytes(METADATA_HEADER);
+        tcx.crate_hash(LOCAL_CRATE).encode(&mut encoder);
+        Encodable::encode(self, &mut encoder);
+        if encoder.unsupported_alloc {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "encoding of allocations is not supported",
+            ));
+        }
+        std::fs::write(CrateMetadata::sidecar_path(&path), encoder.opaque.finish())
+    }
+
+    /// Reads the metadata of an external crate if it was checked with flux. If the metadata
+    /// cannot be decoded, e.g., because it mentions a definition that no longer exists in the
+    /// dependency, we emit a warning and ignore it.
+    pub fn read(tcx: TyCtxt, sess: &FluxSession, cnum: CrateNum) -> Option<CrateMetadata> {
+        let path = dependency_sidecar_path(tcx, cnum)?;
+        CrateMetadata::read_file(tcx, sess, tcx.crate_name(cnum), &path, Some(tcx.crate_hash(cnum)))
+    }
+
+    /// Reads the metadata in the sidecar file at `path`, which must have been written for a crate
+    /// named `krate`. If `svh` is given, the metadata is ignored if it was written for a different
+    /// version of the crate. Crates that are not loaded in the current session, e.g., the specs
+    /// bundled with flux, cannot be checked for staleness so they must be versioned by the caller.
+    pub fn read_file(
+        tcx: TyCtxt,
+        sess: &FluxSession,
+        krate: Symbol,
+        path: &Path,
+        svh: Option<Svh>,
+    ) -> Option<CrateMetadata> {
+        let data = std::fs::read(path).ok()?;
+        if !data.starts_with(METADATA_HEADER) {
+            return None;
+        }
+        let mut decoder = DecodeContext::new(tcx, &data, METADATA_HEADER.len());
+        let encoded_svh: Svh = Decodable::decode(&mut decoder);
+        if matches!(svh, Some(svh) if svh != encoded_svh) {
+            decoder.report(DecodeError::Stale);
+        }
+        let meta = decoder
+            .err
+            .is_none()
+            .then(|| Decodable::decode(&mut decoder));
+        if let Some(err) = decoder.err {
+            sess.emit_warning(errors::CannotReadMetadata { krate, err: err.to_string() });
+            return None;
+        }
+        meta
+    }
+
+    /// The path of the sidecar file for a crate whose `.rmeta` file is at `rmeta_path`.
+    pub fn sidecar_path(rmeta_path: &Path) -> PathBuf {
+        rmeta_path.with_extension(METADATA_EXTENSION)
+    }
+
+    /// Reads the metadata of all the external crates that were checked with flux.
+    pub fn read_dependencies(tcx: TyCtxt, sess: &FluxSession) -> Vec<CrateMetadata> {
+        tcx.crates(())
+            .iter()
+            .filter_map(|cnum| CrateMetadata::read(tcx, sess, *cnum))
+            .collect()
+    }
+}
+
+impl AdtMetadata {
+    /// The definition used to desugar types mentioning the adt. Invariants are already part of
+    /// the exported [`rty::AdtDef`] so we don't need them here.
+    pub fn to_fhir(&self) -> fhir::AdtDef {
+        let params = self
+            .refined_by
+            .iter()
+            .enumerate()
+            .map(|(idx, (sym, sort))| {
+                let name = fhir::Ident {
+                    name: fhir::Name::from_usize(idx),
+                    source_info: (DUMMY_SP, *sym),
+                };
+                fhir::RefineParam { name, sort: sort.clone() }
+            })
+            .collect();
+        let refined_by = fhir::RefinedBy { params, span: DUMMY_SP };
+        fhir::AdtDef::new(self.adt_def.def_id(), refined_by, vec![], self.adt_def.is_opaque())
+    }
+}
+
+fn dependency_sidecar_path(tcx: TyCtxt, cnum: CrateNum) -> Option<PathBuf> {
+    let source = tcx.used_crate_source(cnum);
+    let path: &Path = source.paths().next()?;
+    let path = CrateMetadata::sidecar_path(path);
+    path.exists().then_some(path)
+}
+
+struct EncodeContext<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    opaque: MemEncoder,
+    type_shorthands: FxHashMap<ty::Ty<'tcx>, usize>,
+    predicate_shorthands: FxHashMap<ty::PredicateKind<'tcx>, usize>,
+    /// Set if we tried to encode an allocation, in which case the encoded data is unusable.
+    unsupported_alloc: bool,
+}
+
+impl<'tcx> EncodeContext<'tcx> {
+    fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Self {
+            tcx,
+            opaque: MemEncoder::new(),
+            type_shorthands: FxHashMap::default(),
+            predicate_shorthands: FxHashMap::default(),
+            unsupported_alloc: false,
+        }
+    }
+}
+
+/// The [`Decodable`] trait doesn't support errors, so when decoding fails we record the error,
+/// return a placeholder value, and check for errors once we are done decoding.
+struct DecodeContext<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    opaque: MemDecoder<'a>,
+    ty_cache: FxHashMap<usize, ty::Ty<'tcx>>,
+    /// The external crates loaded in the current session. [`DefPathHash`]es of other crates
+    /// cannot be resolved.
+    extern_crates: FxHashSet<StableCrateId>,
+    err: Option<DecodeError>,
+}
+
+enum DecodeError {
+    Stale,
+    UnresolvedDefPathHash(DefPathHash),
+    UnsupportedAlloc,
+}
+
+impl<'a, 'tcx> DecodeContext<'a, 'tcx> {
+    fn new(tcx: TyCtxt<'tcx>, data: &'a [u8], position: usize) -> Self {
+        let extern_crates = tcx
+            .crates(())
+            .iter()
+            .map(|cnum| tcx.stable_crate_id(*cnum))
+            .collect();
+        Self {
+            tcx,
+            opaque: MemDecoder::new(data, position),
+            ty_cache: FxHashMap::default(),
+            extern_crates,
+            err: None,
+        }
+    }
+
+    fn report(&mut self, err: DecodeError) {
+        self.err.get_or_insert(err);
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Stale => write!(f, "the crate was modified after it was checked"),
+            DecodeError::UnresolvedDefPathHash(def_path_hash) => {
+                write!(f, "cannot resolve definition with hash {def_path_hash:?}")
+            }
+            DecodeError::UnsupportedAlloc => write!(f, "decoding of allocations is not supported"),
+        }
+    }
+}
+
+macro_rules! encoder_methods {
+    ($($name:ident($ty:ty);)*) => {
+        $(fn $name(&mut self, value: $ty) {
+            self.opaque.$name(value)
+        })*
+    }
+}
+
+impl<'tcx> Encoder for EncodeContext<'tcx> {
+    encoder_methods! {
+        emit_usize(usize);
+        emit_u128(u128);
+        emit_u64(u64);
+        emit_u32(u32);
+        emit_u16(u16);
+        emit_u8(u8);
+
+        emit_isize(isize);
+        emit_i128(i128);
+        emit_i64(i64);
+        emit_i32(i32);
+        emit_i16(i16);
+        emit_i8(i8);
+
+        emit_bool(bool);
+        emit_f64(f64);
+        emit_f32(f32);
+        emit_char(char);
+        emit_str(&str);
+        emit_raw_bytes(&[u8]);
+    }
+}
+
+macro_rules! decoder_methods {
+    ($($name:ident -> $ty:ty;)*) => {
+        $(fn $name(&mut self) -> $ty {
+            self.opaque.$name()
+        })*
+    }
+}
+
+impl<'a, 'tcx> Decoder for DecodeContext<'a, 'tcx> {
+    decoder_methods! {
+        read_usize -> usize;
+        read_u128 -> u128;
+        read_u64 -> u64;
+        read_u32 -> u32;
+        read_u16 -> u16;
+        read_u8 -> u8;
+
+        read_isize -> isize;
+        read_i128 -> i128;
+        read_i64 -> i64;
+        read_i32 -> i32;
+        read_i16 -> i16;
+        read_i8 -> i8;
+
+        read_bool -> bool;
+        read_f64 -> f64;
+        read_f32 -> f32;
+        read_char -> char;
+        read_str -> &str;
+    }
+
+    fn read_raw_bytes(&mut self, len: usize) -> &[u8] {
+        self.opaque.read_raw_bytes(len)
+    }
+}
+
+// The refinement types we encode never contain allocations, but we report an error instead of
+// producing corrupted data if they ever do.
+
+impl<'tcx> TyEncoder for EncodeContext<'tcx> {
+    type I = TyCtxt<'tcx>;
+    const CLEAR_CROSS_CRATE: bool = true;
+
+    fn position(&self) -> usize {
+        self.opaque.position()
+    }
+
+    fn type_shorthands(&mut self) -> &mut FxHashMap<ty::Ty<'tcx>, usize> {
+        &mut self.type_shorthands
+    }
+
+    fn predicate_shorthands(&mut self) -> &mut FxHashMap<ty::PredicateKind<'tcx>, usize> {
+        &mut self.predicate_shorthands
+    }
+
+    fn encode_alloc_id(&mut self, _: &AllocId) {
+        self.unsupported_alloc = true;
+    }
+}
+
+impl<'a, 'tcx> TyDecoder for DecodeContext<'a, 'tcx> {
+    type I = TyCtxt<'tcx>;
+    const CLEAR_CROSS_CRATE: bool = true;
+
+    fn interner(&self) -> Self::I {
+        self.tcx
+    }
+
+    fn peek_byte(&self) -> u8 {
+        self.opaque.data[self.opaque.position()]
+    }
+
+    fn position(&self) -> usize {
+        self.opaque.position()
+    }
+
+    fn cached_ty_for_shorthand<F>(&mut self, shorthand: usize, or_insert_with: F) -> ty::Ty<'tcx>
+    where
+        F: FnOnce(&mut Self) -> ty::Ty<'tcx>,
+    {
+        if let Some(ty) = self.ty_cache.get(&shorthand) {
+            return *ty;
+        }
+        let ty = or_insert_with(self);
+        self.ty_cache.insert(shorthand, ty);
+        ty
+    }
+
+    fn with_position<F, R>(&mut self, pos: usize, f: F) -> R
+    where
+        F: FnOnce(&mut Self) -> R,
+    {
+        let old_pos = self.opaque.position();
+        self.opaque.set_position(pos);
+        let r = f(self);
+        self.opaque.set_position(old_pos);
+        r
+    }
+
+    fn decode_alloc_id(&mut self) -> AllocId {
+        self.report(DecodeError::UnsupportedAlloc);
+        self.tcx.reserve_alloc_id()
+    }
+}
+
+impl<'tcx> Encodable<EncodeContext<'tcx>> for DefId {
+    fn encode(&self, s: &mut EncodeContext<'tcx>) {
+        s.tcx.def_path_hash(*self).encode(s);
+    }
+}
+
+impl<'a, 'tcx> Decodable<DecodeContext<'a, 'tcx>> for DefId {
+    fn decode(d: &mut DecodeContext<'a, 'tcx>) -> Self {
+        let def_path_hash: DefPathHash = Decodable::decode(d);
+        // Within a crate that is not stale, resolution cannot fail. Resolving a hash from a crate
+        // that is not loaded would panic, so we check for it first.
+        if !d.extern_crates.contains(&def_path_hash.stable_crate_id()) {
+            d.report(DecodeError::UnresolvedDefPathHash(def_path_hash));
+            return CRATE_DEF_ID.to_def_id();
+        }
+        d.tcx.def_path_hash_to_def_id(def_path_hash, &mut || {
+            unreachable!("hash of an external crate resolved in the local crate")
+        })
+    }
+}
+
+mod errors {
+    use flux_macros::Diagnostic;
+    use rustc_span::Symbol;
+
+    #[derive(Diagnostic)]
+    #[diag(refineck::cannot_read_metadata, code = "FLUX")]
+    pub struct CannotReadMetadata {
+        pub krate: Symbol,
+        pub err: String,
+    }
+}
@@ -26,12 +26,14 @@ use crate::{
 
 pub struct ConvCtxt<'a, 'genv, 'tcx> {
     genv: &'a GlobalEnv<'genv, 'tcx>,
-    name_map: NameMap,
+    name_map: NameMap<'a>,
 }
 
-#[derive(Default, Debug)]
-struct NameMap {
+#[derive(Clone)]
+struct NameMap<'a> {
     map: FxHashMap<fhir::Name, Entry>,
+    /// Applications of defined functions are inlined using their definitions here.
+    defns: &'a rty::Defns,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -46,8 +48,19 @@ impl From<rty::Name> for Entry {
     }
 }
 
-pub(crate) fn conv_adt_def(tcx: TyCtxt, adt_def: &fhir::AdtDef) -> rty::AdtDef {
-    let mut name_map = NameMap::default();
+pub(crate) fn conv_defn(defns: &rty::Defns, defn: &fhir::Defn) -> Binders<rty::Expr> {
+    let mut name_map = NameMap::new(defns);
+    let sorts = name_map.conv_refine_params(&defn.args);
+    Binders::new(name_map.conv_expr(&defn.expr, 1), sorts)
+}
+
+/// Converts an axiom into a closed formula universally quantifying over its arguments.
+pub(crate) fn conv_axiom(defns: &rty::Defns, axiom: &fhir::Axiom) -> rty::Expr {
+    NameMap::new(defns).conv_forall(&axiom.args, &axiom.expr, 0)
+}
+
+pub(crate) fn conv_adt_def(tcx: TyCtxt, defns: &rty::Defns, adt_def: &fhir::AdtDef) -> rty::AdtDef {
+    let mut name_map = NameMap::new(defns);
     let sorts = name_map.conv_refined_by(&adt_def.refined_by);
 
     let invariants = adt_def
@@ -61,7 +74,7 @@ pub(crate) fn conv_adt_def(tcx: TyCtxt, adt_def: &fhir::AdtDef) -> rty::AdtDef {
 
 impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
     pub(crate) fn new(genv: &'a GlobalEnv<'genv, 'tcx>) -> Self {
-        Self { genv, name_map: NameMap::default() }
+        Self { genv, name_map: NameMap::new(genv.defns()) }
     }
 
     pub(crate) fn conv_fn_sig(genv: &GlobalEnv, fn_sig: &fhir::FnSig) -> rty::Binders<rty::FnSig> {
@@ -89,6 +102,10 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
         rty::Binders::new(rty::FnSig::new(requires, args, ret, ensures), params)
     }
 
+    pub(crate) fn conv_static_ty(genv: &GlobalEnv, ty: &fhir::Ty) -> rty::Ty {
+        ConvCtxt::new(genv).conv_ty(ty, 0)
+    }
+
     pub(crate) fn conv_enum_def_variants(
         genv: &GlobalEnv,
         enum_def: &fhir::EnumDef,
@@ -162,6 +179,12 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
                             }))
                         }
                         GenericParamDefKind::Lifetime => rty::GenericArg::Lifetime,
+                        GenericParamDefKind::Const => {
+                            rty::GenericArg::Const(rty::Expr::const_param(rty::ParamConst {
+                                index: param.index,
+                                name: param.name,
+                            }))
+                        }
                     }
                 })
                 .collect_vec();
@@ -205,8 +228,8 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
         }
     }
 
-    pub fn conv_qualifier(qualifier: &fhir::Qualifier) -> rty::Qualifier {
-        let mut name_map = NameMap::default();
+    pub fn conv_qualifier(defns: &rty::Defns, qualifier: &fhir::Qualifier) -> rty::Qualifier {
+        let mut name_map = NameMap::new(defns);
         let name_gen = IndexGen::new();
 
         let args = qualifier
@@ -266,14 +289,53 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
                 rty::Ty::tuple(tys)
             }
             fhir::Ty::Never => rty::Ty::never(),
+            fhir::Ty::Const(_) => unreachable!("const generic argument used as a type"),
+            fhir::Ty::Opaque(def_id) => {
+                let Ok(substs) = self.genv.identity_substs(*def_id) else {
+                    unreachable!("identity substs of an opaque type are always supported")
+                };
+                rty::Ty::opaque(*def_id, substs)
+            }
+            fhir::Ty::Projection(param, def_id) => {
+                rty::Ty::projection(*def_id, vec![rty::GenericArg::Ty(rty::Ty::param(*param))])
+            }
             fhir::Ty::Constr(pred, ty) => {
                 let pred = self.name_map.conv_pred(pred, nbinders);
                 rty::Ty::constr(pred, self.conv_ty(ty, nbinders))
             }
-            fhir::Ty::Array(ty, _) => rty::Ty::array(self.conv_ty(ty, nbinders), rty::Const),
-            fhir::Ty::Slice(ty) => rty::Ty::slice(self.conv_ty(ty, nbinders)),
-            fhir::Ty::Str => rty::Ty::str(),
-            fhir::Ty::Char => rty::Ty::char(),
+            fhir::Ty::Array(ty, len) => {
+                let bty = rty::BaseTy::Array(self.conv_ty(ty, nbinders));
+                self.conv_array_len(bty, len, nbinders)
+            }
+            fhir::Ty::Slice(ty, len) => {
+                let bty = rty::BaseTy::Slice(self.conv_ty(ty, nbinders));
+                self.conv_array_len(bty, len, nbinders)
+            }
+            fhir::Ty::FnPtr(inputs, output) => {
+                // The signature is wrapped in an (empty) binder so we need to account for the extra
+                // binding level when converting inputs and output.
+                let args = inputs
+                    .iter()
+                    .map(|ty| self.conv_ty(ty, nbinders + 1))
+                    .collect_vec();
+                let ret = self.conv_ty(output, nbinders + 1);
+                let fn_sig = rty::FnSig::new(vec![], args, ret, vec![]);
+                rty::Ty::fn_ptr(rty::Binders::new(fn_sig, vec![]))
+            }
+        }
+    }
+
+    fn conv_array_len(&mut self, bty: rty::BaseTy, len: &fhir::ArrayLen, nbinders: u32) -> rty::Ty {
+        match len {
+            fhir::ArrayLen::Infer => {
+                let pred = rty::Binders::new(rty::Pred::tt(), bty.sorts());
+                rty::Ty::exists(bty, pred)
+            }
+            fhir::ArrayLen::Arg(arg) => {
+                let is_binder = matches!(arg, fhir::RefineArg::Expr { is_binder: true, .. });
+                let arg = self.conv_arg(arg, &fhir::Sort::Int, nbinders);
+                rty::Ty::indexed(bty, rty::RefineArgs::new([(arg, is_binder)]))
+            }
         }
     }
 
@@ -324,6 +386,10 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
             fhir::BaseTy::Int(int_ty) => rty::BaseTy::Int(*int_ty),
             fhir::BaseTy::Uint(uint_ty) => rty::BaseTy::Uint(*uint_ty),
             fhir::BaseTy::Bool => rty::BaseTy::Bool,
+            fhir::BaseTy::Float(float_ty) => rty::BaseTy::Real(*float_ty),
+            fhir::BaseTy::Str => rty::BaseTy::Str,
+            fhir::BaseTy::Char => rty::BaseTy::Char,
+            fhir::BaseTy::Param(param_ty) => rty::BaseTy::Param(*param_ty),
             fhir::BaseTy::Adt(did, substs) => {
                 let mut i = 0;
                 let substs = List::from_vec(
@@ -345,6 +411,10 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
                                     }
                                 }
                                 GenericParamDefKind::Lifetime => rty::GenericArg::Lifetime,
+                                GenericParamDefKind::Const => {
+                                    i += 1;
+                                    self.conv_generic_arg(&substs[i - 1], nbinders)
+                                }
                             }
                         })
                         .collect(),
@@ -356,11 +426,19 @@ impl<'a, 'genv, 'tcx> ConvCtxt<'a, 'genv, 'tcx> {
     }
 
     fn conv_generic_arg(&mut self, arg: &fhir::Ty, nbinders: u32) -> rty::GenericArg {
-        rty::GenericArg::Ty(self.conv_ty(arg, nbinders))
+        if let fhir::Ty::Const(param) = arg {
+            rty::GenericArg::Const(rty::Expr::const_param(*param))
+        } else {
+            rty::GenericArg::Ty(self.conv_ty(arg, nbinders))
+        }
     }
 }
 
-impl NameMap {
+impl<'a> NameMap<'a> {
+    fn new(defns: &'a rty::Defns) -> Self {
+        NameMap { map: FxHashMap::default(), defns }
+    }
+
     fn insert(&mut self, name: fhir::Name, var: impl Into<Entry>) {
         self.map.insert(name, var.into());
     }
@@ -389,12 +467,37 @@ impl NameMap {
         }
         r
     }
+    /// Quantifiers bind a single variable, so we nest one quantifier per parameter.
+    fn conv_forall(
+        &mut self,
+        params: &[fhir::RefineParam],
+        body: &fhir::Expr,
+        nbinders: u32,
+    ) -> rty::Expr {
+        match params {
+            [] => self.conv_expr(body, nbinders),
+            [param, params @ ..] => {
+                self.with_binders(&[param.name.name], nbinders, |map, nbinders| {
+                    let body = map.conv_forall(params, body, nbinders);
+                    rty::Expr::quant(
+                        fhir::Quantifier::Forall,
+                        Binders::new(body, vec![param.sort.clone()]),
+                    )
+                })
+            }
+        }
+    }
+
     fn conv_invariant(&self, sorts: &[rty::Sort], invariant: &fhir::Expr) -> rty::Invariant {
         rty::Invariant { pred: Binders::new(self.conv_expr(invariant, 1), sorts) }
     }
 
     fn conv_refined_by(&mut self, refined_by: &fhir::RefinedBy) -> Vec<rty::Sort> {
-        refined_by
+        self.conv_refine_params(&refined_by.params)
+    }
+
+    fn conv_refine_params(&mut self, params: &[fhir::RefineParam]) -> Vec<rty::Sort> {
+        params
             .iter()
             .enumerate()
             .map(|(index, param)| {
@@ -431,6 +534,7 @@ impl NameMap {
     fn conv_expr(&self, expr: &fhir::Expr, nbinders: u32) -> rty::Expr {
         match &expr.kind {
             fhir::ExprKind::Const(did, _) => rty::Expr::const_def_id(*did),
+            fhir::ExprKind::ConstParam(param, _) => rty::Expr::const_param(*param),
             fhir::ExprKind::Var(name, ..) => self.get(*name, nbinders).to_expr(),
             fhir::ExprKind::Literal(lit) => rty::Expr::constant(conv_lit(*lit)),
             fhir::ExprKind::BinaryOp(op, box [e1, e2]) => {
@@ -443,7 +547,16 @@ impl NameMap {
             fhir::ExprKind::App(func, args) => {
                 match func {
                     fhir::Func::Uif(sym, _) => {
-                        rty::Expr::app(*sym, self.conv_exprs(args, nbinders))
+                        let args = self.conv_exprs(args, nbinders);
+                        self.defns
+                            .unfold(*sym, &args)
+                            .unwrap_or_else(|| rty::Expr::app(*sym, args))
+                    }
+                    fhir::Func::Theory(func) => {
+                        rty::Expr::theory_app(*func, self.conv_exprs(args, nbinders))
+                    }
+                    fhir::Func::Data(func) => {
+                        rty::Expr::data_app(*func, self.conv_exprs(args, nbinders))
                     }
                     fhir::Func::Var(..) => unreachable!("refinement variable in wrong position"),
                 }
@@ -455,6 +568,22 @@ impl NameMap {
                     self.conv_expr(e2, nbinders),
                 )
             }
+            fhir::ExprKind::Quant(quant, var, box [lo, hi, body]) => {
+                self.clone()
+                    .with_binders(&[var.name], nbinders, |map, nbinders| {
+                        let var = rty::Expr::bvar(rty::BoundVar::innermost(0));
+                        let bounds = rty::Expr::and([
+                            rty::Expr::le(map.conv_expr(lo, nbinders), var.clone()),
+                            rty::Expr::lt(var, map.conv_expr(hi, nbinders)),
+                        ]);
+                        let body = map.conv_expr(body, nbinders);
+                        let body = match quant {
+                            fhir::Quantifier::Forall => rty::Expr::implies(bounds, body),
+                            fhir::Quantifier::Exists => rty::Expr::and([bounds, body]),
+                        };
+                        rty::Expr::quant(*quant, Binders::new(body, vec![rty::Sort::Int]))
+                    })
+            }
         }
     }
 
@@ -467,5 +596,7 @@ fn conv_lit(lit: fhir::Lit) -> rty::Constant {
     match lit {
         fhir::Lit::Int(n) => rty::Constant::from(n),
         fhir::Lit::Bool(b) => rty::Constant::from(b),
+        // Desugaring only produces finite literals
+        fhir::Lit::Real(r) => rty::Constant::real(r).unwrap(),
     }
 }
@@ -1,55 +1,71 @@
 use std::{fmt, sync::OnceLock};
 
 use flux_fixpoint::Sign;
-pub use flux_fixpoint::{BinOp, Constant, UnOp};
+pub use flux_fixpoint::{BinOp, Constant, Quantifier, TheoryFunc, UnOp};
 use rustc_hir::def_id::DefId;
 use rustc_index::newtype_index;
-use rustc_middle::mir::{Field, Local};
+use rustc_macros::{TyDecodable, TyEncodable};
+use rustc_middle::{
+    mir::{Field, Local},
+    ty::ParamConst,
+};
 use rustc_span::Symbol;
 
-use super::BaseTy;
+use super::{BaseTy, Binders, DataFunc};
 use crate::{
     intern::{impl_internable, Interned, List},
     rty::fold::{TypeFoldable, TypeFolder},
-    rustc::mir::{Place, PlaceElem},
+    rustc::{
+        mir::{Place, PlaceElem},
+        ty::Const,
+    },
 };
 
 pub type Expr = Interned<ExprS>;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct ExprS {
     kind: ExprKind,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum ExprKind {
     ConstDefId(DefId),
+    /// A const generic parameter, e.g., the `N` in `fn foo<const N: usize>(x: [i32; N])`
+    ConstParam(ParamConst),
     FreeVar(Name),
     BoundVar(BoundVar),
     Local(Local),
     Constant(Constant),
     BinaryOp(BinOp, Expr, Expr),
     App(Symbol, List<Expr>),
+    /// An application of a function on sets or maps interpreted by the solver
+    TheoryApp(TheoryFunc, List<Expr>),
+    /// An application of a constructor, field selector or constructor test of a datatype
+    DataApp(DataFunc, List<Expr>),
     UnaryOp(UnOp, Expr),
     TupleProj(Expr, u32),
     Tuple(List<Expr>),
     PathProj(Expr, Field),
     IfThenElse(Expr, Expr, Expr),
+    /// A quantified expression. The bounds of a quantifier in the surface syntax are part of the
+    /// body, e.g., `forall i in lo..hi { p }` is represented as `∀i. lo ≤ i ∧ i < hi ⇒ p`.
+    Quant(Quantifier, Binders<Expr>),
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum Var {
     Bound(BoundVar),
     Free(Name),
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord, TyEncodable, TyDecodable)]
 pub struct Path {
     pub loc: Loc,
     projection: List<Field>,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, TyEncodable, TyDecodable)]
 pub enum Loc {
     Local(Local),
     Free(Name),
@@ -60,7 +76,7 @@ pub enum Loc {
 /// into a list of [`Binders`] and index into that list.
 ///
 /// [`Binders`]: super::Binders
-#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, TyEncodable, TyDecodable)]
 pub struct BoundVar {
     pub debruijn: DebruijnIndex,
     pub index: usize,
@@ -92,6 +108,13 @@ impl Expr {
             .clone()
     }
 
+    pub fn ff() -> Expr {
+        static FALSE: OnceLock<Expr> = OnceLock::new();
+        FALSE
+            .get_or_init(|| ExprKind::Constant(Constant::Bool(false)).intern())
+            .clone()
+    }
+
     pub fn and(exprs: impl IntoIterator<Item = Expr>) -> Expr {
         exprs
             .into_iter()
@@ -139,6 +162,17 @@ impl Expr {
         ExprKind::ConstDefId(c).intern()
     }
 
+    pub fn const_param(param: ParamConst) -> Expr {
+        ExprKind::ConstParam(param).intern()
+    }
+
+    pub fn from_const(c: &Const) -> Expr {
+        match c {
+            Const::Value(val) => Expr::from(*val),
+            Const::Param(param) => Expr::const_param(*param),
+        }
+    }
+
     pub fn tuple(exprs: impl Into<List<Expr>>) -> Expr {
         ExprKind::Tuple(exprs.into()).intern()
     }
@@ -150,14 +184,21 @@ impl Expr {
                 let bits = bits as i128;
                 ExprKind::Constant(Constant::from(bits)).intern()
             }
-            BaseTy::Uint(_) => ExprKind::Constant(Constant::from(bits)).intern(),
+            BaseTy::Uint(_) | BaseTy::Char => ExprKind::Constant(Constant::from(bits)).intern(),
             BaseTy::Bool => ExprKind::Constant(Constant::Bool(bits != 0)).intern(),
             BaseTy::Adt(_, _)
-            | BaseTy::Array(..)
+            | BaseTy::Array(_)
             | BaseTy::Str
             | BaseTy::Float(_)
+            | BaseTy::Real(_)
             | BaseTy::Slice(_)
-            | BaseTy::Char => panic!(),
+            | BaseTy::Closure(..)
+            | BaseTy::FnDef(..)
+            | BaseTy::FnPtr(_)
+            | BaseTy::Dynamic(..)
+            | BaseTy::Opaque(..)
+            | BaseTy::Projection(..)
+            | BaseTy::Param(_) => panic!(),
         }
     }
 
@@ -165,6 +206,10 @@ impl Expr {
         ExprKind::IfThenElse(p.into(), e1.into(), e2.into()).intern()
     }
 
+    pub fn quant(quant: Quantifier, body: Binders<Expr>) -> Expr {
+        ExprKind::Quant(quant, body).intern()
+    }
+
     pub fn binary_op(op: BinOp, e1: impl Into<Expr>, e2: impl Into<Expr>) -> Expr {
         ExprKind::BinaryOp(op, e1.into(), e2.into()).intern()
     }
@@ -173,6 +218,14 @@ impl Expr {
         ExprKind::App(func, args.into()).intern()
     }
 
+    pub fn theory_app(func: TheoryFunc, args: impl Into<List<Expr>>) -> Expr {
+        ExprKind::TheoryApp(func, args.into()).intern()
+    }
+
+    pub fn data_app(func: DataFunc, args: impl Into<List<Expr>>) -> Expr {
+        ExprKind::DataApp(func, args.into()).intern()
+    }
+
     pub fn unary_op(op: UnOp, e: impl Into<Expr>) -> Expr {
         ExprKind::UnaryOp(op, e.into()).intern()
     }
@@ -277,6 +330,15 @@ impl Expr {
         self.fold_with(&mut Simplify)
     }
 
+    /// Shifts the bound variables escaping the expression by `amount` binders.
+    pub fn shift_in_bvars(&self, amount: u32) -> Expr {
+        if amount == 0 {
+            self.clone()
+        } else {
+            self.fold_with(&mut super::subst::BVarShifter::new(amount))
+        }
+    }
+
     pub fn to_loc(&self) -> Option<Loc> {
         match self.kind() {
             ExprKind::FreeVar(name) => Some(Loc::Free(*name)),
@@ -467,7 +529,17 @@ macro_rules! impl_ops {
         }
     )*};
 }
-impl_ops!(Add: add, Sub: sub, Mul: mul, Div: div);
+impl_ops!(
+    Add: add,
+    Sub: sub,
+    Mul: mul,
+    Div: div,
+    BitAnd: bitand,
+    BitOr: bitor,
+    BitXor: bitxor,
+    Shl: shl,
+    Shr: shr
+);
 
 impl From<i32> for Expr {
     fn from(value: i32) -> Self {
@@ -479,6 +551,12 @@ impl From<i32> for Expr {
     }
 }
 
+impl From<usize> for Expr {
+    fn from(value: usize) -> Self {
+        Expr::constant(Constant::Int(Sign::Positive, value as u128))
+    }
+}
+
 impl From<&Expr> for Expr {
     fn from(e: &Expr) -> Self {
         e.clone()
@@ -534,6 +612,10 @@ mod pretty {
         Or,
         And,
         Cmp,
+        BitOr,
+        BitXor,
+        BitAnd,
+        Shift,
         AddSub,
         MulDiv,
     }
@@ -547,6 +629,10 @@ mod pretty {
             BinOp::Eq | BinOp::Ne | BinOp::Gt | BinOp::Lt | BinOp::Ge | BinOp::Le => {
                 Precedence::Cmp
             }
+            BinOp::BitOr => Precedence::BitOr,
+            BinOp::BitXor => Precedence::BitXor,
+            BinOp::BitAnd => Precedence::BitAnd,
+            BinOp::Shl | BinOp::Shr => Precedence::Shift,
             BinOp::Add | BinOp::Sub => Precedence::AddSub,
             BinOp::Mul | BinOp::Div | BinOp::Mod => Precedence::MulDiv,
         }
@@ -554,7 +640,7 @@ mod pretty {
 
     impl Precedence {
         pub fn is_associative(&self) -> bool {
-            !matches!(self, Precedence::Imp | Precedence::Cmp)
+            !matches!(self, Precedence::Imp | Precedence::Cmp | Precedence::Shift)
         }
     }
 
@@ -574,6 +660,7 @@ mod pretty {
             match e.kind() {
                 ExprKind::FreeVar(name) => w!("{:?}", ^name),
                 ExprKind::ConstDefId(did) => w!("{:?}", ^did),
+                ExprKind::ConstParam(param) => w!("{}", ^param),
                 ExprKind::BoundVar(bvar) => w!("{:?}", bvar),
                 ExprKind::Local(local) => w!("{:?}", ^local),
                 ExprKind::BinaryOp(op, e1, e2) => {
@@ -622,9 +709,18 @@ mod pretty {
                 ExprKind::App(f, exprs) => {
                     w!("{}({:?})", ^f, join!(", ", exprs))
                 }
+                ExprKind::TheoryApp(f, exprs) => {
+                    w!("{}({:?})", ^f, join!(", ", exprs))
+                }
+                ExprKind::DataApp(f, exprs) => {
+                    w!("{:?}({:?})", ^f, join!(", ", exprs))
+                }
                 ExprKind::IfThenElse(p, e1, e2) => {
                     w!("if {:?} {{ {:?} }} else {{ {:?} }}", p, e1, e2)
                 }
+                ExprKind::Quant(quant, body) => {
+                    w!("{}({:?}). {:?}", ^quant, join!(", ", &body.params), &body.value)
+                }
             }
         }
     }
@@ -677,6 +773,11 @@ mod pretty {
                 BinOp::Mul => w!("*"),
                 BinOp::Div => w!("/"),
                 BinOp::Mod => w!("mod"),
+                BinOp::BitAnd => w!("&"),
+                BinOp::BitOr => w!("|"),
+                BinOp::BitXor => w!("^"),
+                BinOp::Shl => w!("<<"),
+                BinOp::Shr => w!(">>"),
             }
         }
     }
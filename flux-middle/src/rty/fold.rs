@@ -5,8 +5,8 @@ use itertools::Itertools;
 use rustc_hash::FxHashSet;
 
 use super::{
-    BaseTy, Binders, Constraint, Expr, ExprKind, FnSig, GenericArg, KVar, Name, Pred, RefineArg,
-    RefineArgs, RefineArgsData, Sort, Ty, TyKind, VariantRet,
+    BaseTy, Binders, Constraint, Expr, ExprKind, FnSig, GenericArg, KVar, Name, ParamConst,
+    ParamTy, Pred, RefineArg, RefineArgs, RefineArgsData, Sort, Ty, TyKind, VariantRet,
 };
 use crate::{
     intern::{Internable, List},
@@ -17,6 +17,10 @@ pub trait TypeVisitor: Sized {
     fn visit_fvar(&mut self, name: Name) {
         name.super_visit_with(self);
     }
+
+    fn visit_bty(&mut self, bty: &BaseTy) {
+        bty.super_visit_with(self);
+    }
 }
 
 pub trait TypeFolder: Sized {
@@ -69,6 +73,25 @@ pub trait TypeFoldable: Sized {
         collector.0
     }
 
+    /// Whether the value mentions an associated type projection that was not normalized.
+    fn has_projections(&self) -> bool {
+        struct HasProjections(bool);
+
+        impl TypeVisitor for HasProjections {
+            fn visit_bty(&mut self, bty: &BaseTy) {
+                if let BaseTy::Projection(..) = bty {
+                    self.0 = true;
+                } else {
+                    bty.super_visit_with(self);
+                }
+            }
+        }
+
+        let mut visitor = HasProjections(false);
+        self.visit_with(&mut visitor);
+        visitor.0
+    }
+
     /// Replaces all [`holes`] with a fresh [`predicate`] generated by calling `mk_pred`.
     ///
     /// [`holes`]: Pred::Hole
@@ -81,7 +104,7 @@ pub trait TypeFoldable: Sized {
             F: FnMut(&[Sort]) -> Binders<Pred>,
         {
             fn fold_binders<T: TypeFoldable>(&mut self, t: &Binders<T>) -> Binders<T> {
-                t.super_fold_with(&mut ReplaceHoles(&mut self.0, &t.params))
+                t.super_fold_with(&mut ReplaceHoles(&mut *self.0, &t.params))
             }
 
             fn fold_pred(&mut self, pred: &Pred) -> Pred {
@@ -124,21 +147,90 @@ pub trait TypeFoldable: Sized {
     fn replace_generic_args(&self, args: &[GenericArg]) -> Self {
         struct GenericsFolder<'a>(&'a [GenericArg]);
 
+        impl GenericsFolder<'_> {
+            fn arg(&self, param_ty: &ParamTy) -> &Ty {
+                match &self.0[param_ty.index as usize] {
+                    GenericArg::Ty(ty) => ty,
+                    GenericArg::Lifetime | GenericArg::Const(_) => {
+                        unreachable!("invalid generic argument")
+                    }
+                }
+            }
+
+            fn const_arg(&self, param: &ParamConst) -> &Expr {
+                match &self.0[param.index as usize] {
+                    GenericArg::Const(e) => e,
+                    GenericArg::Ty(_) | GenericArg::Lifetime => {
+                        unreachable!("invalid generic argument")
+                    }
+                }
+            }
+        }
+
         impl TypeFolder for GenericsFolder<'_> {
             fn fold_ty(&mut self, ty: &Ty) -> Ty {
-                if let TyKind::Param(param_ty) = ty.kind() {
-                    match &self.0[param_ty.index as usize] {
-                        GenericArg::Ty(ty) => ty.clone(),
-                        GenericArg::Lifetime => {
-                            unreachable!("invalid generic argument")
-                        }
+                match ty.kind() {
+                    TyKind::Param(param_ty) => self.arg(param_ty).clone(),
+                    TyKind::Indexed(BaseTy::Param(param_ty), idxs) => {
+                        let idxs = idxs.fold_with(self);
+                        with_index(self.arg(param_ty), &idxs)
+                    }
+                    TyKind::Exists(BaseTy::Param(param_ty), pred) => {
+                        let pred = pred.fold_with(self);
+                        with_pred(self.arg(param_ty), &pred)
                     }
+                    _ => ty.super_fold_with(self),
+                }
+            }
+
+            fn fold_expr(&mut self, expr: &Expr) -> Expr {
+                if let ExprKind::ConstParam(param) = expr.kind() {
+                    self.const_arg(param).clone()
                 } else {
-                    ty.super_fold_with(self)
+                    expr.super_fold_with(self)
+                }
+            }
+        }
+
+        /// Instantiates `T[idxs]` with `T := ty`.
+        fn with_index(ty: &Ty, idxs: &RefineArgs) -> Ty {
+            match ty.kind() {
+                TyKind::Indexed(bty, _) if is_int_indexed(bty) => {
+                    Ty::indexed(bty.clone(), idxs.clone())
+                }
+                TyKind::Exists(bty, pred) if is_int_indexed(bty) => {
+                    Ty::constr(
+                        pred.replace_bound_vars(idxs.args()),
+                        Ty::indexed(bty.clone(), idxs.clone()),
+                    )
                 }
+                TyKind::Constr(pred, ty) => Ty::constr(pred.clone(), with_index(ty, idxs)),
+                _ => ty.clone(),
             }
         }
 
+        /// Instantiates `T{v: pred}` with `T := ty`.
+        fn with_pred(ty: &Ty, pred: &Binders<Pred>) -> Ty {
+            match ty.kind() {
+                TyKind::Indexed(bty, idxs) if is_int_indexed(bty) => {
+                    let eq = Expr::eq(Expr::nu(), idxs.nth(0).as_expr().clone());
+                    let conj = Pred::And(List::from_vec(vec![pred.value.clone(), Pred::Expr(eq)]));
+                    Ty::exists(bty.clone(), Binders::new(conj, pred.params.clone()))
+                }
+                TyKind::Exists(bty, ty_pred) if is_int_indexed(bty) => {
+                    let conj =
+                        Pred::And(List::from_vec(vec![ty_pred.value.clone(), pred.value.clone()]));
+                    Ty::exists(bty.clone(), Binders::new(conj, ty_pred.params.clone()))
+                }
+                TyKind::Constr(constr, ty) => Ty::constr(constr.clone(), with_pred(ty, pred)),
+                _ => ty.clone(),
+            }
+        }
+
+        fn is_int_indexed(bty: &BaseTy) -> bool {
+            bty.sorts() == [Sort::Int]
+        }
+
         self.fold_with(&mut GenericsFolder(args))
     }
 }
@@ -356,33 +448,68 @@ impl TypeFoldable for RefineArg {
 }
 
 impl TypeFoldable for BaseTy {
+    fn visit_with<V: TypeVisitor>(&self, visitor: &mut V) {
+        visitor.visit_bty(self);
+    }
+
     fn super_fold_with<F: TypeFolder>(&self, folder: &mut F) -> Self {
         match self {
             BaseTy::Adt(adt_def, substs) => {
                 let substs = List::from_vec(substs.iter().map(|ty| ty.fold_with(folder)).collect());
                 BaseTy::adt(adt_def.clone(), substs)
             }
-            BaseTy::Array(ty, c) => BaseTy::Array(ty.fold_with(folder), c.clone()),
+            BaseTy::Array(ty) => BaseTy::Array(ty.fold_with(folder)),
             BaseTy::Slice(ty) => BaseTy::Slice(ty.fold_with(folder)),
+            BaseTy::Closure(def_id, upvar_tys) => {
+                BaseTy::Closure(*def_id, upvar_tys.iter().map(|ty| ty.fold_with(folder)).collect())
+            }
+            BaseTy::FnDef(def_id, substs) => {
+                BaseTy::FnDef(*def_id, substs.iter().map(|arg| arg.fold_with(folder)).collect())
+            }
+            BaseTy::FnPtr(fn_sig) => BaseTy::FnPtr(fn_sig.fold_with(folder)),
+            BaseTy::Dynamic(def_id, substs) => {
+                BaseTy::Dynamic(*def_id, substs.iter().map(|arg| arg.fold_with(folder)).collect())
+            }
+            BaseTy::Opaque(def_id, substs) => {
+                BaseTy::Opaque(*def_id, substs.iter().map(|arg| arg.fold_with(folder)).collect())
+            }
+            BaseTy::Projection(def_id, substs) => {
+                BaseTy::Projection(
+                    *def_id,
+                    substs.iter().map(|arg| arg.fold_with(folder)).collect(),
+                )
+            }
             BaseTy::Int(_)
             | BaseTy::Uint(_)
             | BaseTy::Bool
             | BaseTy::Float(_)
+            | BaseTy::Real(_)
             | BaseTy::Str
-            | BaseTy::Char => self.clone(),
+            | BaseTy::Char
+            | BaseTy::Param(_) => self.clone(),
         }
     }
 
     fn super_visit_with<V: TypeVisitor>(&self, visitor: &mut V) {
         match self {
-            BaseTy::Adt(_, substs) => substs.iter().for_each(|ty| ty.visit_with(visitor)),
-            BaseTy::Array(ty, _) | BaseTy::Slice(ty) => ty.visit_with(visitor),
+            BaseTy::Adt(_, substs)
+            | BaseTy::FnDef(_, substs)
+            | BaseTy::Dynamic(_, substs)
+            | BaseTy::Opaque(_, substs)
+            | BaseTy::Projection(_, substs) => {
+                substs.iter().for_each(|ty| ty.visit_with(visitor));
+            }
+            BaseTy::FnPtr(fn_sig) => fn_sig.visit_with(visitor),
+            BaseTy::Array(ty) | BaseTy::Slice(ty) => ty.visit_with(visitor),
+            BaseTy::Closure(_, upvar_tys) => upvar_tys.iter().for_each(|ty| ty.visit_with(visitor)),
             BaseTy::Int(_)
             | BaseTy::Uint(_)
             | BaseTy::Bool
             | BaseTy::Float(_)
+            | BaseTy::Real(_)
             | BaseTy::Str
-            | BaseTy::Char => {}
+            | BaseTy::Char
+            | BaseTy::Param(_) => {}
         }
     }
 }
@@ -392,6 +519,7 @@ impl TypeFoldable for GenericArg {
         match self {
             GenericArg::Ty(ty) => GenericArg::Ty(ty.fold_with(folder)),
             GenericArg::Lifetime => GenericArg::Lifetime,
+            GenericArg::Const(e) => GenericArg::Const(e.fold_with(folder)),
         }
     }
 
@@ -399,6 +527,7 @@ impl TypeFoldable for GenericArg {
         match self {
             GenericArg::Ty(ty) => ty.visit_with(visitor),
             GenericArg::Lifetime => {}
+            GenericArg::Const(e) => e.visit_with(visitor),
         }
     }
 }
@@ -459,6 +588,7 @@ impl TypeFoldable for Expr {
             ExprKind::FreeVar(name) => Expr::fvar(name.fold_with(folder)),
             ExprKind::BoundVar(bvar) => Expr::bvar(*bvar),
             ExprKind::ConstDefId(did) => Expr::const_def_id(*did),
+            ExprKind::ConstParam(param) => Expr::const_param(*param),
             ExprKind::Local(local) => Expr::local(*local),
             ExprKind::Constant(c) => Expr::constant(*c),
             ExprKind::BinaryOp(op, e1, e2) => {
@@ -472,9 +602,12 @@ impl TypeFoldable for Expr {
             }
             ExprKind::PathProj(e, field) => Expr::path_proj(e.fold_with(folder), *field),
             ExprKind::App(func, args) => Expr::app(*func, args.fold_with(folder)),
+            ExprKind::TheoryApp(func, args) => Expr::theory_app(*func, args.fold_with(folder)),
+            ExprKind::DataApp(func, args) => Expr::data_app(*func, args.fold_with(folder)),
             ExprKind::IfThenElse(p, e1, e2) => {
                 Expr::ite(p.fold_with(folder), e1.fold_with(folder), e2.fold_with(folder))
             }
+            ExprKind::Quant(quant, body) => Expr::quant(*quant, body.fold_with(folder)),
         }
     }
 
@@ -496,8 +629,9 @@ impl TypeFoldable for Expr {
             ExprKind::Constant(_)
             | ExprKind::BoundVar(_)
             | ExprKind::Local(_)
-            | ExprKind::ConstDefId(_) => {}
-            ExprKind::App(_, args) => {
+            | ExprKind::ConstDefId(_)
+            | ExprKind::ConstParam(_) => {}
+            ExprKind::App(_, args) | ExprKind::TheoryApp(_, args) | ExprKind::DataApp(_, args) => {
                 for e in args {
                     e.visit_with(visitor);
                 }
@@ -507,6 +641,7 @@ impl TypeFoldable for Expr {
                 e1.visit_with(visitor);
                 e2.visit_with(visitor);
             }
+            ExprKind::Quant(_, body) => body.visit_with(visitor),
         }
     }
 
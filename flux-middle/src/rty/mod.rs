@@ -12,19 +12,26 @@ pub mod subst;
 use std::{borrow::Cow, fmt, sync::LazyLock};
 
 pub use expr::{BoundVar, DebruijnIndex, Expr, ExprKind, Loc, Name, Path, Var, INNERMOST};
-pub use flux_fixpoint::{BinOp, Constant, UnOp};
+pub use flux_fixpoint::{BinOp, Constant, TheoryFunc, UnOp};
 use itertools::Itertools;
+use rustc_hash::FxHashMap;
 use rustc_hir::def_id::DefId;
 use rustc_index::{bit_set::BitSet, newtype_index};
-use rustc_middle::mir::Field;
-pub use rustc_middle::ty::{AdtFlags, FloatTy, IntTy, ParamTy, ScalarInt, UintTy};
+use rustc_macros::{TyDecodable, TyEncodable};
+pub use rustc_middle::ty::{AdtFlags, FloatTy, IntTy, ParamConst, ParamTy, ScalarInt, UintTy};
+use rustc_middle::{
+    mir::Field,
+    ty::{
+        codec::{TyDecoder, TyEncoder},
+        TyCtxt,
+    },
+};
+use rustc_serialize::{Decodable, Encodable};
+use rustc_span::Symbol;
 pub use rustc_target::abi::VariantIdx;
 
 use self::{fold::TypeFoldable, subst::BVarFolder};
-pub use crate::{
-    fhir::{FuncSort, RefKind, Sort},
-    rustc::ty::Const,
-};
+pub use crate::fhir::{DataFunc, FuncSort, RefKind, Sort};
 use crate::{
     intern::{impl_internable, Interned, List},
     rustc::mir::Place,
@@ -43,26 +50,26 @@ pub struct AdtDefData {
     opaque: bool,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Debug, Eq, PartialEq, Hash, TyEncodable, TyDecodable)]
 pub struct Invariant {
     pub pred: Binders<Expr>,
 }
 
 pub type PolyVariant = Binders<VariantDef>;
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash, TyEncodable, TyDecodable)]
 pub struct VariantDef {
     pub fields: List<Ty>,
     pub ret: VariantRet,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(Clone, Eq, PartialEq, Hash, Debug, TyEncodable, TyDecodable)]
 pub struct VariantRet {
     pub bty: BaseTy,
     pub args: List<RefineArg>,
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash, TyEncodable, TyDecodable)]
 pub struct Binders<T> {
     params: List<Sort>,
     value: T,
@@ -70,7 +77,7 @@ pub struct Binders<T> {
 
 pub type PolySig = Binders<FnSig>;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct FnSig {
     requires: List<Constraint>,
     args: List<Ty>,
@@ -80,27 +87,34 @@ pub struct FnSig {
 
 pub type Constraints = List<Constraint>;
 
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash, TyEncodable, TyDecodable)]
 pub enum Constraint {
     Type(Path, Ty),
     Pred(Pred),
 }
 
-#[derive(Debug)]
+#[derive(Debug, TyEncodable, TyDecodable)]
 pub struct Qualifier {
     pub name: String,
     pub args: Vec<(Name, Sort)>,
     pub expr: Expr,
 }
 
+/// The bodies of the refinement functions defined with `#[flux::defs]`, abstracted over their
+/// arguments. Applications of these functions are inlined during conversion.
+#[derive(Default)]
+pub struct Defns {
+    defns: FxHashMap<Symbol, Binders<Expr>>,
+}
+
 pub type Ty = Interned<TyS>;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct TyS {
     kind: TyKind,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum TyKind {
     Indexed(BaseTy, RefineArgs),
     Exists(BaseTy, Binders<Pred>),
@@ -127,10 +141,10 @@ pub enum TyKind {
     Discr(Place),
 }
 
-#[derive(Clone, Eq, Hash, PartialEq)]
+#[derive(Clone, Eq, Hash, PartialEq, TyEncodable, TyDecodable)]
 pub struct RefineArgs(Interned<RefineArgsData>);
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Eq, Hash, PartialEq, TyEncodable, TyDecodable)]
 struct RefineArgsData {
     args: Vec<RefineArg>,
     /// Set containing all the indices of arguments that were used as binders in the surface syntax.
@@ -138,35 +152,63 @@ struct RefineArgsData {
     is_binder: BitSet<usize>,
 }
 
-#[derive(Clone, Eq, Hash, PartialEq)]
+#[derive(Clone, Eq, Hash, PartialEq, TyEncodable, TyDecodable)]
 pub enum RefineArg {
     Expr(Expr),
     Abs(Binders<Pred>),
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum BaseTy {
     Int(IntTy),
     Uint(UintTy),
     Bool,
     Str,
     Char,
-    Array(Ty, Const),
+    /// An array indexed by its length.
+    Array(Ty),
+    /// A slice indexed by its length.
     Slice(Ty),
     Adt(AdtDef, Substs),
     Float(FloatTy),
+    /// A floating-point number indexed by a real approximating its value. Floats are only
+    /// refined like this when the `real_floats` setting is enabled, otherwise they are
+    /// represented by an unrefined [`BaseTy::Float`].
+    Real(FloatTy),
+    /// A closure identified by its [`DefId`] together with the types of its captured upvars.
+    Closure(DefId, List<Ty>),
+    /// The zero-sized type of a function item. Values of this type are reified into a
+    /// [`BaseTy::FnPtr`] when cast to a function pointer.
+    FnDef(DefId, Substs),
+    /// A function pointer together with the refined signature it must satisfy.
+    FnPtr(PolySig),
+    /// A trait object `dyn Trait` identified by the [`DefId`] of the principal trait. The substs
+    /// don't include the `Self` type. Calls to methods through a trait object are checked against
+    /// the signature of the method in the trait.
+    Dynamic(DefId, Substs),
+    /// An opaque type `impl Trait` in return position. Values of an opaque type are not refined
+    /// and calls through it are checked against the signature of the method in the trait.
+    Opaque(DefId, Substs),
+    /// An associated type projection that could not be normalized, e.g., `<I as Iterator>::Item`
+    /// for a generic `I`. The substs start with the `Self` type. Projections are not refined.
+    Projection(DefId, Substs),
+    /// A type parameter indexed by an integer. When the parameter is instantiated with a type
+    /// indexed by a single integer the index is transferred to it; otherwise it is dropped.
+    Param(ParamTy),
 }
 
 pub type Substs = List<GenericArg>;
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum GenericArg {
     Ty(Ty),
     /// We treat lifetime opaquely
     Lifetime,
+    /// A const generic argument, e.g., the `3` in `Buf<3>`
+    Const(Expr),
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum Pred {
     Hole,
     Kvar(KVar),
@@ -181,7 +223,7 @@ pub enum Pred {
 /// Flux generalizes the self argument to be a list in order to deal with multiple indices. When
 /// generating the fixpoint constraint, the kvar will be split into multiple kvars, one for each
 /// argument in the list.
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct KVar {
     pub kvid: KVid,
     pub args: List<Expr>,
@@ -212,6 +254,23 @@ impl<T> Binders<T> {
     }
 }
 
+impl Defns {
+    pub fn insert(&mut self, name: Symbol, body: Binders<Expr>) {
+        self.defns.insert(name, body);
+    }
+
+    /// Returns the body of the function `func` with its arguments substituted by `args` or
+    /// `None` if `func` is not a defined function.
+    pub fn unfold(&self, func: Symbol, args: &[Expr]) -> Option<Expr> {
+        let body = self.defns.get(&func)?;
+        let args = args
+            .iter()
+            .map(|arg| RefineArg::Expr(arg.clone()))
+            .collect_vec();
+        Some(body.replace_bound_vars(&args))
+    }
+}
+
 impl<T> Binders<T>
 where
     T: TypeFoldable,
@@ -373,6 +432,27 @@ impl AdtDef {
     }
 }
 
+/// Only the refinement information of an [`AdtDef`] is encoded, the rest is recovered from the
+/// rustc definition when decoding.
+impl<'tcx, E: TyEncoder<I = TyCtxt<'tcx>>> Encodable<E> for AdtDef {
+    fn encode(&self, e: &mut E) {
+        <DefId as Encodable<E>>::encode(&self.def_id(), e);
+        self.0.sorts.encode(e);
+        self.0.invariants.encode(e);
+        self.0.opaque.encode(e);
+    }
+}
+
+impl<'tcx, D: TyDecoder<I = TyCtxt<'tcx>>> Decodable<D> for AdtDef {
+    fn decode(d: &mut D) -> Self {
+        let def_id = <DefId as Decodable<D>>::decode(d);
+        let sorts = Decodable::decode(d);
+        let invariants = Decodable::decode(d);
+        let opaque = Decodable::decode(d);
+        AdtDef::new(d.interner().adt_def(def_id), sorts, invariants, opaque)
+    }
+}
+
 impl VariantDef {
     pub fn new(fields: Vec<Ty>, ret: VariantRet) -> Self {
         VariantDef { fields: List::from_vec(fields), ret }
@@ -394,12 +474,12 @@ impl Ty {
         TyKind::Ptr(rk, path.into()).intern()
     }
 
-    pub fn array(ty: Ty, c: Const) -> Ty {
-        Ty::indexed(BaseTy::Array(ty, c), RefineArgs::empty())
+    pub fn array(ty: Ty, len: impl Into<Expr>) -> Ty {
+        Ty::indexed(BaseTy::Array(ty), RefineArgs::one(len.into()))
     }
 
-    pub fn slice(ty: Ty) -> Ty {
-        Ty::indexed(BaseTy::Slice(ty), RefineArgs::empty())
+    pub fn slice(ty: Ty, len: impl Into<Expr>) -> Ty {
+        Ty::indexed(BaseTy::Slice(ty), RefineArgs::one(len.into()))
     }
 
     pub fn box_ptr(loc: Name, alloc: Ty) -> Ty {
@@ -453,11 +533,35 @@ impl Ty {
     }
 
     pub fn str() -> Ty {
-        Ty::indexed(BaseTy::Str, RefineArgs::empty())
+        Ty::exists(BaseTy::Str, Binders::new(Pred::tt(), vec![Sort::Int]))
     }
 
     pub fn char() -> Ty {
-        Ty::indexed(BaseTy::Char, RefineArgs::empty())
+        Ty::exists(BaseTy::Char, Binders::new(Pred::tt(), vec![Sort::Int]))
+    }
+
+    pub fn closure(def_id: DefId, upvar_tys: impl Into<List<Ty>>) -> Ty {
+        Ty::indexed(BaseTy::Closure(def_id, upvar_tys.into()), RefineArgs::empty())
+    }
+
+    pub fn fn_def(def_id: DefId, substs: impl Into<Substs>) -> Ty {
+        Ty::indexed(BaseTy::FnDef(def_id, substs.into()), RefineArgs::empty())
+    }
+
+    pub fn fn_ptr(fn_sig: PolySig) -> Ty {
+        Ty::indexed(BaseTy::FnPtr(fn_sig), RefineArgs::empty())
+    }
+
+    pub fn dynamic(def_id: DefId, substs: impl Into<Substs>) -> Ty {
+        Ty::indexed(BaseTy::Dynamic(def_id, substs.into()), RefineArgs::empty())
+    }
+
+    pub fn opaque(def_id: DefId, substs: impl Into<Substs>) -> Ty {
+        Ty::indexed(BaseTy::Opaque(def_id, substs.into()), RefineArgs::empty())
+    }
+
+    pub fn projection(item_def_id: DefId, substs: impl Into<Substs>) -> Ty {
+        Ty::indexed(BaseTy::Projection(item_def_id, substs.into()), RefineArgs::empty())
     }
 
     pub fn never() -> Ty {
@@ -494,6 +598,10 @@ impl Ty {
     pub fn float(float_ty: FloatTy) -> Ty {
         Ty::indexed(BaseTy::Float(float_ty), RefineArgs::empty())
     }
+
+    pub fn real(float_ty: FloatTy) -> Ty {
+        Ty::exists(BaseTy::Real(float_ty), Binders::new(Pred::tt(), vec![Sort::Real]))
+    }
 }
 
 impl TyKind {
@@ -525,6 +633,11 @@ impl TyS {
         matches!(self.kind(), TyKind::Indexed(bty, _) | TyKind::Exists(bty, _) if bty.is_bool())
     }
 
+    /// Whether the type is a `char`
+    pub fn is_char(&self) -> bool {
+        matches!(self.kind(), TyKind::Indexed(bty, _) | TyKind::Exists(bty, _) if bty.is_char())
+    }
+
     pub fn is_uninit(&self) -> bool {
         matches!(self.kind(), TyKind::Uninit)
     }
@@ -567,6 +680,10 @@ impl BaseTy {
         matches!(self, BaseTy::Bool)
     }
 
+    fn is_char(&self) -> bool {
+        matches!(self, BaseTy::Char)
+    }
+
     pub fn is_box(&self) -> bool {
         match self {
             BaseTy::Adt(adt_def, _) => adt_def.is_box(),
@@ -584,29 +701,57 @@ impl BaseTy {
             }
         });
 
+        // A code point is at most `0x10FFFF`
+        static CHAR: LazyLock<Invariant> = LazyLock::new(|| {
+            let nu = Expr::bvar(BoundVar::NU);
+            let max = Expr::constant(Constant::from(char::MAX as u128));
+            Invariant {
+                pred: Binders::new(
+                    Expr::and([Expr::ge(&nu, Expr::zero()), Expr::le(nu, max)]),
+                    vec![Sort::Int],
+                ),
+            }
+        });
+
         match self {
             BaseTy::Adt(adt_def, _) => adt_def.invariants(),
-            BaseTy::Uint(_) => std::slice::from_ref(&GE0),
+            BaseTy::Uint(_) | BaseTy::Array(_) | BaseTy::Slice(_) | BaseTy::Str => {
+                std::slice::from_ref(&GE0)
+            }
+            BaseTy::Char => std::slice::from_ref(&CHAR),
             BaseTy::Int(_)
             | BaseTy::Bool
-            | BaseTy::Str
-            | BaseTy::Array(_, _)
             | BaseTy::Float(_)
-            | BaseTy::Slice(_)
-            | BaseTy::Char => &[],
+            | BaseTy::Real(_)
+            | BaseTy::Closure(..)
+            | BaseTy::FnDef(..)
+            | BaseTy::FnPtr(_)
+            | BaseTy::Dynamic(..)
+            | BaseTy::Opaque(..)
+            | BaseTy::Projection(..)
+            | BaseTy::Param(_) => &[],
         }
     }
 
     pub fn sorts(&self) -> &[Sort] {
         match self {
-            BaseTy::Int(_) | BaseTy::Uint(_) => &[Sort::Int],
+            BaseTy::Int(_)
+            | BaseTy::Uint(_)
+            | BaseTy::Array(_)
+            | BaseTy::Slice(_)
+            | BaseTy::Str
+            | BaseTy::Char
+            | BaseTy::Param(_) => &[Sort::Int],
             BaseTy::Bool => &[Sort::Bool],
+            BaseTy::Real(_) => &[Sort::Real],
             BaseTy::Adt(adt_def, _) => adt_def.sorts(),
             BaseTy::Float(_)
-            | BaseTy::Str
-            | BaseTy::Array(..)
-            | BaseTy::Slice(_)
-            | BaseTy::Char => &[],
+            | BaseTy::Closure(..)
+            | BaseTy::FnDef(..)
+            | BaseTy::FnPtr(_)
+            | BaseTy::Dynamic(..)
+            | BaseTy::Opaque(..)
+            | BaseTy::Projection(..) => &[],
         }
     }
 }
@@ -747,6 +892,14 @@ macro_rules! _Float {
 }
 pub use crate::_Float as Float;
 
+#[macro_export]
+macro_rules! _Real {
+    ($float_ty:pat, $idxs:pat) => {
+        TyKind::Indexed(BaseTy::Real($float_ty), $idxs)
+    };
+}
+pub use crate::_Real as Real;
+
 mod pretty {
     use rustc_middle::ty::TyCtxt;
 
@@ -900,11 +1053,26 @@ mod pretty {
                 BaseTy::Str => w!("str")?,
                 BaseTy::Char => w!("char")?,
                 BaseTy::Adt(adt_def, _) => w!("{:?}", adt_def.def_id())?,
-                BaseTy::Float(float_ty) => w!("{}", ^float_ty.name_str())?,
-                BaseTy::Array(ty, c) => w!("[{:?}; {:?}]", ty, ^c)?,
+                BaseTy::Float(float_ty) | BaseTy::Real(float_ty) => {
+                    w!("{}", ^float_ty.name_str())?;
+                }
+                BaseTy::Array(ty) => w!("[{:?}; _]", ty)?,
                 BaseTy::Slice(ty) => w!("[{:?}]", ty)?,
+                BaseTy::Closure(def_id, upvar_tys) => {
+                    w!("Closure {:?}({:?})", def_id, join!(", ", upvar_tys))?;
+                }
+                BaseTy::FnDef(def_id, _) => w!("FnDef {:?}", def_id)?,
+                BaseTy::FnPtr(fn_sig) => w!("{:?}", fn_sig)?,
+                BaseTy::Dynamic(def_id, _) => w!("dyn {:?}", def_id)?,
+                BaseTy::Opaque(def_id, _) => w!("impl {:?}", def_id)?,
+                BaseTy::Projection(def_id, _) => w!("Projection {:?}", def_id)?,
+                BaseTy::Param(param_ty) => w!("{}", ^param_ty)?,
             }
-            if let BaseTy::Adt(_, args) = self && !args.is_empty() {
+            if let BaseTy::Adt(_, args)
+            | BaseTy::FnDef(_, args)
+            | BaseTy::Dynamic(_, args)
+            | BaseTy::Opaque(_, args)
+            | BaseTy::Projection(_, args) = self && !args.is_empty() {
                 w!("<{:?}>", join!(", ", args))?;
             }
             Ok(())
@@ -917,6 +1085,7 @@ mod pretty {
             match self {
                 GenericArg::Ty(ty) => w!("{:?}", ty),
                 GenericArg::Lifetime => w!("'_"),
+                GenericArg::Const(e) => w!("{:?}", e),
             }
         }
     }
@@ -138,7 +138,8 @@ impl TypeFolder for BVarFolder<'_> {
     fn fold_expr(&mut self, e: &Expr) -> Expr {
         if let ExprKind::BoundVar(bvar) = e.kind() && bvar.debruijn == self.outer_binder {
             if let RefineArg::Expr(e) = &self.args[bvar.index] {
-                e.clone()
+                // The argument is moved under the binders we have traversed
+                e.shift_in_bvars(self.outer_binder.as_u32())
             } else {
                 panic!("expected expr for `{bvar:?}` but found `{:?}` when substituting", self.args[bvar.index])
             }
@@ -147,3 +148,36 @@ impl TypeFolder for BVarFolder<'_> {
         }
     }
 }
+
+/// Shifts the bound variables escaping the expression by a given amount. This is needed when
+/// moving an expression under binders.
+pub(super) struct BVarShifter {
+    amount: u32,
+    outer_binder: DebruijnIndex,
+}
+
+impl BVarShifter {
+    pub(super) fn new(amount: u32) -> BVarShifter {
+        BVarShifter { amount, outer_binder: INNERMOST }
+    }
+}
+
+impl TypeFolder for BVarShifter {
+    fn fold_binders<T>(&mut self, t: &Binders<T>) -> Binders<T>
+    where
+        T: TypeFoldable,
+    {
+        self.outer_binder.shift_in(1);
+        let r = t.super_fold_with(self);
+        self.outer_binder.shift_out(1);
+        r
+    }
+
+    fn fold_expr(&mut self, e: &Expr) -> Expr {
+        if let ExprKind::BoundVar(bvar) = e.kind() && bvar.debruijn >= self.outer_binder {
+            Expr::bvar(BoundVar::new(bvar.index, bvar.debruijn.shifted_in(self.amount)))
+        } else {
+            e.super_fold_with(self)
+        }
+    }
+}
@@ -9,6 +9,7 @@ use rustc_middle::{
     mir as rustc_mir,
     ty::{
         self as rustc_ty,
+        adjustment::PointerCast,
         subst::{GenericArgKind, SubstsRef},
         ParamEnv, TyCtxt, TypeVisitable,
     },
@@ -102,6 +103,12 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
     ) -> Result<Statement, ErrorGuaranteed> {
         let span = stmt.source_info.span;
         let kind = match &stmt.kind {
+            // Shallow borrows are fake borrows introduced for match guards. They are only used by
+            // the borrow checker, so we ignore them.
+            rustc_mir::StatementKind::Assign(box (
+                _,
+                rustc_mir::Rvalue::Ref(_, rustc_mir::BorrowKind::Shallow, _),
+            )) => StatementKind::Nop,
             rustc_mir::StatementKind::Assign(box (place, rvalue)) => {
                 StatementKind::Assign(
                     self.lower_place(place)
@@ -122,9 +129,7 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
             }
             rustc_mir::StatementKind::FakeRead(box (cause, place)) => {
                 StatementKind::FakeRead(Box::new((
-                    self.lower_fake_read_cause(*cause)
-                        .ok_or_else(|| errors::UnsupportedMir::from(stmt))
-                        .emit(self.sess)?,
+                    self.lower_fake_read_cause(*cause),
                     self.lower_place(place)
                         .map_err(|reason| errors::UnsupportedMir::statement(span, reason))
                         .emit(self.sess)?,
@@ -155,15 +160,15 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
         Ok(Statement { kind, source_info: stmt.source_info })
     }
 
-    fn lower_fake_read_cause(&self, cause: rustc_mir::FakeReadCause) -> Option<FakeReadCause> {
+    fn lower_fake_read_cause(&self, cause: rustc_mir::FakeReadCause) -> FakeReadCause {
         match cause {
-            rustc_mir::FakeReadCause::ForLet(def_id) => Some(FakeReadCause::ForLet(def_id)),
+            rustc_mir::FakeReadCause::ForLet(def_id) => FakeReadCause::ForLet(def_id),
             rustc_mir::FakeReadCause::ForMatchedPlace(def_id) => {
-                Some(FakeReadCause::ForMatchedPlace(def_id))
+                FakeReadCause::ForMatchedPlace(def_id)
             }
-            rustc_mir::FakeReadCause::ForMatchGuard
-            | rustc_mir::FakeReadCause::ForGuardBinding
-            | rustc_mir::FakeReadCause::ForIndex { .. } => None,
+            rustc_mir::FakeReadCause::ForMatchGuard => FakeReadCause::ForMatchGuard,
+            rustc_mir::FakeReadCause::ForGuardBinding => FakeReadCause::ForGuardBinding,
+            rustc_mir::FakeReadCause::ForIndex => FakeReadCause::ForIndex,
         }
     }
 
@@ -177,6 +182,20 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
             rustc_mir::TerminatorKind::Call {
                 func, args, destination, target, cleanup, ..
             } => {
+                let destination = self
+                    .lower_place(destination)
+                    .map_err(|reason| errors::UnsupportedMir::new(span, "terminator", reason))
+                    .emit(self.sess)?;
+                let args = args
+                    .iter()
+                    .map(|arg| {
+                        self.lower_operand(arg).map_err(|reason| {
+                            errors::UnsupportedMir::new(span, "terminator", reason)
+                        })
+                    })
+                    .try_collect()
+                    .emit(self.sess)?;
+
                 let (func, substs) = match func.ty(&self.rustc_mir, self.tcx).kind() {
                     rustc_middle::ty::TyKind::FnDef(fn_def, substs) => {
                         let lowered_substs = lower_substs(self.tcx, substs)
@@ -184,14 +203,23 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                             .emit(self.sess)?;
                         (*fn_def, CallSubsts { orig: substs, lowered: lowered_substs })
                     }
+                    rustc_middle::ty::TyKind::FnPtr(_) => {
+                        let func = self
+                            .lower_operand(func)
+                            .map_err(|reason| errors::UnsupportedMir::terminator(span, reason))
+                            .emit(self.sess)?;
+                        let kind = TerminatorKind::CallFnPtr {
+                            func,
+                            args,
+                            destination,
+                            target: *target,
+                            cleanup: *cleanup,
+                        };
+                        return Ok(Terminator { kind, source_info: terminator.source_info });
+                    }
                     _ => Err(errors::UnsupportedMir::from(terminator)).emit(self.sess)?,
                 };
 
-                let destination = self
-                    .lower_place(destination)
-                    .map_err(|reason| errors::UnsupportedMir::new(span, "terminator", reason))
-                    .emit(self.sess)?;
-
                 let instance = self
                     .lower_instance(func, substs.orig)
                     .map_err(|_| errors::UnsupportedMir::from(terminator))
@@ -202,15 +230,7 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                     substs,
                     destination,
                     target: *target,
-                    args: args
-                        .iter()
-                        .map(|arg| {
-                            self.lower_operand(arg).map_err(|reason| {
-                                errors::UnsupportedMir::new(span, "terminator", reason)
-                            })
-                        })
-                        .try_collect()
-                        .emit(self.sess)?,
+                    args,
                     cleanup: *cleanup,
                     instance,
                 }
@@ -249,7 +269,7 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                     unwind: *unwind,
                 }
             }
-            rustc_mir::TerminatorKind::Assert { cond, target, expected, msg, .. } => {
+            rustc_mir::TerminatorKind::Assert { cond, target, expected, msg, cleanup } => {
                 TerminatorKind::Assert {
                     cond: self
                         .lower_operand(cond)
@@ -261,6 +281,7 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                         .lower_assert_msg(msg)
                         .ok_or_else(|| errors::UnsupportedMir::from(terminator))
                         .emit(self.sess)?,
+                    cleanup: *cleanup,
                 }
             }
             rustc_mir::TerminatorKind::Unreachable => TerminatorKind::Unreachable,
@@ -289,6 +310,22 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
         trait_f: DefId,
         substs: SubstsRef<'tcx>,
     ) -> Result<Option<Instance>, UnsupportedType> {
+        // Calls to `Fn::call`, `FnMut::call_mut` or `FnOnce::call_once` on a closure are resolved
+        // directly to the closure. We do this before `resolve_instance` because calling a `Fn`
+        // closure through `FnOnce` resolves to a shim and not to the closure itself.
+        // This is also done before the `needs_infer` check because closure substs
+        // may mention region variables.
+        if let Some(trait_id) = self.tcx.trait_of_item(trait_f)
+            && self.tcx.fn_trait_kind_from_lang_item(trait_id).is_some()
+            && let rustc_ty::Closure(closure_id, closure_substs) = substs.type_at(0).kind()
+        {
+            let substs = lower_substs(self.tcx, closure_substs.as_closure().parent_substs())?;
+            return Ok(Some(Instance { impl_f: *closure_id, substs }));
+        }
+
+        // Regions are irrelevant to select an impl, but they would make the check below fail.
+        let substs = self.tcx.erase_regions(substs);
+
         // tcx.resolve_instance panics without this check
         if substs.needs_infer() {
             return Ok(None);
@@ -319,6 +356,13 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                     self.lower_operand(&operands.1)?,
                 ))
             }
+            rustc_mir::Rvalue::CheckedBinaryOp(bin_op, operands) => {
+                Ok(Rvalue::CheckedBinaryOp(
+                    self.lower_bin_op(*bin_op)?,
+                    self.lower_operand(&operands.0)?,
+                    self.lower_operand(&operands.1)?,
+                ))
+            }
             rustc_mir::Rvalue::Ref(_, rustc_mir::BorrowKind::Mut { .. }, p) => {
                 Ok(Rvalue::MutRef(self.lower_place(p)?))
             }
@@ -333,6 +377,14 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                 let args = args.iter().map(|op| self.lower_operand(op)).try_collect()?;
                 Ok(Rvalue::Aggregate(aggregate_kind, args))
             }
+            rustc_mir::Rvalue::Repeat(op, len) => {
+                let ty = op.ty(&self.rustc_mir, self.tcx);
+                Ok(Rvalue::Repeat(
+                    self.lower_operand(op)?,
+                    lower_ty(self.tcx, ty).map_err(|err| err.reason)?,
+                    lower_const(self.tcx, *len).map_err(|err| err.reason)?,
+                ))
+            }
             rustc_mir::Rvalue::Discriminant(p) => Ok(Rvalue::Discriminant(self.lower_place(p)?)),
             rustc_mir::Rvalue::Len(place) => Ok(Rvalue::Len(self.lower_place(place)?)),
             rustc_mir::Rvalue::Cast(kind, op, ty) => {
@@ -341,11 +393,12 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                 let ty = lower_ty(self.tcx, *ty).map_err(|err| err.reason)?;
                 Ok(Rvalue::Cast(kind, op, ty))
             }
-            rustc_mir::Rvalue::Repeat(_, _)
-            | rustc_mir::Rvalue::Ref(_, _, _)
+            rustc_mir::Rvalue::ThreadLocalRef(def_id) if !self.tcx.is_mutable_static(*def_id) => {
+                Ok(Rvalue::Use(Operand::Constant(Constant::Static(*def_id))))
+            }
+            rustc_mir::Rvalue::Ref(_, _, _)
             | rustc_mir::Rvalue::ThreadLocalRef(_)
             | rustc_mir::Rvalue::AddressOf(_, _)
-            | rustc_mir::Rvalue::CheckedBinaryOp(_, _)
             | rustc_mir::Rvalue::NullaryOp(_, _)
             | rustc_mir::Rvalue::CopyForDeref(_)
             | rustc_mir::Rvalue::ShallowInitBox(_, _) => {
@@ -359,6 +412,10 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
             rustc_mir::CastKind::IntToInt => Some(CastKind::IntToInt),
             rustc_mir::CastKind::IntToFloat => Some(CastKind::IntToFloat),
             rustc_mir::CastKind::FloatToInt => Some(CastKind::FloatToInt),
+            rustc_mir::CastKind::Pointer(PointerCast::ReifyFnPointer) => {
+                Some(CastKind::ReifyFnPointer)
+            }
+            rustc_mir::CastKind::Pointer(PointerCast::Unsize) => Some(CastKind::Unsize),
             _ => None,
         }
     }
@@ -379,9 +436,14 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                 Ok(AggregateKind::Array(lower_ty(self.tcx, *ty).map_err(|err| err.reason)?))
             }
             rustc_mir::AggregateKind::Tuple => Ok(AggregateKind::Tuple),
-            rustc_mir::AggregateKind::Adt(..)
-            | rustc_mir::AggregateKind::Closure(_, _)
-            | rustc_mir::AggregateKind::Generator(_, _, _) => {
+            rustc_mir::AggregateKind::Closure(def_id, substs) => {
+                let parent_substs = substs.as_closure().parent_substs();
+                Ok(AggregateKind::Closure(
+                    def_id.to_def_id(),
+                    lower_substs(self.tcx, parent_substs).map_err(|err| err.reason)?,
+                ))
+            }
+            rustc_mir::AggregateKind::Adt(..) | rustc_mir::AggregateKind::Generator(_, _, _) => {
                 Err(format!("unsupported aggregate kind `{aggregate_kind:?}`"))
             }
         }
@@ -401,11 +463,11 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
             rustc_mir::BinOp::Div => Ok(BinOp::Div),
             rustc_mir::BinOp::Rem => Ok(BinOp::Rem),
             rustc_mir::BinOp::BitAnd => Ok(BinOp::BitAnd),
-            rustc_mir::BinOp::BitXor
-            | rustc_mir::BinOp::BitOr
-            | rustc_mir::BinOp::Shl
-            | rustc_mir::BinOp::Shr
-            | rustc_mir::BinOp::Offset => Err(format!("unsupported binary op `{bin_op:?}`")),
+            rustc_mir::BinOp::BitOr => Ok(BinOp::BitOr),
+            rustc_mir::BinOp::BitXor => Ok(BinOp::BitXor),
+            rustc_mir::BinOp::Shl => Ok(BinOp::Shl),
+            rustc_mir::BinOp::Shr => Ok(BinOp::Shr),
+            rustc_mir::BinOp::Offset => Err(format!("unsupported binary op `{bin_op:?}`")),
         }
     }
 
@@ -427,6 +489,12 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
                     projection.push(PlaceElem::Downcast(variant_idx));
                 }
                 rustc_mir::PlaceElem::Index(v) => projection.push(PlaceElem::Index(v)),
+                rustc_mir::PlaceElem::ConstantIndex { offset, min_length, from_end } => {
+                    projection.push(PlaceElem::ConstantIndex { offset, min_length, from_end });
+                }
+                rustc_mir::PlaceElem::Subslice { from, to, from_end } => {
+                    projection.push(PlaceElem::Subslice { from, to, from_end });
+                }
                 _ => {
                     return Err(format!("unsupported place `{place:?}`"));
                 }
@@ -438,27 +506,46 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
     fn lower_constant(&self, constant: &rustc_mir::Constant<'tcx>) -> Result<Constant, String> {
         use rustc_middle::ty::TyKind;
         // use rustc_ty::ScalarInt;
-        use rustc_mir::interpret::Scalar;
+        use rustc_mir::interpret::{GlobalAlloc, Scalar};
         use rustc_mir::ConstantKind;
         let tcx = self.tcx;
 
         // HACK(nilehmann) we evaluate the constant to support u32::MAX
         // we should instead lower it as is and refine its type.
+        if let TyKind::FnDef(def_id, substs) = constant.ty().kind() {
+            let substs = lower_substs(tcx, substs).map_err(|err| err.reason)?;
+            return Ok(Constant::FnDef(*def_id, substs));
+        }
+
         let kind = constant.literal.eval(tcx, ParamEnv::empty());
         match (kind, constant.ty().kind()) {
             (ConstantKind::Val(ConstValue::Scalar(Scalar::Int(scalar)), ty), _) => {
                 scalar_int_to_constant(tcx, scalar, ty)
             }
-            (ConstantKind::Val(ConstValue::Slice { .. }, _), TyKind::Ref(_, ref_ty, _))
+            (ConstantKind::Val(ConstValue::Scalar(Scalar::Ptr(ptr, _)), _), TyKind::Ref(..)) => {
+                if let GlobalAlloc::Static(def_id) = tcx.global_alloc(ptr.provenance) &&
+                   !tcx.is_mutable_static(def_id)
+                {
+                    Some(Constant::Static(def_id))
+                } else {
+                    None
+                }
+            }
+            (ConstantKind::Val(ConstValue::Slice { start, end, .. }, _), TyKind::Ref(_, ref_ty, _))
                 if ref_ty.is_str() =>
             {
-                Some(Constant::Str)
+                Some(Constant::Str(end - start))
             }
             (ConstantKind::Ty(c), _) => {
-                if let rustc_ty::ConstKind::Value(rustc_ty::ValTree::Leaf(scalar)) = c.kind() {
-                    scalar_int_to_constant(tcx, scalar, c.ty())
-                } else {
-                    None
+                match c.kind() {
+                    rustc_ty::ConstKind::Value(rustc_ty::ValTree::Leaf(scalar)) => {
+                        scalar_int_to_constant(tcx, scalar, c.ty())
+                    }
+                    rustc_ty::ConstKind::Param(param) => {
+                        let ty = lower_ty(tcx, c.ty()).map_err(|err| err.reason)?;
+                        Some(Constant::Param(param, ty))
+                    }
+                    _ => None,
                 }
             }
             (_, TyKind::Tuple(tys)) if tys.is_empty() => return Ok(Constant::Unit),
@@ -474,6 +561,11 @@ impl<'a, 'tcx> LoweringCtxt<'a, 'tcx> {
             RemainderByZero(_) => Some("possible remainder with a divisor of zero"),
             Overflow(rustc_mir::BinOp::Div, _, _) => Some("possible division with overflow"),
             Overflow(rustc_mir::BinOp::Rem, _, _) => Some("possible remainder with overflow"),
+            Overflow(rustc_mir::BinOp::Shl | rustc_mir::BinOp::Shr, _, _) => {
+                Some("possible shift with overflow")
+            }
+            Overflow(..) => Some("arithmetic operation might overflow"),
+            OverflowNeg(_) => Some("negation might overflow"),
             BoundsCheck { .. } => Some("index out of bounds"),
             _ => None,
         }
@@ -550,11 +642,47 @@ pub fn lower_variant_def(
 }
 
 pub fn lower_fn_sig_of(tcx: TyCtxt, def_id: DefId) -> Result<PolyFnSig, errors::UnsupportedFnSig> {
-    let fn_sig = tcx.fn_sig(def_id);
+    let fn_sig = if tcx.is_closure(def_id) {
+        // `tcx.fn_sig` cannot be used on closures. We take the signature from the closure type
+        // instead and untuple the arguments to match the arguments in the closure's body.
+        let rustc_ty::Closure(_, substs) = tcx.type_of(def_id).kind() else {
+            unreachable!("closure without a closure type")
+        };
+        tcx.signature_unclosure(substs.as_closure().sig(), rustc_hir::Unsafety::Normal)
+    } else {
+        tcx.fn_sig(def_id)
+    };
+    // Projections that can be resolved with the bounds in scope are normalized, e.g., `I::Item`
+    // with `I: Iterator<Item = u32>` becomes `u32`. The remaining ones are kept symbolic.
+    let fn_sig = if fn_sig.has_projections() {
+        tcx.try_normalize_erasing_regions(tcx.param_env(def_id), fn_sig)
+            .unwrap_or(fn_sig)
+    } else {
+        fn_sig
+    };
     let span = tcx.def_span(def_id);
     lower_fn_sig(tcx, fn_sig).map_err(|err| errors::UnsupportedFnSig { span, reason: err.reason })
 }
 
+/// Lowers the signature of `def_id` instantiated with the generic arguments of a call in `caller`.
+/// Projections are normalized with the bounds in scope at the call site, e.g., `I::Item` becomes
+/// `i32` when calling a function with `I = Range<i32>`.
+pub fn lower_call_fn_sig<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    caller: DefId,
+    def_id: DefId,
+    substs: SubstsRef<'tcx>,
+) -> Result<FnSig, errors::UnsupportedFnSig> {
+    let fn_sig = rustc_ty::EarlyBinder(tcx.fn_sig(def_id)).subst(tcx, substs);
+    let fn_sig = tcx
+        .try_normalize_erasing_regions(tcx.param_env(caller), fn_sig)
+        .unwrap_or(fn_sig);
+    let span = tcx.def_span(def_id);
+    lower_fn_sig(tcx, fn_sig)
+        .map(Binder::skip_binder)
+        .map_err(|err| errors::UnsupportedFnSig { span, reason: err.reason })
+}
+
 fn lower_fn_sig<'tcx>(
     tcx: TyCtxt<'tcx>,
     fn_sig: rustc_ty::PolyFnSig<'tcx>,
@@ -582,6 +710,9 @@ fn lower_binder<S, T>(
                 def_id,
                 symbol,
             )) => vars.push(BoundVariableKind::Region(BoundRegionKind::BrNamed(def_id, symbol))),
+            rustc_ty::BoundVariableKind::Region(rustc_ty::BoundRegionKind::BrAnon(idx)) => {
+                vars.push(BoundVariableKind::Region(BoundRegionKind::BrAnon(idx)));
+            }
             _ => {
                 return Err(UnsupportedType {
                     reason: format!("unsupported bound variable {var:?}"),
@@ -600,6 +731,10 @@ pub fn lower_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: rustc_ty::Ty<'tcx>) -> Result<Ty, U
         rustc_ty::Uint(uint_ty) => Ok(Ty::mk_uint(*uint_ty)),
         rustc_ty::Float(float_ty) => Ok(Ty::mk_float(*float_ty)),
         rustc_ty::Param(param_ty) => Ok(Ty::mk_param(*param_ty)),
+        rustc_ty::Projection(proj) => {
+            Ok(Ty::mk_projection(proj.item_def_id, lower_substs(tcx, proj.substs)?))
+        }
+        rustc_ty::Opaque(def_id, substs) => Ok(Ty::mk_opaque(*def_id, lower_substs(tcx, substs)?)),
         rustc_ty::Adt(adt_def, substs) => {
             let substs = List::from_vec(
                 substs
@@ -616,20 +751,43 @@ pub fn lower_ty<'tcx>(tcx: TyCtxt<'tcx>, ty: rustc_ty::Ty<'tcx>) -> Result<Ty, U
             let tys = List::from_vec(tys.iter().map(|ty| lower_ty(tcx, ty)).try_collect()?);
             Ok(Ty::mk_tuple(tys))
         }
-        rustc_ty::Array(ty, _) => Ok(Ty::mk_array(lower_ty(tcx, *ty)?, Const)),
+        rustc_ty::Array(ty, len) => Ok(Ty::mk_array(lower_ty(tcx, *ty)?, lower_const(tcx, *len)?)),
         rustc_ty::Slice(ty) => Ok(Ty::mk_slice(lower_ty(tcx, *ty)?)),
+        rustc_ty::Closure(def_id, substs) => {
+            let upvar_tys = List::from_vec(
+                substs
+                    .as_closure()
+                    .upvar_tys()
+                    .map(|ty| lower_ty(tcx, ty))
+                    .try_collect()?,
+            );
+            Ok(Ty::mk_closure(*def_id, upvar_tys))
+        }
+        rustc_ty::FnDef(def_id, substs) => Ok(Ty::mk_fn_def(*def_id, lower_substs(tcx, substs)?)),
+        rustc_ty::FnPtr(fn_sig) => Ok(Ty::mk_fn_ptr(lower_fn_sig(tcx, *fn_sig)?)),
+        rustc_ty::Dynamic(preds, _, rustc_ty::DynKind::Dyn) => {
+            // Auto traits (e.g., `dyn Trait + Send`) do not affect how methods are checked, so we
+            // only keep the principal trait. Associated type bindings are not supported yet.
+            match preds.principal() {
+                Some(trait_ref) if preds.projection_bounds().next().is_none() => {
+                    let trait_ref = trait_ref.skip_binder();
+                    Ok(Ty::mk_dynamic(trait_ref.def_id, lower_substs(tcx, trait_ref.substs)?))
+                }
+                _ => Err(UnsupportedType { reason: format!("unsupported type `{ty:?}`") }),
+            }
+        }
         _ => Err(UnsupportedType { reason: format!("unsupported type `{ty:?}`") }),
     }
 }
 
-fn lower_substs<'tcx>(
+pub fn lower_substs<'tcx>(
     tcx: TyCtxt<'tcx>,
-    substs: rustc_middle::ty::subst::SubstsRef<'tcx>,
+    substs: &[rustc_middle::ty::subst::GenericArg<'tcx>],
 ) -> Result<List<GenericArg>, UnsupportedType> {
     Ok(List::from_vec(
         substs
             .iter()
-            .map(|arg| lower_generic_arg(tcx, arg))
+            .map(|arg| lower_generic_arg(tcx, *arg))
             .try_collect()?,
     ))
 }
@@ -640,25 +798,33 @@ fn lower_generic_arg<'tcx>(
 ) -> Result<GenericArg, UnsupportedType> {
     match arg.unpack() {
         GenericArgKind::Type(ty) => Ok(GenericArg::Ty(lower_ty(tcx, ty)?)),
-        GenericArgKind::Lifetime(region) => Ok(GenericArg::Lifetime(lower_region(&region)?)),
-        GenericArgKind::Const(_) => {
-            Err(UnsupportedType { reason: format!("unsupported generic const `{arg:?}`") })
-        }
+        GenericArgKind::Lifetime(region) => Ok(GenericArg::Lifetime(lower_region(&region))),
+        GenericArgKind::Const(c) => Ok(GenericArg::Const(lower_const(tcx, c)?)),
+    }
+}
+
+fn lower_const<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    c: rustc_middle::ty::Const<'tcx>,
+) -> Result<Const, UnsupportedType> {
+    if let rustc_ty::ConstKind::Param(param) = c.kind() {
+        return Ok(Const::Param(param));
     }
+    c.try_eval_usize(tcx, ParamEnv::empty())
+        .map(|val| Const::Value(val as usize))
+        .ok_or_else(|| UnsupportedType { reason: format!("unsupported constant `{c:?}`") })
 }
 
-fn lower_region(region: &rustc_middle::ty::Region) -> Result<Region, UnsupportedType> {
+fn lower_region(region: &rustc_middle::ty::Region) -> Region {
     use rustc_middle::ty::RegionKind;
     match region.kind() {
-        RegionKind::ReVar(rvid) => Ok(Region::ReVar(rvid)),
-        RegionKind::ReLateBound(debruijn, bregion) => Ok(Region::ReLateBound(debruijn, bregion)),
-        RegionKind::ReEarlyBound(bregion) => Ok(Region::ReEarlyBound(bregion)),
-        RegionKind::ReFree(_)
-        | RegionKind::ReStatic
-        | RegionKind::RePlaceholder(_)
-        | RegionKind::ReErased => {
-            Err(UnsupportedType { reason: format!("unsupported region `{region:?}`") })
-        }
+        RegionKind::ReVar(rvid) => Region::ReVar(rvid),
+        RegionKind::ReLateBound(debruijn, bregion) => Region::ReLateBound(debruijn, bregion),
+        RegionKind::ReEarlyBound(bregion) => Region::ReEarlyBound(bregion),
+        RegionKind::ReStatic => Region::ReStatic,
+        RegionKind::ReFree(fregion) => Region::ReFree(fregion),
+        RegionKind::RePlaceholder(placeholder) => Region::RePlaceholder(placeholder),
+        RegionKind::ReErased => Region::ReErased,
     }
 }
 
@@ -683,10 +849,17 @@ fn lower_generic_param_def(
     generic: &rustc_ty::GenericParamDef,
 ) -> Result<GenericParamDef, ErrorGuaranteed> {
     let kind = match generic.kind {
-        rustc_ty::GenericParamDefKind::Type { has_default, synthetic: false } => {
+        // Synthetic parameters come from `impl Trait` in argument position and are treated as
+        // any other type parameter.
+        rustc_ty::GenericParamDefKind::Type { has_default, .. } => {
             GenericParamDefKind::Type { has_default }
         }
         rustc_ty::GenericParamDefKind::Lifetime => GenericParamDefKind::Lifetime,
+        rustc_ty::GenericParamDefKind::Const { has_default: false }
+            if tcx.type_of(generic.def_id).is_integral() =>
+        {
+            GenericParamDefKind::Const
+        }
         _ => {
             return Err(errors::UnsupportedGenericParam::new(tcx.def_span(generic.def_id)))
                 .emit(sess);
@@ -711,7 +884,7 @@ fn scalar_int_to_constant<'tcx>(
         TyKind::Float(float_ty) => {
             Some(Constant::Float(scalar_to_bits(tcx, scalar, ty).unwrap(), *float_ty))
         }
-        TyKind::Char => Some(Constant::Char),
+        TyKind::Char => Some(Constant::Char(char::try_from(scalar).ok()?)),
         TyKind::Bool => Some(Constant::Bool(scalar_to_bits(tcx, scalar, ty).unwrap() != 0)),
         TyKind::Tuple(tys) if tys.is_empty() => Some(Constant::Unit),
         _ => None,
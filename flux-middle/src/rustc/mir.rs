@@ -6,9 +6,10 @@ use flux_common::index::{Idx, IndexVec};
 use itertools::Itertools;
 use rustc_data_structures::graph::dominators::Dominators;
 use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_macros::{Decodable, Encodable};
 use rustc_middle::{
     mir,
-    ty::{subst::SubstsRef, FloatTy, IntTy, UintTy},
+    ty::{subst::SubstsRef, FloatTy, IntTy, ParamConst, UintTy},
 };
 pub use rustc_middle::{
     mir::{BasicBlock, Field, Local, SourceInfo, SwitchTargets, UnOp, RETURN_PLACE, START_BLOCK},
@@ -17,7 +18,7 @@ pub use rustc_middle::{
 use rustc_span::Span;
 use rustc_target::abi::VariantIdx;
 
-use super::ty::{GenericArg, Ty};
+use super::ty::{Const, GenericArg, Ty};
 use crate::intern::List;
 
 pub struct Body<'tcx> {
@@ -69,6 +70,15 @@ pub enum TerminatorKind<'tcx> {
         cleanup: Option<BasicBlock>,
         instance: Option<Instance>,
     },
+    /// A call through a function pointer. The operand is checked against the refined signature
+    /// stored in its type.
+    CallFnPtr {
+        func: Operand,
+        args: Vec<Operand>,
+        destination: Place,
+        target: Option<BasicBlock>,
+        cleanup: Option<BasicBlock>,
+    },
     SwitchInt {
         discr: Operand,
         targets: SwitchTargets,
@@ -92,6 +102,7 @@ pub enum TerminatorKind<'tcx> {
         expected: bool,
         target: BasicBlock,
         msg: &'static str,
+        cleanup: Option<BasicBlock>,
     },
     Unreachable,
     FalseEdge {
@@ -124,8 +135,13 @@ pub enum Rvalue {
     MutRef(Place),
     ShrRef(Place),
     BinaryOp(BinOp, Operand, Operand),
+    /// A binary operation producing a tuple with the result and a flag indicating whether the
+    /// operation overflowed.
+    CheckedBinaryOp(BinOp, Operand, Operand),
     UnaryOp(UnOp, Operand),
     Aggregate(AggregateKind, Vec<Operand>),
+    /// An array `[op; n]` with `n` copies of the operand. The type is the type of the elements.
+    Repeat(Operand, Ty, Const),
     Discriminant(Place),
     Len(Place),
     Cast(CastKind, Operand, Ty),
@@ -136,12 +152,17 @@ pub enum CastKind {
     IntToInt,
     FloatToInt,
     IntToFloat,
+    /// Reify a function item into a function pointer.
+    ReifyFnPointer,
+    /// Unsize a pointer, e.g., `&[T; N]` to `&[T]` or `&T` to `&dyn Trait`.
+    Unsize,
 }
 
 pub enum AggregateKind {
     Adt(DefId, VariantIdx, List<GenericArg>),
     Array(Ty),
     Tuple,
+    Closure(DefId, List<GenericArg>),
 }
 
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
@@ -158,6 +179,10 @@ pub enum BinOp {
     Div,
     Rem,
     BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 pub enum Operand {
@@ -166,18 +191,32 @@ pub enum Operand {
     Constant(Constant),
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub struct Place {
     pub local: Local,
     pub projection: Vec<PlaceElem>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Encodable, Decodable)]
 pub enum PlaceElem {
     Deref,
     Field(Field),
     Downcast(VariantIdx),
     Index(Local),
+    /// An element of an array or slice at a constant offset, which is produced by slice patterns,
+    /// e.g., `[first, ..]`. If `from_end` is true the offset is counted from the end.
+    ConstantIndex {
+        offset: u64,
+        min_length: u64,
+        from_end: bool,
+    },
+    /// A subslice produced by slice patterns, e.g., `[_, rest @ ..]`. If `from_end` is true this
+    /// is `slice[from..len - to]`, otherwise it is `array[from..to]`.
+    Subslice {
+        from: u64,
+        to: u64,
+        from_end: bool,
+    },
 }
 
 pub enum Constant {
@@ -185,16 +224,24 @@ pub enum Constant {
     Uint(u128, UintTy),
     Float(u128, FloatTy),
     Bool(bool),
-    /// We only support opaque string slices, so no data stored here for now.
-    Str,
-    /// We only support opaque chars, so no data stored here for now
-    Char,
+    /// A string literal. We only keep its length in bytes.
+    Str(usize),
+    Char(char),
     Unit,
+    /// A function item. This is a zero-sized constant whose type identifies the function.
+    FnDef(DefId, List<GenericArg>),
+    /// A shared reference to a `static` item, either a regular static or a thread local one.
+    Static(DefId),
+    /// A const generic parameter used as a value, e.g., the `N` in `let n = N;`
+    Param(ParamConst, Ty),
 }
 
 pub enum FakeReadCause {
     ForLet(Option<LocalDefId>),
     ForMatchedPlace(Option<LocalDefId>),
+    ForMatchGuard,
+    ForGuardBinding,
+    ForIndex,
 }
 
 impl<'tcx> Terminator<'tcx> {
@@ -214,6 +261,10 @@ impl<'tcx> Body<'tcx> {
         self.rustc_mir.span
     }
 
+    pub fn def_id(&self) -> DefId {
+        self.rustc_mir.source.def_id()
+    }
+
     #[inline]
     pub fn args_iter(&self) -> impl ExactSizeIterator<Item = Local> {
         (1..self.rustc_mir.arg_count + 1).map(Local::new)
@@ -241,6 +292,26 @@ impl<'tcx> Body<'tcx> {
         real_preds > usize::from(bb != START_BLOCK)
     }
 
+    /// Returns the [`DefId`]s of the closures created in this body. Closures nested inside other
+    /// closures are not included.
+    pub fn closures(&self) -> Vec<DefId> {
+        self.basic_blocks
+            .iter()
+            .flat_map(|data| &data.statements)
+            .filter_map(|stmt| {
+                if let StatementKind::Assign(
+                    _,
+                    Rvalue::Aggregate(AggregateKind::Closure(def_id, _), _),
+                ) = &stmt.kind
+                {
+                    Some(*def_id)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     #[inline]
     pub fn dominators(&self) -> Dominators<BasicBlock> {
         self.rustc_mir.basic_blocks.dominators()
@@ -334,6 +405,13 @@ impl<'tcx> fmt::Debug for Terminator<'tcx> {
                     args.iter().format(", "),
                 )
             }
+            TerminatorKind::CallFnPtr { func, args, destination, target, cleanup } => {
+                write!(
+                    f,
+                    "{destination:?} = call {func:?}({:?}) -> [return: {target:?}, cleanup: {cleanup:?}]",
+                    args.iter().format(", "),
+                )
+            }
             TerminatorKind::SwitchInt { discr, targets } => {
                 write!(
                     f,
@@ -356,10 +434,10 @@ impl<'tcx> fmt::Debug for Terminator<'tcx> {
             TerminatorKind::Drop { place, target, unwind } => {
                 write!(f, "drop({place:?}) -> [{target:?}, unwind: {unwind:?}]")
             }
-            TerminatorKind::Assert { cond, target, expected, msg } => {
+            TerminatorKind::Assert { cond, target, expected, msg, cleanup } => {
                 write!(
                     f,
-                    "assert({cond:?} is expected to be {expected:?}, \"{msg}\") -> {target:?}"
+                    "assert({cond:?} is expected to be {expected:?}, \"{msg}\") -> [success: {target:?}, cleanup: {cleanup:?}]"
                 )
             }
             TerminatorKind::FalseEdge { real_target, imaginary_target } => {
@@ -399,6 +477,26 @@ impl fmt::Debug for Place {
                     p = format!("{p}[{v:?}]");
                     need_parens = false;
                 }
+                PlaceElem::ConstantIndex { offset, min_length, from_end: false } => {
+                    p = format!("{p}[{offset:?} of {min_length:?}]");
+                    need_parens = false;
+                }
+                PlaceElem::ConstantIndex { offset, min_length, from_end: true } => {
+                    p = format!("{p}[-{offset:?} of {min_length:?}]");
+                    need_parens = false;
+                }
+                PlaceElem::Subslice { from, to, from_end: true } if *to == 0 => {
+                    p = format!("{p}[{from:?}:]");
+                    need_parens = false;
+                }
+                PlaceElem::Subslice { from, to, from_end: true } => {
+                    p = format!("{p}[{from:?}:-{to:?}]");
+                    need_parens = false;
+                }
+                PlaceElem::Subslice { from, to, from_end: false } => {
+                    p = format!("{p}[{from:?}..{to:?}]");
+                    need_parens = false;
+                }
             }
         }
         write!(f, "{p}")
@@ -413,6 +511,9 @@ impl fmt::Debug for Rvalue {
             Rvalue::ShrRef(place) => write!(f, "& {place:?}"),
             Rvalue::Discriminant(place) => write!(f, "discriminant({place:?})"),
             Rvalue::BinaryOp(bin_op, op1, op2) => write!(f, "{bin_op:?}({op1:?}, {op2:?})"),
+            Rvalue::CheckedBinaryOp(bin_op, op1, op2) => {
+                write!(f, "Checked{bin_op:?}({op1:?}, {op2:?})")
+            }
             Rvalue::UnaryOp(un_op, op) => write!(f, "{un_op:?}({op:?})"),
             Rvalue::Aggregate(AggregateKind::Adt(def_id, variant_idx, substs), args) => {
                 let fname = rustc_middle::ty::tls::with(|tcx| {
@@ -436,6 +537,10 @@ impl fmt::Debug for Rvalue {
             Rvalue::Aggregate(AggregateKind::Tuple, args) => {
                 write!(f, "({:?})", args.iter().format(", "))
             }
+            Rvalue::Aggregate(AggregateKind::Closure(def_id, _), args) => {
+                write!(f, "closure({def_id:?}, {:?})", args.iter().format(", "))
+            }
+            Rvalue::Repeat(op, _, len) => write!(f, "[{op:?}; {len:?}]"),
             Rvalue::Len(place) => write!(f, "Len({place:?})"),
             Rvalue::Cast(kind, op, ty) => write!(f, "{op:?} as {ty:?} [{kind:?}]"),
         }
@@ -448,6 +553,8 @@ impl fmt::Debug for CastKind {
             CastKind::IntToInt => write!(f, "IntToInt"),
             CastKind::FloatToInt => write!(f, "FloatToInt"),
             CastKind::IntToFloat => write!(f, "IntToFloat"),
+            CastKind::ReifyFnPointer => write!(f, "ReifyFnPointer"),
+            CastKind::Unsize => write!(f, "Unsize"),
         }
     }
 }
@@ -470,8 +577,21 @@ impl fmt::Debug for Constant {
             Constant::Float(bits, float_ty) => write!(f, "{bits}{}", float_ty.name_str()),
             Constant::Bool(b) => write!(f, "{b}"),
             Constant::Unit => write!(f, "()"),
-            Constant::Str => write!(f, "\"<opaque str>\""),
-            Constant::Char => write!(f, "\"<opaque char>\""),
+            Constant::Str(len) => write!(f, "\"<str of length {len}>\""),
+            Constant::Char(c) => write!(f, "{c:?}"),
+            Constant::FnDef(def_id, substs) => {
+                let fname = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "{fname}")?;
+                if !substs.is_empty() {
+                    write!(f, "::<{:?}>", substs.iter().format(", "))?;
+                }
+                Ok(())
+            }
+            Constant::Static(def_id) => {
+                let name = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "&{name}")
+            }
+            Constant::Param(param, _) => write!(f, "{param}"),
         }
     }
 }
@@ -481,6 +601,9 @@ impl fmt::Debug for FakeReadCause {
         match self {
             FakeReadCause::ForLet(def_id) => write!(f, "ForLet({def_id:?})"),
             FakeReadCause::ForMatchedPlace(def_id) => write!(f, "ForMatchedPlace({def_id:?})"),
+            FakeReadCause::ForMatchGuard => write!(f, "ForMatchGuard"),
+            FakeReadCause::ForGuardBinding => write!(f, "ForGuardBinding"),
+            FakeReadCause::ForIndex => write!(f, "ForIndex"),
         }
     }
 }
@@ -4,11 +4,12 @@ use std::iter;
 
 use itertools::Itertools;
 use rustc_hir::def_id::DefId;
+use rustc_macros::{TyDecodable, TyEncodable};
 pub use rustc_middle::{
     mir::Mutability,
     ty::{
-        BoundRegion, DebruijnIndex, EarlyBoundRegion, FloatTy, IntTy, ParamTy, RegionVid,
-        ScalarInt, UintTy,
+        BoundRegion, DebruijnIndex, EarlyBoundRegion, FloatTy, FreeRegion, IntTy, ParamConst,
+        ParamTy, PlaceholderRegion, RegionVid, ScalarInt, UintTy,
     },
 };
 use rustc_span::Symbol;
@@ -20,15 +21,17 @@ pub struct Generics<'tcx> {
     pub rustc: &'tcx rustc_middle::ty::Generics,
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct Binder<T>(T, List<BoundVariableKind>);
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum BoundVariableKind {
     Region(BoundRegionKind),
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum BoundRegionKind {
+    BrAnon(u32),
     BrNamed(DefId, Symbol),
 }
 
@@ -42,11 +45,15 @@ pub struct GenericParamDef {
 
 #[derive(Hash, Eq, PartialEq)]
 pub enum GenericParamDefKind {
-    Type { has_default: bool },
+    Type {
+        has_default: bool,
+    },
     Lifetime,
+    /// A const generic parameter of integral type, e.g., `const N: usize`.
+    Const,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct FnSig {
     pub(crate) inputs_and_output: List<Ty>,
 }
@@ -72,45 +79,67 @@ pub struct VariantDef {
     pub ret: Ty,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub struct Ty(Interned<TyS>);
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 struct TyS {
     kind: TyKind,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum TyKind {
     Adt(DefId, List<GenericArg>),
     Array(Ty, Const),
     Bool,
     Str,
     Char,
+    Closure(DefId, List<Ty>),
+    /// A trait object `dyn Trait<..>`. We only keep the principal trait and its generic
+    /// arguments, excluding `Self`.
+    Dynamic(DefId, List<GenericArg>),
     Float(FloatTy),
+    FnDef(DefId, List<GenericArg>),
+    FnPtr(PolyFnSig),
     Int(IntTy),
     Never,
+    /// An opaque type `impl Trait` in return position, identified by the [`DefId`] of the opaque
+    /// item together with its generic arguments.
+    Opaque(DefId, List<GenericArg>),
     Param(ParamTy),
+    /// An associated type projection `<T as Trait>::Item`, identified by the [`DefId`] of the
+    /// associated item. The generic arguments start with the `Self` type.
+    Projection(DefId, List<GenericArg>),
     Ref(Ty, Mutability),
     Tuple(List<Ty>),
     Uint(UintTy),
     Slice(Ty),
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-pub struct Const;
+#[derive(Clone, PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
+pub enum Const {
+    /// A constant with a known value, e.g., the length in `[i32; 4]`.
+    Value(usize),
+    /// A const generic parameter, e.g., the length in `[T; N]`.
+    Param(ParamConst),
+}
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum GenericArg {
     Ty(Ty),
     Lifetime(Region),
+    Const(Const),
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(PartialEq, Eq, Hash, TyEncodable, TyDecodable)]
 pub enum Region {
     ReVar(RegionVid),
     ReLateBound(DebruijnIndex, BoundRegion),
     ReEarlyBound(EarlyBoundRegion),
+    ReStatic,
+    ReFree(FreeRegion),
+    RePlaceholder(PlaceholderRegion),
+    ReErased,
 }
 
 impl<T> Binder<T> {
@@ -162,6 +191,22 @@ impl Ty {
         TyKind::Slice(ty).intern()
     }
 
+    pub fn mk_closure(def_id: DefId, upvar_tys: impl Into<List<Ty>>) -> Ty {
+        TyKind::Closure(def_id, upvar_tys.into()).intern()
+    }
+
+    pub fn mk_dynamic(def_id: DefId, substs: impl Into<List<GenericArg>>) -> Ty {
+        TyKind::Dynamic(def_id, substs.into()).intern()
+    }
+
+    pub fn mk_fn_def(def_id: DefId, substs: impl Into<List<GenericArg>>) -> Ty {
+        TyKind::FnDef(def_id, substs.into()).intern()
+    }
+
+    pub fn mk_fn_ptr(fn_sig: PolyFnSig) -> Ty {
+        TyKind::FnPtr(fn_sig).intern()
+    }
+
     pub fn mk_bool() -> Ty {
         TyKind::Bool.intern()
     }
@@ -178,10 +223,18 @@ impl Ty {
         TyKind::Never.intern()
     }
 
+    pub fn mk_opaque(def_id: DefId, substs: impl Into<List<GenericArg>>) -> Ty {
+        TyKind::Opaque(def_id, substs.into()).intern()
+    }
+
     pub fn mk_param(param: ParamTy) -> Ty {
         TyKind::Param(param).intern()
     }
 
+    pub fn mk_projection(item_def_id: DefId, substs: impl Into<List<GenericArg>>) -> Ty {
+        TyKind::Projection(item_def_id, substs.into()).intern()
+    }
+
     pub fn mk_ref(ty: Ty, mutability: Mutability) -> Ty {
         TyKind::Ref(ty, mutability).intern()
     }
@@ -218,6 +271,7 @@ impl std::fmt::Debug for GenericArg {
         match self {
             GenericArg::Ty(ty) => write!(f, "{ty:?}"),
             GenericArg::Lifetime(region) => write!(f, "{region:?}"),
+            GenericArg::Const(c) => write!(f, "{c:?}"),
         }
     }
 }
@@ -228,6 +282,10 @@ impl std::fmt::Debug for Region {
             Region::ReVar(rvid) => write!(f, "{rvid:?}"),
             Region::ReLateBound(_, bregion) => write!(f, "{bregion:?}"),
             Region::ReEarlyBound(bregion) => write!(f, "{bregion:?}"),
+            Region::ReStatic => write!(f, "'static"),
+            Region::ReFree(fregion) => write!(f, "{fregion:?}"),
+            Region::RePlaceholder(placeholder) => write!(f, "{placeholder:?}"),
+            Region::ReErased => write!(f, "'<erased>"),
         }
     }
 }
@@ -247,6 +305,29 @@ impl std::fmt::Debug for Ty {
                 Ok(())
             }
             TyKind::Bool => write!(f, "bool"),
+            TyKind::Closure(def_id, upvar_tys) => {
+                write!(f, "Closure {def_id:?}({:?})", upvar_tys.iter().format(", "))
+            }
+            TyKind::Dynamic(def_id, substs) => {
+                let trait_name = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "dyn {trait_name}")?;
+                if !substs.is_empty() {
+                    write!(f, "<{:?}>", substs.iter().format(", "))?;
+                }
+                Ok(())
+            }
+            TyKind::FnDef(def_id, substs) => {
+                let fn_name = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "fn {fn_name}")?;
+                if !substs.is_empty() {
+                    write!(f, "::<{:?}>", substs.iter().format(", "))?;
+                }
+                Ok(())
+            }
+            TyKind::FnPtr(fn_sig) => {
+                let fn_sig = &fn_sig.0;
+                write!(f, "fn({:?}) -> {:?}", fn_sig.inputs().iter().format(", "), fn_sig.output())
+            }
             TyKind::Str => write!(f, "str"),
             TyKind::Char => write!(f, "char"),
             TyKind::Float(float_ty) => write!(f, "{}", float_ty.name_str()),
@@ -254,6 +335,18 @@ impl std::fmt::Debug for Ty {
             TyKind::Uint(uint_ty) => write!(f, "{}", uint_ty.name_str()),
             TyKind::Never => write!(f, "!"),
             TyKind::Param(param_ty) => write!(f, "{param_ty}"),
+            TyKind::Opaque(def_id, substs) => {
+                let opaque_name = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*def_id));
+                write!(f, "impl {opaque_name}")?;
+                if !substs.is_empty() {
+                    write!(f, "<{:?}>", substs.iter().format(", "))?;
+                }
+                Ok(())
+            }
+            TyKind::Projection(item_def_id, substs) => {
+                let item_name = rustc_middle::ty::tls::with(|tcx| tcx.def_path_str(*item_def_id));
+                write!(f, "<{:?}>::{item_name}", substs.iter().format(", "))
+            }
             TyKind::Ref(ty, Mutability::Mut) => write!(f, "&mut {ty:?}"),
             TyKind::Ref(ty, Mutability::Not) => write!(f, "&{ty:?}"),
             TyKind::Array(ty, c) => write!(f, "[{ty:?}; {c:?}]"),
@@ -267,6 +360,9 @@ impl std::fmt::Debug for Ty {
 
 impl std::fmt::Debug for Const {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "_")
+        match self {
+            Const::Value(val) => write!(f, "{val}"),
+            Const::Param(param) => write!(f, "{param}"),
+        }
     }
 }
@@ -28,6 +28,7 @@ pub enum Token {
     Ge,
     At,
     Fn,
+    Impl,
     Iff,
     FatArrow,
     Mut,
@@ -42,6 +43,9 @@ pub enum Token {
     Ref,
     And,
     Percent,
+    Hat,
+    Shl,
+    Shr,
     Strg,
     Type,
     Ignore,
@@ -49,6 +53,11 @@ pub enum Token {
     Check,
     If,
     Else,
+    Forall,
+    Exists,
+    In,
+    DotDot,
+    PathSep,
 }
 
 pub(crate) struct Cursor {
@@ -63,6 +72,8 @@ struct Symbols {
     requires: Symbol,
     ensures: Symbol,
     strg: Symbol,
+    forall: Symbol,
+    exists: Symbol,
 }
 
 struct Frame {
@@ -84,6 +95,8 @@ impl Cursor {
                 strg: Symbol::intern("strg"),
                 requires: Symbol::intern("requires"),
                 ensures: Symbol::intern("ensures"),
+                forall: Symbol::intern("forall"),
+                exists: Symbol::intern("exists"),
             },
         }
     }
@@ -106,6 +119,8 @@ impl Cursor {
             TokenKind::Semi => Token::Semi,
             TokenKind::RArrow => Token::RArrow,
             TokenKind::Dot => Token::Dot,
+            TokenKind::DotDot => Token::DotDot,
+            TokenKind::ModSep => Token::PathSep,
             TokenKind::OpenDelim(delim) => Token::OpenDelim(delim),
             TokenKind::CloseDelim(delim) => Token::CloseDelim(delim),
             TokenKind::Literal(lit) if lit.suffix.is_none() => Token::Literal(lit),
@@ -117,11 +132,15 @@ impl Cursor {
             TokenKind::Ident(symb, _) if symb == self.symbs.strg => Token::Strg,
             TokenKind::Ident(symb, _) if symb == self.symbs.requires => Token::Requires,
             TokenKind::Ident(symb, _) if symb == self.symbs.ensures => Token::Ensures,
+            TokenKind::Ident(symb, _) if symb == self.symbs.forall => Token::Forall,
+            TokenKind::Ident(symb, _) if symb == self.symbs.exists => Token::Exists,
             TokenKind::Ident(symb, _) if symb == kw::Mut => Token::Mut,
+            TokenKind::Ident(symb, _) if symb == kw::Impl => Token::Impl,
             TokenKind::Ident(symb, _) if symb == kw::Where => Token::Where,
             TokenKind::Ident(symb, _) if symb == kw::Type => Token::Type,
             TokenKind::Ident(symb, _) if symb == kw::If => Token::If,
             TokenKind::Ident(symb, _) if symb == kw::Else => Token::Else,
+            TokenKind::Ident(symb, _) if symb == kw::In => Token::In,
             TokenKind::Ident(symb, _) => Token::Ident(symb),
             TokenKind::BinOp(BinOpToken::Or) => Token::Caret,
             TokenKind::BinOp(BinOpToken::Plus) => Token::Plus,
@@ -129,6 +148,9 @@ impl Cursor {
             TokenKind::BinOp(BinOpToken::And) => Token::And,
             TokenKind::BinOp(BinOpToken::Percent) => Token::Percent,
             TokenKind::BinOp(BinOpToken::Star) => Token::Star,
+            TokenKind::BinOp(BinOpToken::Caret) => Token::Hat,
+            TokenKind::BinOp(BinOpToken::Shl) => Token::Shl,
+            TokenKind::BinOp(BinOpToken::Shr) => Token::Shr,
             _ => Token::Invalid,
         };
         (Location(span.lo() - self.offset), token, Location(span.hi() - self.offset))
@@ -57,6 +57,21 @@ pub fn parse_uif_def(tokens: TokenStream, span: Span) -> ParseResult<surface::Ui
     parse!(surface_grammar::UifDefParser, tokens, span)
 }
 
+pub fn parse_opaque_sort_decl(
+    tokens: TokenStream,
+    span: Span,
+) -> ParseResult<surface::OpaqueSortDecl> {
+    parse!(surface_grammar::OpaqueSortDeclParser, tokens, span)
+}
+
+pub fn parse_axiom(tokens: TokenStream, span: Span) -> ParseResult<surface::Axiom> {
+    parse!(surface_grammar::AxiomParser, tokens, span)
+}
+
+pub fn parse_defns(tokens: TokenStream, span: Span) -> ParseResult<Vec<surface::Defn>> {
+    parse!(surface_grammar::DefnsParser, tokens, span)
+}
+
 pub fn parse_ty(tokens: TokenStream, span: Span) -> ParseResult<surface::Ty> {
     parse!(surface_grammar::TyParser, tokens, span)
 }
@@ -2,7 +2,7 @@ use std::fmt;
 
 pub use rustc_ast::token::LitKind;
 use rustc_hir::def_id::{DefId, LocalDefId};
-pub use rustc_middle::ty::{FloatTy, IntTy, ParamTy, TyCtxt, UintTy};
+pub use rustc_middle::ty::{FloatTy, IntTy, ParamConst, ParamTy, TyCtxt, UintTy};
 pub use rustc_span::symbol::Ident;
 use rustc_span::{Span, Symbol};
 
@@ -28,6 +28,32 @@ pub struct UifDef {
     pub span: Span,
 }
 
+/// An uninterpreted sort declared with `#[flux::opaque_sort(Name)]`
+#[derive(Debug)]
+pub struct OpaqueSortDecl {
+    pub name: Ident,
+    pub span: Span,
+}
+
+/// A fact assumed by the solver, e.g., `forall x: int. f(x) >= 0`. The parameters are universally
+/// quantified over the body.
+#[derive(Debug)]
+pub struct Axiom {
+    pub args: Vec<RefineParam>,
+    pub expr: Expr,
+    pub span: Span,
+}
+
+/// A refinement function with an interpreted body, e.g., `fn abs(x: int) -> int { ... }`.
+#[derive(Debug)]
+pub struct Defn {
+    pub name: Ident,
+    pub args: Vec<RefineParam>,
+    pub output: Sort,
+    pub body: Expr,
+    pub span: Span,
+}
+
 #[derive(Debug)]
 pub struct Alias<T = Ident> {
     pub name: Ident,
@@ -84,6 +110,12 @@ pub struct RefineParam {
 pub enum Sort {
     /// A _base_ sort, e.g., `int` or `bool`.
     Base(Ident),
+    /// A bit vector sort of a fixed width, e.g., `bitvec<32>`.
+    BitVec(u32),
+    /// A set sort, e.g., `Set<int>`.
+    Set(Box<Sort>),
+    /// A map sort with the sorts of the keys and values, e.g., `Map<int, bool>`.
+    Map(Box<Sort>, Box<Sort>),
     /// A _function_ sort of the form `(bi,...) -> bo` where `bi..` and `bo`
     /// are all base sorts.
     Func {
@@ -152,13 +184,26 @@ pub enum TyKind<T = Ident> {
     Ref(RefKind, Box<Ty<T>>),
     /// Constrained type: an exists without binder
     Constr(Expr, Box<Ty<T>>),
+    /// `[t; n]`
     Array(Box<Ty<T>>, ArrayLen),
-    Slice(Box<Ty<T>>),
+    /// `[t]` or `[t][n]`
+    Slice(Box<Ty<T>>, ArrayLen),
     Tuple(Vec<Ty<T>>),
+    /// Function pointer `fn(t1, ..., tn) -> t`. A missing return type is desugared to unit.
+    FnPtr(Vec<Ty<T>>, Box<Ty<T>>),
+    /// `impl Trait` in argument or return position. Before resolution the path is the name of
+    /// the trait; after it, it is the synthetic type parameter or the opaque type.
+    ImplTrait(Path<T>),
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct ArrayLen;
+/// The length of an array or a slice
+#[derive(Debug, Clone)]
+pub enum ArrayLen {
+    /// The length is not refined, e.g., `[i32; _]` or `[i32]`
+    Infer,
+    /// e.g., `[i32; 4]`, `[i32; @n]` or `[i32][n]`
+    Arg(RefineArg),
+}
 
 #[derive(Debug, Clone)]
 pub struct Indices {
@@ -191,6 +236,13 @@ pub enum Res {
     Str,
     Char,
     Param(ParamTy),
+    /// A const generic parameter, only valid as a generic argument, e.g., the `N` in `Buf<N>`
+    ConstParam(ParamConst),
+    /// The opaque type of an `impl Trait` in return position
+    Opaque(DefId),
+    /// An associated type of a type parameter, e.g., `T::Item`, identified by the [`DefId`] of
+    /// the associated item
+    Projection(ParamTy, DefId),
 }
 
 #[derive(Eq, PartialEq, Clone, Copy, Debug)]
@@ -213,6 +265,15 @@ pub enum ExprKind {
     BinaryOp(BinOp, Box<[Expr; 2]>),
     App(Ident, Vec<Expr>),
     IfThenElse(Box<[Expr; 3]>),
+    /// A bounded quantifier, e.g., `forall i in lo..hi { p }`. The bounds are stored before the
+    /// body, i.e., `[lo, hi, p]`.
+    Quant(Quantifier, Ident, Box<[Expr; 3]>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Quantifier {
+    Forall,
+    Exists,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -237,6 +298,11 @@ pub enum BinOp {
     Sub,
     Mod,
     Mul,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
 impl RefinedBy {
@@ -295,6 +361,11 @@ impl fmt::Debug for BinOp {
             BinOp::Sub => write!(f, "-"),
             BinOp::Mod => write!(f, "mod"),
             BinOp::Mul => write!(f, "*"),
+            BinOp::BitAnd => write!(f, "&"),
+            BinOp::BitOr => write!(f, "|"),
+            BinOp::BitXor => write!(f, "^"),
+            BinOp::Shl => write!(f, "<<"),
+            BinOp::Shr => write!(f, ">>"),
         }
     }
 }
@@ -306,7 +377,7 @@ pub mod expand {
     use rustc_span::symbol::Ident;
 
     use super::{
-        AliasMap, Arg, BinOp, Expr, ExprKind, FnSig, Indices, Path, RefineArg, Ty, TyKind,
+        AliasMap, Arg, ArrayLen, BinOp, Expr, ExprKind, FnSig, Indices, Path, RefineArg, Ty, TyKind,
     };
 
     /// `expand_sig(aliases, sig)` replaces all the alias-applications in `sig`
@@ -405,11 +476,18 @@ pub mod expand {
             TyKind::Constr(pred, t) => {
                 TyKind::Constr(pred.clone(), Box::new(expand_ty(aliases, t)))
             }
-            TyKind::Array(ty, len) => TyKind::Array(Box::new(expand_ty(aliases, ty)), *len),
-            TyKind::Slice(ty) => TyKind::Slice(Box::new(expand_ty(aliases, ty))),
+            TyKind::Array(ty, len) => TyKind::Array(Box::new(expand_ty(aliases, ty)), len.clone()),
+            TyKind::Slice(ty, len) => TyKind::Slice(Box::new(expand_ty(aliases, ty)), len.clone()),
             TyKind::Tuple(tys) => {
                 TyKind::Tuple(tys.iter().map(|t| expand_ty(aliases, t)).collect())
             }
+            TyKind::FnPtr(inputs, output) => {
+                TyKind::FnPtr(
+                    inputs.iter().map(|t| expand_ty(aliases, t)).collect(),
+                    Box::new(expand_ty(aliases, output)),
+                )
+            }
+            TyKind::ImplTrait(path) => TyKind::ImplTrait(expand_path(aliases, path)),
         }
     }
 
@@ -481,6 +559,23 @@ pub mod expand {
                     span: e.span,
                 }
             }
+            ExprKind::Quant(quant, x, box [lo, hi, body]) => {
+                let body = if subst.contains_key(x) {
+                    let mut subst = subst.clone();
+                    subst.remove(x);
+                    subst_expr(&subst, body)
+                } else {
+                    subst_expr(subst, body)
+                };
+                Expr {
+                    kind: ExprKind::Quant(
+                        *quant,
+                        *x,
+                        Box::new([subst_expr(subst, lo), subst_expr(subst, hi), body]),
+                    ),
+                    span: e.span,
+                }
+            }
         }
     }
 
@@ -511,6 +606,13 @@ pub mod expand {
         }
     }
 
+    fn subst_array_len(subst: &Subst, len: &ArrayLen) -> ArrayLen {
+        match len {
+            ArrayLen::Infer => ArrayLen::Infer,
+            ArrayLen::Arg(arg) => ArrayLen::Arg(subst_arg(subst, arg)),
+        }
+    }
+
     fn subst_tykind(subst: &Subst, k: &TyKind) -> TyKind {
         match k {
             TyKind::Path(p) => TyKind::Path(subst_path(subst, p)),
@@ -531,9 +633,20 @@ pub mod expand {
             TyKind::Constr(pred, t) => {
                 TyKind::Constr(subst_expr(subst, pred), Box::new(subst_ty(subst, t)))
             }
-            TyKind::Array(ty, len) => TyKind::Array(Box::new(subst_ty(subst, ty)), *len),
-            TyKind::Slice(ty) => TyKind::Slice(Box::new(subst_ty(subst, ty))),
+            TyKind::Array(ty, len) => {
+                TyKind::Array(Box::new(subst_ty(subst, ty)), subst_array_len(subst, len))
+            }
+            TyKind::Slice(ty, len) => {
+                TyKind::Slice(Box::new(subst_ty(subst, ty)), subst_array_len(subst, len))
+            }
             TyKind::Tuple(tys) => TyKind::Tuple(tys.iter().map(|t| subst_ty(subst, t)).collect()),
+            TyKind::FnPtr(inputs, output) => {
+                TyKind::FnPtr(
+                    inputs.iter().map(|t| subst_ty(subst, t)).collect(),
+                    Box::new(subst_ty(subst, output)),
+                )
+            }
+            TyKind::ImplTrait(path) => TyKind::ImplTrait(subst_path(subst, path)),
         }
     }
 }
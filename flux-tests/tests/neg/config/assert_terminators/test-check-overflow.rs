@@ -0,0 +1,17 @@
+// compile-flags: -C overflow-checks=on
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check", check_overflow = "true")]
+
+// With the compiler's overflow checks the only obligation is the compiler-inserted assert
+
+#[flux::sig(fn(x: i32) -> i32)]
+pub fn incr(x: i32) -> i32 {
+    x + 1 //~ ERROR arithmetic operation might overflow
+}
+
+#[flux::sig(fn(x: i32) -> i32)]
+pub fn neg(x: i32) -> i32 {
+    -x //~ ERROR negation might overflow
+}
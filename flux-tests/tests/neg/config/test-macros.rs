@@ -0,0 +1,20 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_macros = "false")]
+
+macro_rules! make_dec {
+    ($name:ident) => {
+        #[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+        pub fn $name(x: i32) -> i32 {
+            x - 1
+        }
+    };
+}
+
+make_dec!(dec); //~ WARNING function generated by a macro is not checked
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+pub fn dec2(x: i32) -> i32 {
+    x - 1 //~ ERROR postcondition might not hold
+}
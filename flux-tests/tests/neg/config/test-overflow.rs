@@ -0,0 +1,34 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_overflow = "true")]
+
+#[flux::sig(fn(x: i32{0 <= x && x < 65536}) -> i32)]
+pub fn to_fixed(x: i32) -> i32 {
+    x * 65536 //~ ERROR arithmetic operation may overflow
+}
+
+#[flux::sig(fn(x: u32, y: u32) -> u32)]
+pub fn add(x: u32, y: u32) -> u32 {
+    x + y //~ ERROR arithmetic operation may overflow
+}
+
+#[flux::sig(fn(x: i32, y: i32) -> i32)]
+pub fn sub(x: i32, y: i32) -> i32 {
+    x - y //~ ERROR arithmetic operation may overflow
+}
+
+#[flux::sig(fn(x: u64, k: u32) -> u64)]
+pub fn shl(x: u64, k: u32) -> u64 {
+    x << k //~ ERROR arithmetic operation may overflow
+}
+
+#[flux::sig(fn(x: i32) -> i32)]
+pub fn neg(x: i32) -> i32 {
+    -x //~ ERROR arithmetic operation may overflow
+}
+
+#[flux::sig(fn(x: i32{x < 0}) -> i32{v: v > 0})]
+pub fn neg_negative(x: i32) -> i32 {
+    -x //~ ERROR arithmetic operation may overflow
+}
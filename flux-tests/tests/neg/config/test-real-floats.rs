@@ -0,0 +1,24 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(real_floats = "true")]
+
+#[flux::sig(fn(x: f64, y: f64) -> f64[x + y])]
+pub fn sub(x: f64, y: f64) -> f64 {
+    x - y //~ ERROR postcondition
+}
+
+#[flux::sig(fn(x: f64) -> f64{v: 0.0 <= v})]
+pub fn double(x: f64) -> f64 {
+    x * 2.0 //~ ERROR postcondition
+}
+
+#[flux::sig(fn(x: f64{x > 0.5}) -> bool[true])]
+pub fn cmp(x: f64) -> bool {
+    x >= 1.0 //~ ERROR postcondition
+}
+
+#[flux::sig(fn(x: f64{0.0 <= x}) -> i32{v: 0 < v})]
+pub fn truncate(x: f64) -> i32 {
+    x as i32 //~ ERROR postcondition
+}
@@ -0,0 +1,12 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: u32) -> u32[x & 255])] //~ ERROR mismatched sorts
+pub fn low_byte(x: u32) -> u32 {
+    x & 0xff
+}
+
+#[flux::sig(fn(x: u32) -> u32[bv64_to_int(int_to_bv32(x))])] //~ ERROR mismatched sorts
+pub fn id(x: u32) -> u32 {
+    x
+}
@@ -0,0 +1,13 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[flux::sig(fn(p: Point) -> i32[p.z])] //~ ERROR no field `z`
+pub fn get_z(p: Point) -> i32 {
+    p.x
+}
@@ -0,0 +1,8 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub struct Point {
+    x: i32,
+    y: f32, //~ ERROR fields of reflected types can only be integers, booleans, or other reflected types
+}
@@ -0,0 +1,9 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+#[flux::refined_by(x: int, y: int)]
+pub struct Point { //~ ERROR cannot have other flux annotations
+    x: i32,
+    y: i32,
+}
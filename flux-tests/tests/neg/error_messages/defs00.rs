@@ -0,0 +1,6 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::defs {
+    fn is_pos(x: int) -> bool { x }  //~ ERROR mismatched sorts
+}]
@@ -0,0 +1,7 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::defs {
+    fn is_even(x: int) -> bool { is_odd(x - 1) } //~ ERROR cannot find value `is_odd` in this scope
+    fn is_odd(x: int) -> bool { x % 2 == 1 }
+}]
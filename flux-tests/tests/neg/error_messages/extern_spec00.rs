@@ -0,0 +1,9 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![allow(dead_code)]
+
+#[flux::extern_spec]
+#[flux::sig(fn(i32) -> i32)]
+fn not_a_call(x: i32) -> i32 { //~ ERROR malformed extern spec
+    x + 1
+}
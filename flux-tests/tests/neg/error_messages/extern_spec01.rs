@@ -0,0 +1,7 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![allow(dead_code)]
+
+#[flux::extern_spec]
+#[flux::refined_by(n: int)]
+struct NotExtern(i32); //~ ERROR malformed extern spec
@@ -0,0 +1,12 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![allow(dead_code)]
+
+#[flux::extern_spec]
+#[flux::refined_by(b: bool)]
+enum OptionSpec<T> { //~ ERROR malformed extern spec
+    #[flux::variant(Option<T>[false])]
+    None,
+    #[flux::variant({T} -> Option<T>[true])]
+    Some(T),
+}
@@ -0,0 +1,12 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(it: impl Fn) -> usize)] //~ ERROR cannot resolve `impl Fn`
+pub fn count(it: impl Iterator<Item = u32>) -> usize {
+    it.count()
+}
+
+#[flux::sig(fn(x: I::Item) -> J::Item)] //~ ERROR cannot resolve `I::Item`
+pub fn item<I: Iterator, J: Iterator>(x: J::Item) -> J::Item {
+    x
+}
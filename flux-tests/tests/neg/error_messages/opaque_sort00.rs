@@ -0,0 +1,17 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::opaque_sort(Handle)]
+#![flux::uf(fn size(Handle) -> int)]
+#![flux::axiom(forall x: int. size(x) >= 0)] //~ ERROR mismatched sorts
+
+#[flux::opaque]
+#[flux::refined_by(h: Handle)]
+pub struct File {
+    fd: i32,
+}
+
+#[flux::sig(fn(&File[@h]) -> i32[h])] //~ ERROR mismatched sorts
+pub fn fd(f: &File) -> i32 {
+    f.fd
+}
@@ -0,0 +1,8 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(b: bool{forall i in 0..b { i > 0 }}))] //~ ERROR mismatched sorts
+pub fn bool_bound(b: bool) {}
+
+#[flux::sig(fn(n: i32{exists i in 0..n { i + 1 }}))] //~ ERROR mismatched sorts
+pub fn int_body(n: i32) {}
@@ -0,0 +1,14 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(real_floats = "true")]
+
+#[flux::sig(fn(x: f64, n: i32) -> bool[x < n])] //~ ERROR mismatched sorts
+pub fn lt(x: f64, n: i32) -> bool {
+    x < n as f64
+}
+
+#[flux::sig(fn(x: f64) -> i32[x])] //~ ERROR mismatched sorts
+pub fn to_i32(x: f64) -> i32 {
+    x as i32
+}
@@ -0,0 +1,13 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub fn sum(n: i32) -> i32 {
+    let mut i = 0;
+    let mut s = 0;
+    while i < n { //~ ERROR loops are not supported in reflected functions
+        s += i;
+        i += 1;
+    }
+    s
+}
@@ -0,0 +1,7 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub fn first(v: &[i32]) -> i32 { //~ ERROR reflected functions can only take and return integers and booleans
+    v[0]
+}
@@ -0,0 +1,11 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub fn fact(n: i32) -> i32 { //~ ERROR reflected function `fact` cannot be recursive
+    if n <= 0 {
+        1
+    } else {
+        n * fact(n - 1)
+    }
+}
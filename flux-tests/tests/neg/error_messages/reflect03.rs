@@ -0,0 +1,11 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::reflect]
+pub fn inc_twice(x: i32) -> i32 {
+    inc(inc(x)) //~ ERROR unsupported operation in reflected function
+}
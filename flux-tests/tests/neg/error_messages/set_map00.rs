@@ -0,0 +1,23 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::opaque]
+#[flux::refined_by(elems: Set<int>)]
+pub struct IntSet {
+    inner: Vec<i32>,
+}
+
+#[flux::sig(fn(IntSet[@s], x: i32) -> bool[member(s, x)])] //~ ERROR mismatched sorts
+pub fn contains(s: IntSet, x: i32) -> bool {
+    s.inner.contains(&x)
+}
+
+#[flux::sig(fn(IntSet[@s]) -> bool[empty() == s])] //~ ERROR cannot infer sort
+pub fn is_empty(s: IntSet) -> bool {
+    s.inner.is_empty()
+}
+
+#[flux::sig(fn(x: i32) -> i32[select(x, 0)])] //~ ERROR mismatched sorts
+pub fn select(x: i32) -> i32 {
+    x
+}
@@ -0,0 +1,30 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn() -> [i32; 4])]
+pub fn make() -> [i32; 3] {
+    [1, 2, 3] //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(&[i32; @n], usize{v: v <= n}) -> i32)]
+pub fn get(arr: &[i32; 5], i: usize) -> i32 {
+    arr[i] //~ ERROR assertion might fail
+}
+
+#[flux::sig(fn(&[i32][@n], usize) -> i32)]
+pub fn get_slice(slice: &[i32], i: usize) -> i32 {
+    slice[i] //~ ERROR assertion might fail
+}
+
+pub fn unsize() -> i32 {
+    let arr = [1, 2, 3];
+    let slice: &[i32] = &arr;
+    get_slice_checked(slice, 3) //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v < n}) -> i32)]
+pub fn get_slice_checked(slice: &[i32], i: usize) -> i32 {
+    slice[i]
+}
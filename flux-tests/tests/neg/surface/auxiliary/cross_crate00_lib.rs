@@ -0,0 +1,21 @@
+// compile-flags: --emit=metadata
+// no-prefer-dynamic
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 1})]
+pub fn incr(x: i32) -> i32 {
+    x + 1
+}
+
+#[flux::refined_by(n: int)]
+#[flux::invariant(n >= 0)]
+pub struct Nat {
+    #[flux::field(i32[@n])]
+    pub n: i32,
+}
+
+#[flux::sig(fn(i32{v: v >= 0}) -> Nat)]
+pub fn nat(n: i32) -> Nat {
+    Nat { n }
+}
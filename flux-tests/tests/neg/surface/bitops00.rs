@@ -0,0 +1,43 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: u32, mask: u32) -> u32{v: v < mask})]
+pub fn and_mask(x: u32, mask: u32) -> u32 {
+    x & mask //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(x: u8) -> u8{v: v < 8})]
+pub fn high_nibble(x: u8) -> u8 {
+    x >> 4 //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(x: i32) -> i32[0 - 1])]
+pub fn sign(x: i32) -> i32 {
+    x >> 31 //~ ERROR postcondition might not hold
+}
+
+#[flux::opaque]
+#[flux::refined_by(bits: bitvec<32>)]
+pub struct BV32(u32);
+
+#[flux::assume]
+#[flux::sig(fn(x: BV32, y: BV32) -> BV32[x & y])]
+pub fn bv_and(x: BV32, y: BV32) -> BV32 {
+    BV32(x.0 & y.0)
+}
+
+#[flux::sig(fn(x: BV32, y: BV32) -> BV32[x | y])]
+pub fn bv_or(x: BV32, y: BV32) -> BV32 {
+    bv_and(x, y) //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(x: u32) -> u32[bv32_to_int(int_to_bv32(x) & int_to_bv32(255))])]
+pub fn low_byte(x: u32) -> u32 {
+    x & 0x7f //~ ERROR postcondition might not hold
+}
+
+// Bitwise operations on 128-bit signed integers are deliberately left unrefined
+#[flux::sig(fn(x: i128) -> i128[0])]
+pub fn and_zero_i128(x: i128) -> i128 {
+    x & 0 //~ ERROR postcondition might not hold
+}
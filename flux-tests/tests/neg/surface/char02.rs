@@ -0,0 +1,28 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> char[98])]
+pub fn literal() -> char {
+    'a' //~ ERROR postcondition
+}
+
+#[flux::sig(fn(char) -> u32{v: v < 10})]
+pub fn digit_value(c: char) -> u32 {
+    if c.is_ascii() {
+        c as u32 //~ ERROR postcondition
+    } else {
+        0
+    }
+}
+
+#[flux::sig(fn(char) -> u32{v: v < 10})]
+pub fn hex_value(c: char) -> u32 {
+    match c.to_digit(16) {
+        Some(d) => d, //~ ERROR postcondition
+        None => 0,
+    }
+}
+
+pub fn bad_radix(c: char) -> Option<u32> {
+    c.to_digit(37) //~ ERROR precondition
+}
@@ -0,0 +1,8 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 0})]
+pub fn capture(n: i32) -> i32 {
+    let get = || n - 1;
+    get() //~ ERROR postcondition might not hold
+}
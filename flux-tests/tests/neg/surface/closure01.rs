@@ -0,0 +1,15 @@
+#![feature(register_tool, stmt_expr_attributes)]
+#![register_tool(flux)]
+
+pub fn annotated() -> i32 {
+    let incr = #[flux::sig(fn(i32[@n]) -> i32[n + 1])]
+    |x| x; //~ ERROR postcondition
+    incr(1)
+}
+
+#[flux::sig(fn() -> i32{v: v > 1})]
+pub fn caller() -> i32 {
+    let incr = #[flux::sig(fn(i32[@n]) -> i32[n + 1])]
+    |x| x + 1;
+    incr(0) //~ ERROR postcondition
+}
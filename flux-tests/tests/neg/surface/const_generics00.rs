@@ -0,0 +1,50 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: [u8; N]) -> usize{v: v > N})]
+pub fn len<const N: usize>(x: [u8; N]) -> usize {
+    x.len() //~ ERROR postcondition
+}
+
+#[flux::sig(fn(x: [u8; N]) -> usize[N])]
+pub fn len_ok<const N: usize>(x: [u8; N]) -> usize {
+    x.len()
+}
+
+#[flux::sig(fn() -> usize[4])]
+pub fn test00() -> usize {
+    len_ok([1, 2, 3]) //~ ERROR postcondition
+}
+
+#[flux::sig(fn(i: usize{i < N}) -> usize{v: v < N})]
+pub fn bounded<const N: usize>(i: usize) -> usize {
+    i
+}
+
+pub fn test01() -> usize {
+    bounded::<3>(3) //~ ERROR precondition
+}
+
+#[flux::refined_by(len: int)]
+pub struct Buf<const N: usize> {
+    #[flux::field({usize[@len] : len <= N})]
+    len: usize,
+    data: [u8; N],
+}
+
+impl<const N: usize> Buf<N> {
+    #[flux::sig(fn() -> Buf<N>)]
+    pub fn new() -> Buf<N> {
+        Buf { len: N + 1, data: [0; N] } //~ ERROR precondition
+    }
+
+    #[flux::sig(fn(&Buf<N>[@n]) -> usize{v: v < N})]
+    pub fn len(&self) -> usize {
+        self.len //~ ERROR postcondition
+    }
+
+    #[flux::sig(fn(&Buf<N>) -> usize[N])]
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
@@ -0,0 +1,25 @@
+// aux-build:cross_crate00_lib.rs
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+extern crate cross_crate00_lib;
+
+use cross_crate00_lib::{incr, nat, Nat};
+
+#[flux::sig(fn() -> i32{v: v > 1})]
+pub fn test00() -> i32 {
+    incr(1)
+}
+
+pub fn test01() -> i32 {
+    incr(0) //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(Nat) -> i32{v: v > 0})]
+pub fn test02(x: Nat) -> i32 {
+    x.n //~ ERROR postcondition might not hold
+}
+
+pub fn test03() -> Nat {
+    nat(-1) //~ ERROR precondition might not hold
+}
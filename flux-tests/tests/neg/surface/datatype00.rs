@@ -0,0 +1,37 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[flux::reflect]
+pub enum Shape {
+    Circle(Point, i32),
+    Rect(Point, Point),
+}
+
+#[flux::sig(fn(x: i32, y: i32) -> Point[Point(y, x)])]
+pub fn mk_point(x: i32, y: i32) -> Point {
+    Point { x, y } //~ ERROR postcondition
+}
+
+#[flux::sig(fn(p: Point) -> i32[p.y])]
+pub fn get_y(p: Point) -> i32 {
+    p.x //~ ERROR postcondition
+}
+
+#[flux::sig(fn(&Shape[@s]) -> bool[is_Circle(s)])]
+pub fn is_rect(s: &Shape) -> bool {
+    match s {
+        Shape::Circle(..) => false, //~ ERROR postcondition
+        Shape::Rect(..) => true,
+    }
+}
+
+#[flux::sig(fn(c: Point, r: i32) -> Shape{v: is_Rect(v)})]
+pub fn circle(c: Point, r: i32) -> Shape {
+    Shape::Circle(c, r) //~ ERROR postcondition
+}
@@ -0,0 +1,22 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::defs {
+    fn abs(x: int) -> int { if x < 0 { 0 - x } else { x } }
+    fn in_bounds(i: int, n: int) -> bool { 0 <= i && i < n }
+}]
+
+#[flux::sig(fn(x: i32) -> i32[abs(x)])]
+pub fn abs(x: i32) -> i32 {
+    x //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: in_bounds(v, n)}) -> i32)]
+pub fn get(s: &[i32], i: usize) -> i32 {
+    s[i]
+}
+
+#[flux::sig(fn(&[i32][@n]) -> i32)]
+pub fn test01(s: &[i32]) -> i32 {
+    get(s, s.len()) //~ ERROR precondition might not hold
+}
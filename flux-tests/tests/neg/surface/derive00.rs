@@ -0,0 +1,22 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+// The derived `Default` would construct a `Pos` with a zero field.
+#[derive(Default)] //~ ERROR precondition might not hold
+pub struct Pos {
+    #[flux::field(i32{v: v > 0})]
+    n: i32,
+}
+
+#[flux::refined_by(n: int)]
+pub struct Counter {
+    #[flux::field(i32[@n])]
+    n: i32,
+}
+
+// Impls of `Clone` without a refined signature are checked against the one of `Clone::clone`
+impl Clone for Counter {
+    fn clone(&self) -> Self {
+        Counter { n: 0 } //~ ERROR postcondition might not hold
+    }
+}
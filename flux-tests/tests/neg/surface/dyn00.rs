@@ -0,0 +1,11 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub trait Pos {
+    #[flux::sig(fn(&Self, x: i32{x > 0}) -> i32{v: v > 0})]
+    fn scale(&self, x: i32) -> i32;
+}
+
+pub fn call(p: &dyn Pos) -> i32 {
+    p.scale(0) //~ ERROR precondition might not hold
+}
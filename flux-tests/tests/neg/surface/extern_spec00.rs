@@ -0,0 +1,36 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![allow(dead_code)]
+
+#[flux::extern_spec]
+#[flux::refined_by(len: int)]
+struct VecSpec<T>(Vec<T>);
+
+#[flux::extern_spec]
+#[flux::sig(fn() -> Vec<T>[0])]
+fn new<T>() -> Vec<T> {
+    Vec::new()
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(v: &strg Vec<T>[@n], T) ensures v: Vec<T>[n + 1])]
+fn push<T>(v: &mut Vec<T>, value: T) {
+    v.push(value)
+}
+
+#[flux::extern_spec]
+#[flux::sig(fn(i32{v: v >= 0}) -> i32{v: v >= 0})]
+fn abs(x: i32) -> i32 {
+    i32::abs(x)
+}
+
+#[flux::sig(fn() -> Vec<i32>[2])]
+pub fn one() -> Vec<i32> {
+    let mut v = Vec::new();
+    v.push(1);
+    v //~ ERROR postcondition might not hold
+}
+
+pub fn negative() -> i32 {
+    (-1i32).abs() //~ ERROR precondition might not hold
+}
@@ -0,0 +1,28 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(f: fn(i32{v: v > 0}) -> i32{v: v > 0}, x: i32) -> i32{v: v > 0})]
+pub fn apply(f: fn(i32) -> i32, x: i32) -> i32 {
+    f(x) //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: v >= 0})]
+pub fn abs(x: i32) -> i32 {
+    if x < 0 { -x } else { x }
+}
+
+#[flux::sig(fn() -> i32{v: v > 0})]
+pub fn test() -> i32 {
+    apply(abs, 1) //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(x: i32) -> i32[0])]
+pub fn zero(_x: i32) -> i32 {
+    0
+}
+
+#[flux::sig(fn(bool) -> i32{v: v >= 0})]
+pub fn choose_different(b: bool) -> i32 {
+    let f = if b { abs } else { zero as fn(i32) -> i32 };
+    f(-1) //~ ERROR postcondition might not hold
+}
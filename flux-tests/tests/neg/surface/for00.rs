@@ -0,0 +1,35 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(&Vec<i32>) -> i32)]
+pub fn sum(v: &Vec<i32>) -> i32 {
+    let mut s = 0;
+    for i in 0..=v.len() {
+        s += v[i]; //~ ERROR precondition might not hold
+    }
+    s
+}
+
+#[flux::sig(fn(&[i32]) -> i32{v: v > 0})]
+pub fn last_or_one(s: &[i32]) -> i32 {
+    let mut r = 1;
+    for x in s.iter() {
+        r = *x;
+    }
+    r //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(x: T[@n]) -> T[n])]
+pub fn id<T>(x: T) -> T {
+    x
+}
+
+#[flux::sig(fn(usize[@n]) -> usize[n + 1])]
+pub fn use_id(n: usize) -> usize {
+    id(n) //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(T) -> T[0])]
+pub fn use_id_generic<T>(x: T) -> T {
+    id(x)
+} //~ ERROR postcondition might not hold
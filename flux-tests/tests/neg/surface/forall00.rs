@@ -0,0 +1,21 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(n: i32{forall i in 0..n { i < 10 }}))]
+pub fn at_most_ten(n: i32) {}
+
+pub fn test00() {
+    at_most_ten(11); //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(n: i32{exists i in 0..n { 2 * i == n }}))]
+pub fn even(n: i32) {}
+
+pub fn test01() {
+    even(0); //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(n: i32) -> bool[forall i in 0..n { exists j in i..n { i < j } }])]
+pub fn nested(n: i32) -> bool {
+    true //~ ERROR postcondition might not hold
+}
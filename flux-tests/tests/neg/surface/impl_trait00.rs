@@ -0,0 +1,23 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(it: impl Iterator) -> i32{v: v >= 0})]
+pub fn sum(it: impl Iterator<Item = i32>) -> i32 {
+    let mut s = 0;
+    for x in it {
+        if x < 100 && s < 1000 {
+            s += x;
+        }
+    }
+    s //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> impl Fn)]
+pub fn adder(n: i32) -> impl Fn(i32) -> i32 {
+    move |x| x + n
+}
+
+pub fn use_adder() -> i32 {
+    let f = adder(0); //~ ERROR precondition might not hold
+    f(2)
+}
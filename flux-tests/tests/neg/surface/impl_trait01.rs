@@ -0,0 +1,18 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[path = "../../lib/my_option.rs"]
+mod my_option;
+use my_option::MyOption;
+
+#[flux::assume]
+#[flux::sig(fn(it: &mut I) -> MyOption<I::Item>[false])]
+pub fn next_none<I: Iterator>(_it: &mut I) -> MyOption<I::Item> {
+    MyOption::none()
+}
+
+#[flux::sig(fn() -> i32)]
+pub fn use_next_none() -> i32 {
+    let mut it = 0..10;
+    next_none(&mut it).unwrap() //~ ERROR precondition might not hold
+}
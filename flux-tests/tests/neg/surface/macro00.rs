@@ -0,0 +1,24 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+macro_rules! make_dec {
+    ($name:ident) => {
+        #[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+        pub fn $name(x: i32) -> i32 {
+            x - 1
+        }
+    };
+}
+
+make_dec!(dec); //~ ERROR postcondition might not hold
+
+macro_rules! neg {
+    ($e:expr) => {
+        -$e
+    };
+}
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v >= 0})]
+pub fn neg(x: i32) -> i32 {
+    neg!(x) //~ ERROR postcondition might not hold
+}
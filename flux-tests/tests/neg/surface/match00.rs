@@ -0,0 +1,26 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn(i32) -> i32{v: v > 0})]
+pub fn guard(x: i32) -> i32 {
+    match x {
+        n if n >= 0 => n, //~ ERROR postcondition might not hold
+        _ => 1,
+    }
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v < n}) -> i32)]
+pub fn rest_get(s: &[i32], i: usize) -> i32 {
+    match s {
+        [_, rest @ ..] => rest[i], //~ ERROR assertion might fail
+        [] => 0,
+    }
+}
+
+#[flux::sig(fn([i32; 3], usize{v: v < 3}) -> i32)]
+pub fn array(arr: [i32; 3], i: usize) -> i32 {
+    let [_, rest @ ..] = arr;
+    rest[i] //~ ERROR assertion might fail
+}
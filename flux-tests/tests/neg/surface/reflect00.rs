@@ -0,0 +1,25 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub fn max(a: i32, b: i32) -> i32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+#[flux::sig(fn(a: i32, b: i32) -> i32{v: v > a})]
+pub fn test00(a: i32, b: i32) -> i32 {
+    max(a, b) //~ ERROR postcondition
+}
+
+#[flux::sig(fn(x: i32) -> i32[max(x, 0)])]
+pub fn test01(x: i32) -> i32 {
+    if x < 0 {
+        x //~ ERROR postcondition
+    } else {
+        x
+    }
+}
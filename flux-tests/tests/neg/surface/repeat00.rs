@@ -0,0 +1,28 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn() -> [i32{v: v > 0}; 4])]
+pub fn make() -> [i32; 4] {
+    [0; 4] //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(usize{v: v <= 64}) -> u8)]
+pub fn get(i: usize) -> u8 {
+    let buf = [0u8; 64];
+    buf[i] //~ ERROR assertion might fail
+}
+
+#[flux::sig(fn() -> i32{v: v >= 0})]
+pub fn write() -> i32 {
+    let mut buf = [0; 8];
+    buf[1] = -1;
+    buf[0] //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(usize{v: v <= N}) -> i32)]
+pub fn get_generic<const N: usize>(i: usize) -> i32 {
+    let arr = [1; N];
+    arr[i] //~ ERROR assertion might fail
+}
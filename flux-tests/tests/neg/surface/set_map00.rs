@@ -0,0 +1,72 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::opaque]
+#[flux::refined_by(elems: Set<int>)]
+pub struct IntSet {
+    inner: Vec<i32>,
+}
+
+impl IntSet {
+    #[flux::assume]
+    #[flux::sig(fn() -> IntSet[empty()])]
+    pub fn new() -> IntSet {
+        IntSet { inner: Vec::new() }
+    }
+
+    #[flux::assume]
+    #[flux::sig(fn(self: &strg IntSet[@s], x: i32) ensures self: IntSet[union(s, singleton(x))])]
+    pub fn insert(&mut self, x: i32) {
+        self.inner.push(x);
+    }
+
+    #[flux::assume]
+    #[flux::sig(fn(&IntSet[@s], x: i32) -> bool[member(x, s)])]
+    pub fn contains(&self, x: i32) -> bool {
+        self.inner.contains(&x)
+    }
+}
+
+#[flux::sig(fn() -> bool[true])]
+pub fn test00() -> bool {
+    let mut s = IntSet::new();
+    s.insert(1);
+    s.contains(2)
+} //~ ERROR postcondition
+
+#[flux::opaque]
+#[flux::refined_by(len: int, elems: Map<int, int>)]
+pub struct IntVec {
+    inner: Vec<i32>,
+}
+
+impl IntVec {
+    #[flux::assume]
+    #[flux::sig(fn(n: usize) -> IntVec[n, map_default(0)])]
+    pub fn zeros(n: usize) -> IntVec {
+        IntVec { inner: vec![0; n] }
+    }
+
+    #[flux::assume]
+    #[flux::sig(fn(&IntVec[@n, @m], i: usize{i < n}) -> i32[select(m, i)])]
+    pub fn get(&self, i: usize) -> i32 {
+        self.inner[i]
+    }
+
+    #[flux::assume]
+    #[flux::sig(
+        fn(self: &strg IntVec[@n, @m], i: usize{i < n}, v: i32)
+        ensures self: IntVec[n, store(m, i, v)]
+    )]
+    pub fn set(&mut self, i: usize, v: i32) {
+        self.inner[i] = v;
+    }
+}
+
+#[flux::sig(fn() -> i32[5])]
+pub fn test01() -> i32 {
+    let mut v = IntVec::zeros(3);
+    v.set(1, 5);
+    v.set(1, 7);
+    v.get(1)
+} //~ ERROR postcondition
@@ -0,0 +1,19 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig([i32{v: v > 0}; 4])]
+static TABLE: [i32; 4] = [1, 0, 3, 4]; //~ ERROR postcondition might not hold
+
+static DEFAULT: [i32; 4] = [1, 2, 3, 4];
+
+#[flux::sig(fn(usize{v: v < 4}) -> i32{v: v > 0})]
+pub fn lookup(i: usize) -> i32 {
+    DEFAULT[i] //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(usize) -> i32)]
+pub fn lookup_unchecked(i: usize) -> i32 {
+    TABLE[i] //~ ERROR assertion might fail
+}
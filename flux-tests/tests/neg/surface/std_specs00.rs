@@ -0,0 +1,34 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub fn first(v: &Vec<i32>) -> i32 {
+    v[0] //~ ERROR precondition might not hold
+}
+
+#[flux::sig(fn(v: &strg Vec<i32>[@n]) -> i32 ensures v: Vec<i32>)]
+pub fn pop(v: &mut Vec<i32>) -> i32 {
+    match v.pop() {
+        Some(x) => x,
+        None => never(0), //~ ERROR precondition might not hold
+    }
+}
+
+pub fn split(s: &[i32]) -> i32 {
+    let (fst, _) = s.split_at(1); //~ ERROR precondition might not hold
+    fst[0]
+}
+
+#[flux::sig(fn(&[i32][@n]) -> Option<&i32>[true])]
+pub fn slice_get(s: &[i32]) -> Option<&i32> {
+    s.get(0) //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(&Vec<i32>[@n]) -> Option<&i32>[true])]
+pub fn vec_get(v: &Vec<i32>) -> Option<&i32> {
+    v.get(0) //~ ERROR postcondition might not hold
+}
+
+#[flux::sig(fn(i32{v: false}) -> T)]
+pub fn never<T>(_: i32) -> T {
+    loop {}
+}
@@ -0,0 +1,16 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> usize[4])]
+pub fn literal_len() -> usize {
+    "hello".len() //~ ERROR postcondition
+}
+
+pub fn byte_at(s: &str, i: usize) -> u8 {
+    s.as_bytes()[i] //~ ERROR assertion might fail
+}
+
+pub fn last_byte(s: &str) -> u8 {
+    let bytes = s.as_bytes();
+    bytes[s.len()] //~ ERROR assertion might fail
+}
@@ -0,0 +1,29 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub trait Pos {
+    #[flux::sig(fn(&Self) -> i32{v: v > 0})]
+    fn get(&self) -> i32;
+}
+
+pub struct Zero;
+
+impl Pos for Zero {
+    #[flux::sig(fn(&Zero) -> i32{v: v >= 0})]
+    fn get(&self) -> i32 { //~ ERROR postcondition might not hold
+        0
+    }
+}
+
+pub struct MinusOne;
+
+impl Pos for MinusOne {
+    fn get(&self) -> i32 {
+        -1 //~ ERROR postcondition might not hold
+    }
+}
+
+#[flux::sig(fn(&T) -> i32{v: v > 1})]
+pub fn id<T: Pos>(x: &T) -> i32 {
+    x.get() //~ ERROR postcondition might not hold
+}
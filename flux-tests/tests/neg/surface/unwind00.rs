@@ -0,0 +1,18 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+fn may_panic(x: i32) -> i32 {
+    if x > 100 {
+        panic!()
+    }
+    x
+}
+
+// The invariant of `x` cannot be broken even temporarily because the call to `may_panic` could
+// unwind before it is restored.
+#[flux::sig(fn(x: &mut i32{v: v > 0}, Box<i32{v: v > 0}>))]
+pub fn update(x: &mut i32, b: Box<i32>) {
+    *x = 0; //~ ERROR assignment might be unsafe
+    *x = may_panic(*b);
+}
@@ -0,0 +1,21 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: &strg i32) ensures x: i32[1])]
+fn reset(x: &mut i32) {
+    *x = 1;
+}
+
+pub struct S {
+    #[flux::field(i32{v: v > 0})]
+    x: i32,
+    b: Box<i32>,
+}
+
+// The field is restored by `reset` when it returns, but if it unwinds the field may still be
+// zero when `s` is dropped.
+pub fn test(b: Box<i32>) {
+    let mut s = S { x: 1, b };
+    s.x = 0;
+    reset(&mut s.x);
+} //~ ERROR fold error
@@ -0,0 +1,9 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check", check_overflow = "true")]
+
+#[flux::sig(fn(x: i32{0 <= x && x < 100}) -> i32{v: v > x})]
+pub fn incr(x: i32) -> i32 {
+    x + 1
+}
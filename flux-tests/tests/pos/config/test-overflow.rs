@@ -0,0 +1,42 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_overflow = "true")]
+
+const SCALE: i32 = 256;
+
+#[flux::sig(fn(x: i32{0 <= x && x < 65536}) -> i32{v: 0 <= v})]
+pub fn to_fixed(x: i32) -> i32 {
+    x * SCALE
+}
+
+#[flux::sig(fn(x: i32{0 <= x && x < 65536}, y: i32{0 <= y && y < 65536}) -> i32)]
+pub fn add_fixed(x: i32, y: i32) -> i32 {
+    to_fixed(x) + to_fixed(y)
+}
+
+#[flux::sig(fn(x: u32{x < 1000}, y: u32{y <= x}) -> u32{v: v <= x})]
+pub fn sub(x: u32, y: u32) -> u32 {
+    x - y
+}
+
+#[flux::sig(fn(x: u64, k: u32{k < 64}) -> u64)]
+pub fn shr(x: u64, k: u32) -> u64 {
+    x >> k
+}
+
+#[flux::sig(fn(n: usize{n < 100}) -> usize)]
+pub fn sum(n: usize) -> usize {
+    let mut i = 0;
+    let mut s = 0;
+    while i < n {
+        s += 1;
+        i += 1;
+    }
+    s
+}
+
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v < 0})]
+pub fn neg(x: i32) -> i32 {
+    -x
+}
@@ -0,0 +1,46 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(real_floats = "true")]
+#![flux::qualifier(RealGeZero(x: real) { 0.0 <= x })]
+
+#[flux::sig(fn(x: f64, y: f64) -> f64[x + y])]
+pub fn add(x: f64, y: f64) -> f64 {
+    x + y
+}
+
+#[flux::sig(fn(x: f64{0.0 <= x}) -> f64{v: 0.0 <= v})]
+pub fn double(x: f64) -> f64 {
+    x * 2.0
+}
+
+#[flux::sig(fn(x: f32) -> f32{v: v == 0.0 - x})]
+pub fn neg(x: f32) -> f32 {
+    -x
+}
+
+#[flux::sig(fn(x: f64{x > 1.5}) -> bool[true])]
+pub fn cmp(x: f64) -> bool {
+    x >= 1.0
+}
+
+#[flux::sig(fn(n: i32) -> f64[to_real(n)])]
+pub fn int_to_float(n: i32) -> f64 {
+    n as f64
+}
+
+#[flux::sig(fn(n: u8) -> u8[n])]
+pub fn round_trip(n: u8) -> u8 {
+    (n as f32) as u8
+}
+
+#[flux::sig(fn(xs: &[f64{v: 0.0 <= v}]) -> f64{v: 0.0 <= v})]
+pub fn sum(xs: &[f64]) -> f64 {
+    let mut acc = 0.0;
+    let mut i = 0;
+    while i < xs.len() {
+        acc += xs[i];
+        i += 1;
+    }
+    acc
+}
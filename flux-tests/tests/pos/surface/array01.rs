@@ -0,0 +1,35 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn() -> [i32; 3])]
+pub fn make() -> [i32; 3] {
+    [1, 2, 3]
+}
+
+#[flux::sig(fn(&[i32; 4]) -> i32)]
+pub fn last(arr: &[i32; 4]) -> i32 {
+    arr[3]
+}
+
+#[flux::sig(fn(&[i32; @n], usize{v: v < n}) -> i32)]
+pub fn get(arr: &[i32; 5], i: usize) -> i32 {
+    arr[i]
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v < n}) -> i32)]
+pub fn get_slice(slice: &[i32], i: usize) -> i32 {
+    slice[i]
+}
+
+#[flux::sig(fn(&mut [i32][@n], usize{v: v < n}))]
+pub fn set_slice(slice: &mut [i32], i: usize) {
+    slice[i] = 0;
+}
+
+pub fn unsize() -> i32 {
+    let arr = [1, 2, 3];
+    let slice: &[i32] = &arr;
+    get_slice(slice, 2)
+}
@@ -0,0 +1,9 @@
+// compile-flags: --emit=metadata
+// no-prefer-dynamic
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 1})]
+pub fn incr(x: i32) -> i32 {
+    x + 1
+}
@@ -0,0 +1,8 @@
+// compile-flags: --emit=metadata
+// no-prefer-dynamic
+// Replaces `cross_crate01_lib` without using the flux tool, so flux doesn't update its metadata
+#![crate_name = "cross_crate01_lib"]
+
+pub fn incr(x: i32) -> i32 {
+    x + 1
+}
@@ -0,0 +1,67 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: u32, mask: u32) -> u32{v: v <= mask})]
+pub fn and_mask(x: u32, mask: u32) -> u32 {
+    x & mask
+}
+
+#[flux::sig(fn(x: u32, y: u32) -> u32{v: v >= x && v >= y})]
+pub fn or(x: u32, y: u32) -> u32 {
+    x | y
+}
+
+#[flux::sig(fn(x: u32) -> u32[0])]
+pub fn xor_self(x: u32) -> u32 {
+    x ^ x
+}
+
+#[flux::sig(fn(x: u8) -> u8{v: v < 16})]
+pub fn high_nibble(x: u8) -> u8 {
+    x >> 4
+}
+
+#[flux::sig(fn(x: u64) -> u64{v: v <= x})]
+pub fn halve(x: u64) -> u64 {
+    x >> 1
+}
+
+#[flux::sig(fn(k: u32{k < 8}) -> u8{v: v > 0})]
+pub fn bit(k: u32) -> u8 {
+    1 << k
+}
+
+#[flux::sig(fn(x: i32{x < 0}) -> i32[0 - 1])]
+pub fn sign(x: i32) -> i32 {
+    x >> 31
+}
+
+#[flux::sig(fn(x: bool, y: bool) -> bool[x || y])]
+pub fn bool_or(x: bool, y: bool) -> bool {
+    x | y
+}
+
+#[flux::opaque]
+#[flux::refined_by(bits: bitvec<32>)]
+pub struct BV32(u32);
+
+#[flux::assume]
+#[flux::sig(fn(x: BV32, y: BV32) -> BV32[x & y])]
+pub fn bv_and(x: BV32, y: BV32) -> BV32 {
+    BV32(x.0 & y.0)
+}
+
+#[flux::sig(fn(x: BV32, y: BV32) -> BV32[y & x])]
+pub fn bv_and_comm(x: BV32, y: BV32) -> BV32 {
+    bv_and(x, y)
+}
+
+#[flux::sig(fn(x: u32) -> u32[bv32_to_int(int_to_bv32(x) & int_to_bv32(255))])]
+pub fn low_byte(x: u32) -> u32 {
+    x & 0xff
+}
+
+#[flux::sig(fn(x: u64, y: u64) -> u64[bv64_to_int(int_to_bv64(x) | int_to_bv64(y))])]
+pub fn or64(x: u64, y: u64) -> u64 {
+    x | y
+}
@@ -0,0 +1,52 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> char[97])]
+pub fn literal() -> char {
+    'a'
+}
+
+#[flux::sig(fn(char[@c]) -> u32[c])]
+pub fn code_point(c: char) -> u32 {
+    c as u32
+}
+
+#[flux::sig(fn(u32{v: v <= 1114111}) -> bool)]
+pub fn in_range(n: u32) -> bool {
+    let c = 'z';
+    (c as u32) < n
+}
+
+#[flux::sig(fn(u8[@n]) -> char[n])]
+pub fn from_byte(b: u8) -> char {
+    b as char
+}
+
+#[flux::sig(fn() -> u8[0])]
+pub fn truncate() -> u8 {
+    '\u{100}' as u8
+}
+
+#[flux::sig(fn(char) -> u32{v: v < 10})]
+pub fn digit_value(c: char) -> u32 {
+    if c.is_ascii_digit() {
+        c as u32 - '0' as u32
+    } else {
+        0
+    }
+}
+
+#[flux::sig(fn(char) -> u32{v: v < 16})]
+pub fn hex_value(c: char) -> u32 {
+    match c.to_digit(16) {
+        Some(d) => d,
+        None => 0,
+    }
+}
+
+pub fn is_vowel(c: char) -> bool {
+    match c {
+        'a' | 'e' | 'i' | 'o' | 'u' => true,
+        _ => false,
+    }
+}
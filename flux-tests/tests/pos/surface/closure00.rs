@@ -0,0 +1,14 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > 0})]
+pub fn test(x: i32) -> i32 {
+    let f = |y: i32| if y > x { y } else { x };
+    f(0)
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> i32{v: v > 0})]
+pub fn capture(n: i32) -> i32 {
+    let get = || n;
+    get()
+}
@@ -0,0 +1,15 @@
+#![feature(register_tool, stmt_expr_attributes)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> i32{v: v > 1})]
+pub fn annotated() -> i32 {
+    let incr = #[flux::sig(fn(i32[@n]) -> i32[n + 1])]
+    |x| x + 1;
+    incr(1)
+}
+
+#[flux::sig(fn() -> i32{v: v >= 5})]
+pub fn inferred() -> i32 {
+    let double = |x: i32| if x > 0 { x + x } else { 0 };
+    double(5)
+}
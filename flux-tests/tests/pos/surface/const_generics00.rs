@@ -0,0 +1,75 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(x: [u8; N]) -> usize[N])]
+pub fn len<const N: usize>(x: [u8; N]) -> usize {
+    x.len()
+}
+
+#[flux::sig(fn() -> usize[3])]
+pub fn test00() -> usize {
+    len([1, 2, 3])
+}
+
+#[flux::sig(fn() -> [i32{v: v >= 0}; N])]
+pub fn zeros<const N: usize>() -> [i32; N] {
+    [0; N]
+}
+
+pub fn test01() -> i32 {
+    let arr = zeros::<4>();
+    arr[3]
+}
+
+#[flux::sig(fn(i: usize{i < N}) -> usize{v: v < N})]
+pub fn bounded<const N: usize>(i: usize) -> usize {
+    i
+}
+
+#[flux::refined_by(len: int)]
+pub struct Buf<const N: usize> {
+    #[flux::field({usize[@len] : len <= N})]
+    len: usize,
+    data: [u8; N],
+}
+
+impl<const N: usize> Buf<N> {
+    #[flux::sig(fn() -> Buf<N>[0])]
+    pub fn new() -> Buf<N> {
+        Buf { len: 0, data: [0; N] }
+    }
+
+    #[flux::sig(fn(&Buf<N>[@n]) -> usize{v: v <= N})]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[flux::sig(fn(&Buf<N>) -> usize[N])]
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+}
+
+#[flux::sig(fn() -> usize[8])]
+pub fn test02() -> usize {
+    let buf = Buf::<8>::new();
+    buf.capacity()
+}
+
+// Const generic parameters that are not integers are left unrefined
+pub fn flag<const B: bool>() -> bool {
+    B
+}
+
+pub fn letter<const C: char>() -> char {
+    C
+}
+
+#[flux::sig(fn(bool) -> i32{v: v >= 0})]
+pub fn select<const B: bool>(b: bool) -> i32 {
+    if B && b {
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,27 @@
+// aux-build:cross_crate00_lib.rs
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+extern crate cross_crate00_lib;
+
+use cross_crate00_lib::{incr, nat, Nat};
+
+#[flux::sig(fn(x: i32{x > 0}) -> i32{v: v > 2})]
+pub fn test00(x: i32) -> i32 {
+    incr(incr(x))
+}
+
+#[flux::sig(fn(Nat[@n]) -> i32[n])]
+pub fn test01(x: Nat) -> i32 {
+    x.n
+}
+
+#[flux::sig(fn(Nat) -> i32{v: v >= 0})]
+pub fn test02(x: Nat) -> i32 {
+    x.n
+}
+
+#[flux::sig(fn() -> Nat[5])]
+pub fn test03() -> Nat {
+    nat(5)
+}
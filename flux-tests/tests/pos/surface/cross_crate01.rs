@@ -0,0 +1,15 @@
+// aux-build:cross_crate01_lib.rs
+// aux-build:cross_crate01_lib_unchecked.rs
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+// The metadata exported when checking `cross_crate01_lib` is stale because the crate was replaced
+// by `cross_crate01_lib_unchecked`, so it is ignored and `incr` has no precondition.
+
+extern crate cross_crate01_lib;
+
+use cross_crate01_lib::incr;
+
+pub fn test00() -> i32 {
+    incr(0)
+}
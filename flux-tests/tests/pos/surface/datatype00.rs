@@ -0,0 +1,89 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::defs {
+    fn dist_x(p: Point, q: Point) -> int { q.x - p.x }
+}]
+
+#[flux::reflect]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[flux::reflect]
+pub enum Shape {
+    Circle(Point, i32),
+    Rect(Point, Point),
+}
+
+#[flux::reflect]
+pub enum Color {
+    Red,
+    Green,
+}
+
+#[flux::refined_by(start: Point, end: Point)]
+pub struct Segment {
+    #[flux::field(Point[@start])]
+    start: Point,
+    #[flux::field(Point[@end])]
+    end: Point,
+}
+
+#[flux::sig(fn(x: i32, y: i32) -> Point[Point(x, y)])]
+pub fn mk_point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[flux::sig(fn(p: Point) -> i32[p.x])]
+pub fn get_x(p: Point) -> i32 {
+    p.x
+}
+
+#[flux::sig(fn(&Point[@p]) -> i32[p.x + p.y])]
+pub fn sum(p: &Point) -> i32 {
+    p.x + p.y
+}
+
+#[flux::sig(fn() -> i32[3])]
+pub fn test00() -> i32 {
+    let p = mk_point(1, 2);
+    sum(&p)
+}
+
+#[flux::sig(fn(s: Segment) -> i32[dist_x(s.start, s.end)])]
+pub fn width(s: Segment) -> i32 {
+    s.end.x - s.start.x
+}
+
+#[flux::sig(fn(s: Segment{v: v.start.y == v.end.y}) -> Segment[s.end, s.start])]
+pub fn flip(s: Segment) -> Segment {
+    Segment { start: s.end, end: s.start }
+}
+
+#[flux::sig(fn(&Shape[@s]) -> bool[is_Circle(s)])]
+pub fn is_circle(s: &Shape) -> bool {
+    match s {
+        Shape::Circle(..) => true,
+        Shape::Rect(..) => false,
+    }
+}
+
+#[flux::sig(fn(c: Point, r: i32) -> Shape{v: is_Circle(v)})]
+pub fn circle(c: Point, r: i32) -> Shape {
+    Shape::Circle(c, r)
+}
+
+#[flux::sig(fn(Color[@c]) -> bool[c == Red])]
+pub fn is_red(c: Color) -> bool {
+    match c {
+        Color::Red => true,
+        Color::Green => false,
+    }
+}
+
+#[flux::sig(fn() -> bool[false])]
+pub fn test01() -> bool {
+    is_red(Color::Green)
+}
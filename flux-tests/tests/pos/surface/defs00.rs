@@ -0,0 +1,43 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::defs {
+    fn abs(x: int) -> int { if x < 0 { 0 - x } else { x } }
+    fn in_bounds(i: int, n: int) -> bool { 0 <= i && i < n }
+    fn all_below(n: int, m: int) -> bool { forall i in 0..n { abs(i) < m } }
+}]
+
+#[flux::sig(fn(x: i32) -> i32[abs(x)])]
+pub fn abs(x: i32) -> i32 {
+    if x < 0 {
+        -x
+    } else {
+        x
+    }
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: v >= 0})]
+pub fn test00(x: i32) -> i32 {
+    abs(x)
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: in_bounds(v, n)}) -> i32)]
+pub fn get(s: &[i32], i: usize) -> i32 {
+    s[i]
+}
+
+#[flux::sig(fn(&[i32][@n]) -> i32)]
+pub fn test01(s: &[i32]) -> i32 {
+    if s.len() > 0 {
+        get(s, s.len() - 1)
+    } else {
+        0
+    }
+}
+
+#[flux::sig(fn(n: i32{all_below(n, 10)}))]
+pub fn below(n: i32) {}
+
+pub fn test02() {
+    below(10);
+}
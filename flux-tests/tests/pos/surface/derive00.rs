@@ -0,0 +1,58 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub enum WasiProto {
+    #[default]
+    Unknown,
+    Tcp,
+    Udp,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Pair {
+    fst: i32,
+    snd: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Wrap<T> {
+    inner: T,
+}
+
+// The derived impls are checked against the refined fields.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[flux::refined_by(n: int)]
+#[flux::invariant(n > 0)]
+pub struct Pos {
+    #[flux::field(i32[@n])]
+    n: i32,
+}
+
+#[flux::sig(fn(&Pos) -> i32{v: v > 0})]
+pub fn get(p: &Pos) -> i32 {
+    p.clone().n
+}
+
+#[flux::sig(fn(Pos, Pos) -> bool)]
+pub fn same(p: Pos, q: Pos) -> bool {
+    p == q
+}
+
+// Derived impls of `Clone` inherit the signature of `Clone::clone`, which preserves the index
+#[flux::sig(fn(&Pos[@n]) -> Pos[n])]
+pub fn copy(p: &Pos) -> Pos {
+    p.clone()
+}
+
+#[derive(Clone)]
+#[flux::refined_by(len: int)]
+pub struct Stack {
+    #[flux::field(Vec<i32>[@len])]
+    items: Vec<i32>,
+}
+
+#[flux::sig(fn(&Stack[@n]) -> Stack[n])]
+pub fn dup(s: &Stack) -> Stack {
+    s.clone()
+}
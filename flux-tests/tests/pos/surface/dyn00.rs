@@ -0,0 +1,30 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub trait Pos {
+    #[flux::sig(fn(&Self, x: i32{x > 0}) -> i32{v: v > 0})]
+    fn scale(&self, x: i32) -> i32;
+}
+
+pub struct Double;
+
+impl Pos for Double {
+    #[flux::sig(fn(&Double, x: i32{x > 0}) -> i32{v: v > x})]
+    fn scale(&self, x: i32) -> i32 {
+        x + x
+    }
+}
+
+pub fn test(d: &Double) -> i32 {
+    let p: &dyn Pos = d;
+    call(p)
+}
+
+pub fn call(p: &dyn Pos) -> i32 {
+    let r = p.scale(1);
+    assert(r > 0);
+    r
+}
+
+#[flux::sig(fn(bool[true]))]
+pub fn assert(_: bool) {}
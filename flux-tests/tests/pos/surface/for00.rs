@@ -0,0 +1,66 @@
+#![feature(register_tool, step_trait)]
+#![register_tool(flux)]
+
+use std::{iter::Step, ops::RangeInclusive};
+
+#[flux::sig(fn(&Vec<i32>) -> i32)]
+pub fn sum(v: &Vec<i32>) -> i32 {
+    let mut s = 0;
+    for i in 0..v.len() {
+        s += v[i];
+    }
+    s
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v < n}) -> i32)]
+pub fn prefix_sum(s: &[i32], m: usize) -> i32 {
+    let mut r = 0;
+    for i in 0..=m {
+        r += s[i];
+    }
+    r
+}
+
+#[flux::sig(fn(&[i32{v: v > 0}]) -> i32{v: v > 0})]
+pub fn last_or_one(s: &[i32]) -> i32 {
+    let mut r = 1;
+    for x in s.iter() {
+        r = *x;
+    }
+    r
+}
+
+#[flux::sig(fn(x: T[@n]) -> T[n])]
+pub fn id<T>(x: T) -> T {
+    x
+}
+
+#[flux::sig(fn(usize[@n]) -> usize[n])]
+pub fn use_id(n: usize) -> usize {
+    id(n)
+}
+
+pub fn use_id_unrefined(v: Vec<i32>) -> Vec<i32> {
+    id(v)
+}
+
+pub fn range_generic<A: Step>(a: A, b: A) -> RangeInclusive<A> {
+    a..=b
+}
+
+pub fn count_generic<A: Step>(a: A, b: A) -> usize {
+    let mut n = 0;
+    for _ in a..=b {
+        n += 1;
+    }
+    n
+}
+
+pub fn use_id_generic<T>(x: T) -> T {
+    id(x)
+}
+
+#[flux::sig(fn(T[@n]) -> T)]
+pub fn use_id_indexed<T>(x: T) -> T {
+    id(x)
+}
@@ -0,0 +1,33 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::qualifier(AllBelow(x: int, n: int) { forall i in 0..x { i < n } })]
+
+#[flux::sig(fn(n: i32{forall i in 0..n { i < 10 }}))]
+pub fn at_most_ten(n: i32) {}
+
+pub fn test00() {
+    at_most_ten(10);
+    at_most_ten(-5);
+}
+
+#[flux::sig(fn(n: i32{exists i in 0..n { 2 * i == n }}))]
+pub fn even(n: i32) {}
+
+pub fn test01() {
+    even(4);
+}
+
+#[flux::sig(fn(n: i32) -> bool[forall i in 0..n { exists j in i..n + 1 { i < j } }])]
+pub fn nested(n: i32) -> bool {
+    true
+}
+
+#[flux::sig(fn(n: i32{n >= 0}) -> i32{v: forall i in 0..v { i < n }})]
+pub fn count(n: i32) -> i32 {
+    let mut k = 0;
+    while k < n {
+        k += 1;
+    }
+    k
+}
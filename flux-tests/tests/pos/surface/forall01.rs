@@ -0,0 +1,24 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::uf(fn good(int) -> bool)]
+#![flux::qualifier(AllGood(x: int) { forall i in 0..x { good(i) } })]
+
+#[flux::assume]
+#[flux::sig(fn(i: i32) -> bool[good(i)])]
+pub fn is_good(i: i32) -> bool {
+    i > 0
+}
+
+// The loop invariant `forall i in 0..k { good(i) }` is inferred with the `AllGood` qualifier
+#[flux::sig(fn(n: i32{n >= 0}) -> bool{b: b => forall i in 0..n { good(i) }})]
+pub fn all_good(n: i32) -> bool {
+    let mut k = 0;
+    while k < n {
+        if !is_good(k) {
+            return false;
+        }
+        k += 1;
+    }
+    true
+}
@@ -0,0 +1,42 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(it: impl Iterator) -> i32{v: v >= 0})]
+pub fn sum_pos(it: impl Iterator<Item = i32>) -> i32 {
+    let mut s = 0;
+    for x in it {
+        if x > 0 && x < 100 && s < 1000 {
+            s += x;
+        }
+    }
+    s
+}
+
+#[flux::sig(fn(v: &Vec<i32>) -> i32{v: v >= 0})]
+pub fn sum_vec(v: &Vec<i32>) -> i32 {
+    sum_pos(v.iter().copied())
+}
+
+#[flux::sig(fn(it: I) -> Option<I::Item>)]
+pub fn first<I: Iterator>(mut it: I) -> Option<I::Item> {
+    it.next()
+}
+
+#[flux::sig(fn(it: &mut I) -> Option<i32{v: v >= 0}>)]
+pub fn first_pos<I: Iterator<Item = i32>>(it: &mut I) -> Option<i32> {
+    match it.next() {
+        Some(x) if x >= 0 => Some(x),
+        _ => None,
+    }
+}
+
+#[flux::sig(fn(n: i32{n > 0}) -> impl Fn)]
+pub fn adder(n: i32) -> impl Fn(i32) -> i32 {
+    move |x| x + n
+}
+
+#[flux::sig(fn() -> i32)]
+pub fn use_adder() -> i32 {
+    let f = adder(1);
+    f(2)
+}
@@ -0,0 +1,33 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[path = "../../lib/my_option.rs"]
+mod my_option;
+use my_option::MyOption;
+
+#[flux::assume]
+#[flux::sig(fn(it: &mut I) -> MyOption<I::Item>[true])]
+pub fn next_some<I: Iterator>(it: &mut I) -> MyOption<I::Item> {
+    MyOption::some(it.next().unwrap())
+}
+
+#[flux::sig(fn() -> i32)]
+pub fn use_next_some() -> i32 {
+    let mut it = 0..10;
+    next_some(&mut it).unwrap()
+}
+
+#[flux::sig(fn(it: I, x: I::Item) -> I::Item)]
+pub fn id_item<I: Iterator>(_it: I, x: I::Item) -> I::Item {
+    x
+}
+
+#[flux::sig(fn() -> i32)]
+pub fn use_id_item() -> i32 {
+    id_item(0..10, 1)
+}
+
+#[flux::sig(fn(it: I, x: T) -> T)]
+pub fn use_id_item_generic<T, I: Iterator<Item = T>>(it: I, x: T) -> T {
+    id_item(it, x)
+}
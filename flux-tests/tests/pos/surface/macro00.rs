@@ -0,0 +1,29 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+macro_rules! make_inc {
+    ($name:ident, $ty:ident) => {
+        #[flux::sig(fn($ty{v: v > 0}) -> $ty{v: v > 1})]
+        pub fn $name(x: $ty) -> $ty {
+            x + 1
+        }
+    };
+}
+
+make_inc!(inc_i32, i32);
+make_inc!(inc_i64, i64);
+
+macro_rules! abs {
+    ($e:expr) => {
+        if $e < 0 {
+            -$e
+        } else {
+            $e
+        }
+    };
+}
+
+#[flux::sig(fn(i32) -> i32{v: v >= 0})]
+pub fn abs(x: i32) -> i32 {
+    abs!(x)
+}
@@ -0,0 +1,58 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn(i32) -> i32{v: v > 0})]
+pub fn guard(x: i32) -> i32 {
+    match x {
+        n if n > 0 => n,
+        _ => 1,
+    }
+}
+
+#[flux::sig(fn(Option<i32>) -> i32{v: v >= 0})]
+pub fn guard_binding(x: Option<i32>) -> i32 {
+    match x {
+        Some(n) if n >= 0 => n,
+        Some(_) | None => 0,
+    }
+}
+
+#[flux::sig(fn(&[i32{v: v > 0}]) -> i32{v: v > 0})]
+pub fn first(s: &[i32]) -> i32 {
+    match s {
+        [first, ..] => *first,
+        [] => 1,
+    }
+}
+
+#[flux::sig(fn(&[i32{v: v >= 0}]) -> i32{v: v >= 0})]
+pub fn last(s: &[i32]) -> i32 {
+    match s {
+        [.., last] => *last,
+        [] => 0,
+    }
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v + 1 < n}) -> i32)]
+pub fn rest_get(s: &[i32], i: usize) -> i32 {
+    match s {
+        [_, rest @ ..] => rest[i],
+        [] => 0,
+    }
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v + 2 < n}) -> i32)]
+pub fn middle_get(s: &[i32], i: usize) -> i32 {
+    match s {
+        [_, middle @ .., _] => middle[i],
+        _ => 0,
+    }
+}
+
+#[flux::sig(fn([i32{v: v > 0}; 3]) -> i32{v: v > 0})]
+pub fn array(arr: [i32; 3]) -> i32 {
+    let [a, rest @ ..] = arr;
+    if a > rest[1] { a } else { rest[1] }
+}
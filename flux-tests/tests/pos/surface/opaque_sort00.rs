@@ -0,0 +1,39 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::opaque_sort(Handle)]
+#![flux::uf(fn size(Handle) -> int)]
+#![flux::axiom(forall h: Handle. size(h) >= 0)]
+#![flux::qualifier(SameHandle(h1: Handle, h2: Handle) { h1 == h2 })]
+
+#[flux::opaque]
+#[flux::refined_by(h: Handle)]
+pub struct File {
+    fd: i32,
+}
+
+#[flux::assume]
+#[flux::sig(fn(&File[@h]) -> i32[size(h)])]
+pub fn len(f: &File) -> i32 {
+    f.fd
+}
+
+#[flux::assume]
+#[flux::sig(fn(&File[@h1], &File[@h2]) -> bool[h1 == h2])]
+pub fn same(f: &File, g: &File) -> bool {
+    f.fd == g.fd
+}
+
+#[flux::sig(fn(&File) -> i32{v: v >= 0})]
+pub fn len_nonneg(f: &File) -> i32 {
+    len(f)
+}
+
+#[flux::sig(fn(&File[@h1], &File[@h2]) -> i32{v: h1 == h2 => v == 0})]
+pub fn diff(f: &File, g: &File) -> i32 {
+    if same(f, g) {
+        len(f) - len(g)
+    } else {
+        1
+    }
+}
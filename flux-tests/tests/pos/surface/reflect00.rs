@@ -0,0 +1,75 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::reflect]
+pub fn max(a: i32, b: i32) -> i32 {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+// Reflected functions can call other reflected functions regardless of the order in which they
+// are defined.
+#[flux::reflect]
+pub fn clamp(x: i32, lo: i32, hi: i32) -> i32 {
+    max(lo, min(x, hi))
+}
+
+#[flux::reflect]
+pub fn min(a: i32, b: i32) -> i32 {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+#[flux::reflect]
+pub fn sign(x: i32) -> i32 {
+    match x {
+        0 => 0,
+        _ if x < 0 => -1,
+        _ => 1,
+    }
+}
+
+#[flux::reflect]
+pub fn is_pos(x: i32) -> bool {
+    x > 0 && !(x == 0)
+}
+
+#[flux::reflect]
+pub fn double_plus_one(x: i32) -> i32 {
+    2 * x + 1
+}
+
+#[flux::sig(fn(a: i32, b: i32) -> i32{v: v >= a && v >= b})]
+pub fn test00(a: i32, b: i32) -> i32 {
+    max(a, b)
+}
+
+#[flux::sig(fn(x: i32) -> i32{v: 0 <= v && v <= 10})]
+pub fn test01(x: i32) -> i32 {
+    clamp(x, 0, 10)
+}
+
+#[flux::sig(fn(x: i32{is_pos(x)}) -> i32[1])]
+pub fn test02(x: i32) -> i32 {
+    sign(x)
+}
+
+#[flux::sig(fn(x: i32) -> i32[max(x, 0)])]
+pub fn test03(x: i32) -> i32 {
+    if x < 0 {
+        0
+    } else {
+        x
+    }
+}
+
+#[flux::sig(fn() -> i32[7])]
+pub fn test04() -> i32 {
+    double_plus_one(3)
+}
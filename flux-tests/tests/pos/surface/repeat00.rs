@@ -0,0 +1,27 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+
+#[flux::sig(fn() -> [i32{v: v > 0}; 4])]
+pub fn make() -> [i32; 4] {
+    [1; 4]
+}
+
+pub fn buffer() -> u8 {
+    let mut buf = [0u8; 64];
+    buf[63] = 1;
+    buf[0]
+}
+
+#[flux::sig(fn(usize{v: v < 64}) -> i32{v: v >= 0})]
+pub fn get(i: usize) -> i32 {
+    let arr = [0; 64];
+    arr[i]
+}
+
+#[flux::sig(fn(usize{v: v < N}) -> i32{v: v > 0})]
+pub fn get_generic<const N: usize>(i: usize) -> i32 {
+    let arr = [1; N];
+    arr[i]
+}
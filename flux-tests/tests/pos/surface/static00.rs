@@ -0,0 +1,41 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+#![feature(custom_inner_attributes)]
+#![flux::cfg(check_asserts = "check")]
+#![feature(thread_local)]
+
+#[flux::sig([i32{v: v > 0}; 4])]
+static TABLE: [i32; 4] = [1, 2, 3, 4];
+
+static NAMES: [&str; 2] = ["zero", "one"];
+
+#[thread_local]
+#[flux::sig(i32{v: v >= 0})]
+static COUNTER: i32 = 0;
+
+#[flux::sig(fn(usize{v: v < 4}) -> i32{v: v > 0})]
+pub fn lookup(i: usize) -> i32 {
+    TABLE[i]
+}
+
+#[flux::sig(fn(usize{v: v < 2}) -> &str)]
+pub fn name(i: usize) -> &'static str {
+    NAMES[i]
+}
+
+pub fn greeting() -> &'static str {
+    "hello"
+}
+
+#[flux::sig(fn() -> i32{v: v >= 0})]
+pub fn counter() -> i32 {
+    COUNTER
+}
+
+pub fn longest<'a>(x: &'a str, _y: &'static str) -> &'a str {
+    x
+}
+
+pub fn with_static<T: 'static>(x: &'static T) -> &'static T {
+    x
+}
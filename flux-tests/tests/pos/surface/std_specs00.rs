@@ -0,0 +1,85 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> Vec<i32>[2])]
+pub fn two() -> Vec<i32> {
+    let mut v = Vec::new();
+    v.push(1);
+    v.push(2);
+    v
+}
+
+#[flux::sig(fn(&Vec<i32>[@n]) -> usize[n])]
+pub fn length(v: &Vec<i32>) -> usize {
+    v.len()
+}
+
+pub fn first(v: &Vec<i32>) -> i32 {
+    if v.is_empty() {
+        0
+    } else {
+        v[0]
+    }
+}
+
+#[flux::sig(fn(v: &strg Vec<i32>[@n]) -> i32 requires n > 0 ensures v: Vec<i32>[n - 1])]
+pub fn pop(v: &mut Vec<i32>) -> i32 {
+    match v.pop() {
+        Some(x) => x,
+        None => never(0),
+    }
+}
+
+#[flux::sig(fn(&[i32][@n], usize{v: v < n}) -> Option<&i32>[true])]
+pub fn slice_get(s: &[i32], i: usize) -> Option<&i32> {
+    s.get(i)
+}
+
+pub fn slice_halves(s: &[i32]) -> i32 {
+    let (fst, snd) = s.split_at(s.len() / 2);
+    if snd.len() > 0 && fst.len() > 0 {
+        fst[0] + snd[snd.len() - 1]
+    } else {
+        0
+    }
+}
+
+pub fn vec_as_slice(v: &Vec<i32>) -> i32 {
+    let s: &[i32] = v;
+    if v.len() > 1 {
+        s[1]
+    } else {
+        0
+    }
+}
+
+// The spec for indexing a vector only applies to `usize` indices
+pub fn vec_range(v: &Vec<i32>) -> &[i32] {
+    &v[1..3]
+}
+
+#[flux::sig(fn(&Vec<i32>[@n], usize{v: v < n}) -> Option<&i32>[true])]
+pub fn vec_get(v: &Vec<i32>, i: usize) -> Option<&i32> {
+    v.get(i)
+}
+
+#[flux::sig(fn(usize[@a], b: usize{b <= a}) -> usize[a - b])]
+pub fn sub(a: usize, b: usize) -> usize {
+    match a.checked_sub(b) {
+        Some(c) => c,
+        None => never(0),
+    }
+}
+
+#[flux::sig(fn(Result<i32, bool>[true]) -> i32)]
+pub fn ok(res: Result<i32, bool>) -> i32 {
+    match res {
+        Ok(x) => x,
+        Err(_) => never(0),
+    }
+}
+
+#[flux::sig(fn(i32{v: false}) -> T)]
+pub fn never<T>(_: i32) -> T {
+    loop {}
+}
@@ -0,0 +1,44 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn() -> usize[5])]
+pub fn literal_len() -> usize {
+    "hello".len()
+}
+
+#[flux::sig(fn(&str[@n]) -> bool[n == 0])]
+pub fn is_empty(s: &str) -> bool {
+    s.is_empty()
+}
+
+#[flux::sig(fn(s: &str, usize[@i]) -> Option<u8>)]
+pub fn byte_at(s: &str, i: usize) -> Option<u8> {
+    if i < s.len() {
+        Some(s.as_bytes()[i])
+    } else {
+        None
+    }
+}
+
+#[flux::sig(fn(s: &str[@n], start: usize{start <= n}) -> usize{v: start <= v && v <= n})]
+pub fn skip_digits(s: &str, start: usize) -> usize {
+    let bytes = s.as_bytes();
+    let mut i = start;
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    i
+}
+
+pub fn count_spaces(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < s.len() {
+        if bytes[i] == b' ' {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
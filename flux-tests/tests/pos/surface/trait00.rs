@@ -0,0 +1,34 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+pub trait Pos {
+    #[flux::sig(fn(&Self) -> i32{v: v > 0})]
+    fn get(&self) -> i32;
+}
+
+pub struct One;
+
+impl Pos for One {
+    #[flux::sig(fn(&One) -> i32{v: v == 1})]
+    fn get(&self) -> i32 {
+        1
+    }
+}
+
+pub struct Two;
+
+impl Pos for Two {
+    fn get(&self) -> i32 {
+        2
+    }
+}
+
+#[flux::sig(fn(&T) -> i32{v: v > 1})]
+pub fn inc<T: Pos>(x: &T) -> i32 {
+    x.get() + 1
+}
+
+#[flux::sig(fn(&One) -> i32{v: v == 1})]
+pub fn test(x: &One) -> i32 {
+    x.get()
+}
@@ -0,0 +1,23 @@
+#![feature(register_tool)]
+#![register_tool(flux)]
+
+#[flux::sig(fn(i32{v: v > 0}) -> i32{v: v > 0})]
+fn may_panic(x: i32) -> i32 {
+    if x > 100 {
+        panic!()
+    }
+    x
+}
+
+#[flux::sig(fn(x: &mut i32{v: v > 0}, Box<i32{v: v > 0}>))]
+pub fn update(x: &mut i32, b: Box<i32>) {
+    *x = may_panic(*b);
+    let y = Box::new(*x);
+    *x = may_panic(*y);
+}
+
+#[flux::sig(fn(x: &strg i32[@n]) ensures x: i32[n + 1])]
+pub fn incr(x: &mut i32) {
+    let b = Box::new(1);
+    *x += *b;
+}
@@ -13,23 +13,27 @@ use flux_common::{
     config::{AssertBehavior, CONFIG},
     index::IndexVec,
 };
+use flux_errors::ResultExt;
 use flux_middle::{
     global_env::GlobalEnv,
+    intern::List,
     rty::{
-        self, BaseTy, BinOp, Binders, Bool, Const, Constraint, Constraints, Expr, Float, FnSig,
-        Int, IntTy, PolySig, Pred, RefKind, RefineArgs, Sort, Ty, TyKind, Uint, UintTy, VariantIdx,
+        self, fold::TypeFoldable, BaseTy, BinOp, Binders, Bool, BoundVar, Constraint, Constraints,
+        Expr, Float, FloatTy, FnSig, Int, IntTy, PolySig, Pred, Real, RefKind, RefineArg,
+        RefineArgs, Sort, TheoryFunc, Ty, TyKind, Uint, UintTy, VariantIdx,
     },
     rustc::{
         self,
         mir::{
-            self, AggregateKind, BasicBlock, Body, CastKind, Constant, Operand, Place, Rvalue,
-            SourceInfo, Statement, StatementKind, Terminator, TerminatorKind, RETURN_PLACE,
+            self, AggregateKind, BasicBlock, Body, CastKind, Constant, Local, Operand, Place,
+            Rvalue, SourceInfo, Statement, StatementKind, Terminator, TerminatorKind, RETURN_PLACE,
             START_BLOCK,
         },
     },
 };
 use itertools::Itertools;
 use rustc_data_structures::graph::dominators::Dominators;
+use rustc_errors::ErrorGuaranteed;
 use rustc_hash::FxHashMap;
 use rustc_hir::def_id::DefId;
 use rustc_index::bit_set::BitSet;
@@ -49,6 +53,7 @@ pub struct Checker<'a, 'tcx, P> {
     body: &'a Body<'tcx>,
     visited: BitSet<BasicBlock>,
     genv: &'a GlobalEnv<'a, 'tcx>,
+    closures: &'a FxHashMap<DefId, ClosureSig>,
     phase: P,
     ret: Ty,
     ensures: Constraints,
@@ -91,21 +96,59 @@ pub struct Check<'a> {
     kvars: &'a mut KVarStore,
 }
 
+/// The signature of a closure checked together with the function that defines it. The types of
+/// captured upvars are shared between the place where the closure is created and the closure's
+/// body. Unless the closure has a `#[flux::sig]` annotation, the upvars and the output are refined
+/// with kvars to be inferred, with the output's kvars scoped over the inputs. The inputs are left
+/// unrefined because we cannot see every call site when the closure escapes to code outside the
+/// current crate.
+pub struct ClosureSig {
+    upvar_tys: List<Ty>,
+    fn_sig: PolySig,
+}
+
 /// A `Guard` describes extra "control" information that holds at the start
 /// of the successor basic block
 enum Guard {
     /// No extra information holds, e.g., for a plain goto.
     None,
     /// A predicate that can be assumed, e.g., an if-then-else or while-do boolean condition.
-    Pred(Expr),
+    Pred(Pred),
     // The corresponding place was found to be of a particular variant.
     Match(Place, VariantIdx),
+    /// The successor is reached by unwinding from a call. The environment is replaced because the
+    /// effects of the call only apply when it returns normally.
+    Unwind(TypeEnv),
+}
+
+/// The result of checking a call. The updates to locations taken by strong reference only happen
+/// when the call returns normally, so the unwind edge uses the environment before the call.
+struct CallResult {
+    ret: Ty,
+    /// The environment to use when unwinding from the call, i.e., before the destination is
+    /// written and without the updates to locations taken by strong reference.
+    unwind_env: TypeEnv,
+}
+
+impl CallResult {
+    fn successors(
+        self,
+        target: Option<BasicBlock>,
+        cleanup: Option<BasicBlock>,
+    ) -> Vec<(BasicBlock, Guard)> {
+        target
+            .map(|target| (target, Guard::None))
+            .into_iter()
+            .chain(cleanup.map(|cleanup| (cleanup, Guard::Unwind(self.unwind_env))))
+            .collect()
+    }
 }
 
 impl<'a, 'tcx, P> Checker<'a, 'tcx, P> {
     fn new(
         genv: &'a GlobalEnv<'a, 'tcx>,
         body: &'a Body<'tcx>,
+        closures: &'a FxHashMap<DefId, ClosureSig>,
         ret: Ty,
         ensures: Constraints,
         dominators: &'a Dominators<BasicBlock>,
@@ -114,6 +157,7 @@ impl<'a, 'tcx, P> Checker<'a, 'tcx, P> {
         Checker {
             genv,
             body,
+            closures,
             visited: BitSet::new_empty(body.basic_blocks.len()),
             ret,
             ensures,
@@ -130,6 +174,7 @@ impl<'a, 'tcx> Checker<'a, 'tcx, Inference<'_>> {
         genv: &GlobalEnv<'a, 'tcx>,
         body: &Body<'tcx>,
         def_id: DefId,
+        closures: &FxHashMap<DefId, ClosureSig>,
     ) -> Result<FxHashMap<BasicBlock, TypeEnvInfer>, CheckerError> {
         dbg::infer_span!(genv.tcx, def_id).in_scope(|| {
             let mut refine_tree = RefineTree::new();
@@ -139,6 +184,7 @@ impl<'a, 'tcx> Checker<'a, 'tcx, Inference<'_>> {
                 &mut refine_tree,
                 body,
                 def_id,
+                closures,
                 Inference { bb_envs: &mut bb_envs },
             )?;
 
@@ -152,6 +198,7 @@ impl<'a, 'tcx> Checker<'a, 'tcx, Check<'_>> {
         genv: &GlobalEnv<'a, 'tcx>,
         body: &Body<'tcx>,
         def_id: DefId,
+        closures: &FxHashMap<DefId, ClosureSig>,
         kvars: &mut KVarStore,
         bb_envs_infer: FxHashMap<BasicBlock, TypeEnvInfer>,
     ) -> Result<RefineTree, CheckerError> {
@@ -163,7 +210,7 @@ impl<'a, 'tcx> Checker<'a, 'tcx, Check<'_>> {
         dbg::check_span!(genv.tcx, def_id, bb_envs).in_scope(|| {
             let mut refine_tree = RefineTree::new();
 
-            Checker::run(genv, &mut refine_tree, body, def_id, Check { bb_envs, kvars })?;
+            Checker::run(genv, &mut refine_tree, body, def_id, closures, Check { bb_envs, kvars })?;
 
             Ok(refine_tree)
         })
@@ -176,21 +223,29 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         refine_tree: &mut RefineTree,
         body: &'a Body<'tcx>,
         def_id: DefId,
+        closures: &'a FxHashMap<DefId, ClosureSig>,
         phase: P,
     ) -> Result<(), CheckerError> {
         let mut rcx = refine_tree.refine_ctxt_at_root();
 
-        let fn_sig = genv
-            .lookup_fn_sig(def_id)
-            .unwrap_or_else(|_| panic!("checking function with unsupported signature"))
-            .replace_bvars_with_fresh_fvars(|sort| rcx.define_var(sort));
+        let (fn_sig, closure_env) = if let Some(closure_sig) = closures.get(&def_id) {
+            let closure_env = closure_sig.env_ty(def_id, body);
+            (closure_sig.fn_sig.clone(), Some(closure_env))
+        } else {
+            let fn_sig = genv
+                .lookup_fn_sig(def_id)
+                .unwrap_or_else(|_| panic!("checking function with unsupported signature"));
+            (fn_sig, None)
+        };
+        let fn_sig = fn_sig.replace_bvars_with_fresh_fvars(|sort| rcx.define_var(sort));
 
-        let env = Self::init(&mut rcx, body, &fn_sig);
+        let env = Self::init(&mut rcx, body, &fn_sig, closure_env);
 
         let dominators = body.dominators();
         let mut ck = Checker::new(
             genv,
             body,
+            closures,
             fn_sig.ret().clone(),
             fn_sig.ensures().clone(),
             &dominators,
@@ -215,7 +270,7 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         Ok(())
     }
 
-    fn init(rcx: &mut RefineCtxt, body: &Body, fn_sig: &FnSig) -> TypeEnv {
+    fn init(rcx: &mut RefineCtxt, body: &Body, fn_sig: &FnSig, closure_env: Option<Ty>) -> TypeEnv {
         let mut env = TypeEnv::new();
 
         for constr in fn_sig.requires() {
@@ -231,7 +286,14 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             }
         }
 
-        for (local, ty) in body.args_iter().zip(fn_sig.args()) {
+        // The first argument of a closure's body is the closure itself, which does not appear in
+        // the closure's signature.
+        let mut args = body.args_iter();
+        if let Some(closure_env) = closure_env {
+            env.alloc_with_ty(args.next().unwrap(), closure_env);
+        }
+
+        for (local, ty) in args.zip(fn_sig.args()) {
             let ty = rcx.unpack_with(ty, UnpackFlags::INVARIANTS);
             env.alloc_with_ty(local, ty);
         }
@@ -306,7 +368,7 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                         .phase
                         .constr_gen(self.genv, rcx, Tag::Assign(stmt.source_info.span));
                 env.write_place(rcx, gen, place, ty)
-                    .map_err(|err| CheckerError::from(err).with_src_info(stmt.source_info))?;
+                    .map_err(|err| err.with_src_info(stmt.source_info))?;
             }
             StatementKind::SetDiscriminant { .. } => {
                 // TODO(nilehmann) double check here that the place is unfolded to
@@ -338,6 +400,10 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
     /// - `BasicBlock` "successors" of the current terminator, and
     /// - `Guard` are extra control information from, e.g. the `SwitchInt` (or `Assert`)
     ///    you can assume when checking the correspondnig successor.
+    ///
+    /// The successors include the `cleanup` (or `unwind`) targets of terminators that may panic,
+    /// which are checked with no extra control information. The `unwind` edge of a `FalseUnwind`
+    /// is not a real edge and it is not included.
     fn check_terminator(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -351,66 +417,121 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             TerminatorKind::Goto { target } => Ok(vec![(*target, Guard::None)]),
             TerminatorKind::SwitchInt { discr, targets } => {
                 let discr_ty = self.check_operand(rcx, env, terminator.source_info, discr)?;
-                if discr_ty.is_integral() || discr_ty.is_bool() {
+                if discr_ty.is_integral() || discr_ty.is_bool() || discr_ty.is_char() {
                     Ok(Self::check_if(&discr_ty, targets))
                 } else {
                     Ok(Self::check_match(&discr_ty, targets))
                 }
             }
-            TerminatorKind::Call { func, substs, args, destination, target, instance, .. } => {
+            TerminatorKind::Call {
+                func,
+                substs: call_substs,
+                args,
+                destination,
+                target,
+                cleanup,
+                instance,
+                ..
+            } => {
                 let (func_id, substs) = match instance {
                     Some(inst) => (inst.impl_f, &inst.substs),
-                    None => (*func, &substs.lowered),
+                    None => (*func, &call_substs.lowered),
+                };
+                let call = if self.genv.tcx.is_closure(func_id) {
+                    let fn_sig = match self.closures.get(&func_id) {
+                        Some(closure_sig) => closure_sig.fn_sig.clone(),
+                        None => {
+                            self.genv.lookup_fn_sig(func_id).map_err(|err| {
+                                CheckerError::from(err).with_src_info(terminator.source_info)
+                            })?
+                        }
+                    };
+                    self.check_closure_call(rcx, env, terminator.source_info, fn_sig, substs, args)?
+                } else {
+                    let fn_sig =
+                        self.genv
+                            .lookup_fn_sig_for_call(func_id, substs)
+                            .map_err(|err| {
+                                CheckerError::from(err).with_src_info(terminator.source_info)
+                            })?;
+                    // Projections in the signature, e.g., `Self::Item` when calling
+                    // `Iterator::next`, are normalized with the generic arguments of the call.
+                    let rust_sig = if fn_sig.has_projections() {
+                        let rust_sig = rustc::lowering::lower_call_fn_sig(
+                            self.genv.tcx,
+                            self.body.def_id(),
+                            *func,
+                            call_substs.orig,
+                        )
+                        .map_err(|err| {
+                            CheckerError::from(err).with_src_info(terminator.source_info)
+                        })?;
+                        Some(rust_sig)
+                    } else {
+                        None
+                    };
+                    self.check_call(
+                        rcx,
+                        env,
+                        terminator.source_info,
+                        fn_sig,
+                        rust_sig.as_ref(),
+                        substs,
+                        args,
+                    )?
                 };
-                let fn_sig = self
-                    .genv
-                    .lookup_fn_sig(func_id)
-                    .map_err(|err| CheckerError::from(err).with_src_info(terminator.source_info))?;
 
-                let ret =
-                    self.check_call(rcx, env, terminator.source_info, fn_sig, substs, args)?;
+                let ret = rcx.unpack(&call.ret);
+                let mut gen =
+                    self.phase
+                        .constr_gen(self.genv, rcx, Tag::Call(terminator.source_info.span));
+                env.write_place(rcx, &mut gen, destination, ret)
+                    .map_err(|err| err.with_src_info_opt(src_info))?;
+
+                Ok(call.successors(*target, *cleanup))
+            }
+            TerminatorKind::CallFnPtr { func, args, destination, target, cleanup } => {
+                let call = self.check_fn_ptr_call(rcx, env, terminator.source_info, func, args)?;
 
-                let ret = rcx.unpack(&ret);
+                let ret = rcx.unpack(&call.ret);
                 let mut gen =
                     self.phase
                         .constr_gen(self.genv, rcx, Tag::Call(terminator.source_info.span));
                 env.write_place(rcx, &mut gen, destination, ret)
-                    .map_err(|err| CheckerError::from(err).with_src_info_opt(src_info))?;
+                    .map_err(|err| err.with_src_info_opt(src_info))?;
 
-                if let Some(target) = target {
-                    Ok(vec![(*target, Guard::None)])
-                } else {
-                    Ok(vec![])
-                }
+                Ok(call.successors(*target, *cleanup))
             }
-            TerminatorKind::Assert { cond, expected, target, msg } => {
-                Ok(vec![(
-                    *target,
-                    self.check_assert(rcx, env, terminator.source_info, cond, *expected, msg)?,
-                )])
+            TerminatorKind::Assert { cond, expected, target, msg, cleanup } => {
+                let guard =
+                    self.check_assert(rcx, env, terminator.source_info, cond, *expected, msg)?;
+                let mut successors = vec![(*target, guard)];
+                successors.extend(cleanup.map(|cleanup| (cleanup, Guard::None)));
+                Ok(successors)
             }
-            TerminatorKind::Drop { place, target, .. } => {
+            TerminatorKind::Drop { place, target, unwind } => {
                 let mut gen =
                     self.phase
                         .constr_gen(self.genv, rcx, Tag::Fold(terminator.source_info.span));
                 let _ = env.move_place(rcx, &mut gen, place);
-                Ok(vec![(*target, Guard::None)])
+                Ok(successors(Some(*target), *unwind))
             }
-            TerminatorKind::DropAndReplace { place, value, target, .. } => {
+            TerminatorKind::DropAndReplace { place, value, target, unwind } => {
                 let ty = self.check_operand(rcx, env, terminator.source_info, value)?;
                 let ty = rcx.unpack(&ty);
                 let mut gen =
                     self.phase
                         .constr_gen(self.genv, rcx, Tag::Assign(terminator.source_info.span));
                 env.write_place(rcx, &mut gen, place, ty)
-                    .map_err(|err| CheckerError::from(err).with_src_info_opt(src_info))?;
-                Ok(vec![(*target, Guard::None)])
+                    .map_err(|err| err.with_src_info_opt(src_info))?;
+                Ok(successors(Some(*target), *unwind))
             }
             TerminatorKind::FalseEdge { real_target, .. } => Ok(vec![(*real_target, Guard::None)]),
             TerminatorKind::FalseUnwind { real_target, .. } => {
                 Ok(vec![(*real_target, Guard::None)])
             }
-            TerminatorKind::Resume => todo!("implement checking of cleanup code"),
+            // Unwinding does not return to the caller so there are no postconditions to check.
+            TerminatorKind::Resume => Ok(vec![]),
         }
     }
 
@@ -427,55 +548,138 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         let gen = &mut self.phase.constr_gen(self.genv, rcx, tag);
         let ret_place_ty = env
             .lookup_place(rcx, gen, Place::RETURN)
-            .map_err(|err| CheckerError::from(err).with_src_info_opt(src_info))?;
+            .map_err(|err| err.with_src_info_opt(src_info))?;
 
         gen.subtyping(rcx, &ret_place_ty, &self.ret);
 
         for constraint in &self.ensures {
             gen.check_constraint(rcx, env, constraint)
-                .map_err(|err| CheckerError::from(err).with_src_info_opt(src_info))?;
+                .map_err(|err| err.with_src_info_opt(src_info))?;
         }
         Ok(vec![])
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn check_call(
         &mut self,
         rcx: &mut RefineCtxt,
         env: &mut TypeEnv,
         src_info: SourceInfo,
         fn_sig: PolySig,
+        rust_sig: Option<&rustc::ty::FnSig>,
         substs: &[rustc::ty::GenericArg],
         args: &[Operand],
-    ) -> Result<Ty, CheckerError> {
+    ) -> Result<CallResult, CheckerError> {
+        let actuals: Vec<Ty> = args
+            .iter()
+            .map(|op| self.check_operand(rcx, env, src_info, op))
+            .try_collect()?;
+
+        let substs = self.refine_substs(substs);
+        self.check_call_with_actuals(rcx, env, src_info, fn_sig, rust_sig, &substs, &actuals)
+    }
+
+    /// Checks a call through a function pointer against the signature stored in its type. The
+    /// signature may mention generic parameters of the function being checked, so we instantiate
+    /// it with the identity.
+    fn check_fn_ptr_call(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        src_info: SourceInfo,
+        func: &Operand,
+        args: &[Operand],
+    ) -> Result<CallResult, CheckerError> {
+        let func_ty = self.check_operand(rcx, env, src_info, func)?;
+        let TyKind::Indexed(BaseTy::FnPtr(fn_sig), _) = func_ty.kind() else {
+            return Err(CheckerError::unsupported(format!("call through `{func_ty:?}`"))
+                .with_src_info(src_info));
+        };
         let actuals: Vec<Ty> = args
             .iter()
             .map(|op| self.check_operand(rcx, env, src_info, op))
             .try_collect()?;
 
-        let substs = substs
+        let root_id = self.genv.tcx.typeck_root_def_id(self.body.def_id());
+        let substs = self
+            .genv
+            .identity_substs(root_id)
+            .map_err(|err| CheckerError::from(err).with_src_info(src_info))?;
+        self.check_call_with_actuals(rcx, env, src_info, fn_sig.clone(), None, &substs, &actuals)
+    }
+
+    /// Checks a call to a closure through one of the `Fn*` traits. The first argument is the
+    /// closure itself and the second one is a tuple with the actual arguments, which are spread
+    /// before checking them against the closure's signature.
+    fn check_closure_call(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        src_info: SourceInfo,
+        fn_sig: PolySig,
+        substs: &[rustc::ty::GenericArg],
+        args: &[Operand],
+    ) -> Result<CallResult, CheckerError> {
+        let [closure, tupled_args] = args else {
+            unreachable!("unexpected arguments in closure call: `{args:?}`")
+        };
+        self.check_operand(rcx, env, src_info, closure)?;
+        let tupled_ty = self.check_operand(rcx, env, src_info, tupled_args)?;
+        let TyKind::Tuple(tys) = tupled_ty.kind() else {
+            let descr = format!("closure arguments of type `{tupled_ty:?}`");
+            return Err(CheckerError::unsupported(descr).with_src_info(src_info));
+        };
+        let actuals = tys.to_vec();
+
+        let substs = self.refine_substs(substs);
+        self.check_call_with_actuals(rcx, env, src_info, fn_sig, None, &substs, &actuals)
+    }
+
+    /// Refines the generic arguments of a call with holes so they are later replaced by fresh kvars.
+    fn refine_substs(&self, substs: &[rustc::ty::GenericArg]) -> Vec<rty::GenericArg> {
+        substs
             .iter()
             .map(|arg| {
                 self.genv
                     .refine_generic_arg(arg, &mut |sorts| Binders::new(Pred::Hole, sorts))
             })
-            .collect_vec();
+            .collect()
+    }
 
+    #[allow(clippy::too_many_arguments)]
+    fn check_call_with_actuals(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        src_info: SourceInfo,
+        fn_sig: PolySig,
+        rust_sig: Option<&rustc::ty::FnSig>,
+        substs: &[rty::GenericArg],
+        actuals: &[Ty],
+    ) -> Result<CallResult, CheckerError> {
         let output = self
             .phase
             .constr_gen(self.genv, rcx, Tag::Call(src_info.span))
-            .check_fn_call(rcx, env, &fn_sig, &substs, &actuals)
+            .check_fn_call(rcx, env, &fn_sig, rust_sig, substs, actuals)
             .map_err(|err| err.with_src_info(src_info))?;
 
+        // If the callee panics, the locations it takes by strong reference may have been partially
+        // updated, so we forget everything we know about them when unwinding.
+        let mut unwind_env = env.clone();
         for constr in &output.ensures {
             match constr {
                 Constraint::Type(path, updated_ty) => {
+                    let havoc = updated_ty
+                        .with_holes()
+                        .replace_holes(&mut |sorts| Binders::new(Pred::tt(), sorts));
+                    unwind_env.update_path(path, rcx.unpack(&havoc));
                     let updated_ty = rcx.unpack(updated_ty);
                     env.update_path(path, updated_ty);
                 }
                 Constraint::Pred(e) => rcx.assume_pred(e.clone()),
             }
         }
-        Ok(output.ret)
+        Ok(CallResult { ret: output.ret, unwind_env })
     }
 
     fn check_assert(
@@ -500,13 +704,13 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
 
         match self.genv.check_asserts() {
             AssertBehavior::Ignore => Ok(Guard::None),
-            AssertBehavior::Assume => Ok(Guard::Pred(pred)),
+            AssertBehavior::Assume => Ok(Guard::Pred(pred.into())),
             AssertBehavior::Check => {
                 self.phase
                     .constr_gen(self.genv, rcx, Tag::Assert(msg, source_info.span))
                     .check_pred(rcx, pred.clone());
 
-                Ok(Guard::Pred(pred))
+                Ok(Guard::Pred(pred.into()))
             }
         }
     }
@@ -521,7 +725,7 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                         idxs.nth(0).as_expr().clone()
                     }
                 }
-                TyKind::Indexed(bty @ (BaseTy::Int(_) | BaseTy::Uint(_)), idxs) => {
+                TyKind::Indexed(bty @ (BaseTy::Int(_) | BaseTy::Uint(_) | BaseTy::Char), idxs) => {
                     Expr::binary_op(
                         BinOp::Eq,
                         idxs.nth(0).as_expr().clone(),
@@ -535,10 +739,10 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         let mut successors = vec![];
 
         for (bits, bb) in targets.iter() {
-            successors.push((bb, Guard::Pred(mk(bits))));
+            successors.push((bb, Guard::Pred(mk(bits).into())));
         }
         let otherwise = Expr::and(targets.iter().map(|(bits, _)| mk(bits).not()));
-        successors.push((targets.otherwise(), Guard::Pred(otherwise)));
+        successors.push((targets.otherwise(), Guard::Pred(otherwise.into())));
 
         successors
     }
@@ -573,8 +777,9 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                 }
                 Guard::Match(place, variant_idx) => {
                     env.downcast(self.genv, &mut rcx, &place, variant_idx)
-                        .map_err(|err| CheckerError::from(err).with_src_info(src_info))?;
+                        .map_err(|err| err.with_src_info(src_info))?;
                 }
+                Guard::Unwind(unwind_env) => env = unwind_env,
             }
             self.check_goto(rcx, env, Some(src_info), target)?;
         }
@@ -613,15 +818,18 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             Rvalue::BinaryOp(bin_op, op1, op2) => {
                 self.check_binary_op(rcx, env, src_info, *bin_op, op1, op2)
             }
+            Rvalue::CheckedBinaryOp(bin_op, op1, op2) => {
+                self.check_checked_binary_op(rcx, env, src_info, *bin_op, op1, op2)
+            }
             Rvalue::MutRef(place) => {
                 let gen = &mut self.phase.constr_gen(self.genv, rcx, Tag::Other);
                 env.borrow(rcx, gen, RefKind::Mut, place)
-                    .map_err(|err| CheckerError::from(err).with_src_info(src_info))
+                    .map_err(|err| err.with_src_info(src_info))
             }
             Rvalue::ShrRef(place) => {
                 let gen = &mut self.phase.constr_gen(self.genv, rcx, Tag::Other);
                 env.borrow(rcx, gen, RefKind::Shr, place)
-                    .map_err(|err| CheckerError::from(err).with_src_info(src_info))
+                    .map_err(|err| err.with_src_info(src_info))
             }
             Rvalue::UnaryOp(un_op, op) => self.check_unary_op(rcx, env, src_info, *un_op, op),
             Rvalue::Aggregate(AggregateKind::Adt(def_id, variant_idx, substs), args) => {
@@ -629,7 +837,9 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                     .genv
                     .variant_sig(*def_id, *variant_idx)
                     .map_err(|err| CheckerError::from(err).with_src_info(src_info))?;
-                self.check_call(rcx, env, src_info, sig, substs, args)
+                Ok(self
+                    .check_call(rcx, env, src_info, sig, None, substs, args)?
+                    .ret)
             }
             Rvalue::Aggregate(AggregateKind::Array(ty), args) => {
                 let args: Vec<Ty> = args
@@ -640,10 +850,26 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                     .genv
                     .refine_ty(ty, &mut |sorts| self.phase.fresh_kvar(sorts));
                 let mut gen = self.phase.constr_gen(self.genv, rcx, Tag::Other);
+                let len = args.len();
                 for arg in args {
                     gen.subtyping(rcx, &arg, &ty);
                 }
-                Ok(Ty::array(ty, Const))
+                Ok(Ty::array(ty, len))
+            }
+            Rvalue::Repeat(op, ty, len) => {
+                let arg = self.check_operand(rcx, env, src_info, op)?;
+                let ty = self
+                    .genv
+                    .refine_ty(ty, &mut |sorts| self.phase.fresh_kvar(sorts));
+                let mut gen = self.phase.constr_gen(self.genv, rcx, Tag::Other);
+                gen.subtyping(rcx, &arg, &ty);
+                Ok(Ty::array(ty, Expr::from_const(len)))
+            }
+            Rvalue::Aggregate(AggregateKind::Closure(def_id, substs), args) => {
+                let sig = self.closures[def_id].constructor_sig(*def_id);
+                Ok(self
+                    .check_call(rcx, env, src_info, sig, None, substs, args)?
+                    .ret)
             }
             Rvalue::Aggregate(AggregateKind::Tuple, args) => {
                 let tys: Vec<Ty> = args
@@ -653,10 +879,33 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                 Ok(Ty::tuple(tys))
             }
             Rvalue::Discriminant(place) => Ok(Ty::discr(place.clone())),
-            Rvalue::Len(_) => Ok(Ty::usize()),
+            Rvalue::Len(place) => {
+                let gen = &mut self.phase.constr_gen(self.genv, rcx, Tag::Other);
+                let ty = env
+                    .lookup_place(rcx, gen, place)
+                    .map_err(|err| err.with_src_info(src_info))?;
+                match rcx.unpack(&ty).kind() {
+                    TyKind::Indexed(BaseTy::Array(_) | BaseTy::Slice(_), idxs) => {
+                        let len = idxs.nth(0).as_expr().clone();
+                        Ok(Ty::indexed(BaseTy::Uint(UintTy::Usize), RefineArgs::one(len)))
+                    }
+                    _ => {
+                        Err(CheckerError::unsupported(format!("length of `{ty:?}`"))
+                            .with_src_info(src_info))
+                    }
+                }
+            }
             Rvalue::Cast(kind, op, to) => {
                 let from = self.check_operand(rcx, env, src_info, op)?;
-                Ok(self.check_cast(*kind, &from, to))
+                // Unsizing a pointer to an array needs the type of the array to know its length.
+                let from = match from.kind() {
+                    TyKind::Ptr(rk, path) if matches!(kind, CastKind::Unsize) => {
+                        env.ptr_to_borrow(*rk, path)
+                    }
+                    _ => from,
+                };
+                self.check_cast(*kind, &from, to)
+                    .map_err(|err| err.with_src_info(src_info))
             }
         }
     }
@@ -684,8 +933,46 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             | mir::BinOp::Sub
             | mir::BinOp::Mul
             | mir::BinOp::Div
+            | mir::BinOp::Rem
             | mir::BinOp::BitAnd
-            | mir::BinOp::Rem => Ok(self.check_arith_op(rcx, source_info, bin_op, &ty1, &ty2)),
+            | mir::BinOp::BitOr
+            | mir::BinOp::BitXor
+            | mir::BinOp::Shl
+            | mir::BinOp::Shr => {
+                // When checking for overflow, the precondition of unsigned subtraction is subsumed
+                // by the overflow check.
+                let overflow_checked = self.check_no_overflow(rcx, source_info, bin_op, &ty1, &ty2);
+                Ok(self.check_arith_op(rcx, source_info, bin_op, &ty1, &ty2, !overflow_checked))
+            }
+        }
+    }
+
+    /// Checks that a plain arithmetic operation does not overflow if `check_overflow` is set.
+    /// Returns whether an obligation was emitted.
+    fn check_no_overflow(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        source_info: SourceInfo,
+        op: mir::BinOp,
+        ty1: &Ty,
+        ty2: &Ty,
+    ) -> bool {
+        if !self.genv.check_overflow() {
+            return false;
+        }
+        let pred = match (ty1.kind(), ty2.kind()) {
+            (TyKind::Indexed(bty, idxs1), TyKind::Indexed(_, idxs2)) => {
+                no_overflow_pred(op, bty, idxs1.nth(0).as_expr(), idxs2.nth(0).as_expr())
+            }
+            _ => None,
+        };
+        if let Some(pred) = pred {
+            self.phase
+                .constr_gen(self.genv, rcx, Tag::Overflow(source_info.span))
+                .check_pred(rcx, pred);
+            true
+        } else {
+            false
         }
     }
 
@@ -696,15 +983,28 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         op: mir::BinOp,
         ty1: &Ty,
         ty2: &Ty,
+        check_pre: bool,
     ) -> Ty {
+        let is_shift = matches!(op, mir::BinOp::Shl | mir::BinOp::Shr);
         let (bty, idx1, idx2, sig) = match (ty1.kind(), ty2.kind()) {
+            // The shift amount can be of any integer type
+            (Int!(int_ty1, idxs1), Int!(_, idxs2) | Uint!(_, idxs2)) if is_shift => {
+                let sig = sigs::signed_bin_ops(op, *int_ty1);
+                (BaseTy::Int(*int_ty1), idxs1.nth(0), idxs2.nth(0), sig)
+            }
+            (Uint!(uint_ty1, idxs1), Int!(_, idxs2) | Uint!(_, idxs2)) if is_shift => {
+                let sig = sigs::unsigned_bin_ops(op, *uint_ty1);
+                (BaseTy::Uint(*uint_ty1), idxs1.nth(0), idxs2.nth(0), sig)
+            }
             (Int!(int_ty1, idxs1), Int!(int_ty2, idxs2)) => {
                 debug_assert_eq!(int_ty1, int_ty2);
-                (BaseTy::Int(*int_ty1), idxs1.nth(0), idxs2.nth(0), sigs::signed_bin_ops(op))
+                let sig = sigs::signed_bin_ops(op, *int_ty1);
+                (BaseTy::Int(*int_ty1), idxs1.nth(0), idxs2.nth(0), sig)
             }
             (Uint!(uint_ty1, idxs1), Uint!(uint_ty2, idxs2)) => {
                 debug_assert_eq!(uint_ty1, uint_ty2);
-                (BaseTy::Uint(*uint_ty1), idxs1.nth(0), idxs2.nth(0), sigs::unsigned_bin_ops(op))
+                let sig = sigs::unsigned_bin_ops(op, *uint_ty1);
+                (BaseTy::Uint(*uint_ty1), idxs1.nth(0), idxs2.nth(0), sig)
             }
             (Bool!(idxs1), Bool!(idxs2)) => {
                 (BaseTy::Bool, idxs1.nth(0), idxs2.nth(0), sigs::bool_bin_ops(op))
@@ -713,10 +1013,15 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                 debug_assert_eq!(float_ty1, float_ty2);
                 return Ty::float(*float_ty1);
             }
+            (Real!(float_ty1, idxs1), Real!(float_ty2, idxs2)) => {
+                debug_assert_eq!(float_ty1, float_ty2);
+                let sig = sigs::float_bin_ops(op);
+                (BaseTy::Real(*float_ty1), idxs1.nth(0), idxs2.nth(0), sig)
+            }
             _ => unreachable!("incompatible types: `{:?}` `{:?}`", ty1, ty2),
         };
         let (e1, e2) = (idx1.as_expr().clone(), idx2.as_expr().clone());
-        if let sigs::Pre::Some(tag, constr) = sig.pre {
+        if let (true, sigs::Pre::Some(tag, constr)) = (check_pre, sig.pre) {
             self.phase
                 .constr_gen(self.genv, rcx, tag(source_info.span))
                 .check_pred(rcx, constr([e1.clone(), e2.clone()]));
@@ -726,11 +1031,40 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             sigs::Output::Indexed(mk) => Ty::indexed(bty, RefineArgs::one(mk([e1, e2]))),
             sigs::Output::Exists(mk) => {
                 let pred = Pred::Expr(mk(Expr::nu(), [e1, e2]));
-                Ty::exists(bty, Binders::new(pred, vec![Sort::Int]))
+                let sorts = bty.sorts().to_vec();
+                Ty::exists(bty, Binders::new(pred, sorts))
             }
         }
     }
 
+    /// A checked operation produces a tuple with the result of the operation and a flag indicating
+    /// whether the operation overflowed. The flag is used by a compiler-inserted assert, which is
+    /// the only overflow obligation for the operation. The assert also subsumes the precondition
+    /// of unsigned subtraction, so we skip preconditions here.
+    fn check_checked_binary_op(
+        &mut self,
+        rcx: &mut RefineCtxt,
+        env: &mut TypeEnv,
+        source_info: SourceInfo,
+        bin_op: mir::BinOp,
+        op1: &Operand,
+        op2: &Operand,
+    ) -> Result<Ty, CheckerError> {
+        let ty1 = self.check_operand(rcx, env, source_info, op1)?;
+        let ty2 = self.check_operand(rcx, env, source_info, op2)?;
+
+        let overflow = match (ty1.kind(), ty2.kind()) {
+            (TyKind::Indexed(bty, idxs1), TyKind::Indexed(_, idxs2)) => {
+                no_overflow_pred(bin_op, bty, idxs1.nth(0).as_expr(), idxs2.nth(0).as_expr())
+                    .map(|pred| pred.not())
+            }
+            _ => None,
+        };
+        let ty = self.check_arith_op(rcx, source_info, bin_op, &ty1, &ty2, false);
+        let flag = Ty::indexed(BaseTy::Bool, RefineArgs::one(overflow.unwrap_or_else(Expr::ff)));
+        Ok(Ty::tuple(vec![ty, flag]))
+    }
+
     fn check_cmp_op(
         &mut self,
         rcx: &mut RefineCtxt,
@@ -742,13 +1076,17 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         let (idx1, idx2, sig) = match (ty1.kind(), ty2.kind()) {
             (Int!(int_ty1, idxs1), Int!(int_ty2, idxs2)) => {
                 debug_assert_eq!(int_ty1, int_ty2);
-                (idxs1.nth(0), idxs2.nth(0), sigs::signed_bin_ops(op))
+                (idxs1.nth(0), idxs2.nth(0), sigs::signed_bin_ops(op, *int_ty1))
             }
             (Uint!(uint_ty1, idxs1), Uint!(uint_ty2, idxs2)) => {
                 debug_assert_eq!(uint_ty1, uint_ty2);
-                (idxs1.nth(0), idxs2.nth(0), sigs::unsigned_bin_ops(op))
+                (idxs1.nth(0), idxs2.nth(0), sigs::unsigned_bin_ops(op, *uint_ty1))
             }
             (Bool!(idxs1), Bool!(idxs2)) => (idxs1.nth(0), idxs2.nth(0), sigs::bool_bin_ops(op)),
+            (Real!(float_ty1, idxs1), Real!(float_ty2, idxs2)) => {
+                debug_assert_eq!(float_ty1, float_ty2);
+                (idxs1.nth(0), idxs2.nth(0), sigs::float_bin_ops(op))
+            }
             _ => return Ty::bool(),
         };
         let (e1, e2) = (idx1.as_expr().clone(), idx2.as_expr().clone());
@@ -788,8 +1126,22 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
             mir::UnOp::Neg => {
                 match ty.kind() {
                     Int!(int_ty, idxs) => {
+                        // With the compiler's overflow checks, negation is preceded by an assert
+                        // that the operand is not the minimum value, so we only check plain ones.
+                        let bty = BaseTy::Int(*int_ty);
+                        if self.genv.check_overflow()
+                            && !self.genv.tcx.sess.overflow_checks()
+                            && let Some(pred) = no_neg_overflow_pred(&bty, idxs.nth(0).as_expr())
+                        {
+                            self.phase
+                                .constr_gen(self.genv, rcx, Tag::Overflow(src_info.span))
+                                .check_pred(rcx, pred);
+                        }
+                        Ty::indexed(bty, RefineArgs::one(idxs.nth(0).as_expr().neg()))
+                    }
+                    Real!(float_ty, idxs) => {
                         Ty::indexed(
-                            BaseTy::Int(*int_ty),
+                            BaseTy::Real(*float_ty),
                             RefineArgs::one(idxs.nth(0).as_expr().neg()),
                         )
                     }
@@ -801,9 +1153,14 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
         Ok(ty)
     }
 
-    fn check_cast(&self, kind: CastKind, from: &Ty, to: &rustc::ty::Ty) -> Ty {
+    fn check_cast(
+        &self,
+        kind: CastKind,
+        from: &Ty,
+        to: &rustc::ty::Ty,
+    ) -> Result<Ty, CheckerError> {
         use rustc::ty::TyKind as RustTy;
-        match kind {
+        let ty = match kind {
             CastKind::IntToInt => {
                 match (from.kind(), to.kind()) {
                     (Bool!(idxs), RustTy::Int(int_ty)) => {
@@ -822,16 +1179,82 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                         uint_int_cast(idxs.nth(0).as_expr(), *uint_ty, *int_ty)
                     }
                     (Int!(_, _), RustTy::Uint(uint_ty)) => Ty::uint(*uint_ty),
+                    (TyKind::Indexed(BaseTy::Char, idxs), RustTy::Int(int_ty)) => {
+                        char_int_cast(idxs.nth(0).as_expr(), *int_ty)
+                    }
+                    (TyKind::Indexed(BaseTy::Char, idxs), RustTy::Uint(uint_ty)) => {
+                        char_uint_cast(idxs.nth(0).as_expr(), *uint_ty)
+                    }
+                    // Only `u8` can be cast to `char`
+                    (Uint!(_, idxs), RustTy::Char) => {
+                        Ty::indexed(BaseTy::Char, RefineArgs::one(idxs.nth(0).as_expr()))
+                    }
                     _ => {
                         panic!("invalid int to int cast")
                     }
                 }
             }
+            CastKind::IntToFloat if self.genv.real_floats() => {
+                match (from.kind(), to.kind()) {
+                    (Int!(_, idxs) | Uint!(_, idxs), RustTy::Float(float_ty)) => {
+                        int_float_cast(idxs.nth(0).as_expr(), *float_ty)
+                    }
+                    _ => return Err(unsupported_cast(from, to)),
+                }
+            }
+            CastKind::FloatToInt if self.genv.real_floats() => {
+                match (from.kind(), to.kind()) {
+                    (Real!(_, idxs), RustTy::Int(int_ty)) => {
+                        float_int_cast(idxs.nth(0).as_expr(), BaseTy::Int(*int_ty))
+                    }
+                    (Real!(_, idxs), RustTy::Uint(uint_ty)) => {
+                        float_int_cast(idxs.nth(0).as_expr(), BaseTy::Uint(*uint_ty))
+                    }
+                    _ => return Err(unsupported_cast(from, to)),
+                }
+            }
             CastKind::FloatToInt | CastKind::IntToFloat => {
                 self.genv
                     .refine_ty(to, &mut |sorts| Binders::new(Pred::tt(), sorts))
             }
-        }
+            CastKind::ReifyFnPointer => {
+                let TyKind::Indexed(BaseTy::FnDef(def_id, substs), _) = from.kind() else {
+                    unreachable!("invalid reify cast from `{from:?}`")
+                };
+                let fn_sig = self.genv.lookup_fn_sig(*def_id)?;
+                // Function pointers cannot carry the locations in a signature with strong
+                // references, so we reject them instead of silently dropping the constraints.
+                let fn_sig_ref = fn_sig.as_ref().skip_binders();
+                if fn_sig_ref
+                    .requires()
+                    .iter()
+                    .chain(fn_sig_ref.ensures())
+                    .any(|constr| matches!(constr, Constraint::Type(..)))
+                {
+                    return Err(CheckerError::unsupported_fn_ptr(self.genv.tcx.def_span(*def_id)));
+                }
+                Ty::fn_ptr(fn_sig.replace_generic_args(substs))
+            }
+            CastKind::Unsize => {
+                match (from.kind(), to.kind()) {
+                    (TyKind::Ref(rk, ty), RustTy::Ref(_, _)) if let Some(BaseTy::Array(elem)) = ty.bty() => {
+                        // The slice has the same length as the array.
+                        let slice = BaseTy::Slice(elem.clone());
+                        let ty = match ty.kind() {
+                            TyKind::Indexed(_, idxs) => Ty::indexed(slice, idxs.clone()),
+                            TyKind::Exists(_, pred) => Ty::exists(slice, pred.clone()),
+                            _ => unreachable!(),
+                        };
+                        Ty::mk_ref(*rk, ty)
+                    }
+                    _ => {
+                        self.genv
+                            .refine_ty(to, &mut |sorts| Binders::new(Pred::tt(), sorts))
+                    }
+                }
+            }
+        };
+        Ok(ty)
     }
 
     fn check_operand(
@@ -848,7 +1271,7 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                     .phase
                     .constr_gen(self.genv, rcx, Tag::Fold(src_info.span));
                 env.lookup_place(rcx, gen, p)
-                    .map_err(|err| CheckerError::from(err).with_src_info(src_info))?
+                    .map_err(|err| err.with_src_info(src_info))?
             }
             Operand::Move(p) => {
                 // OWNERSHIP SAFETY CHECK
@@ -856,14 +1279,14 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                     .phase
                     .constr_gen(self.genv, rcx, Tag::Fold(src_info.span));
                 env.move_place(rcx, gen, p)
-                    .map_err(|err| CheckerError::from(err).with_src_info(src_info))?
+                    .map_err(|err| err.with_src_info(src_info))?
             }
-            Operand::Constant(c) => Self::check_constant(c),
+            Operand::Constant(c) => self.check_constant(c),
         };
         Ok(rcx.unpack(&ty))
     }
 
-    fn check_constant(c: &Constant) -> Ty {
+    fn check_constant(&self, c: &Constant) -> Ty {
         match c {
             Constant::Int(n, int_ty) => {
                 let idx = Expr::constant(rty::Constant::from(*n));
@@ -877,10 +1300,54 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
                 let idx = Expr::constant(rty::Constant::from(*b));
                 Ty::indexed(BaseTy::Bool, RefineArgs::one(idx))
             }
+            Constant::Float(bits, float_ty) if self.genv.real_floats() => {
+                let val = match float_ty {
+                    FloatTy::F32 => f32::from_bits(*bits as u32) as f64,
+                    FloatTy::F64 => f64::from_bits(*bits as u64),
+                };
+                // Infinities and NaN cannot be represented as reals so we leave them unrefined
+                match rty::Constant::real(val) {
+                    Some(c) => {
+                        Ty::indexed(BaseTy::Real(*float_ty), RefineArgs::one(Expr::constant(c)))
+                    }
+                    None => Ty::real(*float_ty),
+                }
+            }
             Constant::Float(_, float_ty) => Ty::float(*float_ty),
             Constant::Unit => Ty::unit(),
-            Constant::Str => Ty::mk_ref(RefKind::Shr, Ty::str()),
-            Constant::Char => Ty::char(),
+            Constant::Str(len) => {
+                let idx = Expr::constant(rty::Constant::from(*len as u128));
+                Ty::mk_ref(RefKind::Shr, Ty::indexed(BaseTy::Str, RefineArgs::one(idx)))
+            }
+            Constant::Char(c) => {
+                let idx = Expr::constant(rty::Constant::from(*c as u128));
+                Ty::indexed(BaseTy::Char, RefineArgs::one(idx))
+            }
+            Constant::Static(def_id) => {
+                Ty::mk_ref(RefKind::Shr, self.genv.lookup_static_ty(*def_id))
+            }
+            Constant::Param(param, ty) => {
+                let ty = self
+                    .genv
+                    .refine_ty(ty, &mut |sorts| Binders::new(Pred::tt(), sorts));
+                // Const generic parameters are only known to refinements if they are integers
+                match ty.bty() {
+                    Some(bty @ (BaseTy::Int(_) | BaseTy::Uint(_))) => {
+                        Ty::indexed(bty.clone(), RefineArgs::one(Expr::const_param(*param)))
+                    }
+                    _ => ty,
+                }
+            }
+            Constant::FnDef(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| {
+                        self.genv
+                            .refine_generic_arg(arg, &mut |sorts| Binders::new(Pred::tt(), sorts))
+                    })
+                    .collect_vec();
+                Ty::fn_def(*def_id, substs)
+            }
         }
     }
 
@@ -891,6 +1358,16 @@ impl<'a, 'tcx, P: Phase> Checker<'a, 'tcx, P> {
     }
 }
 
+/// The successors of a terminator that continues to `target` (if any) and unwinds to `cleanup` (if
+/// any). Neither edge carries extra control information.
+fn successors(target: Option<BasicBlock>, cleanup: Option<BasicBlock>) -> Vec<(BasicBlock, Guard)> {
+    target
+        .into_iter()
+        .chain(cleanup)
+        .map(|bb| (bb, Guard::None))
+        .collect()
+}
+
 fn bool_int_cast(b: &Expr, int_ty: IntTy) -> Ty {
     let idx = Expr::ite(b, 1, 0);
     Ty::indexed(BaseTy::Int(int_ty), RefineArgs::one(idx))
@@ -925,6 +1402,103 @@ fn uint_uint_cast(idx: &Expr, uint_ty1: UintTy, uint_ty2: UintTy) -> Ty {
     }
 }
 
+/// Code points fit in 21 bits. Casting a `char` to a narrower integer type truncates it.
+fn char_int_cast(idx: &Expr, int_ty: IntTy) -> Ty {
+    if int_bit_width(int_ty) > 21 {
+        Ty::indexed(BaseTy::Int(int_ty), RefineArgs::one(idx))
+    } else {
+        Ty::int(int_ty)
+    }
+}
+
+fn char_uint_cast(idx: &Expr, uint_ty: UintTy) -> Ty {
+    let width = uint_bit_width(uint_ty);
+    let idx = if width >= 21 {
+        idx.clone()
+    } else {
+        let modulus = Expr::constant(rty::Constant::from(1u128 << width));
+        Expr::binary_op(BinOp::Mod, idx, modulus)
+    };
+    Ty::indexed(BaseTy::Uint(uint_ty), RefineArgs::one(idx))
+}
+
+fn int_float_cast(idx: &Expr, float_ty: FloatTy) -> Ty {
+    let idx = Expr::theory_app(TheoryFunc::IntToReal, vec![idx.clone()]);
+    Ty::indexed(BaseTy::Real(float_ty), RefineArgs::one(idx))
+}
+
+fn unsupported_cast(from: &Ty, to: &rustc::ty::Ty) -> CheckerError {
+    CheckerError::unsupported(format!("cast from `{from:?}` to `{to:?}`"))
+}
+
+/// Casting a float to an integer rounds towards zero and saturates at the bounds of the integer
+/// type, so we only know the exact value when the truncated float is in range. Note that `to_int`
+/// rounds towards negative infinity.
+fn float_int_cast(idx: &Expr, bty: BaseTy) -> Ty {
+    let (min, max, _) = int_bounds(&bty).unwrap();
+    let to_int = |e: Expr| Expr::theory_app(TheoryFunc::RealToInt, vec![e]);
+    let zero = Expr::constant(rty::Constant::real(0.0).unwrap());
+    let trunc = Expr::ite(Expr::ge(idx, zero), to_int(idx.clone()), to_int(idx.neg()).neg());
+    let in_range =
+        Expr::and([Expr::ge(&trunc, Expr::constant(min)), Expr::le(&trunc, Expr::constant(max))]);
+    let pred = Pred::Expr(Expr::implies(in_range, Expr::eq(Expr::nu(), trunc)));
+    Ty::exists(bty, Binders::new(pred, vec![Sort::Int]))
+}
+
+/// Returns a predicate stating that applying `op` to integers `e1` and `e2` of type `bty` does not
+/// overflow, or [`None`] if the operation cannot overflow. For shifts, this means that the shift
+/// amount is smaller than the bit width of the type.
+///
+/// The result of an arithmetic operation is always the mathematical one, which is only sound if
+/// the operation does not overflow. This is guaranteed for plain operations only when
+/// `check_overflow` is set, because we then check this predicate. For checked operations, which
+/// only appear in MIR when overflow checks are enabled, the predicate is the negation of the
+/// overflow flag and the compiler-inserted assert panics before the result can be used.
+fn no_overflow_pred(op: mir::BinOp, bty: &BaseTy, e1: &Expr, e2: &Expr) -> Option<Expr> {
+    let (min, max, width) = int_bounds(bty)?;
+    let in_range =
+        |e: Expr| Expr::and([Expr::ge(&e, Expr::constant(min)), Expr::le(e, Expr::constant(max))]);
+    match op {
+        mir::BinOp::Add => Some(in_range(e1 + e2)),
+        mir::BinOp::Sub => Some(in_range(e1 - e2)),
+        mir::BinOp::Mul => Some(in_range(e1 * e2)),
+        mir::BinOp::Shl | mir::BinOp::Shr => {
+            let width = Expr::constant(rty::Constant::from(width as u128));
+            Some(Expr::and([Expr::ge(e2, 0), Expr::lt(e2, width)]))
+        }
+        _ => None,
+    }
+}
+
+/// Returns a predicate stating that negating the integer `e` of type `bty` does not overflow, i.e.,
+/// that `e` is not the minimum value of the type, or [`None`] if `bty` is not an integer type.
+fn no_neg_overflow_pred(bty: &BaseTy, e: &Expr) -> Option<Expr> {
+    let (min, _, _) = int_bounds(bty)?;
+    Some(Expr::ne(e, Expr::constant(min)))
+}
+
+/// Returns the minimum and maximum values of an integer type together with its bit width, or
+/// [`None`] if `bty` is not an integer type.
+fn int_bounds(bty: &BaseTy) -> Option<(rty::Constant, rty::Constant, u64)> {
+    match bty {
+        BaseTy::Int(int_ty) => {
+            let width = int_bit_width(*int_ty);
+            let shift = 128 - width as u32;
+            Some((
+                rty::Constant::from(i128::MIN >> shift),
+                rty::Constant::from(i128::MAX >> shift),
+                width,
+            ))
+        }
+        BaseTy::Uint(uint_ty) => {
+            let width = uint_bit_width(*uint_ty);
+            let shift = 128 - width as u32;
+            Some((rty::Constant::ZERO, rty::Constant::from(u128::MAX >> shift), width))
+        }
+        _ => None,
+    }
+}
+
 fn uint_bit_width(uint_ty: UintTy) -> u64 {
     uint_ty.bit_width().unwrap_or(CONFIG.pointer_width)
 }
@@ -1012,7 +1586,7 @@ impl Phase for Check<'_> {
         let tag = Tag::Goto(src_info.map(|s| s.span), target);
         let gen = &mut ConstrGen::new(ck.genv, fresh_kvar, tag);
         env.check_goto(&mut rcx, gen, bb_env)
-            .map_err(|err| CheckerError::from(err).with_src_info_opt(src_info))?;
+            .map_err(|err| err.with_src_info_opt(src_info))?;
 
         Ok(!ck.visited.contains(target))
     }
@@ -1026,6 +1600,86 @@ impl Phase for Check<'_> {
     }
 }
 
+impl ClosureSig {
+    pub fn new(
+        genv: &GlobalEnv,
+        def_id: DefId,
+        kvars: &mut KVarStore,
+    ) -> Result<ClosureSig, ErrorGuaranteed> {
+        let closure_ty = rustc::lowering::lower_type_of(genv.tcx, genv.sess, def_id)?;
+        let rustc::ty::TyKind::Closure(_, upvar_tys) = closure_ty.kind() else {
+            unreachable!("closure without a closure type")
+        };
+        // The types of upvars are shared between the body of the closure and the function creating
+        // it, so there are no variables we can scope their kvars over.
+        let fresh_kvar = &mut |sorts: &[Sort]| kvars.fresh(sorts, []);
+        let upvar_tys = upvar_tys
+            .iter()
+            .map(|ty| genv.refine_ty(ty, fresh_kvar))
+            .collect();
+
+        let fn_sig = if genv.map().fn_sig(def_id).is_some() {
+            genv.lookup_fn_sig(def_id).emit(genv.sess)?
+        } else {
+            let fn_sig = rustc::lowering::lower_fn_sig_of(genv.tcx, def_id)
+                .emit(genv.sess)?
+                .skip_binder();
+            // Inputs of base type are indexed by a refinement parameter of the signature such that
+            // the output can depend on them.
+            let mut params = vec![];
+            let args = fn_sig
+                .inputs()
+                .iter()
+                .map(|ty| {
+                    let ty = genv.refine_ty(ty, &mut |sorts| Binders::new(Pred::tt(), sorts));
+                    match ty.kind() {
+                        TyKind::Exists(bty, _) => {
+                            let idxs = bty
+                                .sorts()
+                                .iter()
+                                .map(|sort| {
+                                    let bvar = BoundVar::innermost(params.len());
+                                    params.push(sort.clone());
+                                    (RefineArg::Expr(Expr::bvar(bvar)), true)
+                                })
+                                .collect_vec();
+                            Ty::indexed(bty.clone(), RefineArgs::new(idxs))
+                        }
+                        _ => ty,
+                    }
+                })
+                .collect_vec();
+            let ret = genv.refine_ty(&fn_sig.output(), &mut |sorts| {
+                kvars.fresh_with_bound_scope(sorts, &params)
+            });
+            PolySig::new(FnSig::new(vec![], args, ret, vec![]), params)
+        };
+        Ok(ClosureSig { upvar_tys, fn_sig })
+    }
+
+    /// The signature used to check the creation of the closure, which takes the captured upvars
+    /// as arguments.
+    fn constructor_sig(&self, def_id: DefId) -> PolySig {
+        let ret = Ty::closure(def_id, self.upvar_tys.clone());
+        PolySig::new(FnSig::new(vec![], self.upvar_tys.clone(), ret, vec![]), vec![])
+    }
+
+    /// The type of the first argument of the closure's body, which can be the closure itself or a
+    /// reference to it depending on the kind of the closure.
+    fn env_ty(&self, def_id: DefId, body: &Body) -> Ty {
+        let closure = Ty::closure(def_id, self.upvar_tys.clone());
+        match body.local_decls[Local::from_u32(1)].ty.kind() {
+            rustc::ty::TyKind::Ref(_, rustc::ty::Mutability::Mut) => {
+                Ty::mk_ref(RefKind::Mut, closure)
+            }
+            rustc::ty::TyKind::Ref(_, rustc::ty::Mutability::Not) => {
+                Ty::mk_ref(RefKind::Shr, closure)
+            }
+            _ => closure,
+        }
+    }
+}
+
 struct Item<'a> {
     bb: BasicBlock,
     dominators: &'a Dominators<BasicBlock>,
@@ -1101,15 +1755,19 @@ pub(crate) mod errors {
         pub span: Option<Span>,
     }
 
+    #[derive(Debug)]
     pub struct CheckerError {
         kind: CheckerErrKind,
         span: Option<Span>,
     }
 
+    #[derive(Debug)]
     pub enum CheckerErrKind {
         Inference,
         OpaqueStruct(DefId),
         UnsupportedCall { def_span: Span, reason: String },
+        UnsupportedFnPtr { def_span: Span },
+        Unsupported { descr: String },
     }
 
     impl CheckerError {
@@ -1124,6 +1782,19 @@ pub(crate) mod errors {
             self.span = Some(src_info.span);
             self
         }
+
+        pub(crate) fn with_span(mut self, span: Span) -> Self {
+            self.span = Some(span);
+            self
+        }
+
+        pub(crate) fn unsupported_fn_ptr(def_span: Span) -> Self {
+            CheckerError { kind: CheckerErrKind::UnsupportedFnPtr { def_span }, span: None }
+        }
+
+        pub(crate) fn unsupported(descr: impl Into<String>) -> Self {
+            CheckerError { kind: CheckerErrKind::Unsupported { descr: descr.into() }, span: None }
+        }
     }
 
     impl<'a> IntoDiagnostic<'a> for CheckerError {
@@ -1136,10 +1807,12 @@ pub(crate) mod errors {
                 CheckerErrKind::Inference => refineck::param_inference_error,
                 CheckerErrKind::OpaqueStruct(_) => refineck::opaque_struct_error,
                 CheckerErrKind::UnsupportedCall { .. } => refineck::unsupported_call,
+                CheckerErrKind::UnsupportedFnPtr { .. } => refineck::unsupported_fn_ptr,
+                CheckerErrKind::Unsupported { .. } => refineck::unsupported,
             };
             let mut builder = handler.struct_err_with_code(fluent, flux_errors::diagnostic_id());
             if let Some(span) = self.span {
-                builder.set_span(span);
+                builder.set_span(span.source_callsite());
             }
 
             match self.kind {
@@ -1151,6 +1824,12 @@ pub(crate) mod errors {
                     builder.span_note(def_span, refineck::function_definition);
                     builder.note(reason);
                 }
+                CheckerErrKind::UnsupportedFnPtr { def_span } => {
+                    builder.span_note(def_span, refineck::function_definition);
+                }
+                CheckerErrKind::Unsupported { descr } => {
+                    builder.set_arg("descr", descr);
+                }
             }
             builder
         }
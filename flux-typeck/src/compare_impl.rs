@@ -0,0 +1,86 @@
+//! Checks that the refined signature of a method in a trait impl is compatible with the refined
+//! signature of the method in the trait. This is required because calls through a generic
+//! `T: Trait` bound are checked against the signature of the trait method.
+
+use flux_middle::{
+    global_env::GlobalEnv,
+    rty::{Constraint, GenericArg, PolySig, Sort},
+};
+use itertools::Itertools;
+use rustc_span::Span;
+
+use crate::{
+    checker::errors::CheckerError,
+    constraint_gen::{ConstrGen, Tag},
+    fixpoint::KVarStore,
+    refine_tree::{RefineTree, UnpackFlags},
+    type_env::TypeEnv,
+};
+
+/// Generates the constraint for `impl_sig <: trait_sig`. This amounts to checking a call to the
+/// impl method in a context where the arguments and preconditions of the trait method are assumed,
+/// and then checking that the output and postconditions of the impl method imply the ones of the
+/// trait method. Preconditions of the impl method that cannot be proven are reported with
+/// [`Tag::Call`] and postconditions of the trait method that cannot be proven with [`Tag::RetAt`].
+/// The generic arguments in `substs` are used to instantiate the impl method and should be the
+/// identity.
+pub(crate) fn check_impl_fn_sig(
+    genv: &GlobalEnv,
+    impl_sig: &PolySig,
+    trait_sig: &PolySig,
+    substs: &[GenericArg],
+    kvars: &mut KVarStore,
+    span: Span,
+) -> Result<RefineTree, CheckerError> {
+    let mut refine_tree = RefineTree::new();
+    let mut rcx = refine_tree.refine_ctxt_at_root();
+
+    let trait_sig = trait_sig.replace_bvars_with_fresh_fvars(|sort| rcx.define_var(sort));
+
+    let mut env = TypeEnv::new();
+    for constr in trait_sig.requires() {
+        match constr {
+            Constraint::Type(path, ty) => {
+                let ty = rcx.unpack(ty);
+                env.alloc_universal_loc(path.loc, ty);
+            }
+            Constraint::Pred(e) => rcx.assume_pred(e.clone()),
+        }
+    }
+    let actuals = trait_sig
+        .args()
+        .iter()
+        .map(|ty| rcx.unpack_with(ty, UnpackFlags::INVARIANTS))
+        .collect_vec();
+
+    let output = {
+        let scope = rcx.scope();
+        let mut gen = ConstrGen::new(
+            genv,
+            |sorts: &[Sort]| kvars.fresh(sorts, scope.iter()),
+            Tag::Call(span),
+        );
+        gen.check_fn_call(&mut rcx, &mut env, impl_sig, None, substs, &actuals)?
+    };
+
+    let ret = rcx.unpack(&output.ret);
+    for constr in &output.ensures {
+        match constr {
+            Constraint::Type(path, updated_ty) => {
+                let updated_ty = rcx.unpack(updated_ty);
+                env.update_path(path, updated_ty);
+            }
+            Constraint::Pred(e) => rcx.assume_pred(e.clone()),
+        }
+    }
+
+    let scope = rcx.scope();
+    let mut gen =
+        ConstrGen::new(genv, |sorts: &[Sort]| kvars.fresh(sorts, scope.iter()), Tag::RetAt(span));
+    gen.subtyping(&mut rcx, &ret, trait_sig.ret());
+    for constr in trait_sig.ensures() {
+        gen.check_constraint(&mut rcx, &mut env, constr)?;
+    }
+
+    Ok(refine_tree)
+}
@@ -1,16 +1,18 @@
 use std::iter;
 
 use flux_middle::{
-    global_env::{GlobalEnv, OpaqueStructErr, Variance},
+    global_env::{GlobalEnv, Variance},
     intern::List,
     rty::{
-        fold::TypeFoldable, BaseTy, BinOp, Binders, Constraint, Constraints, Expr, ExprKind,
-        GenericArg, PolySig, PolyVariant, Pred, RefKind, RefineArg, RefineArgs, Sort, Ty, TyKind,
-        Var, VariantRet,
+        fold::TypeFoldable, BaseTy, BinOp, Binders, Constraint, Constraints, Expr, ExprKind, FnSig,
+        GenericArg, ParamTy, Path, PolySig, PolyVariant, Pred, RefKind, RefineArg, RefineArgs,
+        Sort, Ty, TyKind, Var, VariantRet,
     },
-    rustc::mir::BasicBlock,
+    rustc::{self, mir::BasicBlock},
 };
 use itertools::{izip, Itertools};
+use rustc_hash::FxHashMap;
+use rustc_hir::def_id::DefId;
 use rustc_span::Span;
 
 use crate::{
@@ -61,6 +63,24 @@ impl Tag {
             _ => None,
         }
     }
+
+    /// Moves the span of the tag out of any macro expansion to the call site of the outermost
+    /// macro.
+    pub fn at_call_site(self) -> Tag {
+        let call_site = Span::source_callsite;
+        match self {
+            Tag::Call(span) => Tag::Call(call_site(span)),
+            Tag::Assign(span) => Tag::Assign(call_site(span)),
+            Tag::RetAt(span) => Tag::RetAt(call_site(span)),
+            Tag::Fold(span) => Tag::Fold(call_site(span)),
+            Tag::Assert(msg, span) => Tag::Assert(msg, call_site(span)),
+            Tag::Div(span) => Tag::Div(call_site(span)),
+            Tag::Rem(span) => Tag::Rem(call_site(span)),
+            Tag::Goto(span, bb) => Tag::Goto(span.map(call_site), bb),
+            Tag::Overflow(span) => Tag::Overflow(call_site(span)),
+            Tag::Ret | Tag::Other => self,
+        }
+    }
 }
 
 impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
@@ -76,7 +96,7 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
         rcx: &mut RefineCtxt,
         env: &mut TypeEnv,
         constraint: &Constraint,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         match constraint {
             Constraint::Type(path, ty) => {
                 let actual_ty = env.lookup_path(rcx, self, path)?;
@@ -106,6 +126,7 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
         rcx: &mut RefineCtxt,
         env: &mut TypeEnv,
         fn_sig: &PolySig,
+        rust_sig: Option<&rustc::ty::FnSig>,
         substs: &[GenericArg],
         actuals: &[Ty],
     ) -> Result<CallOutput, CheckerError> {
@@ -130,12 +151,22 @@ impl<'a, 'tcx> ConstrGen<'a, 'tcx> {
             .map(|arg| arg.replace_holes(&mut self.fresh_kvar))
             .collect_vec();
 
+        let infer_actuals = iter::zip(&actuals, fn_sig.as_ref().skip_binders().args())
+            .map(|(actual, formal)| infer_actual(rcx, actual, formal))
+            .collect_vec();
+
         // Infer refinement parameters
-        let exprs = param_infer::infer_from_fn_call(env, &actuals, fn_sig, &mut self.fresh_kvar)?;
-        let fn_sig = fn_sig
+        let exprs =
+            param_infer::infer_from_fn_call(env, &infer_actuals, fn_sig, &mut self.fresh_kvar)?;
+        let mut fn_sig = fn_sig
             .replace_generic_args(&substs)
             .replace_bound_vars(&exprs);
 
+        // Normalize projections, which can only be resolved once the generic arguments are known
+        if let Some(rust_sig) = rust_sig {
+            fn_sig = self.genv.normalize_fn_sig_projections(&fn_sig, rust_sig);
+        }
+
         let constr = &mut rcx.check_constr();
 
         // Convert pointers to borrows
@@ -219,6 +250,14 @@ fn subtyping(genv: &GlobalEnv, constr: &mut ConstrBuilder, ty1: &Ty, ty2: &Ty, t
     }
 
     match (ty1.kind(), ty2.kind()) {
+        (
+            _,
+            TyKind::Indexed(BaseTy::Opaque(def_id, _), _)
+            | TyKind::Exists(BaseTy::Opaque(def_id, _), _),
+        ) if !is_opaque(ty1, *def_id) => {
+            // The hidden type of an `impl Trait`. Opaque types are not refined and Rust already
+            // checked that the hidden type satisfies the bounds, so there is nothing to check.
+        }
         (TyKind::Indexed(bty1, idxs1), TyKind::Indexed(bty2, idx2)) => {
             bty_subtyping(genv, constr, bty1, bty2, tag);
             for (arg1, arg2) in iter::zip(idxs1.args(), idx2.args()) {
@@ -247,9 +286,18 @@ fn subtyping(genv: &GlobalEnv, constr: &mut ConstrBuilder, ty1: &Ty, ty2: &Ty, t
         (_, TyKind::Uninit) => {
             // FIXME: we should rethink in which situation this is sound.
         }
-        (TyKind::Param(param1), TyKind::Param(param2)) => {
+        (TyKind::Param(param1), TyKind::Param(param2))
+        | (TyKind::Indexed(BaseTy::Param(param1), _), TyKind::Param(param2)) => {
             debug_assert_eq!(param1, param2);
         }
+        (
+            TyKind::Param(param),
+            TyKind::Indexed(BaseTy::Param(_), _) | TyKind::Exists(BaseTy::Param(_), _),
+        ) => {
+            // An unrefined type parameter is indexed by an arbitrary value
+            let ty1 = unrefined_param(*param);
+            subtyping(genv, constr, &ty1, ty2, tag);
+        }
         (TyKind::Tuple(tys1), TyKind::Tuple(tys2)) => {
             debug_assert_eq!(tys1.len(), tys2.len());
             for (ty1, ty2) in iter::zip(tys1, tys2) {
@@ -268,6 +316,35 @@ fn subtyping(genv: &GlobalEnv, constr: &mut ConstrBuilder, ty1: &Ty, ty2: &Ty, t
     }
 }
 
+/// The index of a type parameter is dropped when the parameter is instantiated with a type not
+/// indexed by an integer, so we infer an arbitrary value for it. This also applies behind a shared
+/// reference, e.g., when a derived impl of `Clone` calls `Clone::clone` on a generic field.
+fn infer_actual(rcx: &mut RefineCtxt, actual: &Ty, formal: &Ty) -> Ty {
+    match (actual.kind(), formal.unconstr().kind()) {
+        (TyKind::Ref(RefKind::Shr, actual), TyKind::Ref(RefKind::Shr, formal)) => {
+            Ty::mk_ref(RefKind::Shr, infer_actual(rcx, actual, formal))
+        }
+        (_, TyKind::Indexed(BaseTy::Param(param), _)) if !is_int_indexed(actual) => {
+            rcx.unpack(&unrefined_param(*param))
+        }
+        _ => actual.clone(),
+    }
+}
+
+fn unrefined_param(param: ParamTy) -> Ty {
+    let bty = BaseTy::Param(param);
+    let sorts = bty.sorts().to_vec();
+    Ty::exists(bty, Binders::new(Pred::tt(), sorts))
+}
+
+fn is_opaque(ty: &Ty, def_id: DefId) -> bool {
+    matches!(ty.kind(), TyKind::Indexed(BaseTy::Opaque(def_id1, _), _) if *def_id1 == def_id)
+}
+
+fn is_int_indexed(ty: &Ty) -> bool {
+    matches!(ty.unconstr().kind(), TyKind::Indexed(bty, _) | TyKind::Exists(bty, _) if bty.sorts() == [Sort::Int])
+}
+
 fn arg_subtyping(constr: &mut ConstrBuilder, arg1: &RefineArg, arg2: &RefineArg, tag: Tag) {
     if arg1 == arg2 {
         return;
@@ -330,15 +407,33 @@ fn bty_subtyping(
                 generic_arg_subtyping(genv, constr, *variance, ty1, ty2, tag);
             }
         }
-        (BaseTy::Float(float_ty1), BaseTy::Float(float_ty2)) => {
+        (BaseTy::Float(float_ty1), BaseTy::Float(float_ty2))
+        | (BaseTy::Real(float_ty1), BaseTy::Real(float_ty2)) => {
             debug_assert_eq!(float_ty1, float_ty2);
         }
-        (BaseTy::Array(ty1, len1), BaseTy::Array(ty2, len2)) => {
-            debug_assert_eq!(len1, len2);
+        (BaseTy::Array(ty1), BaseTy::Array(ty2)) | (BaseTy::Slice(ty1), BaseTy::Slice(ty2)) => {
             subtyping(genv, constr, ty1, ty2, tag);
         }
-        (BaseTy::Slice(ty1), BaseTy::Slice(ty2)) => {
-            subtyping(genv, constr, ty1, ty2, tag);
+        (BaseTy::Closure(def_id1, upvar_tys1), BaseTy::Closure(def_id2, upvar_tys2)) => {
+            debug_assert_eq!(def_id1, def_id2);
+            for (ty1, ty2) in iter::zip(upvar_tys1, upvar_tys2) {
+                subtyping(genv, constr, ty1, ty2, tag);
+            }
+        }
+        (BaseTy::FnPtr(fn_sig1), BaseTy::FnPtr(fn_sig2)) => {
+            fn_sig_subtyping(genv, constr, fn_sig1, fn_sig2, tag);
+        }
+        (BaseTy::FnDef(def_id1, substs1), BaseTy::FnDef(def_id2, substs2))
+        | (BaseTy::Dynamic(def_id1, substs1), BaseTy::Dynamic(def_id2, substs2))
+        | (BaseTy::Opaque(def_id1, substs1), BaseTy::Opaque(def_id2, substs2))
+        | (BaseTy::Projection(def_id1, substs1), BaseTy::Projection(def_id2, substs2)) => {
+            debug_assert_eq!(def_id1, def_id2);
+            for (arg1, arg2) in iter::zip(substs1, substs2) {
+                generic_arg_subtyping(genv, constr, Variance::Invariant, arg1, arg2, tag);
+            }
+        }
+        (BaseTy::Param(param1), BaseTy::Param(param2)) => {
+            debug_assert_eq!(param1, param2);
         }
         (BaseTy::Bool, BaseTy::Bool)
         | (BaseTy::Str, BaseTy::Str)
@@ -349,6 +444,92 @@ fn bty_subtyping(
     }
 }
 
+/// A function pointer of type `fn_sig1` can be used where one of type `fn_sig2` is expected if every
+/// call satisfying `fn_sig2` also satisfies `fn_sig1`. We check this by assuming the arguments and
+/// preconditions of `fn_sig2`, checking a call to `fn_sig1` with them, and then checking that the
+/// output and postconditions of `fn_sig1` imply the ones of `fn_sig2`.
+fn fn_sig_subtyping(
+    genv: &GlobalEnv,
+    constr: &mut ConstrBuilder,
+    fn_sig1: &PolySig,
+    fn_sig2: &PolySig,
+    tag: Tag,
+) {
+    let constr = &mut constr.breadcrumb();
+    assert!(
+        !has_type_constraints(fn_sig1.as_ref().skip_binders())
+            && !has_type_constraints(fn_sig2.as_ref().skip_binders()),
+        "function pointer with strong references"
+    );
+
+    let vars = constr
+        .define_vars(fn_sig2.params())
+        .into_iter()
+        .map(|name| RefineArg::Expr(Expr::fvar(name)))
+        .collect_vec();
+    let fn_sig2 = fn_sig2.replace_bound_vars(&vars);
+    for constraint in fn_sig2.requires() {
+        if let Constraint::Pred(pred) = constraint {
+            constr.push_guard(pred.clone());
+        }
+    }
+    let actuals = fn_sig2
+        .args()
+        .iter()
+        .map(|ty| {
+            match ty.kind() {
+                TyKind::Exists(bty, pred) => {
+                    let idxs = constr.push_bound_guard(pred);
+                    Ty::indexed(bty.clone(), RefineArgs::multi(idxs))
+                }
+                _ => ty.clone(),
+            }
+        })
+        .collect_vec();
+
+    let env: FxHashMap<Path, Ty> = FxHashMap::default();
+    let Ok(exprs) = param_infer::infer_from_fn_call(&env, &actuals, fn_sig1, &mut |sorts| {
+        Binders::new(Pred::tt(), sorts)
+    }) else {
+        constr.push_head(Expr::ff(), tag);
+        return;
+    };
+    let fn_sig1 = fn_sig1.replace_bound_vars(&exprs);
+
+    for (actual, formal) in iter::zip(&actuals, fn_sig1.args()) {
+        subtyping(genv, constr, actual, formal, tag);
+    }
+    for constraint in fn_sig1.requires() {
+        if let Constraint::Pred(pred) = constraint {
+            constr.push_head(pred.clone(), tag);
+        }
+    }
+
+    for constraint in fn_sig1.ensures() {
+        if let Constraint::Pred(pred) = constraint {
+            constr.push_guard(pred.clone());
+        }
+    }
+    subtyping(genv, constr, fn_sig1.ret(), fn_sig2.ret(), tag);
+    for constraint in fn_sig2.ensures() {
+        if let Constraint::Pred(pred) = constraint {
+            constr.push_head(pred.clone(), tag);
+        }
+    }
+}
+
+/// Signatures with strong references cannot be turned into function pointers (they are rejected
+/// when checking [`CastKind::ReifyFnPointer`]), so they never reach [`fn_sig_subtyping`].
+///
+/// [`CastKind::ReifyFnPointer`]: flux_middle::rustc::mir::CastKind::ReifyFnPointer
+fn has_type_constraints(fn_sig: &FnSig) -> bool {
+    fn_sig
+        .requires()
+        .iter()
+        .chain(fn_sig.ensures())
+        .any(|constr| matches!(constr, Constraint::Type(..)))
+}
+
 fn generic_arg_subtyping(
     genv: &GlobalEnv,
     constr: &mut ConstrBuilder,
@@ -369,7 +550,9 @@ fn generic_arg_subtyping(
                 rustc_middle::ty::Variance::Bivariant => {}
             }
         }
-        (GenericArg::Lifetime, GenericArg::Lifetime) => {}
+        // Const arguments come from the Rust types, which are known to be equal
+        (GenericArg::Lifetime, GenericArg::Lifetime)
+        | (GenericArg::Const(_), GenericArg::Const(_)) => {}
         _ => unreachable!("incompatible generic args:  `{arg1:?}` `{arg2:?}"),
     };
 }
@@ -9,13 +9,17 @@ use flux_fixpoint as fixpoint;
 use flux_middle::{
     fhir,
     global_env::GlobalEnv,
-    rty::{self, Binders, BoundVar},
+    rty::{
+        self,
+        fold::{TypeFoldable, TypeFolder},
+        Binders, BoundVar, DebruijnIndex, INNERMOST,
+    },
 };
 use itertools::Itertools;
 use rustc_hash::FxHashMap;
 use rustc_hir::def_id::DefId;
 use rustc_index::newtype_index;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{GenericParamDefKind, TyCtxt, TyKind};
 
 use crate::refine_tree::Scope;
 
@@ -53,10 +57,12 @@ pub struct KVarGenScopeChain<'a, G: ?Sized> {
 
 type NameMap = FxHashMap<rty::Name, fixpoint::Name>;
 type KVidMap = FxHashMap<rty::KVid, Vec<fixpoint::KVid>>;
-type ConstMap = FxHashMap<DefId, ConstInfo>;
+type ConstMap = FxHashMap<ConstKey, ConstInfo>;
 
 pub struct FixpointCtxt<'genv, 'tcx, T> {
     genv: &'genv GlobalEnv<'genv, 'tcx>,
+    /// The item whose constraint is being checked
+    did: DefId,
     kvars: KVarStore,
     fixpoint_kvars: IndexVec<fixpoint::KVid, Vec<fixpoint::Sort>>,
     kvid_map: KVidMap,
@@ -67,21 +73,33 @@ pub struct FixpointCtxt<'genv, 'tcx, T> {
     tags_inv: FxHashMap<T, TagIdx>,
 }
 
+/// Constants are declared globally in the fixpoint task. Besides the constants in the crate, we
+/// declare the const generic parameters in scope of the item being checked.
+#[derive(PartialEq, Eq, Hash)]
+enum ConstKey {
+    Global(DefId),
+    Param(rty::ParamConst),
+}
+
 struct ConstInfo {
     name: fixpoint::Name,
-    val: i128,
+    /// The value of the constant, which is unknown for a const generic parameter
+    val: Option<i128>,
+    /// Whether the constant has an unsigned type and thus can be assumed to be non-negative
+    unsigned: bool,
 }
 
 impl<'genv, 'tcx, Tag> FixpointCtxt<'genv, 'tcx, Tag>
 where
     Tag: std::hash::Hash + Eq + Copy,
 {
-    pub fn new(genv: &'genv GlobalEnv<'genv, 'tcx>, kvars: KVarStore) -> Self {
+    pub fn new(genv: &'genv GlobalEnv<'genv, 'tcx>, did: DefId, kvars: KVarStore) -> Self {
         let name_gen = IndexGen::new();
-        let const_map = fixpoint_const_map(genv, &name_gen);
+        let const_map = fixpoint_const_map(genv, did, &name_gen);
         Self {
             kvars,
             genv,
+            did,
             name_gen,
             fixpoint_kvars: IndexVec::new(),
             kvid_map: KVidMap::default(),
@@ -112,18 +130,18 @@ where
         cstr: fixpoint::Constraint<TagIdx>,
         const_info: &ConstInfo,
     ) -> fixpoint::Constraint<TagIdx> {
-        let name = const_info.name;
-        let e1 = fixpoint::Expr::from(name);
-        let e2 = fixpoint::Expr::from(const_info.val);
-        let pred = fixpoint::Pred::Expr(e1.eq(e2));
-        fixpoint::Constraint::Guard(pred, Box::new(cstr))
+        let e1 = fixpoint::Expr::from(const_info.name);
+        let e = match const_info.val {
+            Some(val) => e1.eq(fixpoint::Expr::from(val)),
+            None if const_info.unsigned => {
+                fixpoint::Expr::BinaryOp(fixpoint::BinOp::Ge, Box::new([e1, fixpoint::Expr::ZERO]))
+            }
+            None => return cstr,
+        };
+        fixpoint::Constraint::Guard(fixpoint::Pred::Expr(e), Box::new(cstr))
     }
 
-    pub fn check(
-        self,
-        did: DefId,
-        constraint: fixpoint::Constraint<TagIdx>,
-    ) -> Result<(), Vec<Tag>> {
+    pub fn check(self, constraint: fixpoint::Constraint<TagIdx>) -> Result<(), Vec<Tag>> {
         let kvars = self
             .fixpoint_kvars
             .into_iter_enumerated()
@@ -155,9 +173,31 @@ where
             .map(uif_def_to_fixpoint)
             .collect_vec();
 
-        let task = fixpoint::Task::new(constants, kvars, closed_constraint, qualifiers, uifs);
+        let axioms = self
+            .genv
+            .axioms
+            .iter()
+            .map(|axiom| expr_to_fixpoint(axiom, &NameMap::default(), &self.const_map, 0))
+            .collect_vec();
+
+        let data_decls = self
+            .genv
+            .map()
+            .data_decls()
+            .map(data_decl_to_fixpoint)
+            .collect_vec();
+
+        let task = fixpoint::Task::new(
+            constants,
+            kvars,
+            closed_constraint,
+            qualifiers,
+            uifs,
+            axioms,
+            data_decls,
+        );
         if CONFIG.dump_constraint {
-            dump_constraint(self.genv.tcx, did, &task, ".smt2").unwrap();
+            dump_constraint(self.genv.tcx, self.did, &task, ".smt2").unwrap();
         }
 
         match task.check() {
@@ -205,7 +245,7 @@ where
                 )
             }
             rty::Pred::Expr(expr) => {
-                let expr = expr_to_fixpoint(expr, &self.name_map, &self.const_map);
+                let expr = expr_to_fixpoint(expr, &self.name_map, &self.const_map, 0);
                 fixpoint::Pred::Expr(expr)
             }
             rty::Pred::Kvar(kvar) => self.kvar_to_fixpoint(kvar, bindings),
@@ -215,7 +255,7 @@ where
                     .get(name)
                     .unwrap_or_else(|| panic!("no entry found for key: `{name:?}`"));
                 let func = fixpoint::Func::Var(*name);
-                let args = exprs_to_fixpoint(args, &self.name_map, &self.const_map);
+                let args = exprs_to_fixpoint(args, &self.name_map, &self.const_map, 0);
                 fixpoint::Pred::Expr(fixpoint::Expr::App(func, args))
             }
             rty::Pred::App(rty::Var::Bound(_), _) => {
@@ -274,7 +314,7 @@ where
                     fixpoint::BinOp::Eq,
                     Box::new([
                         fixpoint::Expr::Var(fresh),
-                        expr_to_fixpoint(arg, &self.name_map, &self.const_map),
+                        expr_to_fixpoint(arg, &self.name_map, &self.const_map, 0),
                     ]),
                 );
                 bindings.push((fresh, sort_to_fixpoint(sort), pred));
@@ -310,16 +350,31 @@ where
 
 fn fixpoint_const_map(
     genv: &GlobalEnv,
+    did: DefId,
     name_gen: &IndexGen<fixpoint::Name>,
-) -> FxHashMap<DefId, ConstInfo> {
-    genv.map()
-        .consts()
-        .map(|const_info| {
-            let name = name_gen.fresh();
-            let cinfo = ConstInfo { name, val: const_info.val };
-            (const_info.def_id, cinfo)
-        })
-        .collect()
+) -> ConstMap {
+    let consts = genv.map().consts().map(|const_info| {
+        let cinfo =
+            ConstInfo { name: name_gen.fresh(), val: Some(const_info.val), unsigned: false };
+        (ConstKey::Global(const_info.def_id), cinfo)
+    });
+
+    let tcx = genv.tcx;
+    let mut const_params = vec![];
+    let mut generics = Some(tcx.generics_of(did));
+    while let Some(g) = generics {
+        for param in &g.params {
+            if let GenericParamDefKind::Const { .. } = param.kind {
+                let unsigned = matches!(tcx.type_of(param.def_id).kind(), TyKind::Uint(_));
+                let cinfo = ConstInfo { name: name_gen.fresh(), val: None, unsigned };
+                let param = rty::ParamConst { index: param.index, name: param.name };
+                const_params.push((ConstKey::Param(param), cinfo));
+            }
+        }
+        generics = g.parent.map(|parent| tcx.generics_of(parent));
+    }
+
+    consts.chain(const_params).collect()
 }
 
 impl KVarStore {
@@ -354,6 +409,33 @@ impl KVarStore {
 
         Binders::new(rty::Pred::Kvar(rty::KVar::new(kvid, args, scope_exprs.clone())), sorts)
     }
+
+    /// Generates a fresh kvar whose scope are the variables bound by the binder immediately
+    /// enclosing the returned one, e.g., the refinement parameters of a function signature.
+    pub fn fresh_with_bound_scope(
+        &mut self,
+        sorts: &[rty::Sort],
+        scope: &[rty::Sort],
+    ) -> Binders<rty::Pred> {
+        let mut scope_sorts = vec![];
+        let mut scope_exprs = vec![];
+        for (idx, sort) in scope.iter().enumerate() {
+            if !sort.is_loc() {
+                scope_sorts.push(sort.clone());
+                scope_exprs.push(rty::Expr::bvar(BoundVar::new(idx, DebruijnIndex::new(1))));
+            }
+        }
+
+        let args = (0..sorts.len())
+            .map(|idx| rty::Expr::bvar(BoundVar::innermost(idx)))
+            .collect_vec();
+
+        let kvid = self
+            .kvars
+            .push(KVarSorts { args: sorts.to_vec(), scope: scope_sorts });
+
+        Binders::new(rty::Pred::Kvar(rty::KVar::new(kvid, args, scope_exprs)), sorts)
+    }
 }
 
 impl KVarGen for KVarStore {
@@ -395,6 +477,8 @@ pub fn sort_to_fixpoint(sort: &rty::Sort) -> fixpoint::Sort {
     match sort {
         rty::Sort::Int => fixpoint::Sort::Int,
         rty::Sort::Bool => fixpoint::Sort::Bool,
+        rty::Sort::Real => fixpoint::Sort::Real,
+        rty::Sort::BitVec(size) => fixpoint::Sort::BitVec(*size),
         rty::Sort::Tuple(sorts) => {
             match &sorts[..] {
                 [] => fixpoint::Sort::Unit,
@@ -413,6 +497,14 @@ pub fn sort_to_fixpoint(sort: &rty::Sort) -> fixpoint::Sort {
             }
         }
         rty::Sort::Func(sort) => fixpoint::Sort::Func(func_sort_to_fixpoint(sort)),
+        rty::Sort::Set(sort) => fixpoint::Sort::Set(Box::new(sort_to_fixpoint(sort))),
+        rty::Sort::Map(k, v) => {
+            fixpoint::Sort::Map(Box::new(sort_to_fixpoint(k)), Box::new(sort_to_fixpoint(v)))
+        }
+        rty::Sort::Data(def_id) => fixpoint::Sort::Data(data_sort_name(*def_id)),
+        // Opaque sorts only support equality and uninterpreted functions, so encoding them as
+        // integers doesn't make more constraints valid.
+        rty::Sort::User(_) => fixpoint::Sort::Int,
         rty::Sort::Infer | rty::Sort::Loc => unreachable!("unexpected sort {sort:?}"),
     }
 }
@@ -445,6 +537,50 @@ fn uif_def_to_fixpoint(uif_def: &fhir::UifDef) -> fixpoint::UifDef {
     fixpoint::UifDef::new(uif_def.name.to_string(), sort)
 }
 
+fn data_decl_to_fixpoint(data_decl: &fhir::DataDecl) -> fixpoint::DataDecl {
+    let ctors = data_decl
+        .ctors
+        .iter()
+        .enumerate()
+        .map(|(variant, ctor)| {
+            let variant = rty::VariantIdx::from_usize(variant);
+            let fields = ctor
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(field, (_, sort))| {
+                    let func = fhir::DataFunc::Field(data_decl.def_id, variant, field as u32);
+                    (data_func_name(func), sort_to_fixpoint(sort))
+                })
+                .collect();
+            let name = data_func_name(fhir::DataFunc::Ctor(data_decl.def_id, variant));
+            fixpoint::DataCtor { name, fields }
+        })
+        .collect();
+    fixpoint::DataDecl { name: data_sort_name(data_decl.def_id), ctors }
+}
+
+/// Datatypes are named after the [`DefId`] of the reflected type, and constructors and fields
+/// after their position, so names don't clash with each other or with user defined functions.
+fn data_sort_name(def_id: DefId) -> String {
+    format!("Data{}_{}", def_id.krate.as_u32(), def_id.index.as_u32())
+}
+
+fn data_func_name(func: fhir::DataFunc) -> String {
+    match func {
+        fhir::DataFunc::Ctor(def_id, variant) => {
+            format!("{}$mk{}", data_sort_name(def_id), variant.as_u32())
+        }
+        fhir::DataFunc::Field(def_id, variant, field) => {
+            format!("{}$fld{field}", data_func_name(fhir::DataFunc::Ctor(def_id, variant)))
+        }
+        fhir::DataFunc::Test(def_id, variant) => {
+            // The solver defines a test `is$ctor` for every constructor
+            format!("is${}", data_func_name(fhir::DataFunc::Ctor(def_id, variant)))
+        }
+    }
+}
+
 fn qualifier_to_fixpoint(const_map: &ConstMap, qualifier: &rty::Qualifier) -> fixpoint::Qualifier {
     let name_gen = IndexGen::skipping(const_map.len());
     let mut name_map = NameMap::default();
@@ -459,11 +595,19 @@ fn qualifier_to_fixpoint(const_map: &ConstMap, qualifier: &rty::Qualifier) -> fi
         })
         .collect();
 
-    let expr = expr_to_fixpoint(&qualifier.expr, &name_map, const_map);
+    let expr = expr_to_fixpoint(&qualifier.expr, &name_map, const_map, 0);
     fixpoint::Qualifier { expr, args, name }
 }
 
-fn expr_to_fixpoint(expr: &rty::Expr, name_map: &NameMap, const_map: &ConstMap) -> fixpoint::Expr {
+/// Encodes an expression into a fixpoint expression. `nbinders` is the number of quantifiers
+/// enclosing the expression. Bound variables are encoded by the level of the quantifier binding
+/// them, so an expression outside any quantifier must not contain bound variables.
+fn expr_to_fixpoint(
+    expr: &rty::Expr,
+    name_map: &NameMap,
+    const_map: &ConstMap,
+    nbinders: u32,
+) -> fixpoint::Expr {
     match expr.kind() {
         rty::ExprKind::FreeVar(name) => {
             let name = name_map
@@ -476,38 +620,115 @@ fn expr_to_fixpoint(expr: &rty::Expr, name_map: &NameMap, const_map: &ConstMap)
             fixpoint::Expr::BinaryOp(
                 *op,
                 Box::new([
-                    expr_to_fixpoint(e1, name_map, const_map),
-                    expr_to_fixpoint(e2, name_map, const_map),
+                    expr_to_fixpoint(e1, name_map, const_map, nbinders),
+                    expr_to_fixpoint(e2, name_map, const_map, nbinders),
                 ]),
             )
         }
         rty::ExprKind::UnaryOp(op, e) => {
-            fixpoint::Expr::UnaryOp(*op, Box::new(expr_to_fixpoint(e, name_map, const_map)))
+            fixpoint::Expr::UnaryOp(
+                *op,
+                Box::new(expr_to_fixpoint(e, name_map, const_map, nbinders)),
+            )
         }
         rty::ExprKind::TupleProj(e, field) => {
             itertools::repeat_n(fixpoint::Proj::Snd, *field as usize)
                 .chain([fixpoint::Proj::Fst])
-                .fold(expr_to_fixpoint(e, name_map, const_map), |e, proj| {
+                .fold(expr_to_fixpoint(e, name_map, const_map, nbinders), |e, proj| {
                     fixpoint::Expr::Proj(Box::new(e), proj)
                 })
         }
-        rty::ExprKind::Tuple(exprs) => tuple_to_fixpoint(exprs, name_map, const_map),
+        rty::ExprKind::Tuple(exprs) => tuple_to_fixpoint(exprs, name_map, const_map, nbinders),
+        rty::ExprKind::BoundVar(bvar) if bvar.debruijn.as_u32() < nbinders => {
+            let level = nbinders - bvar.debruijn.as_u32() - 1;
+            fixpoint::Expr::BoundVar(fixpoint::BoundVar::from_u32(level))
+        }
         rty::ExprKind::Local(_) | rty::ExprKind::BoundVar(_) | rty::ExprKind::PathProj(..) => {
             panic!("unexpected expr: `{expr:?}`")
         }
-        rty::ExprKind::ConstDefId(did) => fixpoint::Expr::Var(const_map[did].name),
+        rty::ExprKind::ConstDefId(did) => {
+            fixpoint::Expr::Var(const_map[&ConstKey::Global(*did)].name)
+        }
+        rty::ExprKind::ConstParam(param) => {
+            fixpoint::Expr::Var(const_map[&ConstKey::Param(*param)].name)
+        }
         rty::ExprKind::App(func, args) => {
-            let args = exprs_to_fixpoint(args, name_map, const_map);
+            let args = exprs_to_fixpoint(args, name_map, const_map, nbinders);
             let uif = fixpoint::Func::Uif(func.to_string());
             fixpoint::Expr::App(uif, args)
         }
+        rty::ExprKind::TheoryApp(func, args) => {
+            let mut args = exprs_to_fixpoint(args, name_map, const_map, nbinders);
+            if let rty::TheoryFunc::SetEmpty = func {
+                args.push(fixpoint::Expr::ZERO);
+            }
+            fixpoint::Expr::App(fixpoint::Func::Theory(*func), args)
+        }
+        rty::ExprKind::DataApp(func, args) => {
+            let args = exprs_to_fixpoint(args, name_map, const_map, nbinders);
+            fixpoint::Expr::App(fixpoint::Func::Uif(data_func_name(*func)), args)
+        }
         rty::ExprKind::IfThenElse(p, e1, e2) => {
             fixpoint::Expr::IfThenElse(Box::new([
-                expr_to_fixpoint(p, name_map, const_map),
-                expr_to_fixpoint(e1, name_map, const_map),
-                expr_to_fixpoint(e2, name_map, const_map),
+                expr_to_fixpoint(p, name_map, const_map, nbinders),
+                expr_to_fixpoint(e1, name_map, const_map, nbinders),
+                expr_to_fixpoint(e2, name_map, const_map, nbinders),
             ]))
         }
+        rty::ExprKind::Quant(quant, body) => {
+            if let [sort] = body.params() {
+                fixpoint::Expr::Quant(
+                    *quant,
+                    fixpoint::BoundVar::from_u32(nbinders),
+                    sort_to_fixpoint(sort),
+                    Box::new(expr_to_fixpoint(
+                        body.as_ref().skip_binders(),
+                        name_map,
+                        const_map,
+                        nbinders + 1,
+                    )),
+                )
+            } else {
+                // Fixpoint quantifiers bind a single variable, so we nest one per variable.
+                let sorts = body.params();
+                let mut split = SplitBinder { nvars: sorts.len() as u32, outer_binder: INNERMOST };
+                let body = body.as_ref().skip_binders().fold_with(&mut split);
+                let expr = sorts.iter().rev().fold(body, |body, sort| {
+                    rty::Expr::quant(*quant, Binders::new(body, vec![sort.clone()]))
+                });
+                expr_to_fixpoint(&expr, name_map, const_map, nbinders)
+            }
+        }
+    }
+}
+
+/// Replaces the innermost binder of an expression, which binds `nvars` variables, with `nvars`
+/// binders binding one variable each. The first variable is bound by the outermost binder.
+struct SplitBinder {
+    nvars: u32,
+    outer_binder: DebruijnIndex,
+}
+
+impl TypeFolder for SplitBinder {
+    fn fold_binders<T: TypeFoldable>(&mut self, t: &Binders<T>) -> Binders<T> {
+        self.outer_binder.shift_in(1);
+        let r = t.super_fold_with(self);
+        self.outer_binder.shift_out(1);
+        r
+    }
+
+    fn fold_expr(&mut self, expr: &rty::Expr) -> rty::Expr {
+        match expr.kind() {
+            rty::ExprKind::BoundVar(bvar) if bvar.debruijn == self.outer_binder => {
+                let debruijn = bvar.debruijn.shifted_in(self.nvars - 1 - bvar.index as u32);
+                rty::Expr::bvar(BoundVar::new(0, debruijn))
+            }
+            rty::ExprKind::BoundVar(bvar) if bvar.debruijn > self.outer_binder => {
+                let debruijn = bvar.debruijn.shifted_in(self.nvars).shifted_out(1);
+                rty::Expr::bvar(BoundVar::new(bvar.index, debruijn))
+            }
+            _ => expr.super_fold_with(self),
+        }
     }
 }
 
@@ -515,10 +736,11 @@ fn exprs_to_fixpoint<'a>(
     exprs: impl IntoIterator<Item = &'a rty::Expr>,
     name_map: &NameMap,
     const_map: &ConstMap,
+    nbinders: u32,
 ) -> Vec<fixpoint::Expr> {
     exprs
         .into_iter()
-        .map(|e| expr_to_fixpoint(e, name_map, const_map))
+        .map(|e| expr_to_fixpoint(e, name_map, const_map, nbinders))
         .collect()
 }
 
@@ -526,13 +748,14 @@ fn tuple_to_fixpoint(
     exprs: &[rty::Expr],
     name_map: &NameMap,
     const_map: &ConstMap,
+    nbinders: u32,
 ) -> fixpoint::Expr {
     match exprs {
         [] => fixpoint::Expr::Unit,
         [e, exprs @ ..] => {
             fixpoint::Expr::Pair(Box::new([
-                expr_to_fixpoint(e, name_map, const_map),
-                tuple_to_fixpoint(exprs, name_map, const_map),
+                expr_to_fixpoint(e, name_map, const_map, nbinders),
+                tuple_to_fixpoint(exprs, name_map, const_map, nbinders),
             ]))
         }
     }
@@ -18,7 +18,7 @@ pub fn check_invariants(genv: &GlobalEnv, adt_def: &AdtDef) -> Result<(), ErrorG
         .iter()
         .enumerate()
         .try_for_each_exhaust(|(idx, invariant)| {
-            let span = genv.map().adt(adt_def.def_id().expect_local()).invariants[idx].span;
+            let span = genv.map().adt(adt_def.def_id()).invariants[idx].span;
             check_invariant(genv, adt_def, span, invariant)
         })
 }
@@ -50,9 +50,9 @@ fn check_invariant(
 
         rcx.check_pred(invariant.pred.replace_bound_vars(&variant.ret.args), Tag::Other);
     }
-    let mut fcx = FixpointCtxt::new(genv, KVarStore::default());
+    let mut fcx = FixpointCtxt::new(genv, adt_def.def_id(), KVarStore::default());
     let constraint = refine_tree.into_fixpoint(&mut fcx);
-    fcx.check(adt_def.def_id(), constraint)
+    fcx.check(constraint)
         .map_err(|_| genv.sess.emit_err(errors::Invalid { span }))
 }
 
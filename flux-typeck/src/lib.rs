@@ -19,6 +19,7 @@ extern crate rustc_session;
 extern crate rustc_span;
 
 mod checker;
+mod compare_impl;
 mod constraint_gen;
 mod dbg;
 pub mod invariants;
@@ -30,49 +31,108 @@ pub mod wf;
 mod fixpoint;
 mod sigs;
 
-use std::{fs, io::Write};
+use std::{fs, io::Write, iter};
 
-use checker::Checker;
+use checker::{Checker, ClosureSig};
 use constraint_gen::Tag;
-use flux_common::config::CONFIG;
+use flux_common::{config::CONFIG, iter::IterExt};
 use flux_errors::ResultExt;
 use flux_middle::{global_env::GlobalEnv, rty, rustc::mir::Body};
+use itertools::Itertools;
 use rustc_errors::ErrorGuaranteed;
 use rustc_hir::def_id::DefId;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
 
+/// Checks the body of a function together with the bodies of all closures defined inside it. The
+/// closures are checked in the same fixpoint task as the function that defines them, so that the
+/// refinements of their upvars and outputs can be inferred from how they are created and called.
 pub fn check<'a, 'tcx>(
     genv: &GlobalEnv<'a, 'tcx>,
     def_id: DefId,
     body: &Body<'tcx>,
+    closures: &[(DefId, Body<'tcx>)],
 ) -> Result<(), ErrorGuaranteed> {
-    let bb_envs = Checker::infer(genv, body, def_id).emit(genv.sess)?;
     let mut kvars = fixpoint::KVarStore::new();
-    let refine_tree = Checker::check(genv, body, def_id, &mut kvars, bb_envs).emit(genv.sess)?;
 
-    if CONFIG.dump_constraint {
-        dump_constraint(genv.tcx, def_id, &refine_tree, ".lrc").unwrap();
+    let closure_sigs = closures
+        .iter()
+        .map(|(closure_id, _)| Ok((*closure_id, ClosureSig::new(genv, *closure_id, &mut kvars)?)))
+        .try_collect_exhaust()?;
+
+    let bodies =
+        iter::once((def_id, body)).chain(closures.iter().map(|(def_id, body)| (*def_id, body)));
+
+    let mut refine_trees = vec![];
+    for (def_id, body) in bodies {
+        let bb_envs = Checker::infer(genv, body, def_id, &closure_sigs).emit(genv.sess)?;
+        let refine_tree = Checker::check(genv, body, def_id, &closure_sigs, &mut kvars, bb_envs)
+            .emit(genv.sess)?;
+
+        if CONFIG.dump_constraint {
+            dump_constraint(genv.tcx, def_id, &refine_tree, ".lrc").unwrap();
+        }
+        refine_trees.push(refine_tree);
     }
 
-    let mut fcx = fixpoint::FixpointCtxt::new(genv, kvars);
+    let mut fcx = fixpoint::FixpointCtxt::new(genv, def_id, kvars);
 
-    let constraint = refine_tree.into_fixpoint(&mut fcx);
+    let mut constraints = refine_trees
+        .into_iter()
+        .map(|refine_tree| refine_tree.into_fixpoint(&mut fcx))
+        .collect_vec();
+    let constraint = if constraints.len() == 1 {
+        constraints.pop().unwrap()
+    } else {
+        flux_fixpoint::Constraint::Conj(constraints)
+    };
 
-    match fcx.check(def_id, constraint) {
+    match fcx.check(constraint) {
         Ok(_) => Ok(()),
         Err(tags) => report_errors(genv, body.span(), tags),
     }
 }
 
+/// Checks that the refined signature of a method in a trait impl is a subtype of the refined
+/// signature of the corresponding method in the trait. Nothing is checked if the trait method
+/// doesn't have a refined signature.
+pub fn check_impl_fn_sig(genv: &GlobalEnv, impl_f: DefId) -> Result<(), ErrorGuaranteed> {
+    let Some(trait_sig) = genv.trait_fn_sig_for_impl_fn(impl_f).emit(genv.sess)? else {
+        return Ok(());
+    };
+    let impl_sig = genv.lookup_fn_sig(impl_f).emit(genv.sess)?;
+    let span = genv.tcx.def_span(impl_f);
+
+    let substs = genv.identity_substs(impl_f).emit(genv.sess)?;
+
+    let mut kvars = fixpoint::KVarStore::new();
+    let refine_tree =
+        compare_impl::check_impl_fn_sig(genv, &impl_sig, &trait_sig, &substs, &mut kvars, span)
+            .map_err(|err| err.with_span(span))
+            .emit(genv.sess)?;
+
+    if CONFIG.dump_constraint {
+        dump_constraint(genv.tcx, impl_f, &refine_tree, ".impl.lrc").unwrap();
+    }
+
+    let mut fcx = fixpoint::FixpointCtxt::new(genv, impl_f, kvars);
+    let constraint = refine_tree.into_fixpoint(&mut fcx);
+    match fcx.check(constraint) {
+        Ok(_) => Ok(()),
+        Err(tags) => report_errors(genv, span, tags),
+    }
+}
+
 fn report_errors(
     genv: &GlobalEnv,
     body_span: Span,
     errors: Vec<Tag>,
 ) -> Result<(), ErrorGuaranteed> {
+    // Errors in code generated by a macro are reported at the call site of the macro.
+    let body_span = body_span.source_callsite();
     let mut e = None;
     for err in errors {
-        e = Some(match err {
+        e = Some(match err.at_call_site() {
             Tag::Call(span) => genv.sess.emit_err(errors::CallError { span }),
             Tag::Assign(span) => genv.sess.emit_err(errors::AssignError { span }),
             Tag::Ret => genv.sess.emit_err(errors::RetError { span: body_span }),
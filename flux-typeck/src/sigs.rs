@@ -1,7 +1,8 @@
 use std::{collections::HashMap, sync::LazyLock};
 
+use flux_common::config::CONFIG;
 use flux_middle::{
-    rty::{BinOp, Expr},
+    rty::{BinOp, Constant, Expr, IntTy, TheoryFunc, UintTy},
     rustc::mir,
 };
 use rustc_span::Span;
@@ -26,18 +27,32 @@ pub enum Output<const N: usize> {
     Exists(fn(Expr, [Expr; N]) -> Expr),
 }
 
-pub fn unsigned_bin_ops(op: mir::BinOp) -> Sig<2> {
-    UNSIGNED_BIN_OPS[&op]
+pub fn unsigned_bin_ops(op: mir::BinOp, uint_ty: UintTy) -> Sig<2> {
+    if is_bit_op(op) {
+        let width = uint_ty.bit_width().unwrap_or(CONFIG.pointer_width) as u32;
+        UNSIGNED_BIT_OPS[&(op, width)]
+    } else {
+        UNSIGNED_BIN_OPS[&op]
+    }
 }
 
-pub fn signed_bin_ops(op: mir::BinOp) -> Sig<2> {
-    SIGNED_BIN_OPS[&op]
+pub fn signed_bin_ops(op: mir::BinOp, int_ty: IntTy) -> Sig<2> {
+    if is_bit_op(op) {
+        let width = int_ty.bit_width().unwrap_or(CONFIG.pointer_width) as u32;
+        SIGNED_BIT_OPS[&(op, width)]
+    } else {
+        SIGNED_BIN_OPS[&op]
+    }
 }
 
 pub fn bool_bin_ops(op: mir::BinOp) -> Sig<2> {
     BOOL_BIN_OPS[&op]
 }
 
+pub fn float_bin_ops(op: mir::BinOp) -> Sig<2> {
+    FLOAT_BIN_OPS[&op]
+}
+
 macro_rules! output {
     (|$($args:ident),+| v = $out:expr) => {
         Output::Indexed(|[$($args),+]| $out)
@@ -81,7 +96,6 @@ static UNSIGNED_BIN_OPS: LazyLock<Sigs<mir::BinOp, 2>> = LazyLock::new(|| {
         (Rem, f!(|a,b|      Rem: E::ne(b, 0)     => v = E::binary_op(BinOp::Mod, a, b))),
         (Add,    f!(|a,b| v = a + b)),
         (Mul,    f!(|a,b| v = a * b)),
-        (BitAnd, f!(|a,b| { v : E::tt() })),
         // CMP
         (Eq, f!(|a,b| v = E::eq(a, b))),
         (Ne, f!(|a,b| v = E::ne(a, b))),
@@ -105,7 +119,6 @@ static SIGNED_BIN_OPS: LazyLock<Sigs<mir::BinOp, 2>> = LazyLock::new(|| {
                                                      E::and([E::ge(&a, 0), E::ge(&b, 0)]),
                                                      E::eq(v, E::binary_op(BinOp::Mod, a, b))) }),
         ),
-        (BitAnd, f!(|a,b| { v : E::tt() })),
         // CMP
         (Eq, f!(|a,b| v = E::eq(a, b))),
         (Ne, f!(|a,b| v = E::ne(a, b))),
@@ -121,6 +134,8 @@ static BOOL_BIN_OPS: LazyLock<Sigs<mir::BinOp, 2>> = LazyLock::new(|| {
     use mir::BinOp::*;
     HashMap::from([
         (BitAnd, f!(|a,b| v = E::and([a, b]))),
+        (BitOr,  f!(|a,b| v = E::binary_op(BinOp::Or, a, b))),
+        (BitXor, f!(|a,b| v = E::ne(a, b))),
         // CMP
         (Eq, f!(|a,b| v = E::eq(a, b))),
         (Ne, f!(|a,b| v = E::ne(a, b))),
@@ -130,3 +145,159 @@ static BOOL_BIN_OPS: LazyLock<Sigs<mir::BinOp, 2>> = LazyLock::new(|| {
         (Gt, f!(|a,b| v = E::and([a, b.not()]))),
     ])
 });
+
+/// Floating-point operations interpreted as operations over reals, i.e., ignoring rounding errors,
+/// infinities and NaN. These are only used when the `real_floats` setting is enabled. Division by
+/// zero does not panic for floats, so it has no precondition.
+#[rustfmt::skip]
+static FLOAT_BIN_OPS: LazyLock<Sigs<mir::BinOp, 2>> = LazyLock::new(|| {
+    use mir::BinOp::*;
+    HashMap::from([
+        // ARITH
+        (Add, f!(|a,b| v = a + b)),
+        (Sub, f!(|a,b| v = a - b)),
+        (Mul, f!(|a,b| v = a * b)),
+        (Div, f!(|a,b| v = a / b)),
+        (Rem, f!(|a,b| { v : E::tt() })),
+        // CMP
+        (Eq, f!(|a,b| v = E::eq(a, b))),
+        (Ne, f!(|a,b| v = E::ne(a, b))),
+        (Le, f!(|a,b| v = E::le(a, b))),
+        (Ge, f!(|a,b| v = E::ge(a, b))),
+        (Lt, f!(|a,b| v = E::lt(a, b))),
+        (Gt, f!(|a,b| v = E::gt(a, b))),
+    ])
+});
+
+fn is_bit_op(op: mir::BinOp) -> bool {
+    use mir::BinOp::*;
+    matches!(op, BitAnd | BitOr | BitXor | Shl | Shr)
+}
+
+/// Signatures for bitwise operators and shifts on unsigned integers indexed by the operator and
+/// the bit width of the integer type. The result is computed exactly using the theory of bit
+/// vectors (see [`unsigned_bit_ops`]). Fixpoint only supports conversions between integers and bit
+/// vectors of 32 and 64 bits, so for 128 bits we fall back to coarser facts over integers.
+#[rustfmt::skip]
+static UNSIGNED_BIT_OPS: LazyLock<Sigs<(mir::BinOp, u32), 2>> = LazyLock::new(|| {
+    use mir::BinOp::*;
+    HashMap::from_iter(itertools::chain!(
+        unsigned_bit_ops::<8, 32>(),
+        unsigned_bit_ops::<16, 32>(),
+        unsigned_bit_ops::<32, 32>(),
+        unsigned_bit_ops::<64, 64>(),
+        [
+            ((BitAnd, 128), f!(|a,b| { v : E::and([E::le(&v, a), E::le(&v, b)]) })),
+            ((BitOr,  128), f!(|a,b| { v : E::and([E::ge(&v, a), E::ge(&v, b)]) })),
+            ((BitXor, 128), f!(|a,b| { v : E::tt() })),
+            ((Shl,    128), f!(|a,b| { v : E::tt() })),
+            ((Shr,    128), f!(|a,b| { v : E::le(v, a) })),
+        ],
+    ))
+});
+
+/// Same as [`UNSIGNED_BIT_OPS`] but for signed integers. For 128 bits the results are deliberately
+/// left unrefined: the coarse facts used for unsigned integers do not hold for negative operands,
+/// and we have no bit vector conversion to compute the result exactly.
+#[rustfmt::skip]
+static SIGNED_BIT_OPS: LazyLock<Sigs<(mir::BinOp, u32), 2>> = LazyLock::new(|| {
+    use mir::BinOp::*;
+    HashMap::from_iter(itertools::chain!(
+        signed_bit_ops::<8, 32>(),
+        signed_bit_ops::<16, 32>(),
+        signed_bit_ops::<32, 32>(),
+        signed_bit_ops::<64, 64>(),
+        [
+            ((BitAnd, 128), f!(|a,b| { v : E::tt() })),
+            ((BitOr,  128), f!(|a,b| { v : E::tt() })),
+            ((BitXor, 128), f!(|a,b| { v : E::tt() })),
+            ((Shl,    128), f!(|a,b| { v : E::tt() })),
+            ((Shr,    128), f!(|a,b| { v : E::tt() })),
+        ],
+    ))
+});
+
+/// Bitwise operators and shifts on unsigned integers of `W` bits. Operands are truncated to `W`
+/// bits and converted to bit vectors of `BV` bits, the operation is performed on bit vectors, and
+/// the result is converted back and truncated to `W` bits. The shift amount is taken modulo `W`,
+/// matching the semantics of unchecked shifts in MIR. Besides the exact result, we also include
+/// some facts over integers which are easier to use for the solver, e.g., `a & b <= b`.
+#[rustfmt::skip]
+fn unsigned_bit_ops<const W: u32, const BV: u32>() -> [((mir::BinOp, u32), Sig<2>); 5] {
+    use mir::BinOp::*;
+    [
+        ((BitAnd, W), f!(|a,b| { v : E::and([E::eq(&v, ubv_to_int::<W, BV>(uint_to_bv::<W, BV>(&a) & uint_to_bv::<W, BV>(&b))),
+                                             E::le(&v, a),
+                                             E::le(&v, b)]) })),
+        ((BitOr,  W), f!(|a,b| { v : E::and([E::eq(&v, ubv_to_int::<W, BV>(uint_to_bv::<W, BV>(&a) | uint_to_bv::<W, BV>(&b))),
+                                             E::ge(&v, a),
+                                             E::ge(&v, b)]) })),
+        ((BitXor, W), f!(|a,b| v = ubv_to_int::<W, BV>(uint_to_bv::<W, BV>(a) ^ uint_to_bv::<W, BV>(b)))),
+        ((Shl,    W), f!(|a,b| v = ubv_to_int::<W, BV>(uint_to_bv::<W, BV>(a) << shift_amount::<W, BV>(b)))),
+        ((Shr,    W), f!(|a,b| { v : E::and([E::eq(&v, ubv_to_int::<W, BV>(uint_to_bv::<W, BV>(&a) >> shift_amount::<W, BV>(b))),
+                                             E::le(&v, a)]) })),
+    ]
+}
+
+/// Bitwise operators and shifts on signed integers of `W` bits. Operands are converted to bit
+/// vectors of `BV` bits, which sign-extends negative integers. The operation is performed on bit
+/// vectors, and the result is converted back, truncated to `W` bits and interpreted in two's
+/// complement. Right shifts are arithmetic.
+#[rustfmt::skip]
+fn signed_bit_ops<const W: u32, const BV: u32>() -> [((mir::BinOp, u32), Sig<2>); 5] {
+    use mir::BinOp::*;
+    [
+        ((BitAnd, W), f!(|a,b| v = sbv_to_int::<W, BV>(int_to_bv::<BV>(a) & int_to_bv::<BV>(b)))),
+        ((BitOr,  W), f!(|a,b| v = sbv_to_int::<W, BV>(int_to_bv::<BV>(a) | int_to_bv::<BV>(b)))),
+        ((BitXor, W), f!(|a,b| v = sbv_to_int::<W, BV>(int_to_bv::<BV>(a) ^ int_to_bv::<BV>(b)))),
+        ((Shl,    W), f!(|a,b| v = sbv_to_int::<W, BV>(int_to_bv::<BV>(a) << shift_amount::<W, BV>(b)))),
+        ((Shr,    W), f!(|a,b| v = sbv_to_int::<W, BV>(E::theory_app(TheoryFunc::BvAShr,
+                                                        vec![int_to_bv::<BV>(a), shift_amount::<W, BV>(b)])))),
+    ]
+}
+
+/// Converts an unsigned integer of `W` bits to a bit vector of `BV` bits.
+fn uint_to_bv<const W: u32, const BV: u32>(e: impl Into<E>) -> E {
+    int_to_bv::<BV>(truncate::<W, BV>(e.into()))
+}
+
+/// Converts a bit vector of `BV` bits to an unsigned integer of `W` bits.
+fn ubv_to_int<const W: u32, const BV: u32>(e: E) -> E {
+    truncate::<W, BV>(bv_to_int::<BV>(e))
+}
+
+/// Converts a bit vector of `BV` bits to a signed integer of `W` bits.
+fn sbv_to_int<const W: u32, const BV: u32>(e: E) -> E {
+    let e = truncate::<W, BV>(bv_to_int::<BV>(e));
+    E::ite(E::ge(&e, pow2(W - 1)), &e - pow2(W), e)
+}
+
+/// Truncates a non-negative integer to `W` bits. This is a no-op if `W` is the width of the bit
+/// vectors, because the conversion from bit vectors already produces an integer in range.
+fn truncate<const W: u32, const BV: u32>(e: E) -> E {
+    if W < BV {
+        E::binary_op(BinOp::Mod, e, pow2(W))
+    } else {
+        e
+    }
+}
+
+fn shift_amount<const W: u32, const BV: u32>(e: E) -> E {
+    int_to_bv::<BV>(E::binary_op(BinOp::Mod, e, E::constant(Constant::from(W as u128))))
+}
+
+/// Converts an integer into a bit vector of `BV` bits. Negative integers are sign-extended.
+fn int_to_bv<const BV: u32>(e: impl Into<E>) -> E {
+    let func = if BV == 32 { TheoryFunc::IntToBv32 } else { TheoryFunc::IntToBv64 };
+    E::theory_app(func, vec![e.into()])
+}
+
+/// Converts a bit vector of `BV` bits into a non-negative integer.
+fn bv_to_int<const BV: u32>(e: E) -> E {
+    let func = if BV == 32 { TheoryFunc::Bv32ToInt } else { TheoryFunc::Bv64ToInt };
+    E::theory_app(func, vec![e])
+}
+
+fn pow2(n: u32) -> E {
+    E::constant(Constant::from(1u128 << n))
+}
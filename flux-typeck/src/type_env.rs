@@ -5,11 +5,11 @@ use std::iter;
 use flux_common::index::IndexGen;
 use flux_middle::{
     fhir::WeakKind,
-    global_env::{GlobalEnv, OpaqueStructErr},
+    global_env::GlobalEnv,
     intern::List,
     rty::{
-        box_args, fold::TypeFoldable, subst::FVarSubst, BaseTy, Binders, Expr, GenericArg, Path,
-        RefKind, RefineArg, RefineArgs, Ty, TyKind,
+        box_args, fold::TypeFoldable, subst::FVarSubst, BaseTy, Binders, Expr, FnSig, GenericArg,
+        Path, PolySig, RefKind, RefineArg, RefineArgs, Ty, TyKind,
     },
     rustc::mir::{Local, Place, PlaceElem},
 };
@@ -20,6 +20,7 @@ use rustc_middle::ty::TyCtxt;
 use self::paths_tree::{Binding, FoldResult, LocKind, PathsTree};
 use super::rty::{Loc, Name, Pred, Sort};
 use crate::{
+    checker::errors::CheckerError,
     constraint_gen::ConstrGen,
     fixpoint::KVarGen,
     param_infer,
@@ -81,7 +82,7 @@ impl TypeEnv {
         rcx: &mut RefineCtxt,
         gen: &mut ConstrGen,
         place: &Place,
-    ) -> Result<Ty, OpaqueStructErr> {
+    ) -> Result<Ty, CheckerError> {
         Ok(self
             .bindings
             .lookup(gen.genv, rcx, place)?
@@ -94,7 +95,7 @@ impl TypeEnv {
         rcx: &mut RefineCtxt,
         gen: &mut ConstrGen,
         path: &Path,
-    ) -> Result<Ty, OpaqueStructErr> {
+    ) -> Result<Ty, CheckerError> {
         Ok(self
             .bindings
             .lookup(gen.genv, rcx, path)?
@@ -112,7 +113,7 @@ impl TypeEnv {
         gen: &mut ConstrGen,
         rk: RefKind,
         place: &Place,
-    ) -> Result<Ty, OpaqueStructErr> {
+    ) -> Result<Ty, CheckerError> {
         let ty = match self
             .bindings
             .lookup(gen.genv, rcx, place)?
@@ -133,7 +134,7 @@ impl TypeEnv {
         gen: &mut ConstrGen,
         place: &Place,
         new_ty: Ty,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         match self
             .bindings
             .lookup(gen.genv, rcx, place)?
@@ -142,10 +143,11 @@ impl TypeEnv {
             FoldResult::Strg(path, _) => {
                 self.bindings.update(&path, new_ty);
             }
-            FoldResult::Weak(WeakKind::Mut, ty) => {
+            // Elements of an owned array are updated weakly, as if behind a `&mut` reference.
+            FoldResult::Weak(WeakKind::Mut | WeakKind::Arr, ty) => {
                 gen.subtyping(rcx, &new_ty, &ty);
             }
-            FoldResult::Weak(WeakKind::Arr, _) | FoldResult::Weak(WeakKind::Shr, _) => {
+            FoldResult::Weak(WeakKind::Shr, _) => {
                 panic!("cannot assign to `{place:?}`, which is behind a `&` reference")
             }
         }
@@ -157,7 +159,7 @@ impl TypeEnv {
         rcx: &mut RefineCtxt,
         gen: &mut ConstrGen,
         place: &Place,
-    ) -> Result<Ty, OpaqueStructErr> {
+    ) -> Result<Ty, CheckerError> {
         match self
             .bindings
             .lookup(gen.genv, rcx, place)?
@@ -189,14 +191,21 @@ impl TypeEnv {
         self.bindings.block(path);
     }
 
+    /// Converts a pointer to `path` into a borrow with the type currently stored at `path`. The
+    /// path is blocked while the borrow is alive.
+    pub fn ptr_to_borrow(&mut self, rk: RefKind, path: &Path) -> Ty {
+        let ty = self.get(path);
+        self.block(path);
+        Ty::mk_ref(rk, ty)
+    }
+
     fn infer_subst_for_bb_env(&self, bb_env: &BasicBlockEnv) -> FVarSubst {
         let params = bb_env.params.iter().map(|(name, _)| *name).collect();
         let mut subst = FVarSubst::empty();
         self.bindings.iter(|path, binding1| {
-            let binding2 = bb_env.bindings.get(&path);
             if bb_env.bindings.contains_loc(path.loc)
               && let Binding::Owned(ty1) = binding1
-              && let Binding::Owned(ty2) = binding2 {
+              && let Binding::Owned(ty2) = bb_env.bindings.get(&path) {
                 self.infer_subst_for_bb_env_ty(bb_env, &params, ty1, &ty2, &mut subst);
             }
         });
@@ -281,7 +290,7 @@ impl TypeEnv {
         rcx: &mut RefineCtxt,
         gen: &mut ConstrGen,
         bb_env: &BasicBlockEnv,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         self.bindings.close_boxes(rcx, gen, &bb_env.scope);
 
         // Look up paths to make sure they are properly folded/unfolded
@@ -344,7 +353,7 @@ impl TypeEnv {
         rcx: &mut RefineCtxt,
         place: &Place,
         variant_idx: VariantIdx,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         let mut down_place = place.clone();
         down_place.projection.push(PlaceElem::Downcast(variant_idx));
         self.bindings.lookup(genv, rcx, &down_place)?;
@@ -439,14 +448,52 @@ impl TypeEnvInfer {
                 );
                 BaseTy::adt(adt_def.clone(), substs)
             }
-            BaseTy::Array(ty, c) => BaseTy::Array(Self::pack_ty(scope, ty), c.clone()),
+            BaseTy::Array(ty) => BaseTy::Array(Self::pack_ty(scope, ty)),
             BaseTy::Slice(ty) => BaseTy::Slice(Self::pack_ty(scope, ty)),
+            BaseTy::Closure(def_id, upvar_tys) => {
+                let upvar_tys = upvar_tys
+                    .iter()
+                    .map(|ty| Self::pack_ty(scope, ty))
+                    .collect();
+                BaseTy::Closure(*def_id, upvar_tys)
+            }
+            BaseTy::FnDef(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| Self::pack_generic_arg(scope, arg))
+                    .collect();
+                BaseTy::FnDef(*def_id, substs)
+            }
+            BaseTy::Dynamic(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| Self::pack_generic_arg(scope, arg))
+                    .collect();
+                BaseTy::Dynamic(*def_id, substs)
+            }
+            BaseTy::Opaque(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| Self::pack_generic_arg(scope, arg))
+                    .collect();
+                BaseTy::Opaque(*def_id, substs)
+            }
+            BaseTy::Projection(def_id, substs) => {
+                let substs = substs
+                    .iter()
+                    .map(|arg| Self::pack_generic_arg(scope, arg))
+                    .collect();
+                BaseTy::Projection(*def_id, substs)
+            }
             BaseTy::Int(_)
             | BaseTy::Uint(_)
             | BaseTy::Bool
             | BaseTy::Float(_)
+            | BaseTy::Real(_)
             | BaseTy::Str
-            | BaseTy::Char => bty.clone(),
+            | BaseTy::Char
+            | BaseTy::FnPtr(_)
+            | BaseTy::Param(_) => bty.clone(),
         }
     }
 
@@ -454,6 +501,7 @@ impl TypeEnvInfer {
         match arg {
             GenericArg::Ty(ty) => GenericArg::Ty(Self::pack_ty(scope, ty)),
             GenericArg::Lifetime => GenericArg::Lifetime,
+            GenericArg::Const(e) => GenericArg::Const(e.clone()),
         }
     }
 
@@ -583,7 +631,17 @@ impl TypeEnvInfer {
                 debug_assert_eq!(rk1, rk2);
                 Ty::mk_ref(*rk1, self.join_ty(ty1, ty2))
             }
-            (TyKind::Param(param_ty1), TyKind::Param(param_ty2)) => {
+            (TyKind::Param(param_ty1), TyKind::Param(param_ty2))
+            | (
+                TyKind::Param(param_ty1),
+                TyKind::Indexed(BaseTy::Param(param_ty2), _)
+                | TyKind::Exists(BaseTy::Param(param_ty2), _),
+            )
+            | (
+                TyKind::Indexed(BaseTy::Param(param_ty1), _)
+                | TyKind::Exists(BaseTy::Param(param_ty1), _),
+                TyKind::Param(param_ty2),
+            ) => {
                 debug_assert_eq!(param_ty1, param_ty2);
                 Ty::param(*param_ty1)
             }
@@ -599,15 +657,37 @@ impl TypeEnvInfer {
     }
 
     fn join_bty(&self, bty1: &BaseTy, bty2: &BaseTy) -> BaseTy {
-        if let (BaseTy::Adt(def1, substs1), BaseTy::Adt(def2, substs2)) = (bty1, bty2) {
-            debug_assert_eq!(def1.def_id(), def2.def_id());
-            let substs = iter::zip(substs1, substs2)
-                .map(|(arg1, arg2)| self.join_generic_arg(arg1, arg2))
-                .collect();
-            BaseTy::adt(def1.clone(), List::from_vec(substs))
-        } else {
-            debug_assert_eq!(bty1, bty2);
-            bty1.clone()
+        match (bty1, bty2) {
+            (BaseTy::Adt(def1, substs1), BaseTy::Adt(def2, substs2)) => {
+                debug_assert_eq!(def1.def_id(), def2.def_id());
+                let substs = iter::zip(substs1, substs2)
+                    .map(|(arg1, arg2)| self.join_generic_arg(arg1, arg2))
+                    .collect();
+                BaseTy::adt(def1.clone(), List::from_vec(substs))
+            }
+            (BaseTy::Closure(def_id1, upvar_tys1), BaseTy::Closure(def_id2, upvar_tys2)) => {
+                debug_assert_eq!(def_id1, def_id2);
+                let upvar_tys = iter::zip(upvar_tys1, upvar_tys2)
+                    .map(|(ty1, ty2)| self.join_ty(ty1, ty2))
+                    .collect();
+                BaseTy::Closure(*def_id1, upvar_tys)
+            }
+            (BaseTy::Array(ty1), BaseTy::Array(ty2)) => BaseTy::Array(self.join_ty(ty1, ty2)),
+            (BaseTy::Slice(ty1), BaseTy::Slice(ty2)) => BaseTy::Slice(self.join_ty(ty1, ty2)),
+            // Function pointers with different signatures are joined by forgetting all refinements
+            // in the signature. Both sides are then checked to be a subtype of the unrefined
+            // signature when jumping to the join point.
+            (BaseTy::FnPtr(fn_sig1), BaseTy::FnPtr(fn_sig2)) => {
+                if fn_sig1 == fn_sig2 {
+                    bty1.clone()
+                } else {
+                    BaseTy::FnPtr(unrefined_fn_sig(fn_sig1))
+                }
+            }
+            _ => {
+                debug_assert_eq!(bty1, bty2);
+                bty1.clone()
+            }
         }
     }
 
@@ -615,6 +695,8 @@ impl TypeEnvInfer {
         match (arg1, arg2) {
             (GenericArg::Ty(ty1), GenericArg::Ty(ty2)) => GenericArg::Ty(self.join_ty(ty1, ty2)),
             (GenericArg::Lifetime, GenericArg::Lifetime) => GenericArg::Lifetime,
+            // Const arguments come from the Rust types, which are known to be equal
+            (GenericArg::Const(e), GenericArg::Const(_)) => GenericArg::Const(e.clone()),
             _ => panic!("incompatible generic args: `{arg1:?}` `{arg2:?}`"),
         }
     }
@@ -662,6 +744,20 @@ impl TypeEnvInfer {
 
 /// This is effectively doing the same as [`RefineCtxt::unpack`] but for moving existentials
 /// to the top level in a [`BasicBlockEnv`]. Maybe we should find a way to abstract over it.
+fn unrefined_fn_sig(fn_sig: &PolySig) -> PolySig {
+    fn unrefined(ty: &Ty) -> Ty {
+        match ty.kind() {
+            TyKind::Constr(_, ty) => unrefined(ty),
+            _ => ty.with_holes(),
+        }
+        .replace_holes(&mut |sorts| Binders::new(Pred::tt(), sorts))
+    }
+    let fn_sig = fn_sig.as_ref().skip_binders();
+    let args = fn_sig.args().iter().map(unrefined).collect_vec();
+    let ret = unrefined(fn_sig.ret());
+    PolySig::new(FnSig::new(vec![], args, ret, vec![]), vec![])
+}
+
 fn generalize(
     name_gen: &IndexGen<Name>,
     ty: &Ty,
@@ -2,11 +2,11 @@ use std::{cell::RefCell, iter, rc::Rc};
 
 use flux_middle::{
     fhir::WeakKind,
-    global_env::{GlobalEnv, OpaqueStructErr},
+    global_env::GlobalEnv,
     rty::{
         box_args,
         fold::{TypeFoldable, TypeFolder, TypeVisitor},
-        AdtDef, BaseTy, Expr, GenericArg, Loc, Path, RefineArg, Sort, Substs, Ty, TyKind,
+        AdtDef, BaseTy, BinOp, Expr, GenericArg, Loc, Path, RefineArg, Sort, Substs, Ty, TyKind,
         VariantIdx,
     },
     rustc::mir::{Field, Place, PlaceElem},
@@ -16,6 +16,7 @@ use rustc_hash::FxHashMap;
 use rustc_hir::def_id::DefId;
 
 use crate::{
+    checker::errors::CheckerError,
     constraint_gen::ConstrGen,
     refine_tree::{RefineCtxt, Scope, UnpackFlags},
 };
@@ -209,7 +210,7 @@ impl PathsTree {
         genv: &GlobalEnv,
         rcx: &mut RefineCtxt,
         key: &impl LookupKey,
-    ) -> Result<LookupResult<'a>, OpaqueStructErr> {
+    ) -> Result<LookupResult<'a>, CheckerError> {
         let mut path = Path::from(key.loc());
         let place_proj = &mut key.proj();
 
@@ -269,10 +270,10 @@ impl PathsTree {
                             _ => panic!("Unsupported Deref: {elem:?} {ty:?}"),
                         }
                     }
-                    PlaceElem::Index(_) => {
+                    PlaceElem::Index(_) | PlaceElem::ConstantIndex { .. } => {
                         let ty = ptr.borrow().expect_owned();
                         match ty.kind() {
-                            TyKind::Indexed(BaseTy::Array(arr_ty, _), _) => {
+                            TyKind::Indexed(BaseTy::Array(arr_ty) | BaseTy::Slice(arr_ty), _) => {
                                 let (rk, ty) =
                                     Self::lookup_ty(genv, rcx, WeakKind::Arr, arr_ty, place_proj)?;
                                 return Ok(LookupResult {
@@ -283,6 +284,11 @@ impl PathsTree {
                             _ => panic!("Unsupported Index: {elem:?} {ty:?}"),
                         }
                     }
+                    PlaceElem::Subslice { from, to, from_end } => {
+                        let ty = subslice(rcx, &ptr.borrow().expect_owned(), from, to, from_end)?;
+                        let (rk, ty) = Self::lookup_ty(genv, rcx, WeakKind::Arr, &ty, place_proj)?;
+                        return Ok(LookupResult { tree: self, kind: LookupKind::Weak(rk, ty) });
+                    }
                 }
             }
 
@@ -299,7 +305,7 @@ impl PathsTree {
         mut rk: WeakKind,
         ty: &Ty,
         proj: &mut impl Iterator<Item = PlaceElem>,
-    ) -> Result<(WeakKind, Ty), OpaqueStructErr> {
+    ) -> Result<(WeakKind, Ty), CheckerError> {
         use PlaceElem::*;
         let mut ty = ty.clone();
         for elem in proj.by_ref() {
@@ -312,7 +318,10 @@ impl PathsTree {
                     let (boxed, _) = box_args(substs);
                     ty = boxed.clone();
                 }
-                (Field(field), TyKind::Tuple(tys)) => {
+                (
+                    Field(field),
+                    TyKind::Tuple(tys) | TyKind::Indexed(BaseTy::Closure(_, tys), _),
+                ) => {
                     ty = tys[field.as_usize()].clone();
                 }
                 (Field(field), TyKind::Indexed(BaseTy::Adt(adt, substs), idxs)) => {
@@ -331,6 +340,16 @@ impl PathsTree {
                         downcast(genv, rcx, adt_def.def_id(), variant_idx, substs, idxs.args())?;
                     ty = rcx.unpack_with(&Ty::tuple(tys), UnpackFlags::INVARIANTS);
                 }
+                (
+                    Index(_) | ConstantIndex { .. },
+                    TyKind::Indexed(BaseTy::Array(elem_ty) | BaseTy::Slice(elem_ty), _)
+                    | TyKind::Exists(BaseTy::Array(elem_ty) | BaseTy::Slice(elem_ty), _),
+                ) => {
+                    ty = elem_ty.clone();
+                }
+                (Subslice { from, to, from_end }, _) => {
+                    ty = subslice(rcx, &ty, from, to, from_end)?;
+                }
                 _ => todo!("{elem:?} {ty:?}"),
             }
         }
@@ -385,6 +404,7 @@ pub enum Binding {
 enum NodeKind {
     Adt(AdtDef, VariantIdx, Substs),
     Tuple,
+    Closure(DefId),
     Uninit,
 }
 
@@ -483,7 +503,7 @@ impl Node {
         genv: &GlobalEnv,
         rcx: &mut RefineCtxt,
         field: Field,
-    ) -> Result<&NodePtr, OpaqueStructErr> {
+    ) -> Result<&NodePtr, CheckerError> {
         if let Node::Leaf(_) = self {
             self.split(genv, rcx)?;
         }
@@ -504,7 +524,7 @@ impl Node {
         genv: &GlobalEnv,
         rcx: &mut RefineCtxt,
         variant_idx: VariantIdx,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         match self {
             Node::Leaf(Binding::Owned(ty)) => {
                 let ty = ty.unconstr();
@@ -541,7 +561,7 @@ impl Node {
         Ok(())
     }
 
-    fn split(&mut self, genv: &GlobalEnv, rcx: &mut RefineCtxt) -> Result<(), OpaqueStructErr> {
+    fn split(&mut self, genv: &GlobalEnv, rcx: &mut RefineCtxt) -> Result<(), CheckerError> {
         let ty = self.expect_owned();
         match ty.kind() {
             TyKind::Tuple(tys) => {
@@ -552,6 +572,14 @@ impl Node {
                     .collect();
                 *self = Node::Internal(NodeKind::Tuple, children);
             }
+            TyKind::Indexed(BaseTy::Closure(def_id, upvar_tys), _) => {
+                let children = upvar_tys
+                    .iter()
+                    .cloned()
+                    .map(|ty| Node::owned(ty).into_ptr())
+                    .collect();
+                *self = Node::Internal(NodeKind::Closure(*def_id), children);
+            }
             TyKind::Indexed(BaseTy::Adt(def, ..), ..) if def.is_struct() => {
                 self.downcast(genv, rcx, VariantIdx::from_u32(0))?;
             }
@@ -600,6 +628,19 @@ impl Node {
                 *self = Node::owned(ty.clone());
                 ty
             }
+            Node::Internal(NodeKind::Closure(def_id), children) => {
+                let upvar_tys = children
+                    .iter_mut()
+                    .map(|node| node.fold(map, rcx, gen, unblock, close_boxes))
+                    .collect_vec();
+                let ty = if upvar_tys.iter().any(|ty| ty.is_uninit()) {
+                    Ty::uninit()
+                } else {
+                    Ty::closure(*def_id, upvar_tys)
+                };
+                *self = Node::owned(ty.clone());
+                ty
+            }
             Node::Internal(NodeKind::Adt(adt_def, variant_idx, substs), children) => {
                 let variant = gen.genv.variant(adt_def.def_id(), *variant_idx).unwrap();
                 let fields = children
@@ -668,7 +709,7 @@ impl NodePtr {
         genv: &GlobalEnv,
         rcx: &mut RefineCtxt,
         field: Field,
-    ) -> Result<NodePtr, OpaqueStructErr> {
+    ) -> Result<NodePtr, CheckerError> {
         Ok(NodePtr::clone(self.borrow_mut().proj(genv, rcx, field)?))
     }
 
@@ -677,7 +718,7 @@ impl NodePtr {
         genv: &GlobalEnv,
         rcx: &mut RefineCtxt,
         variant_idx: VariantIdx,
-    ) -> Result<(), OpaqueStructErr> {
+    ) -> Result<(), CheckerError> {
         self.borrow_mut().downcast(genv, rcx, variant_idx)
     }
 }
@@ -718,6 +759,36 @@ impl TypeFoldable for Binding {
     }
 }
 
+/// The type of a subslice produced by a slice pattern. The length of the subslice is computed
+/// from the length of the array or slice.
+fn subslice(
+    rcx: &mut RefineCtxt,
+    ty: &Ty,
+    from: u64,
+    to: u64,
+    from_end: bool,
+) -> Result<Ty, CheckerError> {
+    let from = Expr::from(from as usize);
+    let to = Expr::from(to as usize);
+    let ty = match rcx.unpack(ty).kind() {
+        TyKind::Indexed(BaseTy::Slice(elem_ty), idxs) if from_end => {
+            let len = idxs.nth(0).as_expr().clone();
+            let len = Expr::binary_op(BinOp::Sub, Expr::binary_op(BinOp::Sub, len, from), to);
+            Ty::slice(elem_ty.clone(), len)
+        }
+        TyKind::Indexed(BaseTy::Array(elem_ty), idxs) if from_end => {
+            let len = idxs.nth(0).as_expr().clone();
+            let len = Expr::binary_op(BinOp::Sub, Expr::binary_op(BinOp::Sub, len, from), to);
+            Ty::array(elem_ty.clone(), len)
+        }
+        TyKind::Indexed(BaseTy::Array(elem_ty), _) => {
+            Ty::array(elem_ty.clone(), Expr::binary_op(BinOp::Sub, to, from))
+        }
+        _ => return Err(CheckerError::unsupported(format!("subslice of `{ty:?}`"))),
+    };
+    Ok(ty)
+}
+
 fn downcast(
     genv: &GlobalEnv,
     rcx: &mut RefineCtxt,
@@ -725,10 +796,14 @@ fn downcast(
     variant_idx: VariantIdx,
     substs: &[GenericArg],
     args: &[RefineArg],
-) -> Result<Vec<Ty>, OpaqueStructErr> {
-    if genv.tcx.adt_def(def_id).is_struct() {
+) -> Result<Vec<Ty>, CheckerError> {
+    let adt_def = genv.tcx.adt_def(def_id);
+    // The variant of a reflected struct is indexed by a constructor application, so we downcast
+    // it like the variant of an enum.
+    let reflected = genv.map().data_decl(def_id).is_some();
+    if adt_def.is_struct() && !reflected {
         downcast_struct(genv, def_id, variant_idx, substs, args)
-    } else if genv.tcx.adt_def(def_id).is_enum() {
+    } else if adt_def.is_enum() || reflected {
         downcast_enum(genv, rcx, def_id, variant_idx, substs, args)
     } else {
         panic!("Downcast without struct or enum!")
@@ -749,7 +824,7 @@ fn downcast_struct(
     variant_idx: VariantIdx,
     substs: &[GenericArg],
     args: &[RefineArg],
-) -> Result<Vec<Ty>, OpaqueStructErr> {
+) -> Result<Vec<Ty>, CheckerError> {
     Ok(genv
         .variant(def_id, variant_idx)?
         .replace_bound_vars(args)
@@ -773,7 +848,7 @@ fn downcast_enum(
     variant_idx: VariantIdx,
     substs: &[GenericArg],
     args: &[RefineArg],
-) -> Result<Vec<Ty>, OpaqueStructErr> {
+) -> Result<Vec<Ty>, CheckerError> {
     let variant_def = genv
         .variant(def_id, variant_idx)?
         .replace_bvars_with_fresh_fvars(|sort| rcx.define_var(sort))
@@ -16,6 +16,7 @@ pub struct Wf<'a> {
     map: &'a fhir::Map,
 }
 
+#[derive(Clone)]
 struct Env<'a> {
     sorts: FxHashMap<fhir::Name, &'a fhir::Sort>,
 }
@@ -45,6 +46,13 @@ impl<'a> Env<'a> {
         }
         r
     }
+
+    /// Returns a copy of the environment extended with a variable bound by a quantifier.
+    fn with_quantified(&self, var: fhir::Name) -> Env<'a> {
+        let mut env = self.clone();
+        env.sorts.insert(var, &fhir::Sort::Int);
+        env
+    }
 }
 
 impl<'a, T: Borrow<fhir::Name>> std::ops::Index<T> for Env<'a> {
@@ -68,6 +76,17 @@ impl<'a> Wf<'a> {
         self.check_expr(&env, &qualifier.expr, &fhir::Sort::Bool)
     }
 
+    pub fn check_axiom(&self, axiom: &fhir::Axiom) -> Result<(), ErrorGuaranteed> {
+        let env = Env::new(&axiom.args);
+
+        self.check_expr(&env, &axiom.expr, &fhir::Sort::Bool)
+    }
+
+    pub fn check_defn(&self, defn: &fhir::Defn) -> Result<(), ErrorGuaranteed> {
+        let env = Env::new(&defn.args);
+        self.check_expr(&env, &defn.expr, defn.sort.output())
+    }
+
     pub fn check_adt_def(&self, adt_def: &fhir::AdtDef) -> Result<(), ErrorGuaranteed> {
         let env = Env::new(&adt_def.refined_by.params);
         adt_def
@@ -111,6 +130,10 @@ impl<'a> Wf<'a> {
         Ok(())
     }
 
+    pub fn check_static_ty(&self, ty: &fhir::Ty) -> Result<(), ErrorGuaranteed> {
+        self.check_type(&mut Env::new(&[]), ty)
+    }
+
     pub fn check_struct_def(
         &self,
         refined_by: &fhir::RefinedBy,
@@ -215,14 +238,25 @@ impl<'a> Wf<'a> {
                 self.check_pred(env, pred)?;
                 self.check_type(env, ty)
             }
-            fhir::Ty::Ref(_, ty) | fhir::Ty::Slice(ty) | fhir::Ty::Array(ty, _) => {
+            fhir::Ty::Array(ty, len) | fhir::Ty::Slice(ty, len) => {
+                if let fhir::ArrayLen::Arg(arg) = len {
+                    self.check_arg(env, arg, &fhir::Sort::Int)?;
+                }
                 self.check_type(env, ty)
             }
+            fhir::Ty::Ref(_, ty) => self.check_type(env, ty),
+            fhir::Ty::FnPtr(inputs, output) => {
+                inputs
+                    .iter()
+                    .chain([&**output])
+                    .try_for_each_exhaust(|ty| self.check_type(env, ty))
+            }
             fhir::Ty::Never
             | fhir::Ty::Param(_)
             | fhir::Ty::Float(_)
-            | fhir::Ty::Str
-            | fhir::Ty::Char => Ok(()),
+            | fhir::Ty::Const(_)
+            | fhir::Ty::Opaque(_)
+            | fhir::Ty::Projection(..) => Ok(()),
         }
     }
 
@@ -234,7 +268,13 @@ impl<'a> Wf<'a> {
                     .map(|ty| self.check_type(env, ty))
                     .try_collect_exhaust()
             }
-            fhir::BaseTy::Int(_) | fhir::BaseTy::Uint(_) | fhir::BaseTy::Bool => Ok(()),
+            fhir::BaseTy::Int(_)
+            | fhir::BaseTy::Uint(_)
+            | fhir::BaseTy::Bool
+            | fhir::BaseTy::Float(_)
+            | fhir::BaseTy::Str
+            | fhir::BaseTy::Char
+            | fhir::BaseTy::Param(_) => Ok(()),
         }
     }
 
@@ -265,10 +305,7 @@ impl<'a> Wf<'a> {
     ) -> Result<(), ErrorGuaranteed> {
         match arg {
             fhir::RefineArg::Expr { expr, .. } => {
-                let found = self.synth_expr(env, expr)?;
-                if found != expected {
-                    return self.emit_err(errors::SortMismatch::new(expr.span, expected, found));
-                }
+                self.check_expr(env, expr, expected)?;
                 self.check_param_uses(env, expr, false)
             }
             fhir::RefineArg::Abs(params, body, span) => {
@@ -296,11 +333,25 @@ impl<'a> Wf<'a> {
         e: &fhir::Expr,
         expected: &fhir::Sort,
     ) -> Result<(), ErrorGuaranteed> {
+        // The sort of the empty set and of constant maps cannot be synthesized from their
+        // arguments, so they can only be checked against an expected sort.
+        if let fhir::ExprKind::App(fhir::Func::Theory(func), args) = &e.kind {
+            match (func, expected) {
+                (fhir::TheoryFunc::SetEmpty, fhir::Sort::Set(_)) => {
+                    return self.check_theory_arity(*func, args, e.span);
+                }
+                (fhir::TheoryFunc::MapDefault, fhir::Sort::Map(_, v)) => {
+                    self.check_theory_arity(*func, args, e.span)?;
+                    return self.check_expr(env, &args[0], v);
+                }
+                _ => {}
+            }
+        }
         let found = self.synth_expr(env, e)?;
-        if found == expected {
+        if &found == expected {
             Ok(())
         } else {
-            self.emit_err(errors::SortMismatch::new(e.span, expected, found))
+            self.emit_err(errors::SortMismatch::new(e.span, expected, &found))
         }
     }
 
@@ -313,19 +364,29 @@ impl<'a> Wf<'a> {
         }
     }
 
-    fn synth_expr(&self, env: &Env<'a>, e: &'a fhir::Expr) -> Result<&fhir::Sort, ErrorGuaranteed> {
+    fn synth_expr(&self, env: &Env<'a>, e: &'a fhir::Expr) -> Result<fhir::Sort, ErrorGuaranteed> {
         match &e.kind {
-            fhir::ExprKind::Var(var, ..) => Ok(env[var]),
+            fhir::ExprKind::Var(var, ..) => Ok(env[var].clone()),
             fhir::ExprKind::Literal(lit) => Ok(synth_lit(*lit)),
             fhir::ExprKind::BinaryOp(op, box [e1, e2]) => self.synth_binary_op(env, *op, e1, e2),
-            fhir::ExprKind::Const(_, _) => Ok(&fhir::Sort::Int), // TODO: generalize const sorts
+            fhir::ExprKind::Const(_, _) => Ok(fhir::Sort::Int), // TODO: generalize const sorts
+            fhir::ExprKind::ConstParam(..) => Ok(fhir::Sort::Int),
+            fhir::ExprKind::App(fhir::Func::Theory(func), es) => {
+                self.synth_theory_app(env, *func, es, e.span)
+            }
             fhir::ExprKind::App(f, es) => self.synth_app(env, f, es, e.span),
             fhir::ExprKind::IfThenElse(box [p, e1, e2]) => {
                 self.check_expr(env, p, &fhir::Sort::Bool)?;
                 let sort = self.synth_expr(env, e1)?;
-                self.check_expr(env, e2, sort)?;
+                self.check_expr(env, e2, &sort)?;
                 Ok(sort)
             }
+            fhir::ExprKind::Quant(_, var, box [lo, hi, body]) => {
+                self.check_expr(env, lo, &fhir::Sort::Int)?;
+                self.check_expr(env, hi, &fhir::Sort::Int)?;
+                self.check_expr(&env.with_quantified(var.name), body, &fhir::Sort::Bool)?;
+                Ok(fhir::Sort::Bool)
+            }
         }
     }
 
@@ -333,41 +394,74 @@ impl<'a> Wf<'a> {
         &self,
         env: &Env<'a>,
         op: fhir::BinOp,
-        e1: &fhir::Expr,
-        e2: &fhir::Expr,
-    ) -> Result<&'a fhir::Sort, ErrorGuaranteed> {
+        e1: &'a fhir::Expr,
+        e2: &'a fhir::Expr,
+    ) -> Result<fhir::Sort, ErrorGuaranteed> {
         match op {
             fhir::BinOp::Or | fhir::BinOp::And | fhir::BinOp::Iff | fhir::BinOp::Imp => {
                 self.check_expr(env, e1, &fhir::Sort::Bool)?;
                 self.check_expr(env, e2, &fhir::Sort::Bool)?;
-                Ok(&fhir::Sort::Bool)
+                Ok(fhir::Sort::Bool)
             }
             fhir::BinOp::Eq | fhir::BinOp::Ne => {
                 let s = self.synth_expr(env, e1)?;
-                self.check_expr(env, e2, s)?;
-                Ok(&fhir::Sort::Bool)
+                self.check_expr(env, e2, &s)?;
+                Ok(fhir::Sort::Bool)
             }
             fhir::BinOp::Lt | fhir::BinOp::Le | fhir::BinOp::Gt | fhir::BinOp::Ge => {
-                self.check_expr(env, e1, &fhir::Sort::Int)?;
-                self.check_expr(env, e2, &fhir::Sort::Int)?;
-                Ok(&fhir::Sort::Bool)
+                let sort = self.synth_numeric(env, e1)?;
+                self.check_expr(env, e2, &sort)?;
+                Ok(fhir::Sort::Bool)
+            }
+            fhir::BinOp::Add | fhir::BinOp::Sub | fhir::BinOp::Mul | fhir::BinOp::Div => {
+                let sort = self.synth_numeric(env, e1)?;
+                self.check_expr(env, e2, &sort)?;
+                Ok(sort)
             }
-            fhir::BinOp::Add
-            | fhir::BinOp::Sub
-            | fhir::BinOp::Mod
-            | fhir::BinOp::Mul
-            | fhir::BinOp::Div => {
+            fhir::BinOp::Mod => {
                 self.check_expr(env, e1, &fhir::Sort::Int)?;
                 self.check_expr(env, e2, &fhir::Sort::Int)?;
-                Ok(&fhir::Sort::Int)
+                Ok(fhir::Sort::Int)
             }
+            fhir::BinOp::BitAnd
+            | fhir::BinOp::BitOr
+            | fhir::BinOp::BitXor
+            | fhir::BinOp::Shl
+            | fhir::BinOp::Shr => {
+                let sort = self.synth_expr(env, e1)?;
+                if !matches!(sort, fhir::Sort::BitVec(_)) {
+                    return self.emit_err(errors::ExpectedBitVec::new(e1.span, &sort));
+                }
+                self.check_expr(env, e2, &sort)?;
+                Ok(sort)
+            }
+        }
+    }
+
+    /// Synthesizes the sort of an operand of an arithmetic operator or a comparison, which can be
+    /// either an `int` or a `real`.
+    fn synth_numeric(
+        &self,
+        env: &Env<'a>,
+        e: &'a fhir::Expr,
+    ) -> Result<fhir::Sort, ErrorGuaranteed> {
+        let sort = self.synth_expr(env, e)?;
+        if matches!(sort, fhir::Sort::Int | fhir::Sort::Real) {
+            Ok(sort)
+        } else {
+            self.emit_err(errors::SortMismatch::new(e.span, &fhir::Sort::Int, &sort))
         }
     }
 
     fn sorts(&self, bty: &fhir::BaseTy) -> &'a [fhir::Sort] {
         match bty {
-            fhir::BaseTy::Int(_) | fhir::BaseTy::Uint(_) => &[fhir::Sort::Int],
+            fhir::BaseTy::Int(_)
+            | fhir::BaseTy::Uint(_)
+            | fhir::BaseTy::Str
+            | fhir::BaseTy::Char
+            | fhir::BaseTy::Param(_) => &[fhir::Sort::Int],
             fhir::BaseTy::Bool => &[fhir::Sort::Bool],
+            fhir::BaseTy::Float(_) => &[fhir::Sort::Real],
             fhir::BaseTy::Adt(def_id, _) => self.map.sorts_of(*def_id).unwrap_or_default(),
         }
     }
@@ -383,7 +477,7 @@ impl<'a> Wf<'a> {
         func: &fhir::Func,
         args: &[fhir::Expr],
         span: Span,
-    ) -> Result<&fhir::Sort, ErrorGuaranteed> {
+    ) -> Result<fhir::Sort, ErrorGuaranteed> {
         let fsort = self.synth_func(env, func)?;
         if args.len() != fsort.inputs().len() {
             return self.emit_err(errors::ParamCountMismatch::new(
@@ -397,18 +491,136 @@ impl<'a> Wf<'a> {
         iter::zip(args, fsort.inputs())
             .try_for_each_exhaust(|(arg, formal)| self.check_expr(env, arg, formal))?;
 
-        Ok(fsort.output())
+        Ok(fsort.output().clone())
+    }
+
+    fn synth_theory_app(
+        &self,
+        env: &Env<'a>,
+        func: fhir::TheoryFunc,
+        args: &'a [fhir::Expr],
+        span: Span,
+    ) -> Result<fhir::Sort, ErrorGuaranteed> {
+        self.check_theory_arity(func, args, span)?;
+        match func {
+            fhir::TheoryFunc::SetEmpty | fhir::TheoryFunc::MapDefault => {
+                self.emit_err(errors::CannotInferSort::new(span))
+            }
+            fhir::TheoryFunc::SetSingleton => {
+                let sort = self.synth_expr(env, &args[0])?;
+                Ok(fhir::Sort::Set(Box::new(sort)))
+            }
+            fhir::TheoryFunc::SetUnion
+            | fhir::TheoryFunc::SetIntersection
+            | fhir::TheoryFunc::SetDifference
+            | fhir::TheoryFunc::SetSubset => {
+                let sort = self.synth_set(env, &args[0])?;
+                self.check_expr(env, &args[1], &sort)?;
+                if let fhir::TheoryFunc::SetSubset = func {
+                    Ok(fhir::Sort::Bool)
+                } else {
+                    Ok(sort)
+                }
+            }
+            fhir::TheoryFunc::SetMember => {
+                let sort = self.synth_expr(env, &args[0])?;
+                self.check_expr(env, &args[1], &fhir::Sort::Set(Box::new(sort)))?;
+                Ok(fhir::Sort::Bool)
+            }
+            fhir::TheoryFunc::IntToReal => {
+                self.check_expr(env, &args[0], &fhir::Sort::Int)?;
+                Ok(fhir::Sort::Real)
+            }
+            fhir::TheoryFunc::RealToInt => {
+                self.check_expr(env, &args[0], &fhir::Sort::Real)?;
+                Ok(fhir::Sort::Int)
+            }
+            fhir::TheoryFunc::IntToBv32 | fhir::TheoryFunc::IntToBv64 => {
+                self.check_expr(env, &args[0], &fhir::Sort::Int)?;
+                Ok(fhir::Sort::BitVec(bv_width(func)))
+            }
+            fhir::TheoryFunc::Bv32ToInt | fhir::TheoryFunc::Bv64ToInt => {
+                self.check_expr(env, &args[0], &fhir::Sort::BitVec(bv_width(func)))?;
+                Ok(fhir::Sort::Int)
+            }
+            fhir::TheoryFunc::BvAShr => {
+                let sort = self.synth_expr(env, &args[0])?;
+                if !matches!(sort, fhir::Sort::BitVec(_)) {
+                    return self.emit_err(errors::ExpectedBitVec::new(args[0].span, &sort));
+                }
+                self.check_expr(env, &args[1], &sort)?;
+                Ok(sort)
+            }
+            fhir::TheoryFunc::MapSelect | fhir::TheoryFunc::MapStore => {
+                let sort = self.synth_expr(env, &args[0])?;
+                let fhir::Sort::Map(k, v) = &sort else {
+                    return self.emit_err(errors::ExpectedMap::new(args[0].span, &sort));
+                };
+                self.check_expr(env, &args[1], k)?;
+                if let fhir::TheoryFunc::MapSelect = func {
+                    Ok((**v).clone())
+                } else {
+                    self.check_expr(env, &args[2], v)?;
+                    Ok(sort)
+                }
+            }
+        }
+    }
+
+    fn synth_set(&self, env: &Env<'a>, e: &'a fhir::Expr) -> Result<fhir::Sort, ErrorGuaranteed> {
+        let sort = self.synth_expr(env, e)?;
+        if let fhir::Sort::Set(_) = sort {
+            Ok(sort)
+        } else {
+            self.emit_err(errors::ExpectedSet::new(e.span, &sort))
+        }
+    }
+
+    fn check_theory_arity(
+        &self,
+        func: fhir::TheoryFunc,
+        args: &[fhir::Expr],
+        span: Span,
+    ) -> Result<(), ErrorGuaranteed> {
+        let arity = match func {
+            fhir::TheoryFunc::SetEmpty => 0,
+            fhir::TheoryFunc::SetSingleton
+            | fhir::TheoryFunc::MapDefault
+            | fhir::TheoryFunc::IntToReal
+            | fhir::TheoryFunc::RealToInt
+            | fhir::TheoryFunc::IntToBv32
+            | fhir::TheoryFunc::IntToBv64
+            | fhir::TheoryFunc::Bv32ToInt
+            | fhir::TheoryFunc::Bv64ToInt => 1,
+            fhir::TheoryFunc::SetUnion
+            | fhir::TheoryFunc::SetIntersection
+            | fhir::TheoryFunc::SetDifference
+            | fhir::TheoryFunc::SetMember
+            | fhir::TheoryFunc::SetSubset
+            | fhir::TheoryFunc::MapSelect
+            | fhir::TheoryFunc::BvAShr => 2,
+            fhir::TheoryFunc::MapStore => 3,
+        };
+        if args.len() != arity {
+            return self.emit_err(errors::ParamCountMismatch::new(
+                Some(span),
+                String::from("function"),
+                arity,
+                args.len(),
+            ));
+        }
+        Ok(())
     }
 
     fn synth_func(
         &self,
         env: &Env<'a>,
         func: &fhir::Func,
-    ) -> Result<&fhir::FuncSort, ErrorGuaranteed> {
+    ) -> Result<fhir::FuncSort, ErrorGuaranteed> {
         match func {
             fhir::Func::Var(var) => {
                 match env[&var.name] {
-                    fhir::Sort::Func(sort) => Ok(sort),
+                    fhir::Sort::Func(sort) => Ok(sort.clone()),
                     sort => {
                         Err(self
                             .sess
@@ -417,12 +629,18 @@ impl<'a> Wf<'a> {
                 }
             }
             fhir::Func::Uif(func, span) => {
-                Ok(&self
+                let sort = self
                     .map
                     .uif(func)
-                    .unwrap_or_else(|| panic!("no definition found for uif `{func:?}` - {span:?}"))
-                    .sort)
+                    .map(|uif| &uif.sort)
+                    .or_else(|| self.map.defn(func).map(|defn| &defn.sort))
+                    .unwrap_or_else(|| panic!("no definition found for uif `{func:?}` - {span:?}"));
+                Ok(sort.clone())
             }
+            fhir::Func::Theory(func) => {
+                unreachable!("theory functions don't have a fixed sort: `{func:?}`")
+            }
+            fhir::Func::Data(func) => Ok(self.map.data_func_sort(*func)),
         }
     }
 
@@ -458,15 +676,32 @@ impl<'a> Wf<'a> {
                     .iter()
                     .try_for_each_exhaust(|e| self.check_param_uses(env, e, false))
             }
-            fhir::ExprKind::Literal(_) | fhir::ExprKind::Const(_, _) => Ok(()),
+            fhir::ExprKind::Quant(_, var, box [lo, hi, body]) => {
+                self.check_param_uses(env, lo, false)?;
+                self.check_param_uses(env, hi, false)?;
+                self.check_param_uses(&env.with_quantified(var.name), body, false)
+            }
+            fhir::ExprKind::Literal(_)
+            | fhir::ExprKind::Const(_, _)
+            | fhir::ExprKind::ConstParam(..) => Ok(()),
         }
     }
 }
 
-fn synth_lit(lit: fhir::Lit) -> &'static fhir::Sort {
+fn synth_lit(lit: fhir::Lit) -> fhir::Sort {
     match lit {
-        fhir::Lit::Int(_) => &fhir::Sort::Int,
-        fhir::Lit::Bool(_) => &fhir::Sort::Bool,
+        fhir::Lit::Int(_) => fhir::Sort::Int,
+        fhir::Lit::Bool(_) => fhir::Sort::Bool,
+        fhir::Lit::Real(_) => fhir::Sort::Real,
+    }
+}
+
+/// The width of the bit vectors produced or consumed by a conversion function.
+fn bv_width(func: fhir::TheoryFunc) -> u32 {
+    match func {
+        fhir::TheoryFunc::IntToBv32 | fhir::TheoryFunc::Bv32ToInt => 32,
+        fhir::TheoryFunc::IntToBv64 | fhir::TheoryFunc::Bv64ToInt => 64,
+        _ => unreachable!("not a bit vector conversion: `{func:?}`"),
     }
 }
 
@@ -554,6 +789,66 @@ mod errors {
         }
     }
 
+    #[derive(Diagnostic)]
+    #[diag(wf::expected_bitvec, code = "FLUX")]
+    #[help]
+    pub(super) struct ExpectedBitVec<'a> {
+        #[primary_span]
+        #[label]
+        span: Span,
+        found: &'a fhir::Sort,
+    }
+
+    impl<'a> ExpectedBitVec<'a> {
+        pub(super) fn new(span: Span, found: &'a fhir::Sort) -> Self {
+            Self { span, found }
+        }
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(wf::expected_set, code = "FLUX")]
+    pub(super) struct ExpectedSet<'a> {
+        #[primary_span]
+        #[label]
+        span: Span,
+        found: &'a fhir::Sort,
+    }
+
+    impl<'a> ExpectedSet<'a> {
+        pub(super) fn new(span: Span, found: &'a fhir::Sort) -> Self {
+            Self { span, found }
+        }
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(wf::expected_map, code = "FLUX")]
+    pub(super) struct ExpectedMap<'a> {
+        #[primary_span]
+        #[label]
+        span: Span,
+        found: &'a fhir::Sort,
+    }
+
+    impl<'a> ExpectedMap<'a> {
+        pub(super) fn new(span: Span, found: &'a fhir::Sort) -> Self {
+            Self { span, found }
+        }
+    }
+
+    #[derive(Diagnostic)]
+    #[diag(wf::cannot_infer_sort, code = "FLUX")]
+    pub(super) struct CannotInferSort {
+        #[primary_span]
+        #[label]
+        span: Span,
+    }
+
+    impl CannotInferSort {
+        pub(super) fn new(span: Span) -> Self {
+            Self { span }
+        }
+    }
+
     #[derive(Diagnostic)]
     #[diag(wf::invalid_param_in_func_pos, code = "FLUX")]
     pub(super) struct InvalidParamPos<'a> {